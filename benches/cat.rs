@@ -0,0 +1,232 @@
+//! Times `parquet2json cat` on generated files, reporting the fastest and slowest of several
+//! runs of each case. Run all cases, or those named, with:
+//!
+//! ```shell
+//! $ cargo bench --bench cat [-- <CASE>...]
+//! ```
+//!
+//! `BENCH_RUNS` sets the runs of each case (3 by default), and `PARQUET2JSON` the executable to
+//! time instead of the one built for the bench, such as one built at an earlier commit to compare
+//! against.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use arrow_array::types::Int32Type;
+use arrow_array::{ArrayRef, DictionaryArray, Float64Array, Int64Array, RecordBatch, StringArray};
+use parquet::file::properties::WriterProperties;
+use wiremock::matchers::any;
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+use common::{even_ids_fixture, write_fixture, Objects};
+
+/// Rows of the dictionary case.
+const DICTIONARY_ROWS: usize = 1_000_000;
+
+/// Rows of the filter case, and its filters.
+const FILTER_ROWS: usize = 10_000_000;
+const FILTERS: [&str; 3] = [
+    "id < 1000000",
+    "country = 'US'",
+    "amount >= 50 and country = 'US'",
+];
+
+/// Files of the footers case, and the latency of each request for them.
+const FOOTERS_FILES: usize = 32;
+const FOOTERS_LATENCY: Duration = Duration::from_millis(50);
+
+/// Writes a file of three low-cardinality string columns, dictionary encoded in Parquet and read
+/// back as Arrow dictionaries, returning its path.
+fn dictionary_fixture(dir: &Path) -> PathBuf {
+    let column = |values: &[&str], step: usize| -> ArrayRef {
+        Arc::new(DictionaryArray::<Int32Type>::from_iter(
+            (0..DICTIONARY_ROWS).map(|row| values[row / step % values.len()]),
+        ))
+    };
+    let batch = RecordBatch::try_from_iter([
+        ("country", column(&["US", "GB", "DE", "FR", "JP"], 1)),
+        ("status", column(&["active", "pending", "closed"], 7)),
+        ("device", column(&["desktop", "mobile", "tablet", "tv"], 3)),
+    ])
+    .unwrap();
+    let path = dir.join("dictionary.parquet");
+    write_fixture(&path, &batch, WriterProperties::default());
+    path
+}
+
+/// Writes a file of `id` (int64), `amount` (float64 from 0 to 100) and `country` (one of 5
+/// strings) with default properties, returning its path.
+fn filter_fixture(dir: &Path) -> PathBuf {
+    let rows = 0..FILTER_ROWS as i64;
+    let countries = ["US", "GB", "DE", "FR", "JP"];
+    let batch = RecordBatch::try_from_iter([
+        (
+            "id",
+            Arc::new(Int64Array::from_iter_values(rows.clone())) as ArrayRef,
+        ),
+        (
+            "amount",
+            Arc::new(Float64Array::from_iter_values(
+                rows.clone().map(|row| (row * 7919 % 10000) as f64 / 100.0),
+            )),
+        ),
+        (
+            "country",
+            Arc::new(StringArray::from_iter_values(
+                rows.map(|row| countries[(row * 31 % 5) as usize]),
+            )),
+        ),
+    ])
+    .unwrap();
+    let path = dir.join("filter.parquet");
+    write_fixture(&path, &batch, WriterProperties::default());
+    path
+}
+
+/// Objects answered after a delay, as from a distant server.
+struct Delayed(Objects);
+
+impl Respond for Delayed {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        self.0.respond(request).set_delay(FOOTERS_LATENCY)
+    }
+}
+
+/// Serves copies of a file of a single column and row group over HTTP with latency, returning
+/// their URLs. Its rows are read with one request and its footer with two, so that reading them
+/// waits on footers unless several are fetched at a time.
+async fn serve_footers(server: &MockServer, dir: &Path) -> Vec<String> {
+    let fixture = std::fs::read(even_ids_fixture(dir, 1000)).unwrap();
+    let objects =
+        (0..FOOTERS_FILES).map(|file| (format!("data/part-{}.parquet", file), fixture.clone()));
+    let urls = (0..FOOTERS_FILES)
+        .map(|file| format!("{}/data/part-{}.parquet", server.uri(), file))
+        .collect();
+    Mock::given(any())
+        .respond_with(Delayed(Objects::new(objects)))
+        .mount(server)
+        .await;
+    urls
+}
+
+/// Runs parquet2json with the arguments, discarding its output, returning how long it took and
+/// the filter throughput of its --summary, if any.
+fn time(executable: &Path, args: &[&str]) -> (Duration, Option<f64>) {
+    let start = Instant::now();
+    let output = Command::new(executable)
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    let elapsed = start.elapsed();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        output.status.success(),
+        "parquet2json {} failed: {}",
+        args.join(" "),
+        stderr
+    );
+    let throughput = stderr.lines().find_map(|line| {
+        let line = line.trim().strip_prefix("filter throughput: ")?;
+        line.split_once(" rows/s")?.0.parse().ok()
+    });
+    (elapsed, throughput)
+}
+
+/// Times the arguments over the runs, printing the fastest and slowest with the description, and
+/// the range of filter throughputs reported.
+fn report(executable: &Path, runs: usize, description: &str, args: &[&str]) {
+    let results: Vec<(Duration, Option<f64>)> = (0..runs).map(|_| time(executable, args)).collect();
+    let min = results.iter().map(|(elapsed, _)| elapsed).min().unwrap();
+    let max = results.iter().map(|(elapsed, _)| elapsed).max().unwrap();
+    let mut line = format!(
+        "{:<60} {:.2}–{:.2} s",
+        description,
+        min.as_secs_f64(),
+        max.as_secs_f64()
+    );
+    let throughputs: Vec<f64> = results
+        .iter()
+        .filter_map(|(_, throughput)| *throughput)
+        .collect();
+    if !throughputs.is_empty() {
+        let min = throughputs.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = throughputs.iter().copied().fold(0.0, f64::max);
+        line.push_str(&format!(
+            ", filters at {:.0}–{:.0} million rows/s",
+            min / 1e6,
+            max / 1e6
+        ));
+    }
+    println!("{} ({} runs)", line, runs);
+}
+
+fn main() {
+    // cargo passes --bench to bench executables
+    let cases: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    let selected = |case: &str| cases.is_empty() || cases.iter().any(|name| name == case);
+    let runs = std::env::var("BENCH_RUNS").map_or(3, |runs| runs.parse().unwrap());
+    let executable = std::env::var_os("PARQUET2JSON")
+        .map_or_else(|| env!("CARGO_BIN_EXE_parquet2json").into(), PathBuf::from);
+    let dir = tempfile::tempdir().unwrap();
+
+    if selected("dictionary") {
+        let path = dictionary_fixture(dir.path());
+        report(
+            &executable,
+            runs,
+            &format!(
+                "dictionary: cat of {} rows of 3 dictionary strings",
+                DICTIONARY_ROWS
+            ),
+            &[path.to_str().unwrap(), "cat"],
+        );
+    }
+
+    if selected("footers") {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let server = runtime.block_on(MockServer::start());
+        let urls = runtime.block_on(serve_footers(&server, dir.path()));
+        for concurrency in ["1", "16"] {
+            let mut args: Vec<&str> = urls.iter().map(String::as_str).collect();
+            args.extend(["cat", "--metadata-concurrency", concurrency]);
+            report(
+                &executable,
+                runs,
+                &format!(
+                    "footers: cat of {} files over HTTP, {} footers at a time",
+                    FOOTERS_FILES, concurrency
+                ),
+                &args,
+            );
+        }
+    }
+
+    if selected("filter") {
+        let path = filter_fixture(dir.path());
+        let path = path.to_str().unwrap();
+        report(
+            &executable,
+            runs,
+            &format!("filter: cat of {} rows", FILTER_ROWS),
+            &[path, "cat"],
+        );
+        for filter in FILTERS {
+            report(
+                &executable,
+                runs,
+                &format!("filter: cat --where \"{}\"", filter),
+                &["--summary", path, "cat", "--where", filter],
+            );
+        }
+    }
+}
@@ -0,0 +1,7 @@
+fn main() {
+    // Used by self-update to select the matching release asset
+    println!(
+        "cargo:rustc-env=TARGET={}",
+        std::env::var("TARGET").unwrap()
+    );
+}
@@ -0,0 +1,34 @@
+//! Reads every row of a file through the async reader and writes it as JSON lines, as the cat
+//! command does with a remote file.
+#![no_main]
+
+use std::io::Cursor;
+
+use arrow_json::writer::LineDelimited;
+use arrow_json::WriterBuilder;
+use futures::executor::block_on;
+use futures::StreamExt;
+use libfuzzer_sys::fuzz_target;
+use parquet::arrow::ParquetRecordBatchStreamBuilder;
+
+fuzz_target!(|data: &[u8]| {
+    block_on(async {
+        let Ok(builder) = ParquetRecordBatchStreamBuilder::new(Cursor::new(data.to_vec())).await
+        else {
+            return;
+        };
+        let Ok(mut stream) = builder.with_batch_size(1024).build() else {
+            return;
+        };
+        let mut writer = WriterBuilder::new()
+            .with_explicit_nulls(true)
+            .build::<_, LineDelimited>(std::io::sink());
+        while let Some(batch) = stream.next().await {
+            let Ok(batch) = batch else {
+                return;
+            };
+            let _ = writer.write(&batch);
+        }
+        let _ = writer.finish();
+    });
+});
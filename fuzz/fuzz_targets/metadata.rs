@@ -0,0 +1,25 @@
+//! Reads the footer of a file as the schema and rowcount commands do, before any data, along with
+//! the page index that cat loads to skip rows with --offset.
+#![no_main]
+
+use bytes::Bytes;
+use libfuzzer_sys::fuzz_target;
+use parquet::arrow::arrow_reader::{ArrowReaderMetadata, ArrowReaderOptions};
+use parquet::arrow::parquet_to_arrow_schema;
+use parquet::schema::printer::print_schema;
+
+fuzz_target!(|data: &[u8]| {
+    let options = ArrowReaderOptions::new().with_page_index(true);
+    let Ok(metadata) = ArrowReaderMetadata::load(&Bytes::copy_from_slice(data), options) else {
+        return;
+    };
+    let parquet_metadata = metadata.metadata();
+    let file_metadata = parquet_metadata.file_metadata();
+    print_schema(&mut std::io::sink(), file_metadata.schema());
+    let _ = parquet_to_arrow_schema(file_metadata.schema_descr(), file_metadata.key_value_metadata());
+    let _ = parquet_metadata
+        .row_groups()
+        .iter()
+        .map(|row_group| row_group.num_rows())
+        .sum::<i64>();
+});
@@ -0,0 +1,64 @@
+/// S3 location given by the ARN of an access point in place of a bucket, e.g.
+/// `s3://arn:aws:s3:us-west-2:123456789012:accesspoint/my-access-point/data/file.parquet`.
+pub struct AccessPoint {
+    pub name: String,
+    pub endpoint: String,
+    /// Signing region, none for Multi-Region Access Points signed for all regions with SigV4A
+    pub region: Option<String>,
+    pub key: String,
+}
+
+/// Parses an S3 location whose bucket is an access point ARN, returning none for other
+/// locations. Regional access points are addressed at their dual-stack (IPv6) endpoint if asked.
+pub fn parse_access_point(file: &str, dualstack: bool) -> Option<AccessPoint> {
+    let arn = file.strip_prefix("s3://")?;
+    if !arn.starts_with("arn:") {
+        return None;
+    }
+    let parts: Vec<&str> = arn.splitn(6, ':').collect();
+    let [_, partition, service, region, account, resource] = parts[..] else {
+        panic!("Invalid S3 access point ARN ({})", arn)
+    };
+    if service != "s3" {
+        panic!(
+            "Unsupported ARN service ({}), only S3 access points are supported",
+            service
+        );
+    }
+    let Some(resource) = resource
+        .strip_prefix("accesspoint/")
+        .or_else(|| resource.strip_prefix("accesspoint:"))
+    else {
+        panic!("Invalid S3 access point ARN ({})", arn)
+    };
+    let (name, key) = resource.split_once('/').unwrap_or((resource, ""));
+    if name.is_empty() || account.is_empty() {
+        panic!("Invalid S3 access point ARN ({})", arn);
+    }
+    let domain = match partition {
+        "aws-cn" => "amazonaws.com.cn",
+        _ => "amazonaws.com",
+    };
+
+    let dualstack = if dualstack { ".dualstack" } else { "" };
+
+    let access_point = if region.is_empty() {
+        AccessPoint {
+            name: name.to_string(),
+            endpoint: format!("https://{}.accesspoint.s3-global.{}", name, domain),
+            region: None,
+            key: key.to_string(),
+        }
+    } else {
+        AccessPoint {
+            name: name.to_string(),
+            endpoint: format!(
+                "https://{}-{}.s3-accesspoint{}.{}.{}",
+                name, account, dualstack, region, domain
+            ),
+            region: Some(region.to_string()),
+            key: key.to_string(),
+        }
+    };
+    Some(access_point)
+}
@@ -0,0 +1,294 @@
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::ptr::null_mut;
+
+use arrow_array::ffi::{to_ffi, FFI_ArrowArray, FFI_ArrowSchema};
+use arrow_array::{Array, RecordBatch, StructArray};
+use libloading::{library_filename, Library, Symbol};
+
+use crate::sink::{Sink, SinkKind};
+use crate::CatArgs;
+
+// Structures of the ADBC 1.0.0 C API (adbc.h), loaded from a driver library
+
+type Status = u8;
+
+const STATUS_OK: Status = 0;
+const ADBC_VERSION_1_0_0: c_int = 1_000_000;
+
+#[allow(dead_code)]
+#[repr(C)]
+struct AdbcError {
+    message: *mut c_char,
+    vendor_code: i32,
+    sqlstate: [c_char; 5],
+    release: Option<unsafe extern "C" fn(*mut AdbcError)>,
+}
+
+#[allow(dead_code)]
+#[repr(C)]
+struct Handle {
+    private_data: *mut c_void,
+    private_driver: *mut AdbcDriver,
+}
+
+type ErrorPtr = *mut AdbcError;
+type Unused = Option<unsafe extern "C" fn()>;
+
+#[allow(dead_code)]
+#[repr(C)]
+struct AdbcDriver {
+    private_data: *mut c_void,
+    private_manager: *mut c_void,
+    release: Option<unsafe extern "C" fn(*mut AdbcDriver, ErrorPtr) -> Status>,
+    database_init: Option<unsafe extern "C" fn(*mut Handle, ErrorPtr) -> Status>,
+    database_new: Option<unsafe extern "C" fn(*mut Handle, ErrorPtr) -> Status>,
+    database_set_option:
+        Option<unsafe extern "C" fn(*mut Handle, *const c_char, *const c_char, ErrorPtr) -> Status>,
+    database_release: Option<unsafe extern "C" fn(*mut Handle, ErrorPtr) -> Status>,
+    connection_commit: Unused,
+    connection_get_info: Unused,
+    connection_get_objects: Unused,
+    connection_get_table_schema: Unused,
+    connection_get_table_types: Unused,
+    connection_init: Option<unsafe extern "C" fn(*mut Handle, *mut Handle, ErrorPtr) -> Status>,
+    connection_new: Option<unsafe extern "C" fn(*mut Handle, ErrorPtr) -> Status>,
+    connection_set_option: Unused,
+    connection_read_partition: Unused,
+    connection_release: Option<unsafe extern "C" fn(*mut Handle, ErrorPtr) -> Status>,
+    connection_rollback: Unused,
+    statement_bind: Option<
+        unsafe extern "C" fn(
+            *mut Handle,
+            *mut FFI_ArrowArray,
+            *mut FFI_ArrowSchema,
+            ErrorPtr,
+        ) -> Status,
+    >,
+    statement_bind_stream: Unused,
+    statement_execute_query:
+        Option<unsafe extern "C" fn(*mut Handle, *mut c_void, *mut i64, ErrorPtr) -> Status>,
+    statement_execute_partitions: Unused,
+    statement_get_parameter_schema: Unused,
+    statement_new: Option<unsafe extern "C" fn(*mut Handle, *mut Handle, ErrorPtr) -> Status>,
+    statement_prepare: Unused,
+    statement_release: Option<unsafe extern "C" fn(*mut Handle, ErrorPtr) -> Status>,
+    statement_set_option:
+        Option<unsafe extern "C" fn(*mut Handle, *const c_char, *const c_char, ErrorPtr) -> Status>,
+    statement_set_sql_query: Unused,
+    statement_set_substrait_plan: Unused,
+}
+
+type DriverInit = unsafe extern "C" fn(c_int, *mut c_void, ErrorPtr) -> Status;
+
+fn new_error() -> AdbcError {
+    AdbcError {
+        message: null_mut(),
+        vendor_code: 0,
+        sqlstate: [0; 5],
+        release: None,
+    }
+}
+
+/// Panics with the driver's message if the call failed.
+fn check(status: Status, error: &mut AdbcError, action: &str) {
+    if status == STATUS_OK {
+        return;
+    }
+    let message = if error.message.is_null() {
+        format!("status {}", status)
+    } else {
+        unsafe { CStr::from_ptr(error.message) }
+            .to_string_lossy()
+            .into_owned()
+    };
+    if let Some(release) = error.release {
+        unsafe { release(error) };
+    }
+    panic!("ADBC {} failed ({})", action, message);
+}
+
+fn new_handle() -> Box<Handle> {
+    Box::new(Handle {
+        private_data: null_mut(),
+        private_driver: null_mut(),
+    })
+}
+
+/// Returns the driver library for the scheme of a database URI, e.g. `adbc_driver_postgresql`
+/// for `postgresql://`, unless given by `ADBC_DRIVER`.
+fn driver_library(uri: &str) -> String {
+    if let Ok(driver) = std::env::var("ADBC_DRIVER") {
+        return driver;
+    }
+    let scheme = uri.split_once("://").map_or("sqlite", |(scheme, _)| scheme);
+    let name = match scheme {
+        "postgres" | "postgresql" => "adbc_driver_postgresql",
+        "grpc" | "grpc+tls" => "adbc_driver_flightsql",
+        "snowflake" => "adbc_driver_snowflake",
+        "file" | "sqlite" => "adbc_driver_sqlite",
+        _ => panic!("Unknown ADBC driver for {}, set ADBC_DRIVER", uri),
+    };
+    library_filename(name).to_string_lossy().into_owned()
+}
+
+/// Bulk ingests record batches into a table through an ADBC driver, creating the table if it
+/// does not exist.
+pub struct AdbcWriter {
+    // Handles are released before the driver library is unloaded
+    statement: Box<Handle>,
+    connection: Box<Handle>,
+    database: Box<Handle>,
+    driver: Box<AdbcDriver>,
+    _library: Library,
+}
+
+impl AdbcWriter {
+    pub fn new(uri: &str, table: &str) -> Self {
+        let library_name = driver_library(uri);
+        let library = unsafe { Library::new(&library_name) }
+            .unwrap_or_else(|error| panic!("ADBC driver not found ({}: {})", library_name, error));
+
+        let mut error = new_error();
+        let mut driver: Box<AdbcDriver> = Box::new(unsafe { std::mem::zeroed() });
+        unsafe {
+            let init: Symbol<DriverInit> = library
+                .get(b"AdbcDriverInit\0")
+                .unwrap_or_else(|error| panic!("Invalid ADBC driver ({})", error));
+            let status = init(
+                ADBC_VERSION_1_0_0,
+                driver.as_mut() as *mut AdbcDriver as *mut c_void,
+                &mut error,
+            );
+            check(status, &mut error, "driver initialization");
+        }
+
+        let (mut database, mut connection, mut statement) =
+            (new_handle(), new_handle(), new_handle());
+        let uri_option = CString::new("uri").unwrap();
+        let uri_value = CString::new(uri).unwrap();
+        let table_option = CString::new("adbc.ingest.target_table").unwrap();
+        let table_value = CString::new(table).unwrap();
+        let mode_option = CString::new("adbc.ingest.mode").unwrap();
+        let mode_value = CString::new("adbc.ingest.mode.create_append").unwrap();
+        unsafe {
+            let status = (driver.database_new.unwrap())(database.as_mut(), &mut error);
+            check(status, &mut error, "database");
+            let status = (driver.database_set_option.unwrap())(
+                database.as_mut(),
+                uri_option.as_ptr(),
+                uri_value.as_ptr(),
+                &mut error,
+            );
+            check(status, &mut error, "database");
+            let status = (driver.database_init.unwrap())(database.as_mut(), &mut error);
+            check(status, &mut error, "database");
+
+            let status = (driver.connection_new.unwrap())(connection.as_mut(), &mut error);
+            check(status, &mut error, "connection");
+            let status = (driver.connection_init.unwrap())(
+                connection.as_mut(),
+                database.as_mut(),
+                &mut error,
+            );
+            check(status, &mut error, "connection");
+
+            let status = (driver.statement_new.unwrap())(
+                connection.as_mut(),
+                statement.as_mut(),
+                &mut error,
+            );
+            check(status, &mut error, "statement");
+            let status = (driver.statement_set_option.unwrap())(
+                statement.as_mut(),
+                table_option.as_ptr(),
+                table_value.as_ptr(),
+                &mut error,
+            );
+            check(status, &mut error, "statement");
+            let status = (driver.statement_set_option.unwrap())(
+                statement.as_mut(),
+                mode_option.as_ptr(),
+                mode_value.as_ptr(),
+                &mut error,
+            );
+            check(status, &mut error, "statement");
+        }
+
+        AdbcWriter {
+            statement,
+            connection,
+            database,
+            driver,
+            _library: library,
+        }
+    }
+
+    pub fn ingest(&mut self, batch: &RecordBatch) {
+        let data = StructArray::from(batch.clone()).into_data();
+        let (mut array, mut schema) = to_ffi(&data).unwrap();
+        let mut error = new_error();
+        let mut rows_affected: i64 = -1;
+        unsafe {
+            // The driver takes ownership of the array, and releases it once ingested
+            let status = (self.driver.statement_bind.unwrap())(
+                self.statement.as_mut(),
+                &mut array,
+                &mut schema,
+                &mut error,
+            );
+            check(status, &mut error, "bind");
+            let status = (self.driver.statement_execute_query.unwrap())(
+                self.statement.as_mut(),
+                null_mut(),
+                &mut rows_affected,
+                &mut error,
+            );
+            check(status, &mut error, "ingest");
+        }
+    }
+}
+
+/// Database table, as adbc:<uri> with --table.
+pub struct AdbcSinkKind;
+
+#[async_trait::async_trait(?Send)]
+impl SinkKind for AdbcSinkKind {
+    fn matches(&self, output: &str) -> bool {
+        output.starts_with("adbc:")
+    }
+
+    async fn open(&self, output: &str, args: &CatArgs) -> Box<dyn Sink> {
+        let table = args
+            .table
+            .as_deref()
+            .unwrap_or_else(|| panic!("A --table is needed for adbc: outputs"));
+        Box::new(AdbcWriter::new(&output["adbc:".len()..], table))
+    }
+}
+
+/// Databases ingest Arrow data as is, without the JSON intermediate.
+#[async_trait::async_trait(?Send)]
+impl Sink for AdbcWriter {
+    async fn write_batch(&mut self, batch: &RecordBatch) -> bool {
+        self.ingest(batch);
+        true
+    }
+
+    async fn write(&mut self, _bytes: Vec<u8>) {}
+
+    async fn finish(&mut self) {}
+}
+
+impl Drop for AdbcWriter {
+    fn drop(&mut self) {
+        let mut error = new_error();
+        unsafe {
+            (self.driver.statement_release.unwrap())(self.statement.as_mut(), &mut error);
+            (self.driver.connection_release.unwrap())(self.connection.as_mut(), &mut error);
+            (self.driver.database_release.unwrap())(self.database.as_mut(), &mut error);
+            if let Some(release) = self.driver.release {
+                release(self.driver.as_mut(), &mut error);
+            }
+        }
+    }
+}
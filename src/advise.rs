@@ -0,0 +1,435 @@
+use std::io::Write;
+use std::ops::Range;
+use std::sync::Arc;
+
+use object_store::{ObjectMeta, ObjectStore};
+use parquet::arrow::arrow_reader::{ArrowReaderMetadata, ArrowReaderOptions};
+use parquet::arrow::async_reader::ParquetObjectReader;
+use parquet::basic::{Compression, Encoding, PageType};
+use parquet::file::metadata::{ColumnChunkMetaData, ParquetMetaData};
+use parquet::format::PageHeader;
+use parquet::thrift::TSerializable;
+use thrift::protocol::TCompactInputProtocol;
+
+use crate::style::{accent, bold, dim};
+
+const TARGET_ROW_GROUP_SIZE: u64 = 128 * 1024 * 1024;
+const TARGET_PAGE_SIZE: u64 = 1024 * 1024;
+/// Bytes fetched to read a page header, which holds at most the statistics of the page
+const PAGE_HEADER_SIZE: u64 = 16 * 1024;
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[derive(Default)]
+struct ColumnSummary {
+    path: String,
+    codecs: Vec<Compression>,
+    compressed: u64,
+    uncompressed: u64,
+    dictionary_pages: u64,
+    fallback_pages: u64,
+    data_pages: u64,
+    v2_pages: u64,
+    has_page_statistics: bool,
+    first_page_type: Option<PageType>,
+    encodings: Vec<Encoding>,
+    has_statistics: bool,
+    pages: Option<(u64, u64)>,
+}
+
+impl ColumnSummary {
+    fn add(&mut self, column: &ColumnChunkMetaData, pages: Option<(u64, u64)>) {
+        if !self.codecs.contains(&column.compression()) {
+            self.codecs.push(column.compression());
+        }
+        self.compressed += column.compressed_size() as u64;
+        self.uncompressed += column.uncompressed_size() as u64;
+        self.has_statistics |= column.statistics().is_some();
+
+        let dictionary_encoded = |encoding: &Encoding| {
+            matches!(
+                encoding,
+                Encoding::PLAIN_DICTIONARY | Encoding::RLE_DICTIONARY
+            )
+        };
+        match column.page_encoding_stats() {
+            Some(stats) => {
+                self.has_page_statistics = true;
+                for stat in stats {
+                    match stat.page_type {
+                        PageType::DICTIONARY_PAGE => self.dictionary_pages += stat.count as u64,
+                        PageType::DATA_PAGE | PageType::DATA_PAGE_V2 => {
+                            self.data_pages += stat.count as u64;
+                            if stat.page_type == PageType::DATA_PAGE_V2 {
+                                self.v2_pages += stat.count as u64;
+                            }
+                            if !dictionary_encoded(&stat.encoding) {
+                                self.fallback_pages += stat.count as u64;
+                                self.add_encoding(stat.encoding);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            // Without page statistics, fallback is only apparent with the legacy dictionary
+            // encoding, as dictionary pages are otherwise plain encoded too
+            None => {
+                for encoding in column.encodings() {
+                    #[allow(deprecated)]
+                    let level_encoded = matches!(encoding, Encoding::RLE | Encoding::BIT_PACKED);
+                    let dictionary_page =
+                        *encoding == Encoding::PLAIN && column.dictionary_page_offset().is_some();
+                    if !level_encoded && !dictionary_page && !dictionary_encoded(encoding) {
+                        self.add_encoding(*encoding);
+                    }
+                }
+                if column.dictionary_page_offset().is_some() {
+                    self.dictionary_pages += 1;
+                    if column.encodings().contains(&Encoding::PLAIN_DICTIONARY) {
+                        self.data_pages += 1;
+                        if column.encodings().contains(&Encoding::PLAIN) {
+                            self.fallback_pages += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some((count, bytes)) = pages {
+            let (total_count, total_bytes) = self.pages.get_or_insert((0, 0));
+            *total_count += count;
+            *total_bytes += bytes;
+        }
+    }
+
+    fn add_encoding(&mut self, encoding: Encoding) {
+        if !self.encodings.contains(&encoding) {
+            self.encodings.push(encoding);
+        }
+    }
+
+    /// Data page format versions, from page statistics or else the first data page
+    fn page_versions(&self) -> Option<&str> {
+        if !self.has_page_statistics {
+            return match self.first_page_type? {
+                PageType::DATA_PAGE => Some("v1"),
+                PageType::DATA_PAGE_V2 => Some("v2"),
+                _ => None,
+            };
+        }
+        match (self.data_pages - self.v2_pages, self.v2_pages) {
+            (0, 0) => None,
+            (_, 0) => Some("v1"),
+            (0, _) => Some("v2"),
+            _ => Some("v1/v2"),
+        }
+    }
+
+    fn ratio(&self) -> f64 {
+        self.uncompressed as f64 / self.compressed.max(1) as f64
+    }
+
+    /// Share of data pages that are not dictionary encoded, if known
+    fn fallback_rate(&self) -> Option<f64> {
+        (self.data_pages > 0).then(|| self.fallback_pages as f64 / self.data_pages as f64)
+    }
+}
+
+fn summarize_columns(metadata: &ParquetMetaData) -> Vec<ColumnSummary> {
+    let schema_descr = metadata.file_metadata().schema_descr();
+    let mut columns: Vec<ColumnSummary> = (0..schema_descr.num_columns())
+        .map(|index| ColumnSummary {
+            path: schema_descr.column(index).path().string(),
+            ..Default::default()
+        })
+        .collect();
+
+    for (row_group_index, row_group) in metadata.row_groups().iter().enumerate() {
+        for (column_index, column) in row_group.columns().iter().enumerate() {
+            let pages = metadata
+                .offset_index()
+                .and_then(|offset_index| offset_index.get(row_group_index)?.get(column_index))
+                .filter(|pages| !pages.is_empty())
+                .map(|pages| {
+                    let bytes: i64 = pages
+                        .iter()
+                        .map(|page| page.compressed_page_size as i64)
+                        .sum();
+                    (pages.len() as u64, bytes as u64)
+                });
+            columns[column_index].add(column, pages);
+        }
+    }
+    columns
+}
+
+/// Returns the page type of the first data page of each column chunk in the first row group,
+/// read from its header.
+async fn first_page_types(
+    storage_container: &Arc<dyn ObjectStore>,
+    meta: &ObjectMeta,
+    metadata: &ParquetMetaData,
+) -> Vec<Option<PageType>> {
+    let Some(row_group) = metadata.row_groups().first() else {
+        return vec![];
+    };
+    let ranges: Vec<Range<usize>> = row_group
+        .columns()
+        .iter()
+        .map(|column| {
+            let (start, length) = column.byte_range();
+            let offset = (column.data_page_offset() as u64).max(start);
+            let end = (start + length).min(offset + PAGE_HEADER_SIZE);
+            offset as usize..end.max(offset) as usize
+        })
+        .collect();
+    let Ok(headers) = storage_container.get_ranges(&meta.location, &ranges).await else {
+        return vec![None; ranges.len()];
+    };
+    headers
+        .iter()
+        .map(|bytes| {
+            let mut protocol = TCompactInputProtocol::new(bytes.as_ref());
+            let header = PageHeader::read_from_in_protocol(&mut protocol).ok()?;
+            PageType::try_from(header.type_).ok()
+        })
+        .collect()
+}
+
+pub fn codec_name(codec: &Compression) -> String {
+    format!("{:?}", codec)
+        .split('(')
+        .next()
+        .unwrap()
+        .to_uppercase()
+}
+
+/// Writes row group, page, dictionary and compression statistics of the file with recommended
+/// writer settings and a command to rewrite it with them.
+pub async fn advise(
+    out: &mut Vec<u8>,
+    storage_container: Arc<dyn ObjectStore>,
+    meta: ObjectMeta,
+    file: &str,
+) {
+    let mut reader = ParquetObjectReader::new(storage_container.clone(), meta.clone());
+    let options = ArrowReaderOptions::new().with_page_index(true);
+    let metadata = ArrowReaderMetadata::load_async(&mut reader, options)
+        .await
+        .unwrap();
+    let metadata = metadata.metadata();
+
+    let row_groups = metadata.row_groups();
+    let num_rows = metadata.file_metadata().num_rows().max(0) as u64;
+    let compressed: u64 = row_groups
+        .iter()
+        .map(|row_group| row_group.compressed_size() as u64)
+        .sum();
+    let uncompressed: u64 = row_groups
+        .iter()
+        .map(|row_group| row_group.total_byte_size() as u64)
+        .sum();
+    let average_row_group_size = compressed / row_groups.len().max(1) as u64;
+    let average_row_group_rows = num_rows / row_groups.len().max(1) as u64;
+    let mut columns = summarize_columns(metadata);
+    if columns.iter().any(|column| !column.has_page_statistics) {
+        let page_types = first_page_types(&storage_container, &meta, metadata).await;
+        for (column, page_type) in columns.iter_mut().zip(page_types) {
+            column.first_page_type = page_type;
+        }
+    }
+
+    writeln!(out, "{}", bold("Row groups")).unwrap();
+    writeln!(
+        out,
+        "  {} row groups, {} rows, {} compressed ({} uncompressed)",
+        row_groups.len(),
+        num_rows,
+        format_bytes(compressed),
+        format_bytes(uncompressed)
+    )
+    .unwrap();
+    if let (Some(smallest), Some(largest)) = (
+        row_groups
+            .iter()
+            .map(|row_group| row_group.num_rows())
+            .min(),
+        row_groups
+            .iter()
+            .map(|row_group| row_group.num_rows())
+            .max(),
+    ) {
+        writeln!(
+            out,
+            "  {} rows and {} compressed on average (rows: {} to {})",
+            average_row_group_rows,
+            format_bytes(average_row_group_size),
+            smallest,
+            largest
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "{}", bold("Columns")).unwrap();
+    for column in &columns {
+        let codecs: Vec<String> = column.codecs.iter().map(codec_name).collect();
+        let dictionary = match (column.dictionary_pages, column.fallback_rate()) {
+            (0, _) => "no dictionary".to_string(),
+            (_, Some(rate)) => format!("dictionary, {:.0}% fallback", rate * 100.0),
+            (_, None) => "dictionary".to_string(),
+        };
+        // Encodings of values not dictionary encoded, e.g. DELTA_BINARY_PACKED
+        let dictionary = match column.encodings.is_empty() {
+            true => dictionary,
+            false => {
+                let encodings: Vec<String> = column
+                    .encodings
+                    .iter()
+                    .map(|encoding| encoding.to_string())
+                    .collect();
+                format!("{}, {}", dictionary, encodings.join("/"))
+            }
+        };
+        let mut pages = match column.pages {
+            Some((count, bytes)) => {
+                format!("{} pages of {}", count, format_bytes(bytes / count.max(1)))
+            }
+            None => "pages unknown".to_string(),
+        };
+        if let Some(versions) = column.page_versions() {
+            pages = format!("{} ({})", pages, versions);
+        }
+        writeln!(
+            out,
+            "  {}: {}, {} ({:.1}x), {}, {}",
+            accent(&column.path),
+            codecs.join("/"),
+            format_bytes(column.compressed),
+            column.ratio(),
+            dictionary,
+            dim(pages)
+        )
+        .unwrap();
+    }
+
+    let mut recommendations: Vec<String> = vec![];
+    let bytes_per_row = compressed as f64 / num_rows.max(1) as f64;
+    let target_rows =
+        ((TARGET_ROW_GROUP_SIZE as f64 / bytes_per_row.max(1.0)) as u64).clamp(1, num_rows.max(1));
+    if row_groups.len() > 1 && average_row_group_size < TARGET_ROW_GROUP_SIZE / 4 {
+        recommendations.push(format!(
+            "Row groups are small for the data size; write about {} rows per row group (~{})",
+            target_rows,
+            format_bytes(TARGET_ROW_GROUP_SIZE.min(compressed))
+        ));
+    } else if average_row_group_size > TARGET_ROW_GROUP_SIZE * 8 {
+        recommendations.push(format!(
+            "Row groups are large, limiting parallelism and pruning; write about {} rows per row group (~{})",
+            target_rows,
+            format_bytes(TARGET_ROW_GROUP_SIZE)
+        ));
+    }
+
+    let codec = if columns.iter().any(|column| {
+        column.codecs.iter().any(|codec| {
+            matches!(
+                codec,
+                Compression::UNCOMPRESSED | Compression::SNAPPY | Compression::GZIP(_)
+            )
+        })
+    }) {
+        recommendations.push(
+            "Use ZSTD compression, for smaller files than SNAPPY or none and faster reads than GZIP"
+                .to_string(),
+        );
+        "ZSTD".to_string()
+    } else {
+        columns
+            .first()
+            .and_then(|column| column.codecs.first())
+            .map(codec_name)
+            .unwrap_or("ZSTD".to_string())
+    };
+
+    let fallback_columns: Vec<&str> = columns
+        .iter()
+        .filter(|column| column.fallback_rate().is_some_and(|rate| rate > 0.5))
+        .map(|column| column.path.as_str())
+        .collect();
+    if !fallback_columns.is_empty() {
+        recommendations.push(format!(
+            "Dictionary encoding mostly falls back to plain for {}; disable it for these high cardinality columns, or raise the dictionary page size limit",
+            fallback_columns.join(", ")
+        ));
+    }
+
+    let incompressible: Vec<&str> = columns
+        .iter()
+        .filter(|column| column.compressed > 1024 * 1024 && column.ratio() < 1.1)
+        .map(|column| column.path.as_str())
+        .collect();
+    if !incompressible.is_empty() {
+        recommendations.push(format!(
+            "Compression barely reduces {}; store them uncompressed to save CPU",
+            incompressible.join(", ")
+        ));
+    }
+
+    if metadata.offset_index().is_none() {
+        recommendations
+            .push("Write the page index, so readers can skip pages within row groups".to_string());
+    } else if columns.iter().any(|column| {
+        column
+            .pages
+            .is_some_and(|(count, bytes)| bytes / count.max(1) > TARGET_PAGE_SIZE * 4)
+    }) {
+        recommendations.push(format!(
+            "Pages are large, limiting page skipping; use a data page size of {}",
+            format_bytes(TARGET_PAGE_SIZE)
+        ));
+    }
+
+    if columns.iter().any(|column| !column.has_statistics) {
+        recommendations.push(
+            "Write column statistics, so readers can skip row groups when filtering".to_string(),
+        );
+    }
+
+    writeln!(out, "{}", bold("Recommendations")).unwrap();
+    if recommendations.is_empty() {
+        writeln!(out, "  None, the file layout is already suitable").unwrap();
+        return;
+    }
+    for recommendation in &recommendations {
+        writeln!(out, "  - {}", recommendation).unwrap();
+    }
+
+    writeln!(out, "{}", bold("Rewrite (with DuckDB)")).unwrap();
+    let row_group_size = if average_row_group_rows > 0 {
+        target_rows
+    } else {
+        122880
+    };
+    writeln!(
+        out,
+        "  duckdb -c \"COPY (SELECT * FROM '{}') TO 'rewritten.parquet' (FORMAT PARQUET, COMPRESSION {}, ROW_GROUP_SIZE {})\"",
+        file.replace('\'', "''"),
+        codec,
+        row_group_size
+    )
+    .unwrap();
+}
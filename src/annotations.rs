@@ -0,0 +1,138 @@
+use arrow_schema::{DataType, Field, Schema};
+use parquet::file::metadata::FileMetaData;
+use serde_json::{json, Map, Value};
+
+/// Field annotations by dotted column path, e.g. `{"address.city": {"comment": "..."}}`
+pub type Annotations = Map<String, Value>;
+
+fn annotate(annotations: &mut Annotations, path: &str, key: &str, value: Value) {
+    if value.is_null() || value.as_object().is_some_and(|object| object.is_empty()) {
+        return;
+    }
+    if let Value::Object(field) = annotations
+        .entry(path.to_string())
+        .or_insert_with(|| json!({}))
+    {
+        field.insert(key.to_string(), value);
+    }
+}
+
+fn child_path(parent_path: &str, name: &str) -> String {
+    if parent_path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", parent_path, name)
+    }
+}
+
+/// Collects annotations from Spark and Avro style schemas (nested `fields` arrays).
+fn from_schema_json(annotations: &mut Annotations, schema: &Value, parent_path: &str) {
+    let Some(fields) = schema["fields"].as_array() else {
+        return;
+    };
+    for field in fields {
+        let Some(name) = field["name"].as_str() else {
+            continue;
+        };
+        let path = child_path(parent_path, name);
+        if let Some(metadata) = field["metadata"].as_object() {
+            for (key, value) in metadata {
+                annotate(annotations, &path, key, value.clone());
+            }
+        }
+        for key in ["doc", "description"] {
+            annotate(annotations, &path, "description", field[key].clone());
+        }
+
+        // Nested records may be wrapped in optional unions or arrays
+        let mut nested = vec![&field["type"]];
+        while let Some(field_type) = nested.pop() {
+            match field_type {
+                Value::Array(types) => nested.extend(types),
+                Value::Object(object) if object.contains_key("fields") => {
+                    from_schema_json(annotations, field_type, &path)
+                }
+                Value::Object(object) => nested.extend(
+                    ["elementType", "items", "valueType", "values"]
+                        .iter()
+                        .filter_map(|key| object.get(*key)),
+                ),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Collects annotations from the `pandas` metadata written by pyarrow.
+fn from_pandas_json(annotations: &mut Annotations, pandas: &Value) {
+    for column in pandas["columns"].as_array().into_iter().flatten() {
+        let Some(name) = column["field_name"].as_str().or(column["name"].as_str()) else {
+            continue;
+        };
+        for key in ["pandas_type", "numpy_type", "metadata"] {
+            annotate(annotations, name, key, column[key].clone());
+        }
+    }
+}
+
+fn from_arrow_field(
+    annotations: &mut Annotations,
+    paths: &mut Vec<String>,
+    field: &Field,
+    parent_path: &str,
+) {
+    let path = child_path(parent_path, field.name());
+    paths.push(path.clone());
+    for (key, value) in field.metadata() {
+        annotate(annotations, &path, key, json!(value));
+    }
+    if let DataType::Struct(fields) = field.data_type() {
+        for field in fields {
+            from_arrow_field(annotations, paths, field, &path);
+        }
+    }
+}
+
+/// Returns field descriptions and source annotations found in the footer key-value metadata,
+/// from pandas, Spark and Avro schemas or Arrow field metadata.
+pub fn field_annotations(file_metadata: &FileMetaData, schema: &Schema) -> Annotations {
+    let mut annotations = Map::new();
+    for key_value in file_metadata.key_value_metadata().into_iter().flatten() {
+        let Some(value) = key_value
+            .value
+            .as_ref()
+            .and_then(|value| serde_json::from_str::<Value>(value).ok())
+        else {
+            continue;
+        };
+        if key_value.key == "pandas" {
+            from_pandas_json(&mut annotations, &value);
+        } else {
+            from_schema_json(&mut annotations, &value, "");
+        }
+    }
+    let mut paths = vec![];
+    for field in schema.fields() {
+        from_arrow_field(&mut annotations, &mut paths, field, "");
+    }
+
+    // Ordered as in the schema, followed by any paths not found in it
+    let mut ordered: Annotations = paths
+        .iter()
+        .filter_map(|path| Some((path.clone(), annotations.remove(path)?)))
+        .collect();
+    ordered.append(&mut annotations);
+    ordered
+}
+
+/// Formats the annotations of one field as `key=value` pairs.
+pub fn describe(annotations: &Annotations, path: &str) -> Option<String> {
+    let field = annotations.get(path)?.as_object()?;
+    Some(
+        field
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+}
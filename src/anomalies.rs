@@ -0,0 +1,208 @@
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Float16Type, Float32Type, Float64Type};
+use arrow_array::Array;
+use arrow_schema::DataType;
+use clap::{Args, ValueEnum};
+use parquet::errors::ParquetError;
+
+use crate::style::{self, accent, bold};
+
+/// Handling of an anomaly in converting rows.
+#[derive(ValueEnum, Clone, Copy, Default, PartialEq)]
+pub enum Policy {
+    /// Continues without a warning
+    Ignore,
+    /// Warns of the first occurrence and counts the others
+    #[default]
+    Warn,
+    /// Stops with an error
+    Error,
+}
+
+/// Values or rows that are output differently than they were read, or not at all.
+#[derive(Clone, Copy)]
+pub enum Anomaly {
+    LossyCast,
+    InvalidUtf8,
+    NaN,
+    OversizedRecord,
+    UnknownLogicalType,
+}
+
+const ANOMALIES: [Anomaly; 5] = [
+    Anomaly::LossyCast,
+    Anomaly::InvalidUtf8,
+    Anomaly::NaN,
+    Anomaly::OversizedRecord,
+    Anomaly::UnknownLogicalType,
+];
+
+impl Anomaly {
+    fn name(&self) -> &'static str {
+        match self {
+            Anomaly::LossyCast => "lossy cast",
+            Anomaly::InvalidUtf8 => "invalid UTF-8",
+            Anomaly::NaN => "NaN or infinity",
+            Anomaly::OversizedRecord => "oversized record",
+            Anomaly::UnknownLogicalType => "unknown logical type",
+        }
+    }
+
+    fn option(&self) -> &'static str {
+        match self {
+            Anomaly::LossyCast => "--on-lossy-cast",
+            Anomaly::InvalidUtf8 => "--on-invalid-utf8",
+            Anomaly::NaN => "--on-nan",
+            Anomaly::OversizedRecord => "--on-oversized-record",
+            Anomaly::UnknownLogicalType => "--on-unknown-logical-type",
+        }
+    }
+
+    fn policy(&self) -> Policy {
+        let policies = POLICIES.get_or_init(Default::default);
+        match self {
+            Anomaly::LossyCast => policies.on_lossy_cast,
+            Anomaly::InvalidUtf8 => policies.on_invalid_utf8,
+            Anomaly::NaN => policies.on_nan,
+            Anomaly::OversizedRecord => policies.on_oversized_record,
+            Anomaly::UnknownLogicalType => policies.on_unknown_logical_type,
+        }
+    }
+}
+
+/// Handling of each anomaly, with a count of those warned of printed when done.
+#[derive(Args, Clone, Default)]
+pub struct AnomalyArgs {
+    /// Values lost in casts to a unified schema, such as overflows to null or integers beyond
+    /// the precision of floats
+    #[clap(default_value_t, long, value_enum, value_name = "POLICY")]
+    pub on_lossy_cast: Policy,
+
+    /// Rows skipped from a string that is not valid UTF-8 to the end of its row group
+    #[clap(default_value_t, long, value_enum, value_name = "POLICY")]
+    pub on_invalid_utf8: Policy,
+
+    /// NaN and infinite floats, which JSON cannot represent and are output as null, while
+    /// formats of text such as csv write them as is
+    #[clap(default_value_t, long, value_enum, value_name = "POLICY")]
+    pub on_nan: Policy,
+
+    /// JSON lines larger than --max-file-size, written to shards of their own
+    #[clap(default_value_t, long, value_enum, value_name = "POLICY")]
+    pub on_oversized_record: Policy,
+
+    /// Columns of logical types newer than the Parquet reader, read as their converted or
+    /// physical type instead
+    #[clap(default_value_t, long, value_enum, value_name = "POLICY")]
+    pub on_unknown_logical_type: Policy,
+}
+
+static POLICIES: OnceLock<AnomalyArgs> = OnceLock::new();
+
+static COUNTS: [AtomicU64; ANOMALIES.len()] = [const { AtomicU64::new(0) }; ANOMALIES.len()];
+
+pub fn init(args: &AnomalyArgs) {
+    let _ = POLICIES.set(args.clone());
+}
+
+/// Returns whether occurrences of the anomaly need to be looked for.
+pub fn enabled(anomaly: Anomaly) -> bool {
+    anomaly.policy() != Policy::Ignore
+}
+
+/// Records occurrences of an anomaly, warning of the first one or stopping, per its policy.
+pub fn report(anomaly: Anomaly, count: usize, detail: impl Display) {
+    if count == 0 {
+        return;
+    }
+    match anomaly.policy() {
+        Policy::Ignore => {}
+        Policy::Warn => {
+            if COUNTS[anomaly as usize].fetch_add(count as u64, Ordering::Relaxed) == 0 {
+                style::warning(format!(
+                    "{} ({}), counting further ones, see {}",
+                    anomaly.name(),
+                    detail,
+                    anomaly.option()
+                ));
+            }
+        }
+        Policy::Error => panic!("{} ({})", anomaly.name(), detail),
+    }
+}
+
+/// Returns whether reading a batch failed on a string that is not valid UTF-8. The reader fails
+/// with a general error of its own validation, which async readers pass on as an Arrow error of
+/// its text, so the error is matched by variant and then by that text.
+pub fn is_invalid_utf8(error: &ParquetError) -> bool {
+    const INVALID_UTF8: &str = "encountered non UTF-8 data";
+    match error {
+        ParquetError::General(message) | ParquetError::ArrowError(message) => {
+            message.contains(INVALID_UTF8)
+        }
+        _ => false,
+    }
+}
+
+/// Prints the count of each anomaly warned of to stderr, if any.
+pub fn print_counts() {
+    let counts: Vec<(Anomaly, u64)> = ANOMALIES
+        .iter()
+        .map(|anomaly| (*anomaly, COUNTS[*anomaly as usize].load(Ordering::Relaxed)))
+        .filter(|(_, count)| *count > 0)
+        .collect();
+    if counts.is_empty() {
+        return;
+    }
+    eprintln!("{}", bold("Anomalies"));
+    for (anomaly, count) in counts {
+        eprintln!("  {}: {}", accent(anomaly.name()), count);
+    }
+}
+
+/// Returns the number of NaN and infinite floats in an array, including nested ones within its
+/// slice.
+pub fn non_finite_values(array: &dyn Array) -> usize {
+    match array.data_type() {
+        DataType::Float16 => (array.as_primitive::<Float16Type>().iter().flatten())
+            .filter(|value| !value.is_finite())
+            .count(),
+        DataType::Float32 => (array.as_primitive::<Float32Type>().iter().flatten())
+            .filter(|value| !value.is_finite())
+            .count(),
+        DataType::Float64 => (array.as_primitive::<Float64Type>().iter().flatten())
+            .filter(|value| !value.is_finite())
+            .count(),
+        DataType::List(_) => {
+            let list = array.as_list::<i32>();
+            let offsets = list.value_offsets();
+            let (start, end) = (offsets[0] as usize, offsets[list.len()] as usize);
+            non_finite_values(&list.values().slice(start, end - start))
+        }
+        DataType::LargeList(_) => {
+            let list = array.as_list::<i64>();
+            let offsets = list.value_offsets();
+            let (start, end) = (offsets[0] as usize, offsets[list.len()] as usize);
+            non_finite_values(&list.values().slice(start, end - start))
+        }
+        DataType::FixedSizeList(_, _) => non_finite_values(array.as_fixed_size_list().values()),
+        DataType::Struct(_) => array
+            .as_struct()
+            .columns()
+            .iter()
+            .map(|column| non_finite_values(column))
+            .sum(),
+        DataType::Map(_, _) => {
+            let map = array.as_map();
+            let offsets = map.value_offsets();
+            let (start, end) = (offsets[0] as usize, offsets[map.len()] as usize);
+            non_finite_values(&map.entries().slice(start, end - start))
+        }
+        DataType::Dictionary(_, _) => non_finite_values(array.as_any_dictionary().values()),
+        _ => 0,
+    }
+}
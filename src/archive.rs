@@ -0,0 +1,419 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+use std::ops::Range;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use flate2::read::DeflateDecoder;
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetResult, GetResultPayload, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    PutMultipartOpts, PutOptions, PutPayload, PutResult, Result,
+};
+use tokio::sync::OnceCell;
+
+use crate::transport::resolve_range;
+
+/// Largest end of central directory record of a zip archive, with a comment of the most bytes.
+const MAX_END_OF_CENTRAL_DIRECTORY: usize = 22 + 65535;
+
+/// Splits an `<archive>.zip!<member>` or `<archive>.tar!<member>` location into the location of
+/// the archive and the path of the members within it.
+pub fn split_archive(location: &str) -> Option<(&str, &str)> {
+    if location.contains(".tar.gz!") || location.contains(".tgz!") {
+        panic!(
+            "Compressed tar archives are not supported, as their members cannot be read by range ({})",
+            location
+        );
+    }
+    [".zip!", ".tar!"].iter().find_map(|extension| {
+        let separator = location.find(extension)? + extension.len() - 1;
+        Some((&location[..separator], &location[separator + 1..]))
+    })
+}
+
+/// How the data of a member is stored in the archive.
+#[derive(Debug)]
+enum Storage {
+    /// Tar member, with its data at the offset
+    Tar,
+    /// Uncompressed zip member, with its local header at the offset
+    Stored,
+    /// Deflated zip member, with its local header at the offset
+    Deflated { compressed_size: usize },
+    /// Zip member compressed with another method
+    Unsupported(u16),
+}
+
+/// Data of a member, resolved on first read.
+#[derive(Debug)]
+enum MemberData {
+    /// Offset of the uncompressed data in the archive, read by range
+    Offset(usize),
+    /// Inflated data, held in memory as deflated data cannot be read by range
+    Inflated(Bytes),
+}
+
+#[derive(Debug)]
+struct Member {
+    offset: usize,
+    storage: Storage,
+    size: usize,
+    data: OnceCell<MemberData>,
+}
+
+/// Store reading the members of a zip or tar archive as objects, by range reads of the archive
+/// for uncompressed members. The central directory of zip archives is read from their end, the
+/// headers of tar archives one member after the other.
+#[derive(Debug)]
+pub struct ArchiveStore {
+    inner: Arc<dyn ObjectStore>,
+    archive: ObjectMeta,
+    members: BTreeMap<Path, Member>,
+}
+
+impl ArchiveStore {
+    pub async fn open(inner: Arc<dyn ObjectStore>, location: &Path) -> Self {
+        let archive = inner.head(location).await.unwrap();
+        let members = if location.as_ref().ends_with(".zip") {
+            zip_members(&inner, &archive).await
+        } else {
+            tar_members(&inner, &archive).await
+        };
+        ArchiveStore {
+            inner,
+            archive,
+            members,
+        }
+    }
+
+    /// Returns whether the path is a member, or a directory of members.
+    pub fn contains(&self, path: &Path) -> bool {
+        self.members
+            .keys()
+            .any(|member| member.prefix_matches(path))
+    }
+
+    fn member(&self, location: &Path) -> Result<&Member> {
+        self.members
+            .get(location)
+            .ok_or_else(|| object_store::Error::NotFound {
+                path: location.to_string(),
+                source: format!("Not a member of {}", self.archive.location).into(),
+            })
+    }
+
+    fn meta(&self, location: &Path, member: &Member) -> ObjectMeta {
+        ObjectMeta {
+            location: location.clone(),
+            last_modified: self.archive.last_modified,
+            size: member.size,
+            e_tag: None,
+            version: None,
+        }
+    }
+
+    async fn read(&self, range: Range<usize>) -> Result<Bytes> {
+        self.inner.get_range(&self.archive.location, range).await
+    }
+
+    async fn data<'a>(&self, location: &Path, member: &'a Member) -> Result<&'a MemberData> {
+        member
+            .data
+            .get_or_try_init(|| async {
+                let start = match member.storage {
+                    Storage::Tar => return Ok(MemberData::Offset(member.offset)),
+                    Storage::Unsupported(method) => {
+                        panic!(
+                            "Unsupported zip compression method ({}) of {}",
+                            method, location
+                        )
+                    }
+                    Storage::Stored | Storage::Deflated { .. } => {
+                        let header = self.read(member.offset..member.offset + 30).await?;
+                        member.offset + 30 + u16_at(&header, 26) + u16_at(&header, 28)
+                    }
+                };
+                match member.storage {
+                    Storage::Deflated { compressed_size } => {
+                        let deflated = self.read(start..start + compressed_size).await?;
+                        let mut inflated = Vec::with_capacity(member.size);
+                        DeflateDecoder::new(deflated.as_ref())
+                            .read_to_end(&mut inflated)
+                            .unwrap_or_else(|error| {
+                                panic!("Invalid deflated data of {} ({})", location, error)
+                            });
+                        Ok(MemberData::Inflated(inflated.into()))
+                    }
+                    _ => Ok(MemberData::Offset(start)),
+                }
+            })
+            .await
+    }
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> usize {
+    u16::from_le_bytes(bytes[offset..offset + 2].try_into().unwrap()) as usize
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> usize {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> usize {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap()) as usize
+}
+
+/// Reads the members of a zip archive from its central directory.
+async fn zip_members(inner: &Arc<dyn ObjectStore>, archive: &ObjectMeta) -> BTreeMap<Path, Member> {
+    let location = &archive.location;
+    let tail_start = archive.size.saturating_sub(MAX_END_OF_CENTRAL_DIRECTORY);
+    let tail = inner
+        .get_range(location, tail_start..archive.size)
+        .await
+        .unwrap();
+    let Some(end) = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|position| tail[*position..].starts_with(b"PK\x05\x06"))
+    else {
+        panic!("Invalid zip archive ({})", location)
+    };
+    let mut directory_size = u32_at(&tail, end + 12);
+    let mut directory_offset = u32_at(&tail, end + 16);
+    // Archives beyond 4 GiB have a Zip64 end of central directory record, located before the end
+    if directory_offset == 0xFFFFFFFF || directory_size == 0xFFFFFFFF {
+        let Some(locator) = end
+            .checked_sub(20)
+            .filter(|locator| tail[*locator..].starts_with(b"PK\x06\x07"))
+        else {
+            panic!("Invalid zip archive ({})", location)
+        };
+        let record_offset = u64_at(&tail, locator + 8);
+        let record = inner
+            .get_range(location, record_offset..record_offset + 56)
+            .await
+            .unwrap();
+        directory_size = u64_at(&record, 40);
+        directory_offset = u64_at(&record, 48);
+    }
+    let directory = inner
+        .get_range(
+            location,
+            directory_offset..directory_offset + directory_size,
+        )
+        .await
+        .unwrap();
+
+    let mut members = BTreeMap::new();
+    let mut position = 0;
+    while position + 46 <= directory.len() && directory[position..].starts_with(b"PK\x01\x02") {
+        let method = u16_at(&directory, position + 10) as u16;
+        let mut compressed_size = u32_at(&directory, position + 20);
+        let mut size = u32_at(&directory, position + 24);
+        let name_length = u16_at(&directory, position + 28);
+        let extra_length = u16_at(&directory, position + 30);
+        let comment_length = u16_at(&directory, position + 32);
+        let mut offset = u32_at(&directory, position + 42);
+        let name_start = position + 46;
+        let name = String::from_utf8_lossy(&directory[name_start..name_start + name_length]);
+
+        // Sizes and offsets beyond 4 GiB are in the Zip64 extra field, in this order
+        let extra_start = name_start + name_length;
+        let mut extra = &directory[extra_start..extra_start + extra_length];
+        while extra.len() >= 4 {
+            let length = u16_at(extra, 2).min(extra.len() - 4);
+            if u16_at(extra, 0) == 1 {
+                let mut values = extra[4..4 + length].chunks_exact(8);
+                for field in [&mut size, &mut compressed_size, &mut offset] {
+                    if *field == 0xFFFFFFFF {
+                        if let Some(value) = values.next() {
+                            *field = u64_at(value, 0);
+                        }
+                    }
+                }
+            }
+            extra = &extra[4 + length..];
+        }
+        position = extra_start + extra_length + comment_length;
+
+        if name.ends_with('/') {
+            continue;
+        }
+        let storage = match method {
+            0 => Storage::Stored,
+            8 => Storage::Deflated { compressed_size },
+            _ => Storage::Unsupported(method),
+        };
+        members.insert(
+            Path::from(name.as_ref()),
+            Member {
+                offset,
+                storage,
+                size,
+                data: OnceCell::new(),
+            },
+        );
+    }
+    members
+}
+
+/// Returns the string of a NUL-terminated tar header field.
+fn tar_string(field: &[u8]) -> String {
+    let end = field
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// Returns the size of a tar header, in octal or, beyond 8 GiB, in base-256.
+fn tar_size(field: &[u8]) -> usize {
+    if field[0] & 0x80 != 0 {
+        let bytes = field[1..].iter();
+        return bytes.fold(0, |size, byte| (size << 8) | *byte as usize);
+    }
+    let octal = tar_string(field);
+    usize::from_str_radix(octal.trim(), 8).unwrap_or(0)
+}
+
+/// Reads the members of a tar archive from their headers.
+async fn tar_members(inner: &Arc<dyn ObjectStore>, archive: &ObjectMeta) -> BTreeMap<Path, Member> {
+    let location = &archive.location;
+    let mut members = BTreeMap::new();
+    // Names too long for the header are given by a GNU or PAX header before it
+    let mut long_name: Option<String> = None;
+    let mut offset = 0;
+    while offset + 512 <= archive.size {
+        let header = inner
+            .get_range(location, offset..offset + 512)
+            .await
+            .unwrap();
+        if header.iter().all(|byte| *byte == 0) {
+            break;
+        }
+        let size = tar_size(&header[124..136]);
+        let data = offset + 512;
+        match header[156] {
+            b'L' => {
+                let name = inner.get_range(location, data..data + size).await.unwrap();
+                long_name = Some(tar_string(&name));
+            }
+            b'x' => {
+                let records = inner.get_range(location, data..data + size).await.unwrap();
+                long_name = String::from_utf8_lossy(&records)
+                    .lines()
+                    .find_map(|record| {
+                        let (_, key_value) = record.split_once(' ')?;
+                        key_value.strip_prefix("path=").map(String::from)
+                    });
+            }
+            b'0' | b'\0' | b'7' => {
+                let name = long_name.take().unwrap_or_else(|| {
+                    let name = tar_string(&header[0..100]);
+                    if &header[257..262] == b"ustar" && header[345] != 0 {
+                        format!("{}/{}", tar_string(&header[345..500]), name)
+                    } else {
+                        name
+                    }
+                });
+                members.insert(
+                    Path::from(name.trim_start_matches("./")),
+                    Member {
+                        offset: data,
+                        storage: Storage::Tar,
+                        size,
+                        data: OnceCell::new(),
+                    },
+                );
+            }
+            _ => long_name = None,
+        }
+        offset = data + size.div_ceil(512) * 512;
+    }
+    members
+}
+
+impl Display for ArchiveStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Archive({}, {})", self.inner, self.archive.location)
+    }
+}
+
+#[async_trait::async_trait]
+impl ObjectStore for ArchiveStore {
+    async fn put_opts(
+        &self,
+        _location: &Path,
+        _payload: PutPayload,
+        _opts: PutOptions,
+    ) -> Result<PutResult> {
+        Err(object_store::Error::NotImplemented)
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        _location: &Path,
+        _opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        Err(object_store::Error::NotImplemented)
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let member = self.member(location)?;
+        let meta = self.meta(location, member);
+        let range = resolve_range(options.range.as_ref(), member.size);
+        let bytes = if options.head {
+            Bytes::new()
+        } else {
+            self.get_range(location, range.clone()).await?
+        };
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(stream::once(async { Ok(bytes) }).boxed()),
+            meta,
+            range,
+            attributes: Default::default(),
+        })
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        let member = self.member(location)?;
+        let range = range.start.min(member.size)..range.end.min(member.size);
+        match self.data(location, member).await? {
+            MemberData::Offset(start) => self.read(start + range.start..start + range.end).await,
+            MemberData::Inflated(bytes) => Ok(bytes.slice(range)),
+        }
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        Ok(self.meta(location, self.member(location)?))
+    }
+
+    async fn delete(&self, _location: &Path) -> Result<()> {
+        Err(object_store::Error::NotImplemented)
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        let metas: Vec<Result<ObjectMeta>> = self
+            .members
+            .iter()
+            .filter(|(path, _)| prefix.map_or(true, |prefix| path.prefix_matches(prefix)))
+            .map(|(path, member)| Ok(self.meta(path, member)))
+            .collect();
+        stream::iter(metas).boxed()
+    }
+
+    async fn list_with_delimiter(&self, _prefix: Option<&Path>) -> Result<ListResult> {
+        Err(object_store::Error::NotImplemented)
+    }
+
+    async fn copy(&self, _from: &Path, _to: &Path) -> Result<()> {
+        Err(object_store::Error::NotImplemented)
+    }
+
+    async fn copy_if_not_exists(&self, _from: &Path, _to: &Path) -> Result<()> {
+        Err(object_store::Error::NotImplemented)
+    }
+}
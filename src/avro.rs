@@ -0,0 +1,658 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::Arc;
+
+use arrow_array::{
+    ArrayRef, BinaryArray, BooleanArray, Decimal128Array, FixedSizeBinaryArray, Float32Array,
+    Float64Array, Int32Array, Int64Array, ListArray, MapArray, NullArray, RecordBatch, StringArray,
+    StructArray,
+};
+use arrow_buffer::{NullBuffer, OffsetBuffer};
+use arrow_cast::cast;
+use arrow_schema::{DataType, Field, Fields, Schema, TimeUnit};
+use bytes::Bytes;
+use serde_json::{Map, Value};
+
+use crate::output::BATCH_SIZE;
+
+/// Avro type, with logical types resolved to the Arrow types they map to.
+#[derive(Clone)]
+enum AvroType {
+    Null,
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    Bytes,
+    String,
+    Record(Vec<(String, AvroType)>),
+    Enum(Vec<String>),
+    Array(Box<AvroType>),
+    Map(Box<AvroType>),
+    Union(Vec<AvroType>),
+    Fixed(usize),
+    /// int days since the epoch
+    Date,
+    /// int milliseconds or long microseconds since midnight
+    Time(TimeUnit),
+    /// long since the epoch, in UTC or local time
+    Timestamp(TimeUnit, bool),
+    /// bytes, or fixed of a size, holding a two's complement unscaled value
+    Decimal(u8, i8, Option<usize>),
+}
+
+/// Value decoded from Avro binary encoding, with enum symbols as strings.
+enum Datum {
+    Null,
+    Boolean(bool),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Bytes(Vec<u8>),
+    String(String),
+    Record(Vec<Datum>),
+    Array(Vec<Datum>),
+    Map(Vec<(String, Datum)>),
+}
+
+static NULL: Datum = Datum::Null;
+
+/// Parses an Avro schema, resolving references to named types defined before them.
+fn parse_type(
+    schema: &Value,
+    namespace: Option<&str>,
+    named: &mut HashMap<String, AvroType>,
+) -> AvroType {
+    let object = match schema {
+        Value::String(name) => {
+            return match name.as_str() {
+                "null" => AvroType::Null,
+                "boolean" => AvroType::Boolean,
+                "int" => AvroType::Int,
+                "long" => AvroType::Long,
+                "float" => AvroType::Float,
+                "double" => AvroType::Double,
+                "bytes" => AvroType::Bytes,
+                "string" => AvroType::String,
+                name => named
+                    .get(&full_name(name, namespace))
+                    .or_else(|| named.get(name))
+                    .cloned()
+                    .unwrap_or_else(|| panic!("Unsupported Avro type ({})", name)),
+            };
+        }
+        Value::Array(types) => {
+            return AvroType::Union(
+                types
+                    .iter()
+                    .map(|schema| parse_type(schema, namespace, named))
+                    .collect(),
+            )
+        }
+        Value::Object(object) => object,
+        schema => panic!("Invalid Avro schema ({})", schema),
+    };
+    let string = |key: &str| object.get(key).and_then(Value::as_str);
+    let logical_type = string("logicalType");
+    let namespace = string("namespace").or(namespace);
+    let name = string("name").map(|name| full_name(name, namespace));
+    let avro_type = match (string("type"), logical_type) {
+        (Some("int"), Some("date")) => AvroType::Date,
+        (Some("int"), Some("time-millis")) => AvroType::Time(TimeUnit::Millisecond),
+        (Some("long"), Some("time-micros")) => AvroType::Time(TimeUnit::Microsecond),
+        (Some("long"), Some("timestamp-millis")) => {
+            AvroType::Timestamp(TimeUnit::Millisecond, true)
+        }
+        (Some("long"), Some("timestamp-micros")) => {
+            AvroType::Timestamp(TimeUnit::Microsecond, true)
+        }
+        (Some("long"), Some("timestamp-nanos")) => AvroType::Timestamp(TimeUnit::Nanosecond, true),
+        (Some("long"), Some("local-timestamp-millis")) => {
+            AvroType::Timestamp(TimeUnit::Millisecond, false)
+        }
+        (Some("long"), Some("local-timestamp-micros")) => {
+            AvroType::Timestamp(TimeUnit::Microsecond, false)
+        }
+        (Some("long"), Some("local-timestamp-nanos")) => {
+            AvroType::Timestamp(TimeUnit::Nanosecond, false)
+        }
+        (Some(physical @ ("bytes" | "fixed")), Some("decimal")) => {
+            let precision = object.get("precision").and_then(Value::as_u64).unwrap_or(0);
+            let scale = object.get("scale").and_then(Value::as_u64).unwrap_or(0);
+            let size = object.get("size").and_then(Value::as_u64);
+            match (precision, physical) {
+                // Decimals wider than Arrow's 128-bit ones are kept as their bytes
+                (1..=38, _) => {
+                    AvroType::Decimal(precision as u8, scale as i8, size.map(|size| size as usize))
+                }
+                (_, "fixed") => AvroType::Fixed(size.unwrap_or(0) as usize),
+                _ => AvroType::Bytes,
+            }
+        }
+        (Some("record" | "error"), _) => {
+            let fields = object
+                .get("fields")
+                .and_then(Value::as_array)
+                .unwrap_or_else(|| panic!("Invalid Avro record ({})", schema));
+            AvroType::Record(
+                fields
+                    .iter()
+                    .map(|field| {
+                        let name = field
+                            .get("name")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default();
+                        let field_type = field.get("type").unwrap_or(&Value::Null);
+                        (name.to_string(), parse_type(field_type, namespace, named))
+                    })
+                    .collect(),
+            )
+        }
+        (Some("enum"), _) => AvroType::Enum(
+            object
+                .get("symbols")
+                .and_then(Value::as_array)
+                .map(|symbols| {
+                    symbols
+                        .iter()
+                        .filter_map(|symbol| symbol.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        ),
+        (Some("array"), _) => AvroType::Array(Box::new(parse_type(
+            object.get("items").unwrap_or(&Value::Null),
+            namespace,
+            named,
+        ))),
+        (Some("map"), _) => AvroType::Map(Box::new(parse_type(
+            object.get("values").unwrap_or(&Value::Null),
+            namespace,
+            named,
+        ))),
+        (Some("fixed"), _) => {
+            AvroType::Fixed(object.get("size").and_then(Value::as_u64).unwrap_or(0) as usize)
+        }
+        // Unknown logical types are read as their underlying type
+        _ => parse_type(object.get("type").unwrap_or(&Value::Null), namespace, named),
+    };
+    if let Some(name) = name {
+        named.insert(name, avro_type.clone());
+    }
+    avro_type
+}
+
+fn full_name(name: &str, namespace: Option<&str>) -> String {
+    match namespace {
+        Some(namespace) if !name.contains('.') && !namespace.is_empty() => {
+            format!("{}.{}", namespace, name)
+        }
+        _ => name.to_string(),
+    }
+}
+
+/// Returns the type of the values of a union of null and a single type, or of another type.
+fn non_null(avro_type: &AvroType) -> &AvroType {
+    match avro_type {
+        AvroType::Union(types) => {
+            let mut non_null = types
+                .iter()
+                .filter(|avro_type| !matches!(avro_type, AvroType::Null));
+            match (non_null.next(), non_null.next()) {
+                (Some(avro_type), None) => avro_type,
+                (None, _) => &AvroType::Null,
+                _ => avro_type,
+            }
+        }
+        avro_type => avro_type,
+    }
+}
+
+fn is_nullable(avro_type: &AvroType) -> bool {
+    match avro_type {
+        AvroType::Null => true,
+        AvroType::Union(types) => types
+            .iter()
+            .any(|avro_type| matches!(avro_type, AvroType::Null)),
+        _ => false,
+    }
+}
+
+fn field(name: &str, avro_type: &AvroType) -> Field {
+    Field::new(name, data_type(avro_type), is_nullable(avro_type))
+}
+
+fn record_fields(fields: &[(String, AvroType)]) -> Fields {
+    fields
+        .iter()
+        .map(|(name, avro_type)| field(name, avro_type))
+        .collect()
+}
+
+fn map_entries(values: &AvroType) -> Field {
+    let entries = Fields::from(vec![
+        Field::new("key", DataType::Utf8, false),
+        field("value", values),
+    ]);
+    Field::new("entries", DataType::Struct(entries), false)
+}
+
+/// Returns the Arrow type of an Avro type. Unions of several types other than null, which
+/// Parquet has no equivalent for, are read as JSON strings.
+fn data_type(avro_type: &AvroType) -> DataType {
+    match non_null(avro_type) {
+        AvroType::Null => DataType::Null,
+        AvroType::Boolean => DataType::Boolean,
+        AvroType::Int => DataType::Int32,
+        AvroType::Long => DataType::Int64,
+        AvroType::Float => DataType::Float32,
+        AvroType::Double => DataType::Float64,
+        AvroType::Bytes => DataType::Binary,
+        AvroType::String | AvroType::Enum(_) | AvroType::Union(_) => DataType::Utf8,
+        AvroType::Record(fields) => DataType::Struct(record_fields(fields)),
+        AvroType::Array(items) => DataType::List(Arc::new(field("item", items))),
+        AvroType::Map(values) => DataType::Map(Arc::new(map_entries(values)), false),
+        AvroType::Fixed(size) => DataType::FixedSizeBinary(*size as i32),
+        AvroType::Date => DataType::Date32,
+        AvroType::Time(TimeUnit::Millisecond) => DataType::Time32(TimeUnit::Millisecond),
+        AvroType::Time(unit) => DataType::Time64(*unit),
+        AvroType::Timestamp(unit, true) => DataType::Timestamp(*unit, Some("UTC".into())),
+        AvroType::Timestamp(unit, false) => DataType::Timestamp(*unit, None),
+        AvroType::Decimal(precision, scale, _) => DataType::Decimal128(*precision, *scale),
+    }
+}
+
+/// Decoder of Avro binary encoding over the rows of a block.
+struct Decoder<'a> {
+    data: &'a [u8],
+    location: &'a str,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> &'a [u8] {
+        if len > self.data.len() {
+            panic!(
+                "Invalid Avro file ({}: unexpected end of block)",
+                self.location
+            );
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        head
+    }
+
+    fn long(&mut self) -> i64 {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)[0];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return (value >> 1) as i64 ^ -((value & 1) as i64);
+            }
+        }
+        panic!("Invalid Avro file ({}: overlong integer)", self.location)
+    }
+
+    fn bytes(&mut self) -> &'a [u8] {
+        let len = self.long();
+        self.take(len.max(0) as usize)
+    }
+
+    /// Reads the blocks of items of an array or map, each prefixed with its count of items.
+    fn blocks(&mut self, mut item: impl FnMut(&mut Self)) {
+        loop {
+            let count = self.long();
+            if count == 0 {
+                break;
+            }
+            if count < 0 {
+                // A negative count is followed by the size of the block in bytes
+                self.long();
+            }
+            for _ in 0..count.unsigned_abs() {
+                item(self);
+            }
+        }
+    }
+
+    fn datum(&mut self, avro_type: &AvroType) -> Datum {
+        match avro_type {
+            AvroType::Null => Datum::Null,
+            AvroType::Boolean => Datum::Boolean(self.take(1)[0] != 0),
+            AvroType::Int | AvroType::Date | AvroType::Time(TimeUnit::Millisecond) => {
+                Datum::Int(self.long() as i32)
+            }
+            AvroType::Long | AvroType::Time(_) | AvroType::Timestamp(_, _) => {
+                Datum::Long(self.long())
+            }
+            AvroType::Float => Datum::Float(f32::from_le_bytes(self.take(4).try_into().unwrap())),
+            AvroType::Double => Datum::Double(f64::from_le_bytes(self.take(8).try_into().unwrap())),
+            AvroType::Bytes | AvroType::Decimal(_, _, None) => Datum::Bytes(self.bytes().to_vec()),
+            AvroType::Fixed(size) | AvroType::Decimal(_, _, Some(size)) => {
+                Datum::Bytes(self.take(*size).to_vec())
+            }
+            AvroType::String => Datum::String(String::from_utf8_lossy(self.bytes()).into_owned()),
+            AvroType::Record(fields) => Datum::Record(
+                fields
+                    .iter()
+                    .map(|(_, avro_type)| self.datum(avro_type))
+                    .collect(),
+            ),
+            AvroType::Enum(symbols) => {
+                let index = self.long();
+                let symbol = symbols.get(index as usize).unwrap_or_else(|| {
+                    panic!(
+                        "Invalid Avro file ({}: enum index {})",
+                        self.location, index
+                    )
+                });
+                Datum::String(symbol.clone())
+            }
+            AvroType::Array(items) => {
+                let mut values = vec![];
+                self.blocks(|decoder| values.push(decoder.datum(items)));
+                Datum::Array(values)
+            }
+            AvroType::Map(values) => {
+                let mut entries = vec![];
+                self.blocks(|decoder| {
+                    let key = String::from_utf8_lossy(decoder.bytes()).into_owned();
+                    entries.push((key, decoder.datum(values)));
+                });
+                Datum::Map(entries)
+            }
+            AvroType::Union(types) => {
+                let index = self.long();
+                let branch = types.get(index as usize).unwrap_or_else(|| {
+                    panic!(
+                        "Invalid Avro file ({}: union index {})",
+                        self.location, index
+                    )
+                });
+                let datum = self.datum(branch);
+                match (non_null(avro_type), &datum) {
+                    (AvroType::Union(_), Datum::Null) => Datum::Null,
+                    (AvroType::Union(_), _) => Datum::String(json(branch, &datum).to_string()),
+                    _ => datum,
+                }
+            }
+        }
+    }
+}
+
+/// Returns a value of a union of several types as JSON.
+fn json(avro_type: &AvroType, datum: &Datum) -> Value {
+    match (non_null(avro_type), datum) {
+        (_, Datum::Null) => Value::Null,
+        (_, Datum::Boolean(value)) => Value::from(*value),
+        (_, Datum::Int(value)) => Value::from(*value),
+        (_, Datum::Long(value)) => Value::from(*value),
+        (_, Datum::Float(value)) => Value::from(*value),
+        (_, Datum::Double(value)) => Value::from(*value),
+        (_, Datum::Bytes(value)) => Value::from(String::from_utf8_lossy(value)),
+        (_, Datum::String(value)) => Value::from(value.as_str()),
+        (AvroType::Record(fields), Datum::Record(values)) => Value::Object(
+            fields
+                .iter()
+                .zip(values)
+                .map(|((name, avro_type), datum)| (name.clone(), json(avro_type, datum)))
+                .collect(),
+        ),
+        (AvroType::Array(items), Datum::Array(values)) => {
+            Value::Array(values.iter().map(|datum| json(items, datum)).collect())
+        }
+        (AvroType::Map(values), Datum::Map(entries)) => Value::Object(
+            entries
+                .iter()
+                .map(|(key, datum)| (key.clone(), json(values, datum)))
+                .collect::<Map<String, Value>>(),
+        ),
+        _ => Value::Null,
+    }
+}
+
+/// Returns the unscaled value of a decimal from its big-endian two's complement bytes.
+fn decimal(bytes: &[u8]) -> i128 {
+    let sign = if bytes.first().is_some_and(|byte| byte & 0x80 != 0) {
+        0xff
+    } else {
+        0
+    };
+    let mut buffer = [sign; 16];
+    let bytes = &bytes[bytes.len().saturating_sub(16)..];
+    buffer[16 - bytes.len()..].copy_from_slice(bytes);
+    i128::from_be_bytes(buffer)
+}
+
+/// Builds the Arrow array of the values of an Avro type, with nulls for values of null
+/// records and of empty union branches.
+fn array(avro_type: &AvroType, values: &[&Datum]) -> ArrayRef {
+    let nulls = NullBuffer::from_iter(values.iter().map(|datum| !matches!(datum, Datum::Null)));
+    let nulls = Some(nulls).filter(|nulls| nulls.null_count() > 0);
+    let array: ArrayRef = match non_null(avro_type) {
+        AvroType::Null => Arc::new(NullArray::new(values.len())),
+        AvroType::Boolean => Arc::new(BooleanArray::from_iter(values.iter().map(
+            |datum| match datum {
+                Datum::Boolean(value) => Some(*value),
+                _ => None,
+            },
+        ))),
+        AvroType::Int | AvroType::Date | AvroType::Time(TimeUnit::Millisecond) => Arc::new(
+            Int32Array::from_iter(values.iter().map(|datum| match datum {
+                Datum::Int(value) => Some(*value),
+                _ => None,
+            })),
+        ),
+        AvroType::Long | AvroType::Time(_) | AvroType::Timestamp(_, _) => Arc::new(
+            Int64Array::from_iter(values.iter().map(|datum| match datum {
+                Datum::Long(value) => Some(*value),
+                _ => None,
+            })),
+        ),
+        AvroType::Float => Arc::new(Float32Array::from_iter(values.iter().map(
+            |datum| match datum {
+                Datum::Float(value) => Some(*value),
+                _ => None,
+            },
+        ))),
+        AvroType::Double => Arc::new(Float64Array::from_iter(values.iter().map(
+            |datum| match datum {
+                Datum::Double(value) => Some(*value),
+                _ => None,
+            },
+        ))),
+        AvroType::Bytes => Arc::new(BinaryArray::from_iter(values.iter().map(
+            |datum| match datum {
+                Datum::Bytes(value) => Some(value.as_slice()),
+                _ => None,
+            },
+        ))),
+        AvroType::Fixed(size) => Arc::new(
+            FixedSizeBinaryArray::try_from_sparse_iter_with_size(
+                values.iter().map(|datum| match datum {
+                    Datum::Bytes(value) => Some(value.as_slice()),
+                    _ => None,
+                }),
+                *size as i32,
+            )
+            .unwrap(),
+        ),
+        AvroType::Decimal(precision, scale, _) => Arc::new(
+            Decimal128Array::from_iter(values.iter().map(|datum| match datum {
+                Datum::Bytes(value) => Some(decimal(value)),
+                _ => None,
+            }))
+            .with_precision_and_scale(*precision, *scale)
+            .unwrap(),
+        ),
+        AvroType::String | AvroType::Enum(_) | AvroType::Union(_) => Arc::new(
+            StringArray::from_iter(values.iter().map(|datum| match datum {
+                Datum::String(value) => Some(value.as_str()),
+                _ => None,
+            })),
+        ),
+        AvroType::Record(fields) => {
+            let columns = fields
+                .iter()
+                .enumerate()
+                .map(|(index, (_, avro_type))| {
+                    let column: Vec<&Datum> = values
+                        .iter()
+                        .map(|datum| match datum {
+                            Datum::Record(record) => &record[index],
+                            _ => &NULL,
+                        })
+                        .collect();
+                    array(avro_type, &column)
+                })
+                .collect();
+            Arc::new(StructArray::new(record_fields(fields), columns, nulls))
+        }
+        AvroType::Array(items) => {
+            let lengths = values.iter().map(|datum| match datum {
+                Datum::Array(items) => items.len(),
+                _ => 0,
+            });
+            let offsets = OffsetBuffer::from_lengths(lengths);
+            let items_values: Vec<&Datum> = values
+                .iter()
+                .flat_map(|datum| match datum {
+                    Datum::Array(items) => items.iter().collect(),
+                    _ => vec![],
+                })
+                .collect();
+            Arc::new(ListArray::new(
+                Arc::new(field("item", items)),
+                offsets,
+                array(items, &items_values),
+                nulls,
+            ))
+        }
+        AvroType::Map(map_values) => {
+            let entries: Vec<&(String, Datum)> = values
+                .iter()
+                .flat_map(|datum| match datum {
+                    Datum::Map(entries) => entries.iter().collect(),
+                    _ => vec![],
+                })
+                .collect();
+            let offsets = OffsetBuffer::from_lengths(values.iter().map(|datum| match datum {
+                Datum::Map(entries) => entries.len(),
+                _ => 0,
+            }));
+            let keys = StringArray::from_iter_values(entries.iter().map(|(key, _)| key));
+            let entry_values: Vec<&Datum> = entries.iter().map(|(_, datum)| datum).collect();
+            let entries_field = map_entries(map_values);
+            let DataType::Struct(entries_fields) = entries_field.data_type() else {
+                unreachable!()
+            };
+            let entries = StructArray::new(
+                entries_fields.clone(),
+                vec![Arc::new(keys), array(map_values, &entry_values)],
+                None,
+            );
+            Arc::new(MapArray::new(
+                Arc::new(entries_field),
+                offsets,
+                entries,
+                nulls,
+                false,
+            ))
+        }
+    };
+    // Logical types are built from their physical values, then cast
+    let data_type = data_type(avro_type);
+    if array.data_type() == &data_type {
+        array
+    } else {
+        cast(&array, &data_type).unwrap()
+    }
+}
+
+/// Reads the header of an Avro object container file, returning its metadata and sync marker.
+fn header(decoder: &mut Decoder) -> (HashMap<String, Vec<u8>>, [u8; 16]) {
+    if decoder.data.get(..4) != Some(b"Obj\x01") {
+        panic!("Invalid Avro file ({}: no magic bytes)", decoder.location);
+    }
+    decoder.take(4);
+    let mut metadata = HashMap::new();
+    decoder.blocks(|decoder| {
+        let key = String::from_utf8_lossy(decoder.bytes()).into_owned();
+        metadata.insert(key, decoder.bytes().to_vec());
+    });
+    let marker = decoder.take(16).try_into().unwrap();
+    (metadata, marker)
+}
+
+fn decompress(codec: &str, data: &[u8], location: &str) -> Vec<u8> {
+    let decompressed = match codec {
+        "null" => Ok(data.to_vec()),
+        "deflate" => {
+            let mut decompressed = vec![];
+            flate2::read::DeflateDecoder::new(data)
+                .read_to_end(&mut decompressed)
+                .map(|_| decompressed)
+        }
+        // The compressed data is followed by a CRC32 checksum of the uncompressed data
+        "snappy" => snap::raw::Decoder::new()
+            .decompress_vec(&data[..data.len().saturating_sub(4)])
+            .map_err(std::io::Error::from),
+        "zstandard" => zstd::decode_all(data),
+        codec => panic!("Unsupported Avro codec ({}: {})", location, codec),
+    };
+    decompressed.unwrap_or_else(|error| panic!("Invalid Avro file ({}: {})", location, error))
+}
+
+/// Decodes an Avro object container file into batches of rows of its record schema.
+pub fn avro_batches(bytes: &Bytes, location: &str) -> (Schema, Vec<RecordBatch>) {
+    let mut decoder = Decoder {
+        data: bytes.as_ref(),
+        location,
+    };
+    let (metadata, marker) = header(&mut decoder);
+    let schema: Value = metadata
+        .get("avro.schema")
+        .and_then(|schema| serde_json::from_slice(schema).ok())
+        .unwrap_or_else(|| panic!("Invalid Avro file ({}: no schema)", location));
+    let avro_type = parse_type(&schema, None, &mut HashMap::new());
+    let AvroType::Record(fields) = &avro_type else {
+        panic!(
+            "Unsupported Avro file ({}: schema is not a record)",
+            location
+        );
+    };
+    let codec = metadata
+        .get("avro.codec")
+        .map(|codec| String::from_utf8_lossy(codec).into_owned())
+        .unwrap_or("null".to_string());
+
+    let mut rows = vec![];
+    while !decoder.data.is_empty() {
+        let count = decoder.long();
+        let data = decoder.bytes();
+        if decoder.take(16) != marker {
+            panic!("Invalid Avro file ({}: sync marker mismatch)", location);
+        }
+        let data = decompress(&codec, data, location);
+        let mut block = Decoder {
+            data: &data,
+            location,
+        };
+        for _ in 0..count {
+            rows.push(block.datum(&avro_type));
+        }
+    }
+
+    let schema = Schema::new(record_fields(fields));
+    let batches = rows
+        .chunks(BATCH_SIZE)
+        .map(|chunk| {
+            let chunk: Vec<&Datum> = chunk.iter().collect();
+            let array = array(&avro_type, &chunk);
+            RecordBatch::from(array.as_any().downcast_ref::<StructArray>().unwrap())
+        })
+        .collect();
+    (schema, batches)
+}
@@ -0,0 +1,72 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+
+use crate::filter::{Comparison, Op};
+
+fn parse_local(value: &str) -> NaiveDateTime {
+    [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .or_else(|| {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .ok()
+            .map(|date| date.and_hms_opt(0, 0, 0).unwrap())
+    })
+    .unwrap_or_else(|| panic!("Invalid date or time ({})", value))
+}
+
+/// Returns the UTC instant of a wall-clock time, using the earlier instant when it occurs twice
+/// and the end of the gap when it is skipped by a DST transition.
+fn to_utc(local: NaiveDateTime, tz: Tz) -> String {
+    let mut candidate = local;
+    loop {
+        if let Some(instant) = tz.from_local_datetime(&candidate).earliest() {
+            return instant.naive_utc().and_utc().to_rfc3339();
+        }
+        candidate += Duration::minutes(1);
+        if candidate - local > Duration::days(1) {
+            panic!("Invalid local time in {} ({})", tz, local);
+        }
+    }
+}
+
+fn bound(value: &str, tz: Tz) -> String {
+    match DateTime::parse_from_rfc3339(value) {
+        // Values with an explicit offset are not in the given time zone
+        Ok(instant) => instant.naive_utc().and_utc().to_rfc3339(),
+        Err(_) => to_utc(parse_local(value), tz),
+    }
+}
+
+/// Returns comparisons selecting values from the start (inclusive) to the end (exclusive) of a
+/// `start..end` range of local dates or times in the time zone, either of which may be omitted.
+pub fn between(column: &str, range: &str, tz: &str) -> Vec<Comparison> {
+    let tz: Tz = tz
+        .parse()
+        .unwrap_or_else(|_| panic!("Invalid time zone ({})", tz));
+    let (start, end) = range
+        .split_once("..")
+        .unwrap_or_else(|| panic!("Invalid range, expected start..end ({})", range));
+
+    let mut comparisons = vec![];
+    if !start.is_empty() {
+        comparisons.push(Comparison {
+            column: column.to_string(),
+            op: Op::GtEq,
+            value: bound(start, tz),
+        });
+    }
+    if !end.is_empty() {
+        comparisons.push(Comparison {
+            column: column.to_string(),
+            op: Op::Lt,
+            value: bound(end, tz),
+        });
+    }
+    comparisons
+}
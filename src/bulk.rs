@@ -0,0 +1,110 @@
+use std::io::Write;
+
+use arrow_array::RecordBatch;
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_json::writer::LineDelimited;
+use arrow_json::WriterBuilder;
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+
+use crate::encoder::{Encoder, EncoderKind, FormatOpts, Serialize};
+use crate::{CatArgs, OutputFormat};
+
+/// Action of es-bulk action lines
+#[derive(ValueEnum, Clone, Copy, Default)]
+pub enum BulkAction {
+    /// Adds or replaces documents
+    #[default]
+    Index,
+    /// Adds documents, failing for those with an existing ID, as data streams require
+    Create,
+}
+
+/// Writes rows as Elasticsearch/OpenSearch `_bulk` request lines, each preceded by its action.
+#[derive(Clone)]
+pub struct BulkWriter {
+    pub action: BulkAction,
+    pub index: Option<String>,
+    pub id_column: Option<String>,
+    pub explicit_nulls: bool,
+}
+
+impl BulkWriter {
+    fn action(&self, id: Option<String>) -> String {
+        let mut metadata = Map::new();
+        if let Some(index) = &self.index {
+            metadata.insert("_index".to_string(), json!(index));
+        }
+        if let Some(id) = id {
+            metadata.insert("_id".to_string(), json!(id));
+        }
+        let action = match self.action {
+            BulkAction::Index => "index",
+            BulkAction::Create => "create",
+        };
+        json!({ action: Value::Object(metadata) }).to_string()
+    }
+
+    pub fn write_batch(&self, out: &mut impl Write, batch: &RecordBatch) {
+        let mut writer = WriterBuilder::new()
+            .with_explicit_nulls(self.explicit_nulls)
+            .build::<_, LineDelimited>(vec![]);
+        writer.write(batch).unwrap();
+        writer.finish().unwrap();
+        let documents = writer.into_inner();
+
+        let ids = self.id_column.as_ref().map(|id_column| {
+            let column = batch
+                .column_by_name(id_column)
+                .unwrap_or_else(|| panic!("Column not selected ({})", id_column));
+            let formatter =
+                ArrayFormatter::try_new(column.as_ref(), &FormatOptions::default()).unwrap();
+            (0..batch.num_rows())
+                .map(|row| {
+                    column
+                        .is_valid(row)
+                        .then(|| formatter.value(row).to_string())
+                })
+                .collect::<Vec<_>>()
+        });
+
+        for (row, document) in documents.split(|byte| *byte == b'\n').enumerate() {
+            if row >= batch.num_rows() {
+                break;
+            }
+            let id = ids.as_ref().and_then(|ids| ids[row].clone());
+            writeln!(out, "{}", self.action(id)).unwrap();
+            out.write_all(document).unwrap();
+            out.write_all(b"\n").unwrap();
+        }
+    }
+}
+
+/// Elasticsearch `_bulk` request lines, taking action, index and id-column.
+pub struct BulkEncoderKind;
+
+impl EncoderKind for BulkEncoderKind {
+    fn format(&self) -> OutputFormat {
+        OutputFormat::EsBulk
+    }
+
+    fn open(&self, args: &CatArgs, options: &mut FormatOpts) -> Box<dyn Encoder> {
+        Box::new(BulkWriter {
+            action: options.value_enum("action").unwrap_or(args.bulk_action),
+            index: options.string("index").or(args.index.clone()),
+            id_column: options.string("id-column").or(args.id_column.clone()),
+            explicit_nulls: args.nulls,
+        })
+    }
+}
+
+impl Encoder for BulkWriter {
+    fn encode(&mut self, batch: RecordBatch) -> Serialize {
+        let bulk_writer = self.clone();
+        Box::new(move || {
+            let mut lines = vec![];
+            bulk_writer.write_batch(&mut lines, &batch);
+            lines
+        })
+    }
+}
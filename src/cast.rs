@@ -1,8 +1,12 @@
 use std::sync::Arc;
 
-use arrow_array::{types::GenericBinaryType, Array, ArrayRef, GenericByteArray, OffsetSizeTrait};
+use arrow_array::{
+    types::GenericBinaryType, Array, ArrayRef, GenericByteArray, OffsetSizeTrait, RecordBatch,
+};
 use arrow_cast::base64::{b64_encode, BASE64_STANDARD};
-use arrow_schema::ArrowError;
+use arrow_cast::display::FormatOptions;
+use arrow_cast::{cast_with_options, CastOptions};
+use arrow_schema::{ArrowError, DataType, Field, SchemaBuilder};
 
 pub fn cast_binary_to_string<O: OffsetSizeTrait>(
     array: &dyn Array,
@@ -14,3 +18,59 @@ pub fn cast_binary_to_string<O: OffsetSizeTrait>(
 
     Ok(Arc::new(b64_encode(&BASE64_STANDARD, array)))
 }
+
+/// Converts binary columns to base64 and decimal columns to strings, which the JSON writer
+/// cannot output as is.
+pub fn json_compatible(batch: RecordBatch) -> RecordBatch {
+    let schema = batch.schema();
+    if schema.fields.iter().any(|field| {
+        matches!(
+            field.data_type(),
+            DataType::Binary | DataType::Decimal128(_, _) | DataType::Decimal256(_, _)
+        )
+    }) {
+        let mut columns: Vec<Arc<dyn Array>> = vec![];
+        let mut builder = SchemaBuilder::new();
+        schema
+            .fields
+            .iter()
+            .for_each(|field| match field.data_type() {
+                DataType::Binary => {
+                    builder.push(Field::new(
+                        field.name(),
+                        DataType::Utf8,
+                        field.is_nullable(),
+                    ));
+                    let column = batch.column_by_name(field.name()).unwrap();
+                    let new_column = cast_binary_to_string::<i32>(column).unwrap();
+                    columns.push(new_column);
+                }
+                DataType::Decimal128(_, _) | DataType::Decimal256(_, _) => {
+                    builder.push(Field::new(
+                        field.name(),
+                        DataType::Utf8,
+                        field.is_nullable(),
+                    ));
+                    let column = batch.column_by_name(field.name()).unwrap();
+                    let new_column = cast_with_options(
+                        column,
+                        &DataType::Utf8,
+                        &CastOptions {
+                            safe: false,
+                            format_options: FormatOptions::default(),
+                        },
+                    )
+                    .unwrap();
+                    columns.push(new_column);
+                }
+                _ => {
+                    builder.push(field.clone());
+                    columns.push(batch.column_by_name(field.name()).unwrap().clone());
+                }
+            });
+        let schema = builder.finish();
+        RecordBatch::try_new(schema.into(), columns).unwrap()
+    } else {
+        batch
+    }
+}
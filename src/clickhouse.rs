@@ -0,0 +1,93 @@
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+
+fn precision(unit: &TimeUnit) -> u8 {
+    match unit {
+        TimeUnit::Second => 0,
+        TimeUnit::Millisecond => 3,
+        TimeUnit::Microsecond => 6,
+        TimeUnit::Nanosecond => 9,
+    }
+}
+
+fn quote(name: &str) -> String {
+    format!("`{}`", name.replace('\\', "\\\\").replace('`', "\\`"))
+}
+
+/// Returns the ClickHouse type of values as written by `cat --profile clickhouse`.
+fn column_type(data_type: &DataType) -> String {
+    match data_type {
+        DataType::Boolean => "Bool".to_string(),
+        DataType::Int8 => "Int8".to_string(),
+        DataType::Int16 => "Int16".to_string(),
+        DataType::Int32 => "Int32".to_string(),
+        DataType::Int64 => "Int64".to_string(),
+        DataType::UInt8 => "UInt8".to_string(),
+        DataType::UInt16 => "UInt16".to_string(),
+        DataType::UInt32 => "UInt32".to_string(),
+        DataType::UInt64 => "UInt64".to_string(),
+        DataType::Float16 | DataType::Float32 => "Float32".to_string(),
+        DataType::Float64 => "Float64".to_string(),
+        DataType::Decimal128(precision, scale) | DataType::Decimal256(precision, scale) => {
+            format!("Decimal({}, {})", precision, scale)
+        }
+        DataType::FixedSizeBinary(size) => format!("FixedString({})", size),
+        DataType::Date32 => "Date32".to_string(),
+        DataType::Date64 => "DateTime64(3)".to_string(),
+        DataType::Timestamp(unit, Some(_)) => format!("DateTime64({}, 'UTC')", precision(unit)),
+        DataType::Timestamp(unit, None) => format!("DateTime64({})", precision(unit)),
+        DataType::List(item) | DataType::LargeList(item) | DataType::FixedSizeList(item, _) => {
+            format!("Array({})", nullable_type(item))
+        }
+        DataType::Map(entries, _) => match entries.data_type() {
+            DataType::Struct(fields) => format!(
+                "Map({}, {})",
+                column_type(fields[0].data_type()),
+                nullable_type(&fields[1])
+            ),
+            _ => unreachable!(),
+        },
+        DataType::Struct(fields) => format!(
+            "Tuple({})",
+            fields
+                .iter()
+                .map(|field| format!("{} {}", quote(field.name()), nullable_type(field)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        DataType::Dictionary(_, value_type) => {
+            format!("LowCardinality({})", column_type(value_type))
+        }
+        _ => "String".to_string(),
+    }
+}
+
+/// Wraps the type in Nullable where ClickHouse allows it.
+fn nullable_type(field: &Field) -> String {
+    let data_type = column_type(field.data_type());
+    match field.data_type() {
+        _ if !field.is_nullable() => data_type,
+        DataType::List(_)
+        | DataType::LargeList(_)
+        | DataType::FixedSizeList(_, _)
+        | DataType::Map(_, _)
+        | DataType::Struct(_) => data_type,
+        DataType::Dictionary(_, value_type) => {
+            format!("LowCardinality(Nullable({}))", column_type(value_type))
+        }
+        _ => format!("Nullable({})", data_type),
+    }
+}
+
+/// Returns a ClickHouse CREATE TABLE statement for loading the JSONEachRow output of the schema.
+pub fn create_table(schema: &Schema, table: &str) -> String {
+    let columns: Vec<String> = schema
+        .fields()
+        .iter()
+        .map(|field| format!("    {} {}", quote(field.name()), nullable_type(field)))
+        .collect();
+    format!(
+        "CREATE TABLE {}\n(\n{}\n)\nENGINE = MergeTree\nORDER BY tuple();",
+        quote(table),
+        columns.join(",\n")
+    )
+}
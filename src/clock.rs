@@ -0,0 +1,27 @@
+use std::time::{Duration, Instant};
+
+/// Source of the time measured by a run, fixed with --deterministic so that output can be
+/// compared with golden files.
+#[derive(Clone, Copy)]
+pub enum Clock {
+    System(Instant),
+    Fixed,
+}
+
+impl Clock {
+    pub fn start(deterministic: bool) -> Self {
+        if deterministic {
+            Clock::Fixed
+        } else {
+            Clock::System(Instant::now())
+        }
+    }
+
+    /// Returns the time since the clock started, or None for a fixed clock.
+    pub fn elapsed(&self) -> Option<Duration> {
+        match self {
+            Clock::System(started) => Some(started.elapsed()),
+            Clock::Fixed => None,
+        }
+    }
+}
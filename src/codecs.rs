@@ -0,0 +1,138 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::io::Write;
+
+use parquet::basic::Encoding;
+use parquet::file::metadata::ParquetMetaData;
+
+use crate::advise::codec_name;
+use crate::dataset::Footer;
+use crate::style::{accent, bold, dim};
+
+/// Codecs and encodings of a column in each file, with the number of files using each.
+#[derive(Default)]
+struct ColumnUsage {
+    path: String,
+    codecs: Vec<(String, usize)>,
+    encodings: Vec<(String, usize)>,
+}
+
+fn count(counts: &mut Vec<(String, usize)>, key: String) {
+    match counts.iter_mut().find(|(other, _)| *other == key) {
+        Some((_, count)) => *count += 1,
+        None => counts.push((key, 1)),
+    }
+}
+
+fn describe(counts: &[(String, usize)], files: usize) -> String {
+    counts
+        .iter()
+        .map(|(key, count)| match files {
+            1 => key.clone(),
+            _ => format!("{} {}", key, dim(format!("({} of {} files)", count, files))),
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Returns the codecs and value encodings of each column across the row groups of a file, as
+/// sorted names joined by slashes.
+fn file_usage(metadata: &ParquetMetaData) -> Vec<(String, String, String)> {
+    let schema_descr = metadata.file_metadata().schema_descr();
+    (0..schema_descr.num_columns())
+        .map(|index| {
+            let mut codecs: Vec<String> = vec![];
+            let mut encodings: Vec<String> = vec![];
+            for row_group in metadata.row_groups() {
+                let column = row_group.column(index);
+                codecs.push(codec_name(&column.compression()));
+                for encoding in column.encodings() {
+                    // Definition and repetition levels are always RLE or bit-packed
+                    #[allow(deprecated)]
+                    if !matches!(encoding, Encoding::RLE | Encoding::BIT_PACKED) {
+                        encodings.push(encoding.to_string());
+                    }
+                }
+            }
+            for names in [&mut codecs, &mut encodings] {
+                names.sort();
+                names.dedup();
+            }
+            (
+                schema_descr.column(index).path().string(),
+                codecs.join("/"),
+                encodings.join("/"),
+            )
+        })
+        .collect()
+}
+
+/// Writes the codecs and encodings of each column across the files of a dataset, and the
+/// columns written differently by some files, which readers then decode at uneven speeds.
+pub fn codecs_report(out: &mut Vec<u8>, footers: &[Footer]) {
+    let mut columns: Vec<ColumnUsage> = vec![];
+    let mut column_indices: HashMap<String, usize> = HashMap::new();
+    for (_, _, metadata) in footers {
+        for (path, codecs, encodings) in file_usage(metadata.metadata()) {
+            let index = *column_indices.entry(path.clone()).or_insert_with(|| {
+                columns.push(ColumnUsage {
+                    path,
+                    ..Default::default()
+                });
+                columns.len() - 1
+            });
+            count(&mut columns[index].codecs, codecs);
+            count(&mut columns[index].encodings, encodings);
+        }
+    }
+    let files = footers.len();
+
+    writeln!(out, "{}", bold("Columns")).unwrap();
+    for column in &mut columns {
+        column.codecs.sort_by_key(|(_, count)| Reverse(*count));
+        column.encodings.sort_by_key(|(_, count)| Reverse(*count));
+        writeln!(
+            out,
+            "  {}: {}; {}",
+            accent(&column.path),
+            describe(&column.codecs, files),
+            describe(&column.encodings, files)
+        )
+        .unwrap();
+    }
+
+    let mut inconsistencies: Vec<String> = vec![];
+    for column in &columns {
+        if column.codecs.len() > 1 {
+            inconsistencies.push(format!(
+                "{} is compressed differently across files ({})",
+                column.path,
+                describe(&column.codecs, files)
+            ));
+        }
+        if column.encodings.len() > 1 {
+            inconsistencies.push(format!(
+                "{} is encoded differently across files ({})",
+                column.path,
+                describe(&column.encodings, files)
+            ));
+        }
+        let column_files: usize = column.codecs.iter().map(|(_, count)| count).sum();
+        if column_files < files {
+            inconsistencies.push(format!(
+                "{} is missing from {} of {} files",
+                column.path,
+                files - column_files,
+                files
+            ));
+        }
+    }
+
+    writeln!(out, "{}", bold("Inconsistencies")).unwrap();
+    if inconsistencies.is_empty() {
+        writeln!(out, "  None, all files use the same codecs and encodings").unwrap();
+    }
+    for inconsistency in &inconsistencies {
+        writeln!(out, "  - {}", inconsistency).unwrap();
+    }
+}
@@ -0,0 +1,232 @@
+use std::sync::Arc;
+
+use arrow_array::cast::AsArray;
+use arrow_array::{new_null_array, Array, ArrayRef, RecordBatch, StructArray};
+use arrow_cast::cast;
+use arrow_ord::cmp::distinct;
+use arrow_schema::{DataType, Field, Fields, Schema, SchemaRef, TimeUnit};
+
+use crate::anomalies::{self, Anomaly};
+
+fn integer_width(data_type: &DataType) -> Option<(bool, u8)> {
+    match data_type {
+        DataType::Int8 => Some((true, 8)),
+        DataType::Int16 => Some((true, 16)),
+        DataType::Int32 => Some((true, 32)),
+        DataType::Int64 => Some((true, 64)),
+        DataType::UInt8 => Some((false, 8)),
+        DataType::UInt16 => Some((false, 16)),
+        DataType::UInt32 => Some((false, 32)),
+        DataType::UInt64 => Some((false, 64)),
+        _ => None,
+    }
+}
+
+fn integer_type(signed: bool, width: u8) -> DataType {
+    match (signed, width) {
+        (true, 8) => DataType::Int8,
+        (true, 16) => DataType::Int16,
+        (true, 32) => DataType::Int32,
+        (true, _) => DataType::Int64,
+        (false, 8) => DataType::UInt8,
+        (false, 16) => DataType::UInt16,
+        (false, 32) => DataType::UInt32,
+        (false, _) => DataType::UInt64,
+    }
+}
+
+fn finest(a: &TimeUnit, b: &TimeUnit) -> TimeUnit {
+    let rank = |unit: &TimeUnit| match unit {
+        TimeUnit::Second => 0,
+        TimeUnit::Millisecond => 1,
+        TimeUnit::Microsecond => 2,
+        TimeUnit::Nanosecond => 3,
+    };
+    if rank(a) >= rank(b) {
+        *a
+    } else {
+        *b
+    }
+}
+
+/// Returns a type that values of both types can be cast to, if any. Casts are without loss, except
+/// for integers beyond 2^53 merged with floats, which are cast to Float64 and reported as lossy
+/// casts.
+pub fn widen_type(a: &DataType, b: &DataType) -> Option<DataType> {
+    if a == b {
+        return Some(a.clone());
+    }
+    match (a, b) {
+        (DataType::Null, other) | (other, DataType::Null) => Some(other.clone()),
+        _ if integer_width(a).is_some() && integer_width(b).is_some() => {
+            let ((a_signed, a_width), (b_signed, b_width)) =
+                (integer_width(a).unwrap(), integer_width(b).unwrap());
+            if a_signed == b_signed {
+                Some(integer_type(a_signed, a_width.max(b_width)))
+            } else {
+                // A signed type needs to be wider than the unsigned one, and values of u64 only
+                // fit in a decimal of their 20 digits
+                let unsigned_width = if a_signed { b_width } else { a_width };
+                let signed_width = if a_signed { a_width } else { b_width };
+                match unsigned_width {
+                    64 => Some(DataType::Decimal128(20, 0)),
+                    _ => Some(integer_type(true, signed_width.max(unsigned_width * 2))),
+                }
+            }
+        }
+        (DataType::Float16 | DataType::Float32 | DataType::Float64, other)
+        | (other, DataType::Float16 | DataType::Float32 | DataType::Float64)
+            if integer_width(other).is_some()
+                || matches!(
+                    other,
+                    DataType::Float16 | DataType::Float32 | DataType::Float64
+                ) =>
+        {
+            Some(DataType::Float64)
+        }
+        (DataType::Utf8 | DataType::LargeUtf8, DataType::Utf8 | DataType::LargeUtf8) => {
+            Some(DataType::LargeUtf8)
+        }
+        (DataType::Binary | DataType::LargeBinary, DataType::Binary | DataType::LargeBinary) => {
+            Some(DataType::LargeBinary)
+        }
+        (DataType::Date32 | DataType::Date64, DataType::Date32 | DataType::Date64) => {
+            Some(DataType::Date64)
+        }
+        (DataType::Timestamp(a_unit, a_tz), DataType::Timestamp(b_unit, b_tz)) => {
+            let tz = match (a_tz, b_tz) {
+                (None, None) => None,
+                (a_tz, b_tz) if a_tz == b_tz => a_tz.clone(),
+                _ => Some("+00:00".into()),
+            };
+            Some(DataType::Timestamp(finest(a_unit, b_unit), tz))
+        }
+        (DataType::Timestamp(unit, tz), DataType::Date32 | DataType::Date64)
+        | (DataType::Date32 | DataType::Date64, DataType::Timestamp(unit, tz)) => {
+            Some(DataType::Timestamp(*unit, tz.clone()))
+        }
+        (
+            DataType::Decimal128(a_precision, a_scale),
+            DataType::Decimal128(b_precision, b_scale),
+        ) => {
+            let scale = *a_scale.max(b_scale);
+            let digits = (*a_precision as i8 - a_scale).max(*b_precision as i8 - b_scale);
+            let precision = (digits + scale).clamp(1, 38) as u8;
+            Some(DataType::Decimal128(precision, scale))
+        }
+        (DataType::List(a_item), DataType::List(b_item))
+        | (DataType::LargeList(a_item), DataType::LargeList(b_item)) => {
+            let item = Arc::new(widen_field(a_item, b_item)?);
+            Some(match a {
+                DataType::List(_) => DataType::List(item),
+                _ => DataType::LargeList(item),
+            })
+        }
+        (DataType::Struct(a_fields), DataType::Struct(b_fields)) => {
+            Some(DataType::Struct(merge_fields(a_fields, b_fields)?))
+        }
+        _ => None,
+    }
+}
+
+fn widen_field(a: &Field, b: &Field) -> Option<Field> {
+    Some(
+        a.clone()
+            .with_data_type(widen_type(a.data_type(), b.data_type())?)
+            .with_nullable(a.is_nullable() || b.is_nullable()),
+    )
+}
+
+/// Merges fields by name, keeping the order of first appearance; fields missing on either side
+/// become nullable.
+fn merge_fields(a: &Fields, b: &Fields) -> Option<Fields> {
+    let mut merged: Vec<Field> = vec![];
+    for field in a.iter() {
+        merged.push(match b.find(field.name()) {
+            Some((_, other)) => widen_field(field, other)?,
+            None => field.as_ref().clone().with_nullable(true),
+        });
+    }
+    for field in b.iter() {
+        if a.find(field.name()).is_none() {
+            merged.push(field.as_ref().clone().with_nullable(true));
+        }
+    }
+    Some(merged.into())
+}
+
+/// Returns a schema that every given schema can be cast to, panicking on incompatible columns.
+pub fn widen_schemas(schemas: &[SchemaRef]) -> SchemaRef {
+    let mut fields = schemas[0].fields().clone();
+    for schema in &schemas[1..] {
+        for field in schema.fields() {
+            if let Some((_, current)) = fields.find(field.name()) {
+                if widen_field(current, field).is_none() {
+                    panic!(
+                        "Incompatible column types ({}: {}, {})",
+                        field.name(),
+                        current.data_type(),
+                        field.data_type()
+                    );
+                }
+            }
+        }
+        fields = merge_fields(&fields, schema.fields()).unwrap();
+    }
+    Arc::new(Schema::new(fields))
+}
+
+fn coerce_array(array: &ArrayRef, data_type: &DataType) -> ArrayRef {
+    match (array.data_type(), data_type) {
+        (from, to) if from == to => array.clone(),
+        (DataType::Struct(_), DataType::Struct(fields)) => {
+            let struct_array = array.as_struct();
+            let columns = fields
+                .iter()
+                .map(|field| match struct_array.column_by_name(field.name()) {
+                    Some(column) => coerce_array(column, field.data_type()),
+                    None => new_null_array(field.data_type(), struct_array.len()),
+                })
+                .collect();
+            Arc::new(StructArray::new(
+                fields.clone(),
+                columns,
+                struct_array.nulls().cloned(),
+            ))
+        }
+        (from, to) => {
+            let cast_array = cast(array, data_type).unwrap();
+            if anomalies::enabled(Anomaly::LossyCast) {
+                report_lossy_cast(array, &cast_array, from, to);
+            }
+            cast_array
+        }
+    }
+}
+
+/// Reports values that became null in a cast, or integers that changed when cast to floats.
+fn report_lossy_cast(array: &ArrayRef, cast_array: &ArrayRef, from: &DataType, to: &DataType) {
+    let mut lost = cast_array.null_count() - array.null_count();
+    if integer_width(from).is_some() && to.is_floating() {
+        let round_trip = cast(cast_array, from).unwrap();
+        lost += distinct(array, &round_trip).unwrap().true_count();
+    }
+    anomalies::report(
+        Anomaly::LossyCast,
+        lost,
+        format!("{} values cast from {} to {}", lost, from, to),
+    );
+}
+
+/// Casts the batch to the schema, filling columns it does not have with nulls.
+pub fn coerce_batch(batch: &RecordBatch, schema: &SchemaRef) -> RecordBatch {
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| match batch.column_by_name(field.name()) {
+            Some(column) => coerce_array(column, field.data_type()),
+            None => new_null_array(field.data_type(), batch.num_rows()),
+        })
+        .collect();
+    RecordBatch::try_new(schema.clone(), columns).unwrap()
+}
@@ -0,0 +1,101 @@
+use std::io::Write;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::sink::Sink;
+
+/// Compression of the output stream of --compress, with its level.
+#[derive(Clone, Copy, PartialEq)]
+pub enum OutputCompression {
+    Gzip(u32),
+    Zstd(i32),
+}
+
+impl OutputCompression {
+    /// Parses gzip or zstd, with an optional level as gzip:<0-9> or zstd:<1-22>.
+    pub fn parse(argument: &str) -> Self {
+        let (codec, level) = match argument.split_once(':') {
+            Some((codec, level)) => (codec, Some(level)),
+            None => (argument, None),
+        };
+        match (codec, level.map(str::parse::<i32>)) {
+            ("gzip", None) => OutputCompression::Gzip(Compression::default().level()),
+            ("gzip", Some(Ok(level @ 0..=9))) => OutputCompression::Gzip(level as u32),
+            ("zstd", None) => OutputCompression::Zstd(zstd::DEFAULT_COMPRESSION_LEVEL),
+            ("zstd", Some(Ok(level @ 1..=22))) => OutputCompression::Zstd(level),
+            _ => panic!(
+                "Invalid compression, expected gzip[:0-9] or zstd[:1-22] ({})",
+                argument
+            ),
+        }
+    }
+}
+
+enum StreamEncoder {
+    Gzip(GzEncoder<Vec<u8>>),
+    Zstd(zstd::stream::write::Encoder<'static, Vec<u8>>),
+}
+
+impl StreamEncoder {
+    /// Compresses bytes, returning the compressed output produced so far.
+    fn write(&mut self, bytes: &[u8]) -> Vec<u8> {
+        match self {
+            StreamEncoder::Gzip(encoder) => {
+                encoder.write_all(bytes).unwrap();
+                std::mem::take(encoder.get_mut())
+            }
+            StreamEncoder::Zstd(encoder) => {
+                encoder.write_all(bytes).unwrap();
+                std::mem::take(encoder.get_mut())
+            }
+        }
+    }
+
+    /// Returns the rest of the compressed output, ending the stream.
+    fn finish(self) -> Vec<u8> {
+        match self {
+            StreamEncoder::Gzip(encoder) => encoder.finish().unwrap(),
+            StreamEncoder::Zstd(encoder) => encoder.finish().unwrap(),
+        }
+    }
+}
+
+/// Compresses output as it is written to another sink, such as stdout, rather than in a second
+/// pass.
+pub struct CompressedSink {
+    inner: Box<dyn Sink>,
+    encoder: Option<StreamEncoder>,
+}
+
+impl CompressedSink {
+    pub fn new(inner: Box<dyn Sink>, compression: OutputCompression) -> Self {
+        let encoder = match compression {
+            OutputCompression::Gzip(level) => {
+                StreamEncoder::Gzip(GzEncoder::new(vec![], Compression::new(level)))
+            }
+            OutputCompression::Zstd(level) => {
+                StreamEncoder::Zstd(zstd::stream::write::Encoder::new(vec![], level).unwrap())
+            }
+        };
+        CompressedSink {
+            inner,
+            encoder: Some(encoder),
+        }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Sink for CompressedSink {
+    async fn write(&mut self, bytes: Vec<u8>) {
+        let compressed = self.encoder.as_mut().unwrap().write(&bytes);
+        self.inner.write(compressed).await;
+    }
+
+    async fn finish(&mut self) {
+        if let Some(encoder) = self.encoder.take() {
+            self.inner.write(encoder.finish()).await;
+        }
+        self.inner.finish().await;
+    }
+}
@@ -0,0 +1,165 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::sync::Arc;
+
+use futures::stream::BoxStream;
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore, PutMultipartOpts,
+    PutOptions, PutPayload, PutResult, Result,
+};
+use reqwest::StatusCode;
+
+use crate::sdk_credentials::{describe, SdkCredentials};
+
+/// Returns whether an error is that of a request denied with the credentials it was signed with.
+/// object_store 0.10 has no variant for such errors, nor exports the retry error holding their
+/// status, so the status is read from the reqwest error it wraps or the retry error's own
+/// "Client error with status" display.
+fn is_access_denied(error: &object_store::Error) -> bool {
+    let object_store::Error::Generic { source, .. } = error else {
+        return false;
+    };
+    let mut error: Option<&(dyn Error + 'static)> = Some(source.as_ref());
+    while let Some(source) = error {
+        if let Some(status) = status(source) {
+            return matches!(status, StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED);
+        }
+        error = source.source();
+    }
+    false
+}
+
+/// Returns the HTTP status of a reqwest or object_store retry error.
+fn status(error: &(dyn Error + 'static)) -> Option<StatusCode> {
+    if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+        return error.status();
+    }
+    let status = error
+        .to_string()
+        .strip_prefix("Client error with status ")?
+        .get(..3)?
+        .parse()
+        .ok()?;
+    StatusCode::from_u16(status).ok()
+}
+
+/// S3 store passing over credential sources whose credentials are denied access, reading
+/// objects with those of the next source. With --anonymous-fallback, objects are read without
+/// signing requests once no source is left. Listings are not retried, as objects are still
+/// read one by one without permission to list the bucket.
+#[derive(Debug)]
+pub struct CredentialFallbackStore {
+    inner: Arc<dyn ObjectStore>,
+    credentials: Arc<SdkCredentials>,
+    anonymous: Option<Arc<dyn ObjectStore>>,
+}
+
+impl CredentialFallbackStore {
+    pub fn new(
+        inner: Arc<dyn ObjectStore>,
+        credentials: Arc<SdkCredentials>,
+        anonymous: Option<Arc<dyn ObjectStore>>,
+    ) -> Self {
+        CredentialFallbackStore {
+            inner,
+            credentials,
+            anonymous,
+        }
+    }
+
+    /// Sends the request with the credentials of each source in turn until one is not denied.
+    async fn with_fallback<T, F, Fut>(&self, request: F) -> Result<T>
+    where
+        F: Fn(Arc<dyn ObjectStore>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        loop {
+            let source = self.credentials.current().await;
+            let Some(source) = source else {
+                // The error of the request lists the sources tried, unless read anonymously
+                return match &self.anonymous {
+                    Some(anonymous) => request(anonymous.clone()).await,
+                    None => request(self.inner.clone()).await,
+                };
+            };
+            match request(self.inner.clone()).await {
+                Err(error) if is_access_denied(&error) => {
+                    self.credentials.reject(source, describe(&error)).await;
+                }
+                // Every source failed to provide credentials
+                Err(_) if self.credentials.current().await.is_none() => {}
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Display for CredentialFallbackStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CredentialFallback({})", self.inner)
+    }
+}
+
+#[async_trait::async_trait]
+impl ObjectStore for CredentialFallbackStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.with_fallback(|store| {
+            // GetOptions is not Clone, so each attempt gets a copy built field by field
+            let options = GetOptions {
+                if_match: options.if_match.clone(),
+                if_none_match: options.if_none_match.clone(),
+                if_modified_since: options.if_modified_since,
+                if_unmodified_since: options.if_unmodified_since,
+                range: options.range.clone(),
+                version: options.version.clone(),
+                head: options.head,
+            };
+            async move { store.get_opts(location, options).await }
+        })
+        .await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.with_fallback(|store| async move { store.head(location).await })
+            .await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+}
@@ -0,0 +1,231 @@
+use arrow_array::RecordBatch;
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_schema::DataType;
+use clap::ValueEnum;
+use csv::QuoteStyle;
+
+use crate::encoder::{Encoder, EncoderKind, FormatOpts, Serialize};
+use crate::jsonl::EncodedValues;
+use crate::{CatArgs, OutputFormat};
+
+/// Fields quoted in CSV output
+#[derive(ValueEnum, Clone, Copy, Default)]
+pub enum CsvQuoting {
+    /// Fields containing the delimiter, quotes or line breaks
+    #[default]
+    Necessary,
+    /// All fields
+    Always,
+    /// All fields but numbers
+    NonNumeric,
+    /// No fields, even if the output can no longer be read back
+    Never,
+}
+
+impl From<CsvQuoting> for QuoteStyle {
+    fn from(quoting: CsvQuoting) -> Self {
+        match quoting {
+            CsvQuoting::Necessary => QuoteStyle::Necessary,
+            CsvQuoting::Always => QuoteStyle::Always,
+            CsvQuoting::NonNumeric => QuoteStyle::NonNumeric,
+            CsvQuoting::Never => QuoteStyle::Never,
+        }
+    }
+}
+
+/// Parses the CSV delimiter, a single ASCII character or \t for tabs.
+pub fn parse_delimiter(delimiter: &str) -> u8 {
+    match delimiter {
+        "\\t" | "tab" => b'\t',
+        delimiter if delimiter.len() == 1 && delimiter.is_ascii() => delimiter.as_bytes()[0],
+        delimiter => panic!(
+            "Invalid delimiter ({}), expected a single ASCII character",
+            delimiter
+        ),
+    }
+}
+
+/// Writes rows as CSV, after a header row of the column names of the first batch. Values of
+/// nested types are written as JSON, and nulls as empty fields.
+#[derive(Clone)]
+pub struct CsvWriter {
+    pub delimiter: u8,
+    pub quoting: CsvQuoting,
+    pub header: bool,
+}
+
+impl CsvWriter {
+    pub fn write_batch(&self, out: &mut Vec<u8>, batch: &RecordBatch) {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .quote_style(self.quoting.into())
+            .from_writer(out);
+        let schema = batch.schema();
+        if self.header {
+            writer
+                .write_record(schema.fields().iter().map(|field| field.name()))
+                .unwrap();
+        }
+        let fields = Fields::new(batch);
+        for row in 0..batch.num_rows() {
+            writer.write_record(fields.row(row)).unwrap();
+        }
+        writer.flush().unwrap();
+    }
+}
+
+/// Writes rows as tab-separated values, after a header row of the column names of the first
+/// batch. Backslashes, tabs and line breaks within values are escaped as \\, \t, \n and \r,
+/// so that each row is a line of as many fields as columns.
+#[derive(Clone)]
+pub struct TsvWriter {
+    pub header: bool,
+}
+
+impl TsvWriter {
+    pub fn write_batch(&self, out: &mut Vec<u8>, batch: &RecordBatch) {
+        let mut write_line = |values: &mut dyn Iterator<Item = String>| {
+            for (index, value) in values.enumerate() {
+                if index > 0 {
+                    out.push(b'\t');
+                }
+                for byte in value.bytes() {
+                    match byte {
+                        b'\\' => out.extend_from_slice(b"\\\\"),
+                        b'\t' => out.extend_from_slice(b"\\t"),
+                        b'\n' => out.extend_from_slice(b"\\n"),
+                        b'\r' => out.extend_from_slice(b"\\r"),
+                        byte => out.push(byte),
+                    }
+                }
+            }
+            out.push(b'\n');
+        };
+        if self.header {
+            let schema = batch.schema();
+            write_line(&mut schema.fields().iter().map(|field| field.name().clone()));
+        }
+        let fields = Fields::new(batch);
+        for row in 0..batch.num_rows() {
+            write_line(&mut fields.row(row).into_iter());
+        }
+    }
+}
+
+enum Column<'a> {
+    Json(EncodedValues),
+    Display(ArrayFormatter<'a>),
+}
+
+/// Values of the columns of a batch as text fields, with values of nested types as JSON and
+/// nulls as empty fields.
+pub struct Fields<'a> {
+    batch: &'a RecordBatch,
+    columns: Vec<Column<'a>>,
+}
+
+impl<'a> Fields<'a> {
+    pub fn new(batch: &'a RecordBatch) -> Self {
+        let options = FormatOptions::default();
+        let schema = batch.schema();
+        let columns = schema
+            .fields()
+            .iter()
+            .zip(batch.columns())
+            .map(|(field, column)| match field.data_type() {
+                DataType::Struct(_)
+                | DataType::List(_)
+                | DataType::LargeList(_)
+                | DataType::FixedSizeList(_, _)
+                | DataType::Map(_, _) => {
+                    Column::Json(EncodedValues::new(field.name(), column, false))
+                }
+                _ => Column::Display(ArrayFormatter::try_new(column.as_ref(), &options).unwrap()),
+            })
+            .collect();
+        Fields { batch, columns }
+    }
+
+    pub fn row(&self, row: usize) -> Vec<String> {
+        self.columns
+            .iter()
+            .zip(self.batch.columns())
+            .map(|(column, array)| match column {
+                Column::Json(encoded) => encoded
+                    .value(row)
+                    .map(|value| String::from_utf8_lossy(value).into_owned())
+                    .unwrap_or_default(),
+                Column::Display(_) if array.is_null(row) => String::new(),
+                Column::Display(formatter) => formatter.value(row).to_string(),
+            })
+            .collect()
+    }
+}
+
+/// CSV, taking delimiter, quoting and header=true|false.
+pub struct CsvEncoderKind;
+
+impl EncoderKind for CsvEncoderKind {
+    fn format(&self) -> OutputFormat {
+        OutputFormat::Csv
+    }
+
+    fn content_type(&self) -> &'static str {
+        "text/csv"
+    }
+
+    fn open(&self, args: &CatArgs, options: &mut FormatOpts) -> Box<dyn Encoder> {
+        let delimiter = options
+            .string("delimiter")
+            .unwrap_or(args.delimiter.clone());
+        Box::new(CsvWriter {
+            delimiter: parse_delimiter(&delimiter),
+            quoting: options.value_enum("quoting").unwrap_or(args.quoting),
+            header: options.value("header").unwrap_or(true),
+        })
+    }
+}
+
+// The header row is written before the first batch only
+impl Encoder for CsvWriter {
+    fn encode(&mut self, batch: RecordBatch) -> Serialize {
+        let csv_writer = self.clone();
+        self.header = false;
+        Box::new(move || {
+            let mut lines = vec![];
+            csv_writer.write_batch(&mut lines, &batch);
+            lines
+        })
+    }
+}
+
+/// Tab-separated values, taking header=true|false.
+pub struct TsvEncoderKind;
+
+impl EncoderKind for TsvEncoderKind {
+    fn format(&self) -> OutputFormat {
+        OutputFormat::Tsv
+    }
+
+    fn content_type(&self) -> &'static str {
+        "text/tab-separated-values"
+    }
+
+    fn open(&self, _args: &CatArgs, options: &mut FormatOpts) -> Box<dyn Encoder> {
+        Box::new(TsvWriter {
+            header: options.value("header").unwrap_or(true),
+        })
+    }
+}
+
+impl Encoder for TsvWriter {
+    fn encode(&mut self, batch: RecordBatch) -> Serialize {
+        let tsv_writer = self.clone();
+        self.header = false;
+        Box::new(move || {
+            let mut lines = vec![];
+            tsv_writer.write_batch(&mut lines, &batch);
+            lines
+        })
+    }
+}
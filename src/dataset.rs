@@ -0,0 +1,259 @@
+use std::sync::Arc;
+
+use arrow_array::{RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, FieldRef, Schema};
+use futures::future::BoxFuture;
+use futures::{FutureExt, StreamExt};
+use glob::{MatchOptions, Pattern};
+use object_store::path::Path;
+use object_store::{ObjectMeta, ObjectStore};
+use parquet::arrow::arrow_reader::{ArrowReaderMetadata, ArrowReaderOptions};
+use parquet::arrow::async_reader::ParquetObjectReader;
+use parquet::errors::ParquetError;
+use tokio::sync::mpsc::{channel, Receiver};
+use tokio::task::JoinHandle;
+use urlencoding::decode;
+
+use crate::input_format::is_input_file;
+use crate::logical_types::load_metadata;
+use crate::source::store_for_location;
+use crate::timeout::{metadata_timeout, timed_out};
+
+/// Default number of footers fetched concurrently with multiple files.
+pub const METADATA_CONCURRENCY: usize = 16;
+
+/// A file with its footer metadata.
+pub type Footer = (Arc<dyn ObjectStore>, ObjectMeta, ArrowReaderMetadata);
+
+/// Lists Parquet objects under a directory or prefix in lexicographic order, falling back to the
+/// location itself when it refers to a single object, or cannot be listed, such as plain HTTP
+/// URLs or S3 objects readable without permission to list the bucket.
+pub async fn list_files(
+    storage_container: &Arc<dyn ObjectStore>,
+    prefix: &Path,
+) -> Vec<ObjectMeta> {
+    // Locations named like files are read without listing, unless they are directories, such as
+    // the .parquet directories of Spark
+    if is_input_file(prefix.as_ref()) {
+        if let Ok(meta) = storage_container.head(prefix).await {
+            return vec![meta];
+        }
+    }
+
+    let mut files: Vec<ObjectMeta> = vec![];
+    let mut listing = storage_container.list(Some(prefix));
+    while let Some(meta) = listing.next().await {
+        let Ok(meta) = meta else {
+            files.clear();
+            break;
+        };
+        if is_input_file(meta.location.as_ref()) {
+            files.push(meta);
+        }
+    }
+
+    if files.is_empty() {
+        files.push(storage_container.head(prefix).await.unwrap());
+    }
+
+    files.sort_by(|a, b| a.location.cmp(&b.location));
+    files
+}
+
+/// Returns the locations listed in a manifest file, skipping blank lines and # comments.
+pub async fn read_manifest(manifest: &str) -> Vec<String> {
+    let contents = if manifest == "-" {
+        std::io::read_to_string(std::io::stdin()).unwrap()
+    } else if manifest.contains("://") {
+        let (storage_container, location) = store_for_location(manifest).await;
+        let bytes = storage_container
+            .get(&location)
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        String::from_utf8_lossy(&bytes).into_owned()
+    } else {
+        std::fs::read_to_string(manifest)
+            .unwrap_or_else(|error| panic!("Cannot read manifest ({}: {})", manifest, error))
+    };
+    let locations: Vec<String> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect();
+    if locations.is_empty() {
+        panic!("No locations in manifest ({})", manifest);
+    }
+    locations
+}
+
+/// Splits a location with wildcards into the directory or prefix to list and the pattern to
+/// match below it, e.g. `data/part-*.parquet` into `data` and `part-*.parquet`.
+pub fn split_glob(location: &str) -> Option<(String, String)> {
+    // In URLs, `?` starts the query (such as a SAS token) rather than matching a character
+    let (path, query) = match location.split_once('?') {
+        Some((path, query)) if location.contains("://") => (path, Some(query)),
+        _ => (location, None),
+    };
+    let wildcard = path.find(['*', '?', '['])?;
+    let (base, pattern) = match path[..wildcard].rfind('/') {
+        Some(separator) => (&path[..separator], &path[separator + 1..]),
+        None => (".", path),
+    };
+    let base = match query {
+        Some(query) => format!("{}?{}", base, query),
+        None => base.to_string(),
+    };
+    Some((base, pattern.to_string()))
+}
+
+/// Lists objects under the prefix matching the glob pattern in lexicographic order, with `*`
+/// and `?` not matching across `/` unlike `**`.
+pub async fn glob_files(
+    storage_container: &Arc<dyn ObjectStore>,
+    prefix: &Path,
+    pattern: &str,
+) -> Vec<ObjectMeta> {
+    let glob = Pattern::new(pattern)
+        .unwrap_or_else(|error| panic!("Invalid glob pattern ({}: {})", pattern, error));
+    let options = MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+
+    let mut files: Vec<ObjectMeta> = vec![];
+    let mut listing = storage_container.list(Some(prefix));
+    while let Some(meta) = listing.next().await {
+        let meta = meta.unwrap();
+        let Some(parts) = meta.location.prefix_match(prefix) else {
+            continue;
+        };
+        let relative: Vec<String> = parts.map(|part| part.as_ref().to_string()).collect();
+        if glob.matches_with(&relative.join("/"), options) {
+            files.push(meta);
+        }
+    }
+    if files.is_empty() {
+        panic!("No files match the pattern ({})", pattern);
+    }
+
+    files.sort_by(|a, b| a.location.cmp(&b.location));
+    files
+}
+
+async fn load_footer(
+    storage_container: Arc<dyn ObjectStore>,
+    meta: ObjectMeta,
+    page_index: bool,
+) -> Result<Footer, ParquetError> {
+    let mut reader = ParquetObjectReader::new(storage_container.clone(), meta.clone());
+    let options = ArrowReaderOptions::new().with_page_index(page_index);
+    let load = load_metadata(&mut reader, meta.size, options, page_index);
+    let metadata = match metadata_timeout() {
+        Some(timeout) => tokio::time::timeout(timeout, load).await.map_err(|_| {
+            ParquetError::External(Box::new(timed_out("metadata", timeout, &meta.location)))
+        })??,
+        None => load.await?,
+    };
+    Ok((storage_container, meta, metadata))
+}
+
+/// Footers being fetched in the background, received in the order of the files. Dropping it
+/// cancels the fetches still in flight.
+pub struct Footers {
+    receiver: Receiver<Result<Footer, ParquetError>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl Footers {
+    /// Receives the next footer, or `None` after the last one. A panic while fetching is raised
+    /// again here rather than ending the files early.
+    pub async fn recv(&mut self) -> Option<Result<Footer, ParquetError>> {
+        if let Some(footer) = self.receiver.recv().await {
+            return Some(footer);
+        }
+        if let Some(task) = self.task.take() {
+            if let Err(error) = task.await {
+                if error.is_panic() {
+                    std::panic::resume_unwind(error.into_panic());
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Drop for Footers {
+    fn drop(&mut self) {
+        if let Some(task) = &self.task {
+            task.abort();
+        }
+    }
+}
+
+/// Fetches the footers of the files in the background, up to `concurrency` at a time and ahead
+/// of the consumer. With `page_index`, the page index is loaded too, so that rows can be skipped
+/// without fetching their pages.
+pub fn prefetch_footers(
+    files: Vec<(Arc<dyn ObjectStore>, ObjectMeta)>,
+    concurrency: usize,
+    page_index: bool,
+) -> Footers {
+    let (sender, receiver) = channel(concurrency.max(1));
+    // Fetches run within the one task, so that aborting it cancels all of them. They are boxed
+    // as Send + 'static futures, which the spawned task needs of them
+    let fetches: Vec<BoxFuture<'static, Result<Footer, ParquetError>>> = files
+        .into_iter()
+        .map(|(storage_container, meta)| load_footer(storage_container, meta, page_index).boxed())
+        .collect();
+    let task = tokio::spawn(async move {
+        let mut footers = futures::stream::iter(fetches).buffered(concurrency.max(1));
+        while let Some(footer) = footers.next().await {
+            // Stops fetching once the consumer is done, e.g. when the limit is reached
+            if sender.send(footer).await.is_err() {
+                break;
+            }
+        }
+    });
+    Footers {
+        receiver,
+        task: Some(task),
+    }
+}
+
+/// Returns the `key=value` segments of a partitioned layout such as
+/// `dt=2024-01-01/country=US/part-0.parquet`, with Hive's default partition as null.
+pub fn partition_values(location: &Path) -> Vec<(String, Option<String>)> {
+    location
+        .parts()
+        .filter_map(|part| {
+            let (key, value) = part.as_ref().split_once('=')?;
+            let key = decode(key).ok()?.into_owned();
+            let value = decode(value).ok()?.into_owned();
+            Some((
+                key,
+                (value != "__HIVE_DEFAULT_PARTITION__").then_some(value),
+            ))
+        })
+        .collect()
+}
+
+/// Appends partition values as string columns.
+pub fn with_partition_columns(
+    batch: &RecordBatch,
+    partitions: &[(String, Option<String>)],
+) -> RecordBatch {
+    let mut fields: Vec<FieldRef> = batch.schema().fields().iter().cloned().collect();
+    let mut columns = batch.columns().to_vec();
+    for (key, value) in partitions {
+        fields.push(Arc::new(Field::new(key, DataType::Utf8, value.is_none())));
+        columns.push(Arc::new(StringArray::from(vec![
+            value.as_deref();
+            batch.num_rows()
+        ])));
+    }
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap()
+}
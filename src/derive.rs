@@ -0,0 +1,489 @@
+use std::iter::Peekable;
+use std::str::Chars;
+use std::sync::Arc;
+
+use arrow_arith::numeric;
+use arrow_array::cast::AsArray;
+use arrow_array::timezone::Tz;
+use arrow_array::types::{Int64Type, TimestampMicrosecondType};
+use arrow_array::{
+    Array, ArrayRef, Float64Array, Int32Array, Int64Array, RecordBatch, StringArray,
+    TimestampMicrosecondArray,
+};
+use arrow_cast::cast;
+use arrow_schema::{DataType, Field, FieldRef, Schema, TimeUnit};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+/// Expression of a derived column over the columns of a batch.
+#[derive(Clone, Debug)]
+enum Expression {
+    Column(String),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Negate(Box<Expression>),
+    Binary(Operator, Box<Expression>, Box<Expression>),
+    Call(String, Vec<Expression>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(String),
+    String(String),
+    Identifier(String),
+    Operator(Operator),
+    Open,
+    Close,
+    Comma,
+}
+
+/// Returns the tokens of an expression: numbers, 'strings', identifiers or "quoted identifiers",
+/// arithmetic operators, parentheses and commas.
+fn tokenize(expression: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut chars: Peekable<Chars> = expression.chars().peekable();
+    let take_while = |chars: &mut Peekable<Chars>, condition: &dyn Fn(char) -> bool| {
+        let mut text = String::new();
+        while let Some(c) = chars.next_if(|c| condition(*c)) {
+            text.push(c);
+        }
+        text
+    };
+    // Quotes within quoted text are written twice, as in SQL
+    let quoted = |chars: &mut Peekable<Chars>, quote: char| {
+        let mut text = String::new();
+        loop {
+            match chars.next() {
+                Some(c) if c == quote && chars.next_if_eq(&quote).is_none() => return text,
+                Some(c) => text.push(c),
+                None => panic!("Unterminated {} in expression ({})", quote, expression),
+            }
+        }
+    };
+    while let Some(&c) = chars.peek() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '0'..='9' | '.' => {
+                Token::Number(take_while(&mut chars, &|c| c.is_ascii_digit() || c == '.'))
+            }
+            'a'..='z' | 'A'..='Z' | '_' => {
+                Token::Identifier(take_while(&mut chars, &|c| c.is_alphanumeric() || c == '_'))
+            }
+            '\'' | '"' => {
+                chars.next();
+                let text = quoted(&mut chars, c);
+                if c == '"' {
+                    Token::Identifier(text)
+                } else {
+                    Token::String(text)
+                }
+            }
+            _ => {
+                chars.next();
+                match c {
+                    '+' => Token::Operator(Operator::Add),
+                    '-' => Token::Operator(Operator::Subtract),
+                    '*' => Token::Operator(Operator::Multiply),
+                    '/' => Token::Operator(Operator::Divide),
+                    '%' => Token::Operator(Operator::Remainder),
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    ',' => Token::Comma,
+                    c => panic!("Unexpected {} in expression ({})", c, expression),
+                }
+            }
+        };
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// Recursive descent parser of expressions, with the usual precedence of arithmetic operators.
+struct Parser<'a> {
+    expression: &'a str,
+    tokens: Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl Parser<'_> {
+    fn invalid(&self) -> ! {
+        panic!("Invalid expression ({})", self.expression)
+    }
+
+    fn expect(&mut self, token: Token) {
+        if self.tokens.next() != Some(token) {
+            self.invalid();
+        }
+    }
+
+    fn binary(
+        &mut self,
+        operators: &[Operator],
+        operand: fn(&mut Self) -> Expression,
+    ) -> Expression {
+        let mut left = operand(self);
+        while let Some(Token::Operator(operator)) = self.tokens.peek().cloned() {
+            if !operators.contains(&operator) {
+                break;
+            }
+            self.tokens.next();
+            left = Expression::Binary(operator, Box::new(left), Box::new(operand(self)));
+        }
+        left
+    }
+
+    fn sum(&mut self) -> Expression {
+        self.binary(&[Operator::Add, Operator::Subtract], Self::product)
+    }
+
+    fn product(&mut self) -> Expression {
+        let operators = [Operator::Multiply, Operator::Divide, Operator::Remainder];
+        self.binary(&operators, Self::unary)
+    }
+
+    fn unary(&mut self) -> Expression {
+        if self
+            .tokens
+            .next_if_eq(&Token::Operator(Operator::Subtract))
+            .is_some()
+        {
+            return Expression::Negate(Box::new(self.unary()));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Expression {
+        match self.tokens.next() {
+            Some(Token::Number(number)) => match number.parse() {
+                Ok(integer) => Expression::Integer(integer),
+                Err(_) => Expression::Float(number.parse().unwrap_or_else(|_| self.invalid())),
+            },
+            Some(Token::String(text)) => Expression::String(text),
+            Some(Token::Identifier(name)) if self.tokens.next_if_eq(&Token::Open).is_some() => {
+                let mut arguments = vec![];
+                if self.tokens.next_if_eq(&Token::Close).is_none() {
+                    loop {
+                        arguments.push(self.sum());
+                        match self.tokens.next() {
+                            Some(Token::Comma) => {}
+                            Some(Token::Close) => break,
+                            _ => self.invalid(),
+                        }
+                    }
+                }
+                Expression::Call(name.to_lowercase(), arguments)
+            }
+            Some(Token::Identifier(name)) => Expression::Column(name),
+            Some(Token::Open) => {
+                let expression = self.sum();
+                self.expect(Token::Close);
+                expression
+            }
+            _ => self.invalid(),
+        }
+    }
+}
+
+/// Casts the operands of arithmetic to a common type: integers to Int64, integers mixed with
+/// decimals to decimals, and other mixed numbers to Float64. Dates and timestamps are left to
+/// the kernels.
+fn common_type(left: &ArrayRef, right: &ArrayRef) -> (ArrayRef, ArrayRef) {
+    let data_type = match (left.data_type(), right.data_type()) {
+        (left_type, right_type) if left_type == right_type => return (left.clone(), right.clone()),
+        (left_type, right_type) if left_type.is_integer() && right_type.is_integer() => {
+            DataType::Int64
+        }
+        (DataType::Decimal128(_, _), other) | (other, DataType::Decimal128(_, _))
+            if other.is_integer() =>
+        {
+            DataType::Decimal128(19, 0)
+        }
+        (left_type, right_type) if left_type.is_numeric() && right_type.is_numeric() => {
+            DataType::Float64
+        }
+        _ => return (left.clone(), right.clone()),
+    };
+    let cast_to = |array: &ArrayRef| match array.data_type() {
+        DataType::Decimal128(_, _) if data_type != DataType::Float64 => array.clone(),
+        _ => cast(array, &data_type).unwrap(),
+    };
+    (cast_to(left), cast_to(right))
+}
+
+/// Returns the start of the period of a unit a local date and time falls in.
+fn truncate(datetime: NaiveDateTime, unit: &str) -> NaiveDateTime {
+    let date = datetime.date();
+    let midnight = |date: NaiveDate| date.and_hms_opt(0, 0, 0).unwrap();
+    match unit {
+        "second" => datetime.with_nanosecond(0).unwrap(),
+        "minute" => date
+            .and_hms_opt(datetime.hour(), datetime.minute(), 0)
+            .unwrap(),
+        "hour" => date.and_hms_opt(datetime.hour(), 0, 0).unwrap(),
+        "day" => midnight(date),
+        "week" => midnight(date - Duration::days(date.weekday().num_days_from_monday() as i64)),
+        "month" => midnight(date.with_day(1).unwrap()),
+        "year" => midnight(date.with_ordinal(1).unwrap()),
+        unit => panic!(
+            "Invalid date_trunc unit, expected second, minute, hour, day, week, month or year ({})",
+            unit
+        ),
+    }
+}
+
+/// Truncates dates and timestamps to the start of the period of a unit, in the time zone of the
+/// timestamps if they have one, keeping their type.
+fn date_trunc(unit: &str, array: &ArrayRef) -> ArrayRef {
+    let timezone = match array.data_type() {
+        DataType::Timestamp(_, timezone) => timezone.clone(),
+        DataType::Date32 | DataType::Date64 => None,
+        data_type => panic!("Cannot apply date_trunc to {}", data_type),
+    };
+    let tz: Option<Tz> = timezone.as_ref().map(|timezone| timezone.parse().unwrap());
+    let micros = cast(
+        array,
+        &DataType::Timestamp(TimeUnit::Microsecond, timezone.clone()),
+    )
+    .unwrap();
+    let truncated: TimestampMicrosecondArray = micros
+        .as_primitive::<TimestampMicrosecondType>()
+        .iter()
+        .map(|value| {
+            let utc = DateTime::from_timestamp_micros(value?)?;
+            let truncated = match &tz {
+                Some(tz) => {
+                    let local = truncate(utc.with_timezone(tz).naive_local(), unit);
+                    tz.from_local_datetime(&local)
+                        .earliest()?
+                        .timestamp_micros()
+                }
+                None => truncate(utc.naive_utc(), unit).and_utc().timestamp_micros(),
+            };
+            Some(truncated)
+        })
+        .collect();
+    let truncated = truncated.with_timezone_opt(timezone);
+    cast(&truncated, array.data_type()).unwrap()
+}
+
+/// Returns the values of an array as strings, for string functions.
+fn strings(array: &ArrayRef) -> ArrayRef {
+    cast(array, &DataType::Utf8).unwrap()
+}
+
+impl Expression {
+    fn columns(&self, columns: &mut Vec<String>) {
+        match self {
+            Expression::Column(name) => columns.push(name.clone()),
+            Expression::Negate(operand) => operand.columns(columns),
+            Expression::Binary(_, left, right) => {
+                left.columns(columns);
+                right.columns(columns);
+            }
+            // The unit of date_trunc is a name rather than a column
+            Expression::Call(name, arguments) => {
+                let skip = if name == "date_trunc" { 1 } else { 0 };
+                for argument in arguments.iter().skip(skip) {
+                    argument.columns(columns);
+                }
+            }
+            Expression::Integer(_) | Expression::Float(_) | Expression::String(_) => {}
+        }
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> ArrayRef {
+        let rows = batch.num_rows();
+        match self {
+            Expression::Column(name) => batch
+                .column_by_name(name)
+                .unwrap_or_else(|| panic!("Column not found ({})", name))
+                .clone(),
+            Expression::Integer(value) => Arc::new(Int64Array::from_value(*value, rows)),
+            Expression::Float(value) => Arc::new(Float64Array::from_value(*value, rows)),
+            Expression::String(value) => Arc::new(StringArray::from(vec![value.as_str(); rows])),
+            Expression::Negate(operand) => numeric::neg(&operand.evaluate(batch))
+                .unwrap_or_else(|error| panic!("Cannot negate: {}", error)),
+            Expression::Binary(operator, left, right) => {
+                let (left, right) = common_type(&left.evaluate(batch), &right.evaluate(batch));
+                let kernel = match operator {
+                    Operator::Add => numeric::add,
+                    Operator::Subtract => numeric::sub,
+                    Operator::Multiply => numeric::mul,
+                    Operator::Divide => numeric::div,
+                    Operator::Remainder => numeric::rem,
+                };
+                kernel(&left, &right).unwrap_or_else(|error| {
+                    panic!(
+                        "Cannot apply {:?} to {} and {}: {}",
+                        operator,
+                        left.data_type(),
+                        right.data_type(),
+                        error
+                    )
+                })
+            }
+            Expression::Call(name, arguments) => self.call(name, arguments, batch),
+        }
+    }
+
+    fn call(&self, name: &str, arguments: &[Expression], batch: &RecordBatch) -> ArrayRef {
+        let arity = |count: usize| {
+            if arguments.len() != count {
+                panic!("{} takes {} arguments ({:?})", name, count, self);
+            }
+        };
+        match name {
+            "upper" | "lower" | "trim" => {
+                arity(1);
+                let values = strings(&arguments[0].evaluate(batch));
+                let function: fn(&str) -> String = match name {
+                    "upper" => str::to_uppercase,
+                    "lower" => str::to_lowercase,
+                    _ => |value| value.trim().to_string(),
+                };
+                let values: StringArray = values
+                    .as_string::<i32>()
+                    .iter()
+                    .map(|value| value.map(function))
+                    .collect();
+                Arc::new(values)
+            }
+            "length" => {
+                arity(1);
+                let values = strings(&arguments[0].evaluate(batch));
+                let lengths: Int32Array = values
+                    .as_string::<i32>()
+                    .iter()
+                    .map(|value| value.map(|value| value.chars().count() as i32))
+                    .collect();
+                Arc::new(lengths)
+            }
+            "substr" => {
+                arity(3);
+                let values = strings(&arguments[0].evaluate(batch));
+                let start = cast(&arguments[1].evaluate(batch), &DataType::Int64).unwrap();
+                let length = cast(&arguments[2].evaluate(batch), &DataType::Int64).unwrap();
+                let values: StringArray = values
+                    .as_string::<i32>()
+                    .iter()
+                    .zip(start.as_primitive::<Int64Type>())
+                    .zip(length.as_primitive::<Int64Type>())
+                    .map(|((value, start), length)| {
+                        // Positions start at 1, as in SQL
+                        let start = (start?.max(1) - 1) as usize;
+                        let length = length?.max(0) as usize;
+                        Some(value?.chars().skip(start).take(length).collect::<String>())
+                    })
+                    .collect();
+                Arc::new(values)
+            }
+            "concat" => {
+                let arguments: Vec<ArrayRef> = arguments
+                    .iter()
+                    .map(|argument| strings(&argument.evaluate(batch)))
+                    .collect();
+                let values: StringArray = (0..batch.num_rows())
+                    .map(|row| {
+                        arguments
+                            .iter()
+                            .map(|argument| {
+                                let argument = argument.as_string::<i32>();
+                                argument.is_valid(row).then(|| argument.value(row))
+                            })
+                            .collect::<Option<String>>()
+                    })
+                    .collect();
+                Arc::new(values)
+            }
+            "date_trunc" => {
+                arity(2);
+                let unit = match &arguments[0] {
+                    Expression::Column(unit) | Expression::String(unit) => unit.to_lowercase(),
+                    _ => panic!("date_trunc takes a unit first ({:?})", self),
+                };
+                date_trunc(&unit, &arguments[1].evaluate(batch))
+            }
+            name => panic!(
+                "Unknown function ({}), expected upper, lower, trim, length, substr, concat or date_trunc",
+                name
+            ),
+        }
+    }
+}
+
+/// Column derived from an expression over the columns of each batch, from a
+/// `name=expression` argument.
+pub struct Derivation {
+    pub name: String,
+    expression: Expression,
+}
+
+impl Derivation {
+    pub fn parse(argument: &str) -> Self {
+        let (name, expression) = argument.split_once('=').unwrap_or_else(|| {
+            panic!(
+                "Invalid derived column, expected name=expression ({})",
+                argument
+            )
+        });
+        let mut parser = Parser {
+            expression,
+            tokens: tokenize(expression).into_iter().peekable(),
+        };
+        let parsed = parser.sum();
+        if parser.tokens.next().is_some() {
+            parser.invalid();
+        }
+        Derivation {
+            name: name.trim().to_string(),
+            expression: parsed,
+        }
+    }
+
+    /// Returns the columns the expression reads.
+    pub fn columns(&self) -> Vec<String> {
+        let mut columns = vec![];
+        self.expression.columns(&mut columns);
+        columns
+    }
+}
+
+/// Adds derived columns after the others, in order, so that later ones may use earlier ones.
+/// Derived columns named as an existing column replace it in place.
+pub fn derive_columns(batch: &RecordBatch, derivations: &[Derivation]) -> RecordBatch {
+    let mut batch = batch.clone();
+    for derivation in derivations {
+        let array = derivation.expression.evaluate(&batch);
+        let field: FieldRef = Arc::new(Field::new(
+            &derivation.name,
+            array.data_type().clone(),
+            true,
+        ));
+        let schema = batch.schema();
+        let mut fields: Vec<FieldRef> = schema.fields().iter().cloned().collect();
+        let mut columns = batch.columns().to_vec();
+        match schema.index_of(&derivation.name) {
+            Ok(index) => {
+                fields[index] = field;
+                columns[index] = array;
+            }
+            Err(_) => {
+                fields.push(field);
+                columns.push(array);
+            }
+        }
+        batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap();
+    }
+    batch
+}
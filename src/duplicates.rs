@@ -0,0 +1,251 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use arrow_array::RecordBatch;
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use object_store::{ObjectMeta, ObjectStore};
+use parquet::arrow::async_reader::ParquetObjectReader;
+use parquet::arrow::{ParquetRecordBatchStreamBuilder, ProjectionMask};
+use serde_json::{json, Map, Value};
+use tokio_stream::StreamExt;
+
+use crate::output::ReportWriter;
+use crate::spill::{SpillArgs, SpillPartitions, SpillRuns};
+
+const BLOOM_HASHES: u64 = 4;
+
+/// Approximate memory of a key held in a set or map, beyond its strings.
+const KEY_OVERHEAD: usize = 64;
+
+pub fn key_size(key: &[String]) -> usize {
+    key.iter().map(|value| value.len() + 24).sum::<usize>() + KEY_OVERHEAD
+}
+
+pub fn key_hash(key: &[String]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A duplicated key with its (file index, row index) locations.
+type Duplicate = (Vec<String>, Vec<(usize, usize)>);
+
+/// A fixed-size Bloom filter used to find keys that may have been seen before.
+struct BloomFilter {
+    bits: Vec<u64>,
+}
+
+impl BloomFilter {
+    fn with_size_mb(size_mb: usize) -> Self {
+        BloomFilter {
+            bits: vec![0; (size_mb.max(1) * 1024 * 1024) / 8],
+        }
+    }
+
+    /// Inserts the key, returning whether it was possibly present already.
+    fn insert<T: Hash>(&mut self, key: &T) -> bool {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let h1 = hasher.finish();
+        h1.hash(&mut hasher);
+        let h2 = hasher.finish() | 1;
+
+        let num_bits = self.bits.len() as u64 * 64;
+        let mut present = true;
+        for i in 0..BLOOM_HASHES {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % num_bits;
+            let (word, mask) = ((bit / 64) as usize, 1u64 << (bit % 64));
+            if self.bits[word] & mask == 0 {
+                present = false;
+                self.bits[word] |= mask;
+            }
+        }
+        present
+    }
+}
+
+/// Calls back with the values of the key columns of each row of a file without nulls in them,
+/// with the index of the row.
+pub async fn for_each_key<F>(
+    storage_container: &Arc<dyn ObjectStore>,
+    file: &ObjectMeta,
+    key_columns: &[&str],
+    mut callback: F,
+) where
+    F: FnMut(Vec<String>, usize),
+{
+    let reader = ParquetObjectReader::new(storage_container.clone(), file.clone());
+    let builder = ParquetRecordBatchStreamBuilder::new(reader).await.unwrap();
+
+    let schema_descr = builder.parquet_schema();
+    let root_schema = schema_descr.root_schema().get_fields();
+    let indices: Vec<usize> = key_columns
+        .iter()
+        .map(|column_name| {
+            root_schema
+                .iter()
+                .position(|field| field.name().eq(*column_name))
+                .unwrap_or_else(|| {
+                    panic!("Column not found ({}) in {}", column_name, file.location)
+                })
+        })
+        .collect();
+    let projection_mask = ProjectionMask::roots(schema_descr, indices);
+    let mut iter = builder.with_projection(projection_mask).build().unwrap();
+
+    let mut row_index = 0;
+    while let Some(batch) = iter.next().await {
+        let batch: RecordBatch = batch.unwrap();
+        let columns: Vec<_> = key_columns
+            .iter()
+            .map(|column_name| batch.column_by_name(column_name).unwrap())
+            .collect();
+        let formatters: Vec<_> = columns
+            .iter()
+            .map(|column| {
+                ArrayFormatter::try_new(column.as_ref(), &FormatOptions::default()).unwrap()
+            })
+            .collect();
+
+        for row in 0..batch.num_rows() {
+            if columns.iter().all(|column| column.is_valid(row)) {
+                let key = formatters
+                    .iter()
+                    .map(|formatter| formatter.value(row).to_string())
+                    .collect();
+                callback(key, row_index + row);
+            }
+        }
+        row_index += batch.num_rows();
+    }
+}
+
+/// Returns the locations of keys occurring more than once, with all keys hash partitioned into
+/// spill files that are each grouped within the memory budget, and the duplicates of each
+/// partition spilled as a sorted run.
+async fn spilled_duplicates(
+    storage_container: &Arc<dyn ObjectStore>,
+    files: &[ObjectMeta],
+    key_columns: &[&str],
+    keys_size: usize,
+    spill_args: &SpillArgs,
+) -> SpillRuns {
+    let mut partitions = SpillPartitions::new(spill_args, keys_size);
+    for (file_index, file) in files.iter().enumerate() {
+        for_each_key(storage_container, file, key_columns, |key, row_index| {
+            partitions.write(key_hash(&key), &(key, file_index, row_index));
+        })
+        .await;
+    }
+
+    let mut runs = SpillRuns::new(spill_args);
+    for records in partitions.partitions::<(Vec<String>, usize, usize)>() {
+        let mut locations: HashMap<Vec<String>, Vec<(usize, usize)>> = HashMap::new();
+        for (key, file_index, row_index) in records {
+            locations
+                .entry(key)
+                .or_default()
+                .push((file_index, row_index));
+        }
+        let mut duplicates: Vec<Duplicate> = locations
+            .into_iter()
+            .filter(|(_, key_locations)| key_locations.len() > 1)
+            .collect();
+        duplicates.sort_by(|a, b| a.1[0].cmp(&b.1[0]));
+        runs.write_run(&duplicates);
+    }
+    runs
+}
+
+/// Outputs each duplicated key with its locations as JSON lines, in two passes: a Bloom filter
+/// pass nominates candidate keys, then an exact pass collects locations for candidates only.
+/// When the candidates exceed the memory budget, the exact pass spills all keys to disk instead.
+/// Returns whether any key is duplicated.
+pub async fn check_duplicates(
+    out: &mut ReportWriter,
+    storage_container: Arc<dyn ObjectStore>,
+    files: &[ObjectMeta],
+    key: &str,
+    bloom_size: usize,
+    spill_args: &SpillArgs,
+) -> bool {
+    let key_columns: Vec<&str> = key.split(',').collect();
+    let budget = spill_args.budget();
+
+    let mut bloom_filter = BloomFilter::with_size_mb(bloom_size);
+    let mut candidates: HashSet<Vec<String>> = HashSet::new();
+    let (mut candidates_size, mut keys_size) = (0, 0);
+    let mut spill = false;
+    for file in files {
+        for_each_key(&storage_container, file, &key_columns, |key, _| {
+            let size = key_size(&key);
+            keys_size += size;
+            if bloom_filter.insert(&key) && !spill {
+                candidates_size += size;
+                candidates.insert(key);
+                if candidates_size > budget {
+                    spill = true;
+                    candidates = HashSet::new();
+                }
+            }
+        })
+        .await;
+    }
+    drop(bloom_filter);
+
+    let duplicates: Box<dyn Iterator<Item = Duplicate>> = if spill {
+        let runs = spilled_duplicates(
+            &storage_container,
+            files,
+            &key_columns,
+            keys_size,
+            spill_args,
+        )
+        .await;
+        Box::new(runs.merge(|(_, key_locations): &Duplicate| key_locations[0]))
+    } else {
+        let mut locations: HashMap<Vec<String>, Vec<(usize, usize)>> = HashMap::new();
+        if !candidates.is_empty() {
+            for (file_index, file) in files.iter().enumerate() {
+                for_each_key(&storage_container, file, &key_columns, |key, row_index| {
+                    if candidates.contains(&key) {
+                        locations
+                            .entry(key)
+                            .or_default()
+                            .push((file_index, row_index));
+                    }
+                })
+                .await;
+            }
+        }
+        let mut duplicates: Vec<Duplicate> = locations
+            .into_iter()
+            .filter(|(_, key_locations)| key_locations.len() > 1)
+            .collect();
+        duplicates.sort_by(|a, b| a.1[0].cmp(&b.1[0]));
+        Box::new(duplicates.into_iter())
+    };
+
+    let mut found = false;
+    for (key, key_locations) in duplicates {
+        found = true;
+        let key_object: Map<String, Value> = key_columns
+            .iter()
+            .zip(&key)
+            .map(|(column_name, value)| (column_name.to_string(), json!(value)))
+            .collect();
+        let location_values: Vec<Value> = key_locations
+            .iter()
+            .map(|(file_index, row_index)| {
+                json!({"file": files[*file_index].location.to_string(), "row": row_index})
+            })
+            .collect();
+        out.write_line(
+            json!({"key": key_object, "count": key_locations.len(), "locations": location_values}),
+        )
+        .await;
+    }
+    found
+}
@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use arrow_array::RecordBatch;
+use clap::ValueEnum;
+
+use crate::bulk::BulkEncoderKind;
+use crate::csv_output::{CsvEncoderKind, TsvEncoderKind};
+use crate::jsonl::JsonLinesEncoderKind;
+use crate::markdown::MarkdownEncoderKind;
+use crate::parquet_output::ParquetEncoderKind;
+use crate::sql::{CopyEncoderKind, SqlEncoderKind};
+use crate::table::TableEncoderKind;
+use crate::vecdb::VectorEncoderKind;
+use crate::yaml::YamlEncoderKind;
+use crate::{CatArgs, OutputFormat};
+
+/// Serialization of a batch, run on a serialize thread.
+pub type Serialize = Box<dyn FnOnce() -> Vec<u8> + Send>;
+
+/// Serializes the batches of cat output in an output format.
+pub trait Encoder {
+    /// Returns the serialization of a batch. Batches are passed in output order, so state
+    /// spanning batches, such as whether the header is written, is kept here.
+    fn encode(&mut self, batch: RecordBatch) -> Serialize;
+
+    /// Whether batches are cast to types the JSON writer can output, such as binary to base64,
+    /// before being encoded.
+    fn json_types(&self) -> bool {
+        true
+    }
+
+    /// Returns the output following all rows, such as that of records gathered across batches.
+    fn finish(&mut self) -> Vec<u8> {
+        vec![]
+    }
+}
+
+/// Output format of --format, opened with its options of --format-opt.
+pub trait EncoderKind: Sync {
+    fn format(&self) -> OutputFormat;
+
+    /// Media type of the output, as sent to post: outputs.
+    fn content_type(&self) -> &'static str {
+        "application/x-ndjson"
+    }
+
+    /// Opens the encoder, taking the options it knows from those of --format-opt.
+    fn open(&self, args: &CatArgs, options: &mut FormatOpts) -> Box<dyn Encoder>;
+}
+
+/// Encoders by --format.
+static ENCODERS: &[&dyn EncoderKind] = &[
+    &JsonLinesEncoderKind,
+    &CsvEncoderKind,
+    &TsvEncoderKind,
+    &BulkEncoderKind,
+    &VectorEncoderKind,
+    &YamlEncoderKind,
+    &SqlEncoderKind,
+    &CopyEncoderKind,
+    &MarkdownEncoderKind,
+    &TableEncoderKind,
+    &ParquetEncoderKind,
+];
+
+pub fn encoder_kind(format: OutputFormat) -> &'static dyn EncoderKind {
+    *ENCODERS
+        .iter()
+        .find(|kind| kind.format() == format)
+        .unwrap()
+}
+
+/// Opens the encoder of --format, failing on options of --format-opt it does not take.
+pub fn open_encoder(args: &CatArgs) -> Box<dyn Encoder> {
+    if args.pretty && args.format != OutputFormat::Jsonl {
+        panic!("--pretty only applies to the jsonl format");
+    }
+    let mut options = FormatOpts::parse(&args.format_opt);
+    let encoder = encoder_kind(args.format).open(args, &mut options);
+    if let Some(key) = options.0.keys().next() {
+        panic!(
+            "Unknown option of the {} format ({})",
+            args.format.to_possible_value().unwrap().get_name(),
+            key
+        );
+    }
+    encoder
+}
+
+/// Options of --format-opt, as KEY=VALUE, each taken by the encoder knowing it.
+pub struct FormatOpts(BTreeMap<String, String>);
+
+impl FormatOpts {
+    fn parse(pairs: &[String]) -> Self {
+        FormatOpts(
+            pairs
+                .iter()
+                .map(|pair| match pair.split_once('=') {
+                    Some((key, value)) => (key.to_string(), value.to_string()),
+                    None => panic!("Invalid format option ({}), expected KEY=VALUE", pair),
+                })
+                .collect(),
+        )
+    }
+
+    pub fn string(&mut self, key: &str) -> Option<String> {
+        self.0.remove(key)
+    }
+
+    pub fn value<T: FromStr>(&mut self, key: &str) -> Option<T>
+    where
+        T::Err: Display,
+    {
+        self.string(key).map(|value| {
+            value.parse().unwrap_or_else(|error| {
+                panic!("Invalid format option ({}={}: {})", key, value, error)
+            })
+        })
+    }
+
+    pub fn value_enum<T: ValueEnum>(&mut self, key: &str) -> Option<T> {
+        self.string(key).map(|value| {
+            T::from_str(&value, true).unwrap_or_else(|error| {
+                panic!("Invalid format option ({}={}: {})", key, value, error)
+            })
+        })
+    }
+}
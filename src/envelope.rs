@@ -0,0 +1,177 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::sync::{Arc, OnceLock};
+
+use arrow_array::{ArrayRef, RecordBatch, StringArray, StructArray, UInt64Array};
+use arrow_cast::cast;
+use arrow_schema::{DataType, Field, FieldRef, Schema};
+use chrono::{SecondsFormat, Utc};
+use clap::ValueEnum;
+use parquet::file::metadata::ParquetMetaData;
+
+use crate::cast::json_compatible;
+use crate::CatArgs;
+
+/// Column of the index of each row in its file, carried through cat until rows are wrapped.
+pub const ROW_INDEX_COLUMN: &str = "__parquet2json_row_index";
+
+/// Envelope of metadata each row is wrapped in
+#[derive(ValueEnum, Clone, Copy)]
+pub enum EnvelopeFormat {
+    /// CloudEvents 1.0 in structured JSON mode, with the row as data
+    Cloudevents,
+}
+
+/// Returns the time rows are ingested at, the same for all rows of a run.
+fn ingested_at() -> &'static str {
+    static INGESTED_AT: OnceLock<String> = OnceLock::new();
+    INGESTED_AT.get_or_init(|| Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true))
+}
+
+/// Indices in a file of the rows read from it, in the order they are read.
+pub struct RowIndices {
+    ranges: VecDeque<Range<usize>>,
+}
+
+impl RowIndices {
+    /// Returns the indices of the rows of the given row groups, or of the given rows if selected,
+    /// after skipping a number of them.
+    pub fn new(
+        parquet_metadata: &ParquetMetaData,
+        row_groups: &[usize],
+        rows: Option<&[usize]>,
+        skipped: usize,
+    ) -> Self {
+        let ranges: VecDeque<Range<usize>> = match rows {
+            Some(rows) => {
+                let num_rows = parquet_metadata.file_metadata().num_rows() as usize;
+                let mut rows: Vec<usize> =
+                    rows.iter().copied().filter(|row| *row < num_rows).collect();
+                rows.sort_unstable();
+                rows.dedup();
+                rows.into_iter().map(|row| row..row + 1).collect()
+            }
+            None => {
+                let mut starts = vec![0];
+                for row_group in parquet_metadata.row_groups() {
+                    starts.push(starts.last().unwrap() + row_group.num_rows() as usize);
+                }
+                (row_groups.iter())
+                    .map(|row_group| starts[*row_group]..starts[row_group + 1])
+                    .collect()
+            }
+        };
+        let mut row_indices = RowIndices { ranges };
+        row_indices.take(skipped);
+        row_indices
+    }
+
+    fn take(&mut self, count: usize) -> Vec<u64> {
+        let mut indices = Vec::with_capacity(count);
+        while indices.len() < count {
+            let Some(range) = self.ranges.front_mut() else {
+                break;
+            };
+            let end = range.end.min(range.start + count - indices.len());
+            indices.extend((range.start..end).map(|row| row as u64));
+            range.start = end;
+            if range.start >= range.end {
+                self.ranges.pop_front();
+            }
+        }
+        indices
+    }
+
+    /// Skips the indices of rows that could not be read.
+    pub fn skip(&mut self, count: usize) {
+        self.take(count);
+    }
+
+    /// Returns the batch with a column of the indices of its rows, as the next rows read.
+    pub fn add_column(&mut self, batch: &RecordBatch) -> RecordBatch {
+        let indices = UInt64Array::from(self.take(batch.num_rows()));
+        let schema = batch.schema();
+        let mut fields: Vec<FieldRef> = schema.fields().iter().cloned().collect();
+        let mut columns = batch.columns().to_vec();
+        fields.push(Arc::new(Field::new(
+            ROW_INDEX_COLUMN,
+            DataType::UInt64,
+            false,
+        )));
+        columns.push(Arc::new(indices));
+        RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap()
+    }
+}
+
+enum Wrapping {
+    CloudEvents { event_type: String },
+    Key(String),
+}
+
+/// Metadata each row is wrapped in, of --envelope or --wrap: the file it was read from, its
+/// index in the file and the time of the run.
+pub struct Envelope {
+    wrapping: Wrapping,
+    source: String,
+}
+
+impl Envelope {
+    pub fn for_args(args: &CatArgs, source: &str) -> Option<Self> {
+        let wrapping = match (args.envelope, &args.wrap) {
+            (Some(EnvelopeFormat::Cloudevents), _) => Wrapping::CloudEvents {
+                event_type: args.event_type.clone(),
+            },
+            (None, Some(key)) => Wrapping::Key(key.clone()),
+            (None, None) => return None,
+        };
+        Some(Envelope {
+            wrapping,
+            source: source.to_string(),
+        })
+    }
+
+    /// Returns a row per row of the batch, with its columns under a single key and the metadata
+    /// of the envelope, taking the row indices from their column.
+    pub fn wrap(&self, batch: &RecordBatch) -> RecordBatch {
+        let schema = batch.schema();
+        let index = schema.index_of(ROW_INDEX_COLUMN).unwrap();
+        let row_indices = batch.column(index).clone();
+        let mut data = batch.clone();
+        data.remove_column(index);
+
+        let rows = batch.num_rows();
+        let constant = |value: &str| -> ArrayRef { Arc::new(StringArray::from(vec![value; rows])) };
+        // Rows are nested values of every format, written as JSON, so binary and decimal values
+        // are cast as for jsonl output beforehand
+        let data: ArrayRef = Arc::new(StructArray::from(json_compatible(data)));
+        let (names, columns): (Vec<&str>, Vec<ArrayRef>) = match &self.wrapping {
+            Wrapping::CloudEvents { event_type } => {
+                let ids = cast(&row_indices, &DataType::Utf8).unwrap();
+                [
+                    ("specversion", constant("1.0")),
+                    ("id", ids),
+                    ("source", constant(&self.source)),
+                    ("type", constant(event_type)),
+                    ("time", constant(ingested_at())),
+                    ("datacontenttype", constant("application/json")),
+                    ("rowindex", row_indices),
+                    ("data", data),
+                ]
+                .into_iter()
+                .unzip()
+            }
+            Wrapping::Key(key) => [
+                ("source", constant(&self.source)),
+                ("row", row_indices),
+                ("ingested_at", constant(ingested_at())),
+                (key.as_str(), data),
+            ]
+            .into_iter()
+            .unzip(),
+        };
+        let fields: Vec<Field> = (names.iter().zip(&columns))
+            .map(|(name, column)| Field::new(*name, column.data_type().clone(), false))
+            .collect();
+        RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap()
+    }
+}
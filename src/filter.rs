@@ -0,0 +1,235 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use arrow_arith::boolean::{and, and_kleene, is_not_null, or};
+use arrow_array::{Array, ArrayRef, BooleanArray, RecordBatch, Scalar, StringArray};
+use arrow_cast::cast;
+use arrow_ord::cmp;
+use arrow_schema::DataType;
+use arrow_select::filter::prep_null_mask_filter;
+
+use crate::between::between;
+use crate::metrics::count_filtered;
+use crate::CatArgs;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Op {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    pub column: String,
+    pub op: Op,
+    pub value: String,
+}
+
+impl Comparison {
+    fn parse(expression: &str) -> Self {
+        const OPS: [(&str, Op); 7] = [
+            ("!=", Op::NotEq),
+            ("<=", Op::LtEq),
+            (">=", Op::GtEq),
+            ("==", Op::Eq),
+            ("=", Op::Eq),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+        ];
+        let (position, token, op) = OPS
+            .iter()
+            .filter_map(|(token, op)| {
+                expression
+                    .find(token)
+                    .map(|position| (position, *token, *op))
+            })
+            .min_by_key(|(position, token, _)| (*position, usize::MAX - token.len()))
+            .unwrap_or_else(|| panic!("Invalid predicate ({})", expression));
+
+        let column = expression[..position].trim().to_string();
+        let value = expression[position + token.len()..].trim();
+        let value = value
+            .strip_prefix('\'')
+            .and_then(|value| value.strip_suffix('\''))
+            .unwrap_or(value)
+            .to_string();
+
+        Comparison { column, op, value }
+    }
+
+    /// Returns the literal value cast to the given type, as a single-element array.
+    pub fn value_as(&self, data_type: &DataType) -> ArrayRef {
+        let value: ArrayRef = Arc::new(StringArray::from(vec![self.value.as_str()]));
+        cast(&value, data_type)
+            .unwrap_or_else(|e| panic!("Invalid value for {} ({}): {}", self.column, self.value, e))
+    }
+
+    /// Compares each value of the array against the literal.
+    pub fn evaluate(&self, array: &ArrayRef) -> BooleanArray {
+        let value = Scalar::new(self.value_as(array.data_type()));
+        match self.op {
+            Op::Eq => cmp::eq(array, &value),
+            Op::NotEq => cmp::neq(array, &value),
+            Op::Lt => cmp::lt(array, &value),
+            Op::LtEq => cmp::lt_eq(array, &value),
+            Op::Gt => cmp::gt(array, &value),
+            Op::GtEq => cmp::gt_eq(array, &value),
+        }
+        .unwrap()
+    }
+
+    /// Returns whether any value within the inclusive range could match.
+    pub fn may_match(&self, min: &ArrayRef, max: &ArrayRef) -> bool {
+        let value = self.value_as(min.data_type());
+        let holds = |result: Result<BooleanArray, _>| result.unwrap().value(0);
+        match self.op {
+            Op::Eq => holds(cmp::lt_eq(min, &value)) && holds(cmp::gt_eq(max, &value)),
+            Op::NotEq => !(holds(cmp::eq(min, &value)) && holds(cmp::eq(max, &value))),
+            Op::Lt => holds(cmp::lt(min, &value)),
+            Op::LtEq => holds(cmp::lt_eq(min, &value)),
+            Op::Gt => holds(cmp::gt(max, &value)),
+            Op::GtEq => holds(cmp::gt_eq(max, &value)),
+        }
+    }
+}
+
+/// Columns that values of rows must not all be null of.
+#[derive(Clone, Debug)]
+pub enum NullCheck {
+    /// Rows with a null in any of the columns are dropped, as with --drop-if-null
+    AllNonNull(Vec<String>),
+    /// Rows with nulls in all of the columns are dropped, as with --keep-if-any-nonnull
+    AnyNonNull(Vec<String>),
+}
+
+impl NullCheck {
+    fn columns(&self) -> &[String] {
+        match self {
+            NullCheck::AllNonNull(columns) | NullCheck::AnyNonNull(columns) => columns,
+        }
+    }
+
+    /// Returns the mask of rows passing the check.
+    fn evaluate(&self, batch: &RecordBatch) -> BooleanArray {
+        let non_null = self.columns().iter().map(|column| {
+            let column = batch
+                .column_by_name(column)
+                .unwrap_or_else(|| panic!("Column not found ({})", column));
+            is_not_null(column).unwrap()
+        });
+        let combined = match self {
+            NullCheck::AllNonNull(_) => non_null.reduce(|a, b| and(&a, &b).unwrap()),
+            NullCheck::AnyNonNull(_) => non_null.reduce(|a, b| or(&a, &b).unwrap()),
+        };
+        combined.unwrap_or_else(|| BooleanArray::from(vec![true; batch.num_rows()]))
+    }
+}
+
+/// Splits an expression on ` and ` or ` AND `, except within quoted literals.
+fn split_conjunction(expression: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut quoted = false;
+    for (position, c) in expression.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            ' ' if !quoted && position >= start => {
+                let rest = &expression[position..];
+                if rest.starts_with(" and ") || rest.starts_with(" AND ") {
+                    parts.push(&expression[start..position]);
+                    start = position + " and ".len();
+                }
+            }
+            _ => {}
+        }
+    }
+    parts.push(&expression[start..]);
+    parts
+}
+
+/// A conjunction of column comparisons, e.g. `country = 'US' and id >= 10`, and of null checks.
+#[derive(Clone, Debug, Default)]
+pub struct Predicate {
+    pub comparisons: Vec<Comparison>,
+    pub null_checks: Vec<NullCheck>,
+}
+
+impl Predicate {
+    pub fn parse(expression: &str) -> Self {
+        let comparisons = split_conjunction(expression)
+            .into_iter()
+            .map(Comparison::parse)
+            .collect();
+
+        Predicate {
+            comparisons,
+            null_checks: vec![],
+        }
+    }
+
+    /// Returns the predicate of --where, --between, --drop-if-null and --keep-if-any-nonnull,
+    /// if any.
+    pub fn for_args(args: &CatArgs) -> Option<Self> {
+        let mut predicate = args.predicate.as_deref().map(Predicate::parse);
+        if let Some(range) = &args.between {
+            predicate
+                .get_or_insert_with(Default::default)
+                .comparisons
+                .extend(between(&range[0], &range[1], &args.tz));
+        }
+        let columns = |columns: &Option<String>| -> Option<Vec<String>> {
+            let columns = columns.as_ref()?.split(',');
+            Some(columns.map(|column| column.trim().to_string()).collect())
+        };
+        if let Some(columns) = columns(&args.drop_if_null) {
+            (predicate.get_or_insert_with(Default::default).null_checks)
+                .push(NullCheck::AllNonNull(columns));
+        }
+        if let Some(columns) = columns(&args.keep_if_any_nonnull) {
+            (predicate.get_or_insert_with(Default::default).null_checks)
+                .push(NullCheck::AnyNonNull(columns));
+        }
+        predicate
+    }
+
+    pub fn columns(&self) -> Vec<String> {
+        let compared = self
+            .comparisons
+            .iter()
+            .map(|comparison| comparison.column.clone());
+        let checked = self
+            .null_checks
+            .iter()
+            .flat_map(|null_check| null_check.columns().to_vec());
+        compared.chain(checked).collect()
+    }
+
+    /// Returns the mask of rows matching all comparisons and null checks; null values never
+    /// match. Masks are computed a column at a time with compute kernels, rather than a row at a
+    /// time.
+    pub fn evaluate(&self, batch: &RecordBatch) -> BooleanArray {
+        let started = Instant::now();
+        let comparisons = self.comparisons.iter().map(|comparison| {
+            let column = batch
+                .column_by_name(&comparison.column)
+                .unwrap_or_else(|| panic!("Column not found ({})", comparison.column));
+            comparison.evaluate(column)
+        });
+        let null_checks = (self.null_checks.iter()).map(|null_check| null_check.evaluate(batch));
+        let mask = comparisons
+            .chain(null_checks)
+            .reduce(|mask, matches| and_kleene(&mask, &matches).unwrap());
+        // Rows compared with null values are null in the mask, and filtered out as not matching
+        let mask = match mask {
+            Some(mask) if mask.nulls().is_some() => prep_null_mask_filter(&mask),
+            Some(mask) => mask,
+            None => BooleanArray::from(vec![true; batch.num_rows()]),
+        };
+        count_filtered(batch.num_rows(), mask.true_count(), started.elapsed());
+        mask
+    }
+}
@@ -0,0 +1,50 @@
+use arrow_schema::{DataType, Field, Schema};
+use sha2::{Digest, Sha256};
+
+/// Returns the type of a field as described in fingerprints, with dictionaries described by
+/// the type of their values, as writers choose whether to dictionary encode.
+fn normalized_type(data_type: &DataType) -> String {
+    match data_type {
+        DataType::Dictionary(_, value_type) => normalized_type(value_type),
+        DataType::List(field) => format!("List<{}>", normalized_field(field)),
+        DataType::LargeList(field) => format!("LargeList<{}>", normalized_field(field)),
+        DataType::FixedSizeList(field, size) => {
+            format!("FixedSizeList<{}, {}>", size, normalized_field(field))
+        }
+        DataType::Struct(fields) => format!(
+            "Struct<{}>",
+            fields
+                .iter()
+                .map(|field| normalized_field(field))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        DataType::Map(field, sorted) => format!("Map<{}, {}>", normalized_field(field), sorted),
+        data_type => format!("{:?}", data_type),
+    }
+}
+
+/// Returns the name, type and nullability of a field, leaving out its metadata.
+fn normalized_field(field: &Field) -> String {
+    format!(
+        "{}: {}{}",
+        serde_json::to_string(field.name()).unwrap(),
+        normalized_type(field.data_type()),
+        if field.is_nullable() { "" } else { " not null" }
+    )
+}
+
+/// Returns the SHA-256 of the names, types and nullability of the columns of a schema, in
+/// order, as hex. Metadata, such as that of pandas or Spark, is left out, so that rewriting
+/// files with other writers keeps their fingerprint.
+pub fn schema_fingerprint(schema: &Schema) -> String {
+    let normalized: Vec<String> = schema
+        .fields()
+        .iter()
+        .map(|field| normalized_field(field))
+        .collect();
+    Sha256::digest(normalized.join("\n"))
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
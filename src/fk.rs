@@ -0,0 +1,201 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use object_store::{ObjectMeta, ObjectStore};
+use serde_json::{json, Map, Value};
+
+use crate::dataset::list_files;
+use crate::duplicates::{for_each_key, key_hash, key_size};
+use crate::output::ReportWriter;
+use crate::source::store_for_location;
+use crate::spill::{SpillArgs, SpillPartitions};
+
+/// A value without a match with its (file index, row index) location.
+type Violation = (Vec<String>, (usize, usize));
+
+/// Key columns of reference files, from a `FILE:COLUMN` argument.
+pub struct Reference {
+    location: String,
+    columns: Vec<String>,
+}
+
+impl Reference {
+    pub fn parse(argument: &str) -> Self {
+        // URLs contain colons too, so the columns follow the last one
+        match argument.rsplit_once(':') {
+            Some((location, columns)) if !location.is_empty() && !columns.trim().is_empty() => {
+                Reference {
+                    location: location.to_string(),
+                    columns: (columns.split(','))
+                        .map(|column| column.trim().to_string())
+                        .collect(),
+                }
+            }
+            _ => panic!(
+                "Invalid reference, expected FILE:COLUMN (e.g. users.parquet:id) ({})",
+                argument
+            ),
+        }
+    }
+
+    async fn files(&self) -> (Arc<dyn ObjectStore>, Vec<ObjectMeta>) {
+        let (storage_container, location) = store_for_location(&self.location).await;
+        let files = list_files(&storage_container, &location).await;
+        (storage_container, files)
+    }
+}
+
+/// Counts of the checked values and of those without a match, with the first of them.
+struct Report {
+    checked: usize,
+    violations: usize,
+    samples: Vec<Violation>,
+    max_samples: usize,
+}
+
+impl Report {
+    fn violation(&mut self, key: Vec<String>, location: (usize, usize)) {
+        self.violations += 1;
+        self.samples.push((key, location));
+        if self.samples.len() > self.max_samples * 2 {
+            self.truncate();
+        }
+    }
+
+    /// Keeps the first samples by location, as partitions are checked out of order.
+    fn truncate(&mut self) {
+        self.samples.sort_by_key(|(_, location)| *location);
+        self.samples.truncate(self.max_samples);
+    }
+}
+
+/// Returns the keys of reference files, unless they exceed the memory budget.
+async fn reference_keys(
+    reference_container: &Arc<dyn ObjectStore>,
+    reference_files: &[ObjectMeta],
+    columns: &[&str],
+    budget: usize,
+) -> Result<HashSet<Vec<String>>, usize> {
+    let mut keys = HashSet::new();
+    let mut keys_size = 0;
+    for file in reference_files {
+        for_each_key(reference_container, file, columns, |key, _| {
+            keys_size += key_size(&key);
+            if keys_size <= budget {
+                keys.insert(key);
+            }
+        })
+        .await;
+    }
+    match keys_size <= budget {
+        true => Ok(keys),
+        false => Err(keys_size),
+    }
+}
+
+/// Outputs the number of non-null values of the columns checked, and of those not found in the
+/// key columns of reference files, with samples, as a JSON line. Reference keys are held in a
+/// hash set within the memory budget; beyond it, reference keys and values are hash partitioned
+/// into spill files alike, and each partition is checked with the reference keys of its own.
+/// Returns whether any value was not found.
+pub async fn check_fk(
+    out: &mut ReportWriter,
+    storage_container: Arc<dyn ObjectStore>,
+    files: &[ObjectMeta],
+    column: &str,
+    reference: &Reference,
+    max_samples: usize,
+    spill_args: &SpillArgs,
+) -> bool {
+    let key_columns: Vec<&str> = column.split(',').map(str::trim).collect();
+    let reference_columns: Vec<&str> = reference.columns.iter().map(String::as_str).collect();
+    if key_columns.len() != reference_columns.len() {
+        panic!(
+            "Invalid reference, expected as many columns as checked ({} and {})",
+            column,
+            reference_columns.join(",")
+        );
+    }
+
+    let (reference_container, reference_files) = reference.files().await;
+    let mut report = Report {
+        checked: 0,
+        violations: 0,
+        samples: vec![],
+        max_samples,
+    };
+    match reference_keys(
+        &reference_container,
+        &reference_files,
+        &reference_columns,
+        spill_args.budget(),
+    )
+    .await
+    {
+        Ok(keys) => {
+            for (file_index, file) in files.iter().enumerate() {
+                for_each_key(&storage_container, file, &key_columns, |key, row_index| {
+                    report.checked += 1;
+                    if !keys.contains(&key) {
+                        report.violation(key, (file_index, row_index));
+                    }
+                })
+                .await;
+            }
+        }
+        Err(keys_size) => {
+            // Both are partitioned alike for the size of the reference keys, which are held in
+            // memory a partition at a time, while values are streamed
+            let mut reference_partitions = SpillPartitions::new(spill_args, keys_size);
+            for file in &reference_files {
+                for_each_key(&reference_container, file, &reference_columns, |key, _| {
+                    reference_partitions.write(key_hash(&key), &key);
+                })
+                .await;
+            }
+            let mut partitions = SpillPartitions::new(spill_args, keys_size);
+            for (file_index, file) in files.iter().enumerate() {
+                for_each_key(&storage_container, file, &key_columns, |key, row_index| {
+                    partitions.write(key_hash(&key), &(key, (file_index, row_index)));
+                })
+                .await;
+            }
+
+            let reference_keys = reference_partitions.partitions::<Vec<String>>();
+            for (keys, values) in reference_keys.zip(partitions.streams::<Violation>()) {
+                let keys: HashSet<Vec<String>> = keys.into_iter().collect();
+                for (key, location) in values {
+                    report.checked += 1;
+                    if !keys.contains(&key) {
+                        report.violation(key, location);
+                    }
+                }
+            }
+        }
+    }
+    report.truncate();
+
+    let samples: Vec<Value> = report
+        .samples
+        .iter()
+        .map(|(key, (file_index, row_index))| {
+            let key_object: Map<String, Value> = key_columns
+                .iter()
+                .zip(key)
+                .map(|(column_name, value)| (column_name.to_string(), json!(value)))
+                .collect();
+            json!({
+                "key": key_object,
+                "file": files[*file_index].location.to_string(),
+                "row": row_index
+            })
+        })
+        .collect();
+    out.write_line(json!({
+        "checked": report.checked,
+        "violations": report.violations,
+        "samples": samples
+    }))
+    .await;
+    report.violations > 0
+}
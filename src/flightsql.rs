@@ -0,0 +1,393 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_buffer::Buffer;
+use arrow_cast::base64::{Engine, BASE64_STANDARD};
+use arrow_ipc::convert::fb_to_schema;
+use arrow_ipc::reader::{read_dictionary, read_record_batch};
+use arrow_ipc::{root_as_message, MessageHeader};
+use arrow_schema::SchemaRef;
+use arrow_select::filter::filter_record_batch;
+use prost::Message;
+use tonic::client::Grpc;
+use tonic::codec::ProstCodec;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::metadata::AsciiMetadataValue;
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+use tonic::{Request, Streaming};
+use url::Url;
+
+use crate::filter::Predicate;
+use crate::output::Output;
+use crate::profile::apply_profile;
+use crate::CatArgs;
+
+// Messages of the Flight and Flight SQL protocols (Flight.proto, FlightSql.proto) used by cat
+
+#[derive(Clone, PartialEq, Message)]
+struct Any {
+    #[prost(string, tag = "1")]
+    type_url: String,
+    #[prost(bytes = "vec", tag = "2")]
+    value: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct CommandStatementQuery {
+    #[prost(string, tag = "1")]
+    query: String,
+    #[prost(bytes = "vec", optional, tag = "2")]
+    transaction_id: Option<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct HandshakeRequest {
+    #[prost(uint64, tag = "1")]
+    protocol_version: u64,
+    #[prost(bytes = "vec", tag = "2")]
+    payload: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct FlightDescriptor {
+    #[prost(int32, tag = "1")]
+    r#type: i32,
+    #[prost(bytes = "vec", tag = "2")]
+    cmd: Vec<u8>,
+    #[prost(string, repeated, tag = "3")]
+    path: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Ticket {
+    #[prost(bytes = "vec", tag = "1")]
+    ticket: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Location {
+    #[prost(string, tag = "1")]
+    uri: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct FlightEndpoint {
+    #[prost(message, optional, tag = "1")]
+    ticket: Option<Ticket>,
+    #[prost(message, repeated, tag = "2")]
+    location: Vec<Location>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct FlightInfo {
+    #[prost(bytes = "vec", tag = "1")]
+    schema: Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    flight_descriptor: Option<FlightDescriptor>,
+    #[prost(message, repeated, tag = "3")]
+    endpoint: Vec<FlightEndpoint>,
+    #[prost(int64, tag = "4")]
+    total_records: i64,
+    #[prost(int64, tag = "5")]
+    total_bytes: i64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct FlightData {
+    #[prost(message, optional, tag = "1")]
+    flight_descriptor: Option<FlightDescriptor>,
+    #[prost(bytes = "vec", tag = "2")]
+    data_header: Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    app_metadata: Vec<u8>,
+    #[prost(bytes = "vec", tag = "1000")]
+    data_body: Vec<u8>,
+}
+
+const DESCRIPTOR_TYPE_CMD: i32 = 2;
+
+pub fn is_flight_sql_location(location: &str) -> bool {
+    location.starts_with("flightsql://") || location.starts_with("flightsql+tls://")
+}
+
+/// TLS settings of connections, trusting the bundled Mozilla roots, as static builds run without
+/// a system certificate store.
+#[cfg(feature = "static")]
+fn client_tls_config() -> ClientTlsConfig {
+    ClientTlsConfig::new().with_webpki_roots()
+}
+
+/// TLS settings of connections, trusting the platform's roots.
+#[cfg(not(feature = "static"))]
+fn client_tls_config() -> ClientTlsConfig {
+    ClientTlsConfig::new().with_native_roots()
+}
+
+async fn connect(uri: &str, tls: bool) -> Grpc<Channel> {
+    let mut endpoint = Endpoint::from_shared(uri.to_string())
+        .unwrap_or_else(|error| panic!("Invalid Flight SQL location ({}: {})", uri, error));
+    if tls {
+        endpoint = endpoint.tls_config(client_tls_config()).unwrap();
+    }
+    let channel = endpoint
+        .connect()
+        .await
+        .unwrap_or_else(|error| panic!("Flight SQL connection failed ({}: {})", uri, error));
+    Grpc::new(channel)
+}
+
+struct FlightSqlClient {
+    grpc: Grpc<Channel>,
+    authorization: Option<AsciiMetadataValue>,
+}
+
+impl FlightSqlClient {
+    fn request<T>(&self, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        if let Some(authorization) = &self.authorization {
+            request
+                .metadata_mut()
+                .insert("authorization", authorization.clone());
+        }
+        request
+    }
+
+    /// Authenticates with basic credentials, continuing with the bearer token returned by the
+    /// server, if any.
+    async fn handshake(&mut self, username: &str, password: &str) {
+        let credentials = BASE64_STANDARD.encode(format!("{}:{}", username, password));
+        self.authorization = Some(format!("Basic {}", credentials).parse().unwrap());
+        let request = self.request(futures::stream::iter(vec![HandshakeRequest::default()]));
+        self.grpc.ready().await.unwrap();
+        let response = self
+            .grpc
+            .streaming::<_, HandshakeRequest, HandshakeRequest, _>(
+                request,
+                PathAndQuery::from_static("/arrow.flight.protocol.FlightService/Handshake"),
+                ProstCodec::default(),
+            )
+            .await
+            .unwrap_or_else(|status| panic!("Flight SQL handshake failed ({})", status.message()));
+        if let Some(authorization) = response.metadata().get("authorization") {
+            self.authorization = Some(authorization.clone());
+        }
+    }
+
+    async fn get_flight_info(&mut self, query: &str) -> FlightInfo {
+        let command = Any {
+            type_url: "type.googleapis.com/arrow.flight.protocol.sql.CommandStatementQuery"
+                .to_string(),
+            value: CommandStatementQuery {
+                query: query.to_string(),
+                transaction_id: None,
+            }
+            .encode_to_vec(),
+        };
+        let request = self.request(FlightDescriptor {
+            r#type: DESCRIPTOR_TYPE_CMD,
+            cmd: command.encode_to_vec(),
+            path: vec![],
+        });
+        self.grpc.ready().await.unwrap();
+        self.grpc
+            .unary(
+                request,
+                PathAndQuery::from_static("/arrow.flight.protocol.FlightService/GetFlightInfo"),
+                ProstCodec::default(),
+            )
+            .await
+            .unwrap_or_else(|status| panic!("Flight SQL query failed ({})", status.message()))
+            .into_inner()
+    }
+
+    async fn do_get(&mut self, ticket: Ticket) -> Streaming<FlightData> {
+        let request = self.request(ticket);
+        self.grpc.ready().await.unwrap();
+        self.grpc
+            .server_streaming(
+                request,
+                PathAndQuery::from_static("/arrow.flight.protocol.FlightService/DoGet"),
+                ProstCodec::default(),
+            )
+            .await
+            .unwrap_or_else(|status| panic!("Flight SQL fetch failed ({})", status.message()))
+            .into_inner()
+    }
+}
+
+/// Decodes the Arrow IPC messages of a Flight data stream.
+#[derive(Default)]
+struct FlightDecoder {
+    schema: Option<SchemaRef>,
+    dictionaries: HashMap<i64, ArrayRef>,
+}
+
+impl FlightDecoder {
+    fn decode(&mut self, data: FlightData) -> Option<RecordBatch> {
+        let message = root_as_message(&data.data_header)
+            .unwrap_or_else(|error| panic!("Invalid Flight data ({})", error));
+        match message.header_type() {
+            MessageHeader::Schema => {
+                let schema = fb_to_schema(message.header_as_schema().unwrap());
+                self.schema = Some(Arc::new(schema));
+                None
+            }
+            MessageHeader::DictionaryBatch => {
+                read_dictionary(
+                    &Buffer::from_vec(data.data_body),
+                    message.header_as_dictionary_batch().unwrap(),
+                    self.schema.as_ref().expect("Flight data without schema"),
+                    &mut self.dictionaries,
+                    &message.version(),
+                )
+                .unwrap();
+                None
+            }
+            MessageHeader::RecordBatch => Some(
+                read_record_batch(
+                    &Buffer::from_vec(data.data_body),
+                    message.header_as_record_batch().unwrap(),
+                    self.schema.clone().expect("Flight data without schema"),
+                    &self.dictionaries,
+                    None,
+                    &message.version(),
+                )
+                .unwrap(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the gRPC URI of a location, and whether it uses TLS.
+fn grpc_uri(location: &Url) -> (String, bool) {
+    let tls = location.scheme().ends_with("+tls");
+    let scheme = if tls { "https" } else { "http" };
+    let host = location.host_str().unwrap_or("localhost");
+    let uri = match location.port() {
+        Some(port) => format!("{}://{}:{}", scheme, host, port),
+        None => format!("{}://{}", scheme, host),
+    };
+    (uri, tls)
+}
+
+fn project(batch: &RecordBatch, columns: &str) -> RecordBatch {
+    let schema = batch.schema();
+    let mut indices = vec![];
+    for column_name in columns.split(',') {
+        let (column_name, is_optional) = match column_name.strip_prefix('?') {
+            Some(column_name) => (column_name, true),
+            None => (column_name, false),
+        };
+        match schema.index_of(column_name) {
+            Ok(index) => indices.push(index),
+            Err(_) if is_optional => {}
+            Err(_) => panic!("Column not found ({})", column_name),
+        }
+    }
+    batch.project(&indices).unwrap()
+}
+
+/// Outputs the result of the `query` of a `flightsql://host:port?query=...` location, with
+/// `flightsql+tls://` for TLS, credentials as user info and a bearer token as `token`.
+pub async fn cat(location: &str, args: &CatArgs, output: &mut Output) {
+    if args.rows.is_some()
+        || args.rows_file.is_some()
+        || args.semi_join.is_some()
+        || args.anti_join.is_some()
+        || args.enrich.is_some()
+        || args.with_annotations
+    {
+        panic!("Row selection, joins and annotations are not supported with Flight SQL queries");
+    }
+    if args.offset.is_negative() {
+        panic!("Negative offsets are not supported with Flight SQL queries");
+    }
+    if args.envelope.is_some() || args.wrap.is_some() {
+        panic!("Envelopes are not supported with Flight SQL queries, which have no source files");
+    }
+
+    let url = Url::parse(location).unwrap();
+    let query_pairs: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    let query = query_pairs
+        .get("query")
+        .unwrap_or_else(|| panic!("Missing query parameter ({})", location));
+
+    let (uri, tls) = grpc_uri(&url);
+    let mut client = FlightSqlClient {
+        grpc: connect(&uri, tls).await,
+        authorization: query_pairs
+            .get("token")
+            .map(|token| format!("Bearer {}", token).parse().unwrap()),
+    };
+    if !url.username().is_empty() {
+        let username = urlencoding::decode(url.username()).unwrap();
+        let password = urlencoding::decode(url.password().unwrap_or_default()).unwrap();
+        client.handshake(&username, &password).await;
+    }
+
+    let predicate = Predicate::for_args(args);
+
+    let mut offset = args.offset as usize;
+    let mut remaining = args.limit;
+    let flight_info = client.get_flight_info(query).await;
+    'endpoints: for endpoint in flight_info.endpoint {
+        let Some(ticket) = endpoint.ticket else {
+            continue;
+        };
+        // Endpoints may be served elsewhere, unless the location asks to reuse the connection
+        let location = endpoint
+            .location
+            .first()
+            .and_then(|location| Url::parse(&location.uri).ok())
+            .filter(|location| location.scheme() != "arrow-flight-reuse-connection");
+        let grpc = match location {
+            Some(location) => {
+                let (uri, tls) = grpc_uri(&location);
+                connect(&uri, tls).await
+            }
+            None => client.grpc.clone(),
+        };
+        let mut endpoint_client = FlightSqlClient {
+            grpc,
+            authorization: client.authorization.clone(),
+        };
+
+        let mut stream = endpoint_client.do_get(ticket).await;
+        let mut decoder = FlightDecoder::default();
+        while let Some(data) = stream
+            .message()
+            .await
+            .unwrap_or_else(|status| panic!("Flight SQL fetch failed ({})", status.message()))
+        {
+            let Some(mut batch) = decoder.decode(data) else {
+                continue;
+            };
+            if offset >= batch.num_rows() {
+                offset -= batch.num_rows();
+                continue;
+            }
+            batch = batch.slice(offset, batch.num_rows() - offset);
+            offset = 0;
+            if let Some(predicate) = &predicate {
+                batch = filter_record_batch(&batch, &predicate.evaluate(&batch)).unwrap();
+            }
+            if let Some(remaining) = remaining.as_mut() {
+                batch = batch.slice(0, batch.num_rows().min(*remaining));
+                *remaining -= batch.num_rows();
+            }
+            if let Some(columns) = &args.columns {
+                batch = project(&batch, columns);
+            }
+            if let Some(profile) = args.profile {
+                batch = apply_profile(&batch, profile);
+            }
+            output.write_batch(batch).await;
+
+            if remaining == Some(0) {
+                break 'endpoints;
+            }
+        }
+    }
+}
@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+
+use arrow_array::cast::AsArray;
+use arrow_array::{new_empty_array, Array, ArrayRef, RecordBatch, UInt32Array, UInt64Array};
+use arrow_cast::base64::{Engine, BASE64_STANDARD};
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_ipc::writer::{IpcDataGenerator, IpcWriteOptions};
+use arrow_schema::{DataType, Field, FieldRef, Schema};
+use arrow_select::concat::concat;
+use arrow_select::take::take;
+use parquet::arrow::arrow_reader::{ArrowReaderMetadata, ArrowReaderOptions};
+use parquet::arrow::async_reader::ParquetObjectReader;
+use parquet::arrow::{ParquetRecordBatchStreamBuilder, ProjectionMask, ARROW_SCHEMA_META_KEY};
+use parquet::file::metadata::{FileMetaData, KeyValue, ParquetMetaData};
+use tokio_stream::StreamExt;
+
+use crate::cast::json_compatible;
+use crate::dataset::Footer;
+use crate::jsonl::JsonLinesWriter;
+use crate::output::{ReportWriter, BATCH_SIZE};
+
+/// Counts of the distinct values of a column, with the values taken from the batches they were
+/// first seen in.
+#[derive(Default)]
+struct Frequencies {
+    positions: HashMap<String, usize>,
+    counts: Vec<usize>,
+    values: Vec<ArrayRef>,
+}
+
+impl Frequencies {
+    /// Adds the counts of values at the given indices of an array.
+    fn add(&mut self, array: &ArrayRef, counts: impl Iterator<Item = (usize, usize)>) {
+        let formatter = ArrayFormatter::try_new(array.as_ref(), &FormatOptions::default()).unwrap();
+        let mut new_indices: Vec<u32> = vec![];
+        let mut key = String::new();
+        for (index, count) in counts {
+            key.clear();
+            write!(key, "{}", formatter.value(index)).unwrap();
+            match self.positions.get(&key) {
+                Some(position) => self.counts[*position] += count,
+                None => {
+                    self.positions.insert(key.clone(), self.counts.len());
+                    self.counts.push(count);
+                    new_indices.push(index as u32);
+                }
+            }
+        }
+        if !new_indices.is_empty() {
+            let new_values = take(array.as_ref(), &UInt32Array::from(new_indices), None).unwrap();
+            self.values.push(new_values);
+        }
+    }
+
+    /// Adds the counts of the keys of a dictionary, by value.
+    fn add_keys(&mut self, values: &ArrayRef, counts: Vec<usize>) {
+        let counts = counts.into_iter().enumerate();
+        self.add(values, counts.filter(|(_, count)| *count > 0));
+    }
+}
+
+/// Encodes an Arrow schema the way Arrow writers embed it in Parquet metadata.
+fn encode_arrow_schema(schema: &Schema) -> String {
+    let encoded = IpcDataGenerator::default().schema_to_bytes(schema, &IpcWriteOptions::default());
+    let mut message = vec![255, 255, 255, 255];
+    message.extend((encoded.ipc_message.len() as u32).to_le_bytes());
+    message.extend(encoded.ipc_message);
+    BASE64_STANDARD.encode(message)
+}
+
+/// Returns the reader metadata of a file with the given string or binary column read as a
+/// dictionary array, so that values decoded from dictionary pages are counted by their keys and
+/// formatted once per column chunk rather than once per row.
+fn dictionary_metadata(metadata: &ArrowReaderMetadata, column: &str) -> ArrowReaderMetadata {
+    let schema = metadata.schema();
+    let fields: Vec<FieldRef> = schema
+        .fields()
+        .iter()
+        .map(|field| match field.data_type() {
+            DataType::Utf8 | DataType::LargeUtf8 | DataType::Binary | DataType::LargeBinary
+                if field.name() == column =>
+            {
+                let data_type = DataType::Dictionary(
+                    Box::new(DataType::Int32),
+                    Box::new(field.data_type().clone()),
+                );
+                Arc::new(field.as_ref().clone().with_data_type(data_type))
+            }
+            _ => field.clone(),
+        })
+        .collect();
+    let schema = Schema::new_with_metadata(fields, schema.metadata().clone());
+
+    let parquet_metadata = metadata.metadata();
+    let file_metadata = parquet_metadata.file_metadata();
+    let mut key_value_metadata: Vec<KeyValue> = file_metadata
+        .key_value_metadata()
+        .into_iter()
+        .flatten()
+        .filter(|key_value| key_value.key != ARROW_SCHEMA_META_KEY)
+        .cloned()
+        .collect();
+    key_value_metadata.push(KeyValue::new(
+        ARROW_SCHEMA_META_KEY.to_string(),
+        encode_arrow_schema(&schema),
+    ));
+    let file_metadata = FileMetaData::new(
+        file_metadata.version(),
+        file_metadata.num_rows(),
+        file_metadata.created_by().map(String::from),
+        Some(key_value_metadata),
+        file_metadata.schema_descr_ptr(),
+        file_metadata.column_orders().cloned(),
+    );
+    let parquet_metadata =
+        ParquetMetaData::new(file_metadata, parquet_metadata.row_groups().to_vec());
+    ArrowReaderMetadata::try_new(Arc::new(parquet_metadata), ArrowReaderOptions::new()).unwrap()
+}
+
+/// Adds the counts of the non-null values of a column in a file, or in the given row groups of it.
+async fn count_values(
+    frequencies: &mut Frequencies,
+    data_type: &mut Option<DataType>,
+    (storage_container, meta, metadata): &Footer,
+    column: &str,
+    row_groups: Option<Vec<usize>>,
+) {
+    let metadata = dictionary_metadata(metadata, column);
+    let reader = ParquetObjectReader::new(storage_container.clone(), meta.clone());
+    let builder = ParquetRecordBatchStreamBuilder::new_with_metadata(reader, metadata);
+
+    let schema_descr = builder.parquet_schema();
+    let index = schema_descr
+        .root_schema()
+        .get_fields()
+        .iter()
+        .position(|field| field.name() == column)
+        .unwrap_or_else(|| panic!("Column not found ({}) in {}", column, meta.location));
+    let projection_mask = ProjectionMask::roots(schema_descr, [index]);
+    let mut builder = builder
+        .with_projection(projection_mask)
+        .with_batch_size(BATCH_SIZE);
+    if let Some(row_groups) = row_groups {
+        builder = builder.with_row_groups(row_groups);
+    }
+    let mut iter = builder.build().unwrap();
+
+    // Keys are counted while batches share the dictionary of a column chunk
+    let mut dictionary: Option<(ArrayRef, Vec<usize>)> = None;
+    while let Some(batch) = iter.next().await {
+        let batch: RecordBatch = batch.unwrap();
+        let array = batch.column(0);
+        match array.as_any_dictionary_opt() {
+            Some(dictionary_array) => {
+                let values = dictionary_array.values();
+                data_type.get_or_insert_with(|| values.data_type().clone());
+                if !matches!(&dictionary, Some((other, _)) if Arc::ptr_eq(other, values)) {
+                    if let Some((values, counts)) = dictionary.take() {
+                        frequencies.add_keys(&values, counts);
+                    }
+                    dictionary = Some((values.clone(), vec![0; values.len()]));
+                }
+                let (_, counts) = dictionary.as_mut().unwrap();
+                for (row, key) in dictionary_array.normalized_keys().into_iter().enumerate() {
+                    if array.is_valid(row) {
+                        counts[key] += 1;
+                    }
+                }
+            }
+            None => {
+                data_type.get_or_insert_with(|| array.data_type().clone());
+                let rows = (0..array.len()).filter(|row| array.is_valid(*row));
+                frequencies.add(array, rows.map(|row| (row, 1)));
+            }
+        }
+    }
+    if let Some((values, counts)) = dictionary.take() {
+        frequencies.add_keys(&values, counts);
+    }
+}
+
+/// Returns the next number of a SplitMix64 sequence.
+fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Returns a random subset of the row groups of all files, as (file, row group) pairs in file
+/// order.
+fn sample_row_groups(footers: &[Footer], count: usize, seed: u64) -> Vec<(usize, usize)> {
+    let mut row_groups: Vec<(usize, usize)> = footers
+        .iter()
+        .enumerate()
+        .flat_map(|(file, (_, _, metadata))| {
+            (0..metadata.metadata().num_row_groups()).map(move |row_group| (file, row_group))
+        })
+        .collect();
+    let count = count.min(row_groups.len());
+    let mut state = seed;
+    for index in 0..count {
+        let other = index + (next_random(&mut state) % (row_groups.len() - index) as u64) as usize;
+        row_groups.swap(index, other);
+    }
+    row_groups.truncate(count);
+    row_groups.sort_unstable();
+    row_groups
+}
+
+/// Sums over the row groups of a sample of the counts of each value, so that the count of a
+/// value across all rows is estimated as a ratio of the sample, along with its variance between
+/// row groups, which are sampled whole rather than row by row.
+#[derive(Default)]
+struct SampleSums {
+    row_groups: usize,
+    rows: f64,
+    rows_squared: f64,
+    counts_squared: Vec<f64>,
+    counts_by_rows: Vec<f64>,
+}
+
+impl SampleSums {
+    /// Adds a row group of the given rows, with the counts of values added since the previous.
+    fn add(&mut self, rows: usize, counts: &[usize], previous: &[usize]) {
+        let rows = rows as f64;
+        self.row_groups += 1;
+        self.rows += rows;
+        self.rows_squared += rows * rows;
+        self.counts_squared.resize(counts.len(), 0.0);
+        self.counts_by_rows.resize(counts.len(), 0.0);
+        for (position, count) in counts.iter().enumerate() {
+            let count = (count - previous.get(position).unwrap_or(&0)) as f64;
+            self.counts_squared[position] += count * count;
+            self.counts_by_rows[position] += count * rows;
+        }
+    }
+
+    /// Returns the estimated count of a value across the given rows and row groups, and the
+    /// margin of error of the estimate at 95% confidence, unknown from a single row group.
+    fn estimate(
+        &self,
+        count: usize,
+        total_rows: u64,
+        total_row_groups: usize,
+        position: usize,
+    ) -> (f64, Option<f64>) {
+        let (count, total_rows) = (count as f64, total_rows as f64);
+        let ratio = count / self.rows;
+        let estimate = ratio * total_rows;
+        if self.row_groups < 2 {
+            return (estimate, None);
+        }
+        let sampled = self.row_groups as f64;
+        let deviations = self.counts_squared[position]
+            - 2.0 * ratio * self.counts_by_rows[position]
+            + ratio * ratio * self.rows_squared;
+        let variance = (1.0 - sampled / total_row_groups as f64) * deviations.max(0.0)
+            / (sampled - 1.0)
+            / sampled
+            / (self.rows / sampled).powi(2);
+        (estimate, Some(1.96 * variance.sqrt() * total_rows))
+    }
+}
+
+/// Outputs the distinct non-null values of a column across all files with their counts, most
+/// frequent first, as {"<column>": value, "count": n} lines ready to load as a dimension table.
+/// With a number of row groups to sample, the counts are estimated from values of those chosen
+/// at random, with a "margin" of error at 95% confidence.
+pub async fn freq(
+    out: &mut ReportWriter,
+    footers: &[Footer],
+    column: &str,
+    min_count: usize,
+    sample: Option<(usize, u64)>,
+) {
+    let mut frequencies = Frequencies::default();
+    let mut data_type = None;
+    let Some((count, seed)) = sample else {
+        for footer in footers {
+            count_values(&mut frequencies, &mut data_type, footer, column, None).await;
+        }
+        return write_frequencies(out, frequencies, data_type, None, column, min_count).await;
+    };
+
+    let mut sums = SampleSums::default();
+    for (file, row_group) in sample_row_groups(footers, count, seed) {
+        let footer = &footers[file];
+        let previous = frequencies.counts.clone();
+        let row_groups = Some(vec![row_group]);
+        count_values(&mut frequencies, &mut data_type, footer, column, row_groups).await;
+        let rows = footer.2.metadata().row_group(row_group).num_rows() as usize;
+        sums.add(rows, &frequencies.counts, &previous);
+    }
+    let total_rows: u64 = footers
+        .iter()
+        .map(|(_, _, metadata)| metadata.metadata().file_metadata().num_rows() as u64)
+        .sum();
+    let total_row_groups: usize = footers
+        .iter()
+        .map(|(_, _, metadata)| metadata.metadata().num_row_groups())
+        .sum();
+    eprintln!(
+        "Sampled {} of {} row groups ({} of {} rows, --seed {}): counts are estimates, with margins of error at 95% confidence",
+        sums.row_groups, total_row_groups, sums.rows as u64, total_rows, seed
+    );
+    let mut margins = vec![];
+    for (position, count) in frequencies.counts.iter_mut().enumerate() {
+        let (estimate, margin) = sums.estimate(*count, total_rows, total_row_groups, position);
+        *count = estimate.round() as usize;
+        margins.push(margin.map(|margin| margin.round() as u64));
+    }
+    write_frequencies(
+        out,
+        frequencies,
+        data_type,
+        Some(margins),
+        column,
+        min_count,
+    )
+    .await;
+}
+
+/// Writes the values counted most frequent first, with the margins of error of sampled counts,
+/// a batch of values at a time.
+async fn write_frequencies(
+    out: &mut ReportWriter,
+    frequencies: Frequencies,
+    data_type: Option<DataType>,
+    margins: Option<Vec<Option<u64>>>,
+    column: &str,
+    min_count: usize,
+) {
+    let mut positions: Vec<usize> = (0..frequencies.counts.len())
+        .filter(|position| frequencies.counts[*position] >= min_count)
+        .collect();
+    positions.sort_by_key(|position| std::cmp::Reverse(frequencies.counts[*position]));
+
+    let data_type = data_type.unwrap_or(DataType::Null);
+    let values = if frequencies.values.is_empty() {
+        new_empty_array(&data_type)
+    } else {
+        let values: Vec<&dyn Array> = frequencies.values.iter().map(AsRef::as_ref).collect();
+        concat(&values).unwrap()
+    };
+    let indices = UInt32Array::from_iter_values(positions.iter().map(|position| *position as u32));
+    let counts = UInt64Array::from_iter_values(
+        positions
+            .iter()
+            .map(|position| frequencies.counts[*position] as u64),
+    );
+    let mut fields = vec![
+        Field::new(column, data_type, false),
+        Field::new("count", DataType::UInt64, false),
+    ];
+    let mut columns = vec![
+        take(values.as_ref(), &indices, None).unwrap(),
+        Arc::new(counts) as ArrayRef,
+    ];
+    if let Some(margins) = margins {
+        fields.push(Field::new("margin", DataType::UInt64, true));
+        columns.push(Arc::new(UInt64Array::from_iter(
+            positions.iter().map(|position| margins[*position]),
+        )));
+    }
+    let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap();
+
+    let batch = json_compatible(batch);
+    let mut writer = JsonLinesWriter::new(false);
+    for offset in (0..batch.num_rows()).step_by(BATCH_SIZE) {
+        let length = BATCH_SIZE.min(batch.num_rows() - offset);
+        let mut lines = vec![];
+        writer
+            .encoder(batch.slice(offset, length))
+            .encode(&mut lines);
+        out.write(&lines).await;
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use object_store::local::LocalFileSystem;
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore, PutMultipartOpts,
+    PutOptions, PutPayload, PutResult, Result,
+};
+use tokio::sync::OnceCell;
+
+use crate::temp::TempDir;
+use crate::HTTP_ARGS;
+
+/// HTTP store for servers that answer range requests with the whole object. Once a range
+/// request is answered in full, the object is downloaded to a temporary file, up to
+/// --max-download-size, and its ranges are read from the file.
+#[derive(Debug)]
+pub struct FullDownloadStore {
+    inner: Arc<dyn ObjectStore>,
+    downloads: Mutex<HashMap<Path, Arc<OnceCell<Path>>>>,
+    dir: OnceLock<TempDir>,
+    files: AtomicUsize,
+}
+
+impl FullDownloadStore {
+    pub fn new(inner: Arc<dyn ObjectStore>) -> Self {
+        FullDownloadStore {
+            inner,
+            downloads: Mutex::new(HashMap::new()),
+            dir: OnceLock::new(),
+            files: AtomicUsize::new(0),
+        }
+    }
+
+    fn download_of(&self, location: &Path) -> Option<Arc<OnceCell<Path>>> {
+        self.downloads.lock().unwrap().get(location).cloned()
+    }
+
+    /// Downloads the object to a temporary file once, returning the location of the file.
+    async fn download(&self, location: &Path) -> Result<Path> {
+        let download = self
+            .downloads
+            .lock()
+            .unwrap()
+            .entry(location.clone())
+            .or_default()
+            .clone();
+        download
+            .get_or_try_init(|| async {
+                let max_download_size = HTTP_ARGS.get_or_init(Default::default).max_download_size;
+                let too_large = || -> ! {
+                    panic!(
+                        "Object too large to download from a server without range requests (over {} MB, raise --max-download-size)",
+                        max_download_size
+                    )
+                };
+                let result = self.inner.get(location).await?;
+                if result.meta.size as u64 > max_download_size * 1024 * 1024 {
+                    too_large();
+                }
+                let dir = self.dir.get_or_init(|| TempDir::new("download", None));
+                let name = self.files.fetch_add(1, Ordering::Relaxed).to_string();
+                let mut file = dir.create(&name);
+                let mut size = 0;
+                let mut stream = result.into_stream();
+                while let Some(bytes) = stream.next().await {
+                    let bytes = bytes?;
+                    size += bytes.len() as u64;
+                    if size > max_download_size * 1024 * 1024 {
+                        too_large();
+                    }
+                    file.write_all(&bytes).unwrap();
+                }
+                file.flush().unwrap();
+                Ok(Path::from_filesystem_path(dir.path().join(name)).unwrap())
+            })
+            .await
+            .cloned()
+    }
+
+    async fn get_downloaded(
+        &self,
+        location: &Path,
+        file: &Path,
+        options: GetOptions,
+    ) -> Result<GetResult> {
+        let mut result = LocalFileSystem::new().get_opts(file, options).await?;
+        result.meta.location = location.clone();
+        Ok(result)
+    }
+}
+
+/// Returns whether an error is that of a range request answered with the whole object.
+fn is_range_not_supported(error: &object_store::Error) -> bool {
+    matches!(error, object_store::Error::NotSupported { source }
+        if source.to_string().starts_with("Range request not supported"))
+}
+
+impl Display for FullDownloadStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FullDownload({})", self.inner)
+    }
+}
+
+#[async_trait::async_trait]
+impl ObjectStore for FullDownloadStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        if options.range.is_none() || options.head {
+            return self.inner.get_opts(location, options).await;
+        }
+        if let Some(file) = self
+            .download_of(location)
+            .and_then(|download| download.get().cloned())
+        {
+            return self.get_downloaded(location, &file, options).await;
+        }
+        let range = options.range.clone();
+        match self.inner.get_opts(location, options).await {
+            Err(error) if is_range_not_supported(&error) => {
+                let file = self.download(location).await?;
+                let options = GetOptions {
+                    range,
+                    ..Default::default()
+                };
+                self.get_downloaded(location, &file, options).await
+            }
+            result => result,
+        }
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+}
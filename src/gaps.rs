@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::TimestampMicrosecondType;
+use arrow_array::{Array, ArrayRef, RecordBatch, TimestampMicrosecondArray};
+use arrow_cast::cast;
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_schema::{DataType, TimeUnit};
+use object_store::{ObjectMeta, ObjectStore};
+use parquet::arrow::async_reader::ParquetObjectReader;
+use parquet::arrow::{ParquetRecordBatchStreamBuilder, ProjectionMask};
+use serde_json::{json, Map, Value};
+use tokio_stream::StreamExt;
+
+use crate::output::ReportWriter;
+
+/// Values of the group columns of a row, with None for nulls.
+type Group = Vec<Option<String>>;
+
+/// Parses an interval of a number and a unit, e.g. 5m, in microseconds.
+pub fn parse_interval(interval: &str) -> i64 {
+    let digits = interval.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let unit_micros = match &interval[digits.len()..] {
+        "ms" => 1_000,
+        "s" => 1_000_000,
+        "m" => 60_000_000,
+        "h" => 3_600_000_000,
+        "d" => 86_400_000_000,
+        "w" => 604_800_000_000,
+        _ => 0,
+    };
+    match digits.parse::<i64>() {
+        Ok(count) if count > 0 && unit_micros > 0 => count * unit_micros,
+        _ => panic!(
+            "Invalid interval, expected a number and a unit of ms, s, m, h, d or w (e.g. 5m) ({})",
+            interval
+        ),
+    }
+}
+
+/// Intervals that values of each group fall in, as indices of intervals since the epoch.
+#[derive(Default)]
+struct Intervals {
+    groups: BTreeMap<Group, Vec<i64>>,
+}
+
+impl Intervals {
+    fn add(&mut self, group: Group, interval: i64) {
+        let intervals = self.groups.entry(group).or_default();
+        // Values are mostly in order, so repeats of the last interval are left out as they come
+        if intervals.last() != Some(&interval) {
+            intervals.push(interval);
+        }
+    }
+}
+
+/// Adds the intervals of the values of the time column of each row of a file to those of its
+/// group, leaving out rows with a null time, and returns the time zone of the column.
+async fn add_intervals(
+    intervals: &mut Intervals,
+    storage_container: &Arc<dyn ObjectStore>,
+    file: &ObjectMeta,
+    by: &str,
+    group_columns: &[&str],
+    interval_micros: i64,
+) -> Option<Arc<str>> {
+    let reader = ParquetObjectReader::new(storage_container.clone(), file.clone());
+    let builder = ParquetRecordBatchStreamBuilder::new(reader).await.unwrap();
+
+    let schema_descr = builder.parquet_schema();
+    let root_schema = schema_descr.root_schema().get_fields();
+    let indices: Vec<usize> = std::iter::once(&by)
+        .chain(group_columns)
+        .map(|column_name| {
+            root_schema
+                .iter()
+                .position(|field| field.name().eq(*column_name))
+                .unwrap_or_else(|| {
+                    panic!("Column not found ({}) in {}", column_name, file.location)
+                })
+        })
+        .collect();
+    let timezone = match builder.schema().field_with_name(by).unwrap().data_type() {
+        DataType::Timestamp(_, timezone) => timezone.clone(),
+        DataType::Date32 | DataType::Date64 => None,
+        data_type => panic!("Cannot check gaps of {} ({})", by, data_type),
+    };
+    let projection_mask = ProjectionMask::roots(schema_descr, indices);
+    let mut iter = builder.with_projection(projection_mask).build().unwrap();
+
+    while let Some(batch) = iter.next().await {
+        let batch: RecordBatch = batch.unwrap();
+        let times = cast(
+            batch.column_by_name(by).unwrap(),
+            &DataType::Timestamp(TimeUnit::Microsecond, timezone.clone()),
+        )
+        .unwrap();
+        let times = times.as_primitive::<TimestampMicrosecondType>();
+        let columns: Vec<&ArrayRef> = group_columns
+            .iter()
+            .map(|column_name| batch.column_by_name(column_name).unwrap())
+            .collect();
+        let formatters: Vec<_> = columns
+            .iter()
+            .map(|column| {
+                ArrayFormatter::try_new(column.as_ref(), &FormatOptions::default()).unwrap()
+            })
+            .collect();
+
+        for row in (0..batch.num_rows()).filter(|row| times.is_valid(*row)) {
+            let group = columns
+                .iter()
+                .zip(&formatters)
+                .map(|(column, formatter)| {
+                    column
+                        .is_valid(row)
+                        .then(|| formatter.value(row).to_string())
+                })
+                .collect();
+            intervals.add(group, times.value(row).div_euclid(interval_micros));
+        }
+    }
+    timezone
+}
+
+/// Outputs each run of missing intervals between the first and the last value of a time column
+/// of each group as a JSON line, with the start of the first missing interval, the start of the
+/// next interval with values and the number of missing intervals. Intervals are aligned to the
+/// Unix epoch. Returns whether any interval is missing.
+pub async fn check_gaps(
+    out: &mut ReportWriter,
+    storage_container: Arc<dyn ObjectStore>,
+    files: &[ObjectMeta],
+    by: &str,
+    interval: &str,
+    group_by: Option<&str>,
+) -> bool {
+    let interval_micros = parse_interval(interval);
+    let group_columns: Vec<&str> = group_by
+        .map(|group_by| group_by.split(',').map(str::trim).collect())
+        .unwrap_or_default();
+
+    let mut intervals = Intervals::default();
+    let mut timezone = None;
+    for file in files {
+        timezone = add_intervals(
+            &mut intervals,
+            &storage_container,
+            file,
+            by,
+            &group_columns,
+            interval_micros,
+        )
+        .await;
+    }
+
+    let mut found = false;
+    for (group, mut group_intervals) in intervals.groups {
+        group_intervals.sort_unstable();
+        group_intervals.dedup();
+        for pair in group_intervals.windows(2) {
+            if pair[1] - pair[0] == 1 {
+                continue;
+            }
+            found = true;
+            // Bounds are displayed as values of the column, in its time zone
+            let bounds = TimestampMicrosecondArray::from(vec![
+                (pair[0] + 1) * interval_micros,
+                pair[1] * interval_micros,
+            ])
+            .with_timezone_opt(timezone.clone());
+            let formatter = ArrayFormatter::try_new(&bounds, &FormatOptions::default()).unwrap();
+            let mut gap = Map::new();
+            if !group_columns.is_empty() {
+                let group_object: Map<String, Value> = group_columns
+                    .iter()
+                    .zip(&group)
+                    .map(|(column_name, value)| (column_name.to_string(), json!(value)))
+                    .collect();
+                gap.insert("group".to_string(), Value::Object(group_object));
+            }
+            gap.insert("from".to_string(), json!(formatter.value(0).to_string()));
+            gap.insert("to".to_string(), json!(formatter.value(1).to_string()));
+            gap.insert("missing".to_string(), json!(pair[1] - pair[0] - 1));
+            out.write_line(Value::Object(gap)).await;
+        }
+    }
+    found
+}
@@ -0,0 +1,322 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Date32Type, Float64Type, Int64Type, UInt64Type};
+use arrow_array::{
+    Array, ArrayRef, BooleanArray, Float64Array, Int32Array, Int64Array, StringArray, UInt64Array,
+};
+use arrow_cast::cast;
+use arrow_ord::ord::make_comparator;
+use arrow_schema::{DataType, SchemaRef, SortOptions};
+use object_store::path::Path;
+use object_store::{ObjectMeta, ObjectStore};
+use parquet::arrow::async_reader::ParquetObjectReader;
+use parquet::arrow::{ParquetRecordBatchStreamBuilder, ProjectionMask};
+use parquet::file::metadata::ParquetMetaData;
+use serde_json::{json, Value};
+use tokio_stream::StreamExt;
+use url::Url;
+
+use crate::filter::Predicate;
+use crate::style;
+
+pub const INDEX_EXTENSION: &str = "p2jidx";
+
+/// Version of the JSON of indexes, those of other versions being ignored.
+const INDEX_VERSION: u64 = 2;
+
+pub fn index_location(location: &Path) -> Path {
+    Path::from(format!("{}.{}", location, INDEX_EXTENSION))
+}
+
+/// Returns the URL of the sidecar index of a file at an HTTP URL, with the extension appended to
+/// its path. HTTP stores are of the URL of the file itself, so the index cannot be located
+/// within the store of the file.
+pub fn index_url(file: &str) -> Option<String> {
+    let mut url = Url::parse(file).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    url.set_path(&format!("{}.{}", url.path(), INDEX_EXTENSION));
+    Some(url.into())
+}
+
+/// Per-row-group min/max values of selected columns, stored as a JSON sidecar next to the file.
+/// Values are typed JSON values that cast back to the type of their column exactly, and columns
+/// of other types are left out.
+pub struct SidecarIndex {
+    pub file_size: usize,
+    pub row_groups: Vec<BTreeMap<String, (Value, Value)>>,
+}
+
+fn min_max(array: &ArrayRef) -> Option<(usize, usize)> {
+    let comparator = make_comparator(array, array, SortOptions::default()).unwrap();
+    let mut valid = (0..array.len()).filter(|i| array.is_valid(*i));
+    let first = valid.next()?;
+    Some(valid.fold((first, first), |(min, max), i| {
+        (
+            if comparator(i, min) == Ordering::Less {
+                i
+            } else {
+                min
+            },
+            if comparator(i, max) == Ordering::Greater {
+                i
+            } else {
+                max
+            },
+        )
+    }))
+}
+
+fn merge(current: Option<ArrayRef>, candidate: ArrayRef, ordering: Ordering) -> ArrayRef {
+    match current {
+        Some(current) => {
+            let comparator = make_comparator(&candidate, &current, SortOptions::default()).unwrap();
+            if comparator(0, 0) == ordering {
+                candidate
+            } else {
+                current
+            }
+        }
+        None => candidate,
+    }
+}
+
+type Bounds = BTreeMap<String, (Option<ArrayRef>, Option<ArrayRef>)>;
+
+fn finish_row_group(bounds: &mut Bounds) -> BTreeMap<String, (Value, Value)> {
+    std::mem::take(bounds)
+        .into_iter()
+        .filter_map(|(column, (min, max))| Some((column, (to_json(&min?)?, to_json(&max?)?))))
+        .collect()
+}
+
+/// Returns the value of a single value array as JSON: numbers and booleans as such, dates and
+/// times as their integer values, strings and decimals as strings. None for other types, such
+/// as binary values, and for non-finite floats.
+fn to_json(array: &ArrayRef) -> Option<Value> {
+    let cast_to = |data_type: &DataType| cast(array, data_type).unwrap();
+    match array.data_type() {
+        DataType::Boolean => Some(json!(array.as_boolean().value(0))),
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => {
+            Some(json!(cast_to(&DataType::Int64)
+                .as_primitive::<Int64Type>()
+                .value(0)))
+        }
+        DataType::UInt8 | DataType::UInt16 | DataType::UInt32 | DataType::UInt64 => {
+            Some(json!(cast_to(&DataType::UInt64)
+                .as_primitive::<UInt64Type>()
+                .value(0)))
+        }
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => {
+            let value = cast_to(&DataType::Float64)
+                .as_primitive::<Float64Type>()
+                .value(0);
+            value.is_finite().then(|| json!(value))
+        }
+        DataType::Date32 => Some(json!(array.as_primitive::<Date32Type>().value(0))),
+        DataType::Date64 | DataType::Timestamp(_, _) => Some(json!(cast_to(&DataType::Int64)
+            .as_primitive::<Int64Type>()
+            .value(0))),
+        DataType::Utf8
+        | DataType::LargeUtf8
+        | DataType::Decimal128(_, _)
+        | DataType::Decimal256(_, _) => {
+            Some(json!(cast_to(&DataType::Utf8).as_string::<i32>().value(0)))
+        }
+        _ => None,
+    }
+}
+
+/// Returns a single value array of the given type from a JSON value of `to_json`, or None if the
+/// value is not of the type.
+fn from_json(value: &Value, data_type: &DataType) -> Option<ArrayRef> {
+    let array: ArrayRef = match data_type {
+        DataType::Boolean => Arc::new(BooleanArray::from(vec![value.as_bool()?])),
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => {
+            Arc::new(Int64Array::from(vec![value.as_i64()?]))
+        }
+        DataType::UInt8 | DataType::UInt16 | DataType::UInt32 | DataType::UInt64 => {
+            Arc::new(UInt64Array::from(vec![value.as_u64()?]))
+        }
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => {
+            Arc::new(Float64Array::from(vec![value.as_f64()?]))
+        }
+        DataType::Date32 => Arc::new(Int32Array::from(vec![i32::try_from(value.as_i64()?).ok()?])),
+        DataType::Date64 | DataType::Timestamp(_, _) => {
+            Arc::new(Int64Array::from(vec![value.as_i64()?]))
+        }
+        DataType::Utf8
+        | DataType::LargeUtf8
+        | DataType::Decimal128(_, _)
+        | DataType::Decimal256(_, _) => Arc::new(StringArray::from(vec![value.as_str()?])),
+        _ => return None,
+    };
+    // Values out of the range of the type cast to null, and are not bounds of its values
+    let array = cast(&array, data_type).ok()?;
+    array.is_valid(0).then_some(array)
+}
+
+impl SidecarIndex {
+    /// Scans the given columns of the file to compute min/max values for every row group.
+    pub async fn build(
+        storage_container: Arc<dyn ObjectStore>,
+        meta: ObjectMeta,
+        columns: &[&str],
+    ) -> Self {
+        let file_size = meta.size;
+        let reader = ParquetObjectReader::new(storage_container, meta);
+        let builder = ParquetRecordBatchStreamBuilder::new(reader).await.unwrap();
+        let row_group_sizes: Vec<usize> = builder
+            .metadata()
+            .row_groups()
+            .iter()
+            .map(|row_group| row_group.num_rows() as usize)
+            .collect();
+
+        let schema_descr = builder.parquet_schema();
+        let root_schema = schema_descr.root_schema().get_fields();
+        let indices: Vec<usize> = columns
+            .iter()
+            .map(|column_name| {
+                root_schema
+                    .iter()
+                    .position(|field| field.name().eq(*column_name))
+                    .unwrap_or_else(|| panic!("Column not found ({})", column_name))
+            })
+            .collect();
+        let projection_mask = ProjectionMask::roots(schema_descr, indices);
+        let mut iter = builder.with_projection(projection_mask).build().unwrap();
+
+        let mut row_groups = vec![];
+        let mut bounds: Bounds = BTreeMap::new();
+        let mut rows_in_row_group = 0;
+
+        while let Some(batch) = iter.next().await {
+            let batch = batch.unwrap();
+            for column_name in columns {
+                let column = batch.column_by_name(column_name).unwrap();
+                let (min, max) = bounds.entry(column_name.to_string()).or_default();
+                if let Some((min_index, max_index)) = min_max(column) {
+                    *min = Some(merge(
+                        min.take(),
+                        column.slice(min_index, 1),
+                        Ordering::Less,
+                    ));
+                    *max = Some(merge(
+                        max.take(),
+                        column.slice(max_index, 1),
+                        Ordering::Greater,
+                    ));
+                }
+            }
+
+            rows_in_row_group += batch.num_rows();
+            if rows_in_row_group >= row_group_sizes[row_groups.len()] {
+                row_groups.push(finish_row_group(&mut bounds));
+                rows_in_row_group = 0;
+            }
+        }
+        while row_groups.len() < row_group_sizes.len() {
+            row_groups.push(finish_row_group(&mut bounds));
+        }
+
+        SidecarIndex {
+            file_size,
+            row_groups,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let row_groups: Vec<Value> = self
+            .row_groups
+            .iter()
+            .map(|row_group| {
+                let columns: serde_json::Map<String, Value> = row_group
+                    .iter()
+                    .map(|(column, (min, max))| (column.clone(), json!({"min": min, "max": max})))
+                    .collect();
+                json!({ "columns": columns })
+            })
+            .collect();
+
+        json!({"version": INDEX_VERSION, "file_size": self.file_size, "row_groups": row_groups})
+    }
+
+    /// Returns the index of its JSON, or None if it is not the JSON of an index of this version.
+    pub fn from_json(value: &Value) -> Option<Self> {
+        if value["version"].as_u64() != Some(INDEX_VERSION) {
+            return None;
+        }
+        let row_groups = value["row_groups"]
+            .as_array()?
+            .iter()
+            .map(|row_group| {
+                row_group["columns"]
+                    .as_object()?
+                    .iter()
+                    .map(|(column, bounds)| {
+                        let bound = |key: &str| bounds.get(key).cloned();
+                        Some((column.clone(), (bound("min")?, bound("max")?)))
+                    })
+                    .collect()
+            })
+            .collect::<Option<_>>()?;
+
+        Some(SidecarIndex {
+            file_size: value["file_size"].as_u64()? as usize,
+            row_groups,
+        })
+    }
+
+    /// Loads the sidecar for the file, ignoring it (with a warning) if it does not match.
+    pub async fn load(
+        storage_container: &Arc<dyn ObjectStore>,
+        location: &Path,
+        meta: &ObjectMeta,
+        parquet_metadata: &ParquetMetaData,
+    ) -> Option<Self> {
+        let bytes = storage_container
+            .get(location)
+            .await
+            .ok()?
+            .bytes()
+            .await
+            .ok()?;
+        let index = serde_json::from_slice(&bytes)
+            .ok()
+            .and_then(|value| SidecarIndex::from_json(&value));
+        let Some(index) = index else {
+            style::warning(format!("Ignoring index that cannot be read ({})", location));
+            return None;
+        };
+
+        if index.file_size != meta.size
+            || index.row_groups.len() != parquet_metadata.num_row_groups()
+        {
+            style::warning(format!("Ignoring index not matching file ({})", location));
+            return None;
+        }
+        Some(index)
+    }
+
+    /// Returns the row groups that may contain rows matching the predicate.
+    pub fn prune(&self, predicate: &Predicate, schema: &SchemaRef) -> Vec<usize> {
+        (0..self.row_groups.len())
+            .filter(|index| {
+                predicate.comparisons.iter().all(|comparison| {
+                    let bounds = self.row_groups[*index].get(&comparison.column);
+                    let field = schema.field_with_name(&comparison.column);
+                    let bound = |value: &Value| from_json(value, field.as_ref().ok()?.data_type());
+                    match bounds.and_then(|(min, max)| Some((bound(min)?, bound(max)?))) {
+                        Some((min, max)) => comparison.may_match(&min, &max),
+                        None => true,
+                    }
+                })
+            })
+            .collect()
+    }
+}
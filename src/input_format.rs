@@ -0,0 +1,240 @@
+use std::io::Cursor;
+use std::sync::{Arc, OnceLock};
+
+use arrow_array::RecordBatch;
+use arrow_ipc::reader::{FileReader, StreamReader};
+use arrow_json::reader::{infer_json_schema_from_iterator, infer_json_schema_from_seekable};
+use arrow_json::ReaderBuilder;
+use arrow_schema::{ArrowError, Schema, SchemaRef};
+use bytes::Bytes;
+use clap::ValueEnum;
+use object_store::memory::InMemory;
+use object_store::{ObjectMeta, ObjectStore};
+use parquet::arrow::ArrowWriter;
+use serde_json::{Map, Value};
+
+use crate::output::BATCH_SIZE;
+
+/// Format of input files, for landing zones of CSV and JSON lines files next to Parquet ones.
+#[derive(ValueEnum, Clone, Copy, Default, PartialEq)]
+pub enum InputFormat {
+    /// Parquet files only
+    #[default]
+    Parquet,
+    /// Detected from the extension (.parquet, .csv, .jsonl, .ndjson, .json, .avro, .arrow,
+    /// .feather or .ipc) or the first bytes
+    Auto,
+    /// CSV with a header row
+    Csv,
+    /// JSON lines
+    Jsonl,
+    /// Avro object container files, with the avro feature
+    Avro,
+    /// Arrow IPC files (Feather v2) or streams, as written by pyarrow or polars
+    Ipc,
+}
+
+static INPUT_FORMAT: OnceLock<InputFormat> = OnceLock::new();
+
+pub fn init(input_format: InputFormat) {
+    let _ = INPUT_FORMAT.set(input_format);
+}
+
+fn input_format() -> InputFormat {
+    *INPUT_FORMAT.get_or_init(Default::default)
+}
+
+/// Returns whether an object under a directory or prefix is read as an input file.
+pub fn is_input_file(location: &str) -> bool {
+    let extensions: &[&str] = match input_format() {
+        InputFormat::Parquet => &[".parquet"],
+        InputFormat::Auto => &[
+            ".parquet", ".csv", ".jsonl", ".ndjson", ".json", ".avro", ".arrow", ".feather", ".ipc",
+        ],
+        InputFormat::Csv => &[".csv"],
+        InputFormat::Jsonl => &[".jsonl", ".ndjson", ".json"],
+        InputFormat::Avro => &[".avro"],
+        InputFormat::Ipc => &[".arrow", ".feather", ".ipc"],
+    };
+    extensions
+        .iter()
+        .any(|extension| location.ends_with(extension))
+}
+
+/// Returns the format of a file, from its extension or, failing that, its first bytes.
+async fn detect(storage_container: &Arc<dyn ObjectStore>, meta: &ObjectMeta) -> InputFormat {
+    let location = meta.location.as_ref();
+    match input_format() {
+        InputFormat::Auto if location.ends_with(".parquet") => InputFormat::Parquet,
+        InputFormat::Auto if location.ends_with(".csv") => InputFormat::Csv,
+        InputFormat::Auto if location.ends_with(".avro") => InputFormat::Avro,
+        InputFormat::Auto
+            if [".arrow", ".feather", ".ipc"]
+                .iter()
+                .any(|extension| location.ends_with(extension)) =>
+        {
+            InputFormat::Ipc
+        }
+        InputFormat::Auto
+            if [".jsonl", ".ndjson", ".json"]
+                .iter()
+                .any(|extension| location.ends_with(extension)) =>
+        {
+            InputFormat::Jsonl
+        }
+        InputFormat::Auto => {
+            let magic = storage_container
+                .get_range(&meta.location, 0..meta.size.min(4))
+                .await
+                .unwrap();
+            if magic.as_ref() == b"PAR1" {
+                InputFormat::Parquet
+            } else if magic.as_ref() == b"Obj\x01" {
+                InputFormat::Avro
+            } else if magic.as_ref() == b"ARRO" || magic.as_ref() == b"\xff\xff\xff\xff" {
+                // The magic bytes of IPC files, or the continuation marker of IPC streams
+                InputFormat::Ipc
+            } else if magic.trim_ascii_start().starts_with(b"{") {
+                InputFormat::Jsonl
+            } else {
+                InputFormat::Csv
+            }
+        }
+        input_format => input_format,
+    }
+}
+
+/// Returns the value of a CSV field, typed as it reads, with empty fields as null and numbers
+/// with leading zeros, such as postal codes, kept as strings.
+fn csv_value(field: &str) -> Value {
+    let leading_zero = field.len() > 1 && field.starts_with('0') && !field.starts_with("0.");
+    if field.is_empty() {
+        Value::Null
+    } else if leading_zero {
+        Value::String(field.to_string())
+    } else if let Ok(integer) = field.parse::<i64>() {
+        Value::from(integer)
+    } else if let Some(float) = field.parse::<f64>().ok().filter(|float| float.is_finite()) {
+        Value::from(float)
+    } else if let Ok(boolean) = field.parse::<bool>() {
+        Value::Bool(boolean)
+    } else {
+        Value::String(field.to_string())
+    }
+}
+
+fn csv_batches(bytes: &Bytes, location: &str) -> (Schema, Vec<RecordBatch>) {
+    let mut reader = csv::Reader::from_reader(bytes.as_ref());
+    let headers = reader.headers().unwrap().clone();
+    let records: Vec<Map<String, Value>> = reader
+        .records()
+        .map(|record| {
+            let record =
+                record.unwrap_or_else(|error| panic!("Invalid CSV ({}: {})", location, error));
+            headers
+                .iter()
+                .zip(record.iter())
+                .map(|(name, field)| (name.to_string(), csv_value(field)))
+                .collect()
+        })
+        .collect();
+
+    let schema = infer_json_schema_from_iterator(
+        records
+            .iter()
+            .map(|record| Ok(Value::Object(record.clone()))),
+    )
+    .unwrap();
+    let mut decoder = ReaderBuilder::new(Arc::new(schema.clone()))
+        .with_batch_size(BATCH_SIZE)
+        .with_coerce_primitive(true)
+        .build_decoder()
+        .unwrap();
+    let mut batches = vec![];
+    for chunk in records.chunks(BATCH_SIZE) {
+        decoder.serialize(chunk).unwrap();
+        batches.extend(decoder.flush().unwrap());
+    }
+    (schema, batches)
+}
+
+/// Reads the batches of an Arrow IPC file, or of an IPC stream without the file footer.
+fn ipc_batches(bytes: &Bytes, location: &str) -> (Schema, Vec<RecordBatch>) {
+    let read = || -> Result<(SchemaRef, Vec<RecordBatch>), ArrowError> {
+        let cursor = Cursor::new(bytes.as_ref());
+        if bytes.starts_with(b"ARROW1") {
+            let reader = FileReader::try_new(cursor, None)?;
+            Ok((reader.schema(), reader.collect::<Result<_, _>>()?))
+        } else {
+            let reader = StreamReader::try_new(cursor, None)?;
+            Ok((reader.schema(), reader.collect::<Result<_, _>>()?))
+        }
+    };
+    let (schema, batches) =
+        read().unwrap_or_else(|error| panic!("Invalid Arrow IPC file ({}: {})", location, error));
+    (schema.as_ref().clone(), batches)
+}
+
+fn jsonl_batches(bytes: &Bytes, location: &str) -> (Schema, Vec<RecordBatch>) {
+    let (schema, _) = infer_json_schema_from_seekable(Cursor::new(bytes.as_ref()), None)
+        .unwrap_or_else(|error| panic!("Invalid JSON lines ({}: {})", location, error));
+    let reader = ReaderBuilder::new(Arc::new(schema.clone()))
+        .with_batch_size(BATCH_SIZE)
+        .with_coerce_primitive(true)
+        .build(Cursor::new(bytes.as_ref()))
+        .unwrap();
+    let batches = reader
+        .collect::<Result<Vec<RecordBatch>, _>>()
+        .unwrap_or_else(|error| panic!("Invalid JSON lines ({}: {})", location, error));
+    (schema, batches)
+}
+
+/// Converts the CSV, JSON lines, Avro and Arrow IPC files among the inputs to Parquet files held
+/// in memory, with schemas inferred from all their rows or read from the file, so they are read
+/// like the Parquet files.
+pub async fn convert_inputs(
+    files: Vec<(Arc<dyn ObjectStore>, ObjectMeta)>,
+) -> Vec<(Arc<dyn ObjectStore>, ObjectMeta)> {
+    if input_format() == InputFormat::Parquet {
+        return files;
+    }
+    let converted: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+    let mut inputs = vec![];
+    for (storage_container, meta) in files {
+        let format = detect(&storage_container, &meta).await;
+        if format == InputFormat::Parquet {
+            inputs.push((storage_container, meta));
+            continue;
+        }
+        let bytes = storage_container
+            .get(&meta.location)
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let location = meta.location.as_ref();
+        let (schema, batches) = match format {
+            InputFormat::Csv => csv_batches(&bytes, location),
+            InputFormat::Ipc => ipc_batches(&bytes, location),
+            #[cfg(feature = "avro")]
+            InputFormat::Avro => crate::avro::avro_batches(&bytes, location),
+            #[cfg(not(feature = "avro"))]
+            InputFormat::Avro => {
+                panic!("Avro files need parquet2json to be built with the avro feature")
+            }
+            _ => jsonl_batches(&bytes, location),
+        };
+
+        let mut buffer = vec![];
+        let mut writer = ArrowWriter::try_new(&mut buffer, Arc::new(schema), None).unwrap();
+        for batch in batches {
+            writer.write(&batch).unwrap();
+        }
+        writer.close().unwrap();
+        converted.put(&meta.location, buffer.into()).await.unwrap();
+        let meta = converted.head(&meta.location).await.unwrap();
+        inputs.push((converted.clone(), meta));
+    }
+    inputs
+}
@@ -0,0 +1,193 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use arrow_array::{BooleanArray, RecordBatch, UInt32Array};
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_json::reader::infer_json_schema_from_iterator;
+use arrow_json::ReaderBuilder;
+use arrow_schema::{Field, SchemaBuilder};
+use arrow_select::filter::filter_record_batch;
+use arrow_select::take::take;
+use serde_json::{Map, Value};
+
+use crate::CatArgs;
+
+/// Reads a small CSV (with header row) or JSON lines file into records.
+pub fn load_table(path: &str) -> Vec<Map<String, Value>> {
+    if path.ends_with(".csv") {
+        let mut reader = csv::Reader::from_path(path).unwrap();
+        let headers = reader.headers().unwrap().clone();
+        reader
+            .records()
+            .map(|record| {
+                headers
+                    .iter()
+                    .zip(record.unwrap().iter())
+                    .map(|(name, value)| (name.to_string(), Value::String(value.to_string())))
+                    .collect()
+            })
+            .collect()
+    } else {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| match serde_json::from_str(line).unwrap() {
+                Value::Object(record) => record,
+                _ => panic!("Expected JSON object per line ({})", path),
+            })
+            .collect()
+    }
+}
+
+/// Returns the key as compared against Parquet values, i.e. as displayed.
+pub fn key_for_value(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(value) => Some(value.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+pub struct SemiJoin {
+    pub keys: HashSet<String>,
+    pub column: String,
+    pub anti: bool,
+}
+
+impl SemiJoin {
+    pub fn load(path: &str, column: &str, anti: bool) -> Self {
+        let keys = load_table(path)
+            .iter()
+            .filter_map(|record| record.get(column).and_then(key_for_value))
+            .collect();
+
+        SemiJoin {
+            keys,
+            column: column.to_string(),
+            anti,
+        }
+    }
+
+    /// Keeps rows whose key is (or, for anti joins, is not) present in the key file.
+    pub fn filter(&self, batch: &RecordBatch) -> RecordBatch {
+        let column = batch
+            .column_by_name(&self.column)
+            .unwrap_or_else(|| panic!("Join column not selected ({})", self.column));
+        let formatter =
+            ArrayFormatter::try_new(column.as_ref(), &FormatOptions::default()).unwrap();
+
+        let mask: BooleanArray = (0..batch.num_rows())
+            .map(|row| {
+                let found =
+                    column.is_valid(row) && self.keys.contains(&formatter.value(row).to_string());
+                Some(found != self.anti)
+            })
+            .collect();
+
+        filter_record_batch(batch, &mask).unwrap()
+    }
+}
+
+pub struct Enrichment {
+    pub indices: HashMap<String, u32>,
+    pub lookup: RecordBatch,
+    pub column: String,
+    pub prefix: String,
+}
+
+impl Enrichment {
+    pub fn load(path: &str, column: &str, prefix: &str) -> Self {
+        let mut records = load_table(path);
+        let mut indices: HashMap<String, u32> = HashMap::new();
+        for (index, record) in records.iter_mut().enumerate() {
+            if let Some(key) = record.remove(column).as_ref().and_then(key_for_value) {
+                indices.entry(key).or_insert(index as u32);
+            }
+        }
+
+        let schema = infer_json_schema_from_iterator(
+            records
+                .iter()
+                .map(|record| Ok(Value::Object(record.clone()))),
+        )
+        .unwrap();
+        let mut decoder = ReaderBuilder::new(Arc::new(schema.clone()))
+            .with_batch_size(records.len().max(1))
+            .build_decoder()
+            .unwrap();
+        decoder.serialize(&records).unwrap();
+        let lookup = decoder
+            .flush()
+            .unwrap()
+            .unwrap_or_else(|| RecordBatch::new_empty(Arc::new(schema)));
+
+        Enrichment {
+            indices,
+            lookup,
+            column: column.to_string(),
+            prefix: prefix.to_string(),
+        }
+    }
+
+    /// Appends the lookup fields of the matching record to each row, or nulls if none match.
+    pub fn enrich(&self, batch: &RecordBatch) -> RecordBatch {
+        let column = batch
+            .column_by_name(&self.column)
+            .unwrap_or_else(|| panic!("Join column not selected ({})", self.column));
+        let formatter =
+            ArrayFormatter::try_new(column.as_ref(), &FormatOptions::default()).unwrap();
+
+        let take_indices: UInt32Array = (0..batch.num_rows())
+            .map(|row| {
+                if column.is_valid(row) {
+                    self.indices.get(&formatter.value(row).to_string()).copied()
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let mut builder = SchemaBuilder::from(batch.schema().fields());
+        let mut columns = batch.columns().to_vec();
+        for (field, lookup_column) in self
+            .lookup
+            .schema()
+            .fields()
+            .iter()
+            .zip(self.lookup.columns())
+        {
+            builder.push(Field::new(
+                format!("{}{}", self.prefix, field.name()),
+                field.data_type().clone(),
+                true,
+            ));
+            columns.push(take(lookup_column.as_ref(), &take_indices, None).unwrap());
+        }
+
+        RecordBatch::try_new(builder.finish().into(), columns).unwrap()
+    }
+}
+
+/// Key and lookup files of --semi-join, --anti-join and --enrich, loaded once for all files.
+#[derive(Default)]
+pub struct Lookups {
+    pub join: Option<SemiJoin>,
+    pub enrichment: Option<Enrichment>,
+}
+
+impl Lookups {
+    pub fn for_args(args: &CatArgs) -> Self {
+        let Some(on) = &args.on else {
+            return Lookups::default();
+        };
+        let join = match (&args.semi_join, &args.anti_join) {
+            (Some(path), _) => Some(SemiJoin::load(path, on, false)),
+            (_, Some(path)) => Some(SemiJoin::load(path, on, true)),
+            _ => None,
+        };
+        let enrichment =
+            (args.enrich.as_ref()).map(|path| Enrichment::load(path, on, &args.prefix));
+        Lookups { join, enrichment }
+    }
+}
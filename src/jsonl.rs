@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use arrow_array::cast::AsArray;
+use arrow_array::{Array, ArrayRef, RecordBatch};
+use arrow_json::writer::LineDelimited;
+use arrow_json::WriterBuilder;
+use arrow_schema::{Field, Schema};
+
+use crate::encoder::{Encoder, EncoderKind, FormatOpts, Serialize};
+use crate::{CatArgs, OutputFormat};
+
+fn encode_lines(lines: &mut Vec<u8>, batch: &RecordBatch, explicit_nulls: bool) {
+    let mut json_writer = WriterBuilder::new()
+        .with_explicit_nulls(explicit_nulls)
+        .build::<_, LineDelimited>(lines);
+    json_writer.write(batch).unwrap();
+    json_writer.finish().unwrap();
+}
+
+/// JSON values of an array, one range of the buffer per element, empty for nulls.
+pub struct EncodedValues {
+    buffer: Vec<u8>,
+    ranges: Vec<Range<usize>>,
+}
+
+impl EncodedValues {
+    pub fn new(name: &str, array: &ArrayRef, explicit_nulls: bool) -> Self {
+        let field = Field::new(name, array.data_type().clone(), true);
+        let batch =
+            RecordBatch::try_new(Arc::new(Schema::new(vec![field])), vec![array.clone()]).unwrap();
+        let mut buffer = vec![];
+        encode_lines(&mut buffer, &batch, explicit_nulls);
+
+        // Each line is {"name":value}, or {} for nulls, with newlines escaped within values
+        let prefix = serde_json::to_string(name).unwrap().len() + 2;
+        let mut ranges = Vec::with_capacity(array.len());
+        let mut start = 0;
+        for (end, _) in buffer
+            .iter()
+            .enumerate()
+            .filter(|(_, byte)| **byte == b'\n')
+        {
+            ranges.push(if end - start > 2 {
+                start + prefix..end - 1
+            } else {
+                end..end
+            });
+            start = end + 1;
+        }
+        EncodedValues { buffer, ranges }
+    }
+
+    pub fn value(&self, index: usize) -> Option<&[u8]> {
+        let range = self.ranges[index].clone();
+        (!range.is_empty()).then(|| &self.buffer[range])
+    }
+}
+
+/// Encoded values of the dictionary a column was last seen with.
+struct DictionaryValues {
+    values: ArrayRef,
+    encoded: Arc<EncodedValues>,
+}
+
+enum ColumnValues<'a> {
+    Dictionary {
+        array: &'a dyn Array,
+        keys: Vec<usize>,
+        encoded: &'a EncodedValues,
+    },
+    Plain(EncodedValues),
+}
+
+/// Writes record batches as JSON lines, encoding the values of dictionary columns once for all
+/// batches sharing the dictionary, as batches read from a column chunk do.
+pub struct JsonLinesWriter {
+    explicit_nulls: bool,
+    /// Whether records are indented over several lines
+    pub pretty: bool,
+    dictionaries: HashMap<String, DictionaryValues>,
+}
+
+impl JsonLinesWriter {
+    pub fn new(explicit_nulls: bool) -> Self {
+        JsonLinesWriter {
+            explicit_nulls,
+            pretty: false,
+            dictionaries: HashMap::new(),
+        }
+    }
+
+    /// Returns the encoder of a batch, with the values of its dictionaries encoded or reused
+    /// from previous batches, so that its rows can be encoded on another thread.
+    pub fn encoder(&mut self, batch: RecordBatch) -> BatchEncoder {
+        let schema = batch.schema();
+        let dictionaries = schema
+            .fields()
+            .iter()
+            .zip(batch.columns())
+            .map(|(field, column)| {
+                let values = column.as_any_dictionary_opt()?.values();
+                let cached = self
+                    .dictionaries
+                    .get(field.name())
+                    .filter(|cached| Arc::ptr_eq(&cached.values, values));
+                let encoded = match cached {
+                    Some(cached) => cached.encoded.clone(),
+                    None => {
+                        let encoded = Arc::new(EncodedValues::new(
+                            field.name(),
+                            values,
+                            self.explicit_nulls,
+                        ));
+                        self.dictionaries.insert(
+                            field.name().clone(),
+                            DictionaryValues {
+                                values: values.clone(),
+                                encoded: encoded.clone(),
+                            },
+                        );
+                        encoded
+                    }
+                };
+                Some(encoded)
+            })
+            .collect();
+        BatchEncoder {
+            batch,
+            dictionaries,
+            explicit_nulls: self.explicit_nulls,
+        }
+    }
+}
+
+/// A record batch ready to be written as JSON lines.
+pub struct BatchEncoder {
+    batch: RecordBatch,
+    dictionaries: Vec<Option<Arc<EncodedValues>>>,
+    explicit_nulls: bool,
+}
+
+impl BatchEncoder {
+    pub fn encode(&self, lines: &mut Vec<u8>) {
+        let batch = &self.batch;
+        if self.dictionaries.iter().all(Option::is_none) {
+            encode_lines(lines, batch, self.explicit_nulls);
+            return;
+        }
+
+        let schema = batch.schema();
+        let columns: Vec<(String, ColumnValues)> = schema
+            .fields()
+            .iter()
+            .zip(batch.columns())
+            .zip(&self.dictionaries)
+            .map(|((field, column), dictionary)| {
+                let key = serde_json::to_string(field.name()).unwrap();
+                let values = match (column.as_any_dictionary_opt(), dictionary) {
+                    (Some(dictionary), Some(encoded)) => ColumnValues::Dictionary {
+                        array: column.as_ref(),
+                        keys: dictionary.normalized_keys(),
+                        encoded,
+                    },
+                    _ => ColumnValues::Plain(EncodedValues::new(
+                        field.name(),
+                        column,
+                        self.explicit_nulls,
+                    )),
+                };
+                (key, values)
+            })
+            .collect();
+
+        for row in 0..batch.num_rows() {
+            lines.push(b'{');
+            let mut first = true;
+            for (key, values) in &columns {
+                let value = match values {
+                    ColumnValues::Dictionary { array, .. } if array.is_null(row) => None,
+                    ColumnValues::Dictionary { keys, encoded, .. } => encoded.value(keys[row]),
+                    ColumnValues::Plain(encoded) => encoded.value(row),
+                };
+                let value = match value {
+                    Some(value) => value,
+                    None if self.explicit_nulls => b"null",
+                    None => continue,
+                };
+                if !first {
+                    lines.push(b',');
+                }
+                first = false;
+                lines.extend_from_slice(key.as_bytes());
+                lines.push(b':');
+                lines.extend_from_slice(value);
+            }
+            lines.extend_from_slice(b"}\n");
+        }
+    }
+}
+
+/// Indents JSON lines by two spaces per level of nesting, each record ending in a line break.
+/// Values are copied as they are, so numbers keep their encoding.
+pub fn indent_lines(lines: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(lines.len() * 2);
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let line_break = |out: &mut Vec<u8>, depth: usize| {
+        out.push(b'\n');
+        out.resize(out.len() + depth * 2, b' ');
+    };
+    for (index, &byte) in lines.iter().enumerate() {
+        if in_string {
+            out.push(byte);
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => {
+                in_string = true;
+                out.push(byte);
+            }
+            b'{' | b'[' => {
+                out.push(byte);
+                depth += 1;
+                // Empty objects and arrays stay on one line
+                if !matches!(lines.get(index + 1), Some(b'}' | b']')) {
+                    line_break(&mut out, depth);
+                }
+            }
+            b'}' | b']' => {
+                depth -= 1;
+                if !matches!(lines[index - 1], b'{' | b'[') {
+                    line_break(&mut out, depth);
+                }
+                out.push(byte);
+            }
+            b',' => {
+                out.push(byte);
+                line_break(&mut out, depth);
+            }
+            b':' => out.extend_from_slice(b": "),
+            byte => out.push(byte),
+        }
+    }
+    out
+}
+
+/// JSON lines, taking nulls=true|false for --nulls.
+pub struct JsonLinesEncoderKind;
+
+impl EncoderKind for JsonLinesEncoderKind {
+    fn format(&self) -> OutputFormat {
+        OutputFormat::Jsonl
+    }
+
+    fn open(&self, args: &CatArgs, options: &mut FormatOpts) -> Box<dyn Encoder> {
+        let explicit_nulls = options.value("nulls").unwrap_or(args.nulls);
+        Box::new(JsonLinesWriter {
+            pretty: args.pretty,
+            ..JsonLinesWriter::new(explicit_nulls)
+        })
+    }
+}
+
+impl Encoder for JsonLinesWriter {
+    fn encode(&mut self, batch: RecordBatch) -> Serialize {
+        let encoder = self.encoder(batch);
+        let pretty = self.pretty;
+        Box::new(move || {
+            let mut lines = vec![];
+            encoder.encode(&mut lines);
+            if pretty {
+                indent_lines(&lines)
+            } else {
+                lines
+            }
+        })
+    }
+}
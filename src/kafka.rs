@@ -0,0 +1,105 @@
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::Client;
+use serde_json::Value;
+
+use crate::sink::{Sink, SinkKind};
+use crate::source::http_client;
+use crate::transport::send_with_retries;
+use crate::{CatArgs, OutputFormat};
+
+/// Kafka topic, as kafka:<url> of the topic on a Confluent REST Proxy (e.g.
+/// kafka:http://localhost:8082/topics/events), producing a JSON record per row.
+pub struct KafkaSinkKind;
+
+#[async_trait::async_trait(?Send)]
+impl SinkKind for KafkaSinkKind {
+    fn matches(&self, output: &str) -> bool {
+        output.starts_with("kafka:")
+    }
+
+    async fn open(&self, output: &str, args: &CatArgs) -> Box<dyn Sink> {
+        if !matches!(args.format, OutputFormat::Jsonl) || args.pretty {
+            panic!("kafka: outputs need the jsonl format, without --pretty");
+        }
+        Box::new(KafkaSink {
+            client: http_client(),
+            url: output["kafka:".len()..].to_string(),
+            max_body_size: (args.write_buffer_size * 1024).max(1),
+            body: vec![],
+            records: 0,
+        })
+    }
+}
+
+/// Gathers rows as records up to --write-buffer-size, then produces them in one request.
+struct KafkaSink {
+    client: Client,
+    url: String,
+    max_body_size: usize,
+    body: Vec<u8>,
+    records: usize,
+}
+
+impl KafkaSink {
+    async fn produce(&mut self) {
+        if self.records == 0 {
+            return;
+        }
+        let mut body = std::mem::take(&mut self.body);
+        body.extend_from_slice(b"]}");
+        self.records = 0;
+        let request = self
+            .client
+            .post(&self.url)
+            .header(CONTENT_TYPE, "application/vnd.kafka.json.v2+json")
+            .header(ACCEPT, "application/vnd.kafka.v2+json")
+            .body(body)
+            .build()
+            .unwrap();
+        let response = send_with_retries(&self.client, request)
+            .await
+            .unwrap_or_else(|error| panic!("Kafka request failed ({}: {})", self.url, error));
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            panic!("Kafka request failed ({}: {} {})", self.url, status, text);
+        }
+        // Records failing on their own are reported per offset
+        let response: Value = serde_json::from_str(&text).unwrap_or_default();
+        let error = response["offsets"].as_array().and_then(|offsets| {
+            offsets
+                .iter()
+                .find_map(|offset| offset["error"].as_str().map(String::from))
+        });
+        if let Some(error) = error {
+            panic!("Kafka record not produced ({}: {})", self.url, error);
+        }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Sink for KafkaSink {
+    async fn write(&mut self, bytes: Vec<u8>) {
+        for line in bytes.split(|byte| *byte == b'\n') {
+            if line.is_empty() {
+                continue;
+            }
+            self.body.extend_from_slice(if self.records == 0 {
+                b"{\"records\":["
+            } else {
+                b","
+            });
+            self.body.extend_from_slice(b"{\"value\":");
+            self.body.extend_from_slice(line);
+            self.body.push(b'}');
+            self.records += 1;
+        }
+        if self.body.len() >= self.max_body_size {
+            self.produce().await;
+        }
+    }
+
+    async fn finish(&mut self) {
+        self.produce().await;
+    }
+}
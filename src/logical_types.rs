@@ -0,0 +1,288 @@
+use std::sync::Arc;
+
+use parquet::arrow::arrow_reader::{ArrowReaderMetadata, ArrowReaderOptions};
+use parquet::arrow::async_reader::{AsyncFileReader, MetadataLoader, ParquetObjectReader};
+use parquet::errors::Result;
+use parquet::file::footer::{decode_footer, decode_metadata};
+use parquet::file::metadata::ParquetMetaData;
+use parquet::file::FOOTER_SIZE;
+
+use crate::anomalies::{self, Anomaly};
+
+/// Members of the LogicalType union that the parquet crate reads, failing on footers with others.
+const KNOWN_LOGICAL_TYPES: [i16; 14] = [1, 2, 3, 4, 5, 6, 7, 8, 10, 11, 12, 13, 14, 15];
+
+// Types of the thrift compact protocol
+const BOOLEAN_TRUE: u8 = 1;
+const BOOLEAN_FALSE: u8 = 2;
+const BYTE: u8 = 3;
+const I16: u8 = 4;
+const I32: u8 = 5;
+const I64: u8 = 6;
+const DOUBLE: u8 = 7;
+const BINARY: u8 = 8;
+const LIST: u8 = 9;
+const SET: u8 = 10;
+const MAP: u8 = 11;
+const STRUCT: u8 = 12;
+
+/// Loads the footer and page index of a file as `ArrowReaderMetadata::load_async` does, reading
+/// columns of logical types unknown to the parquet crate without them.
+pub async fn load_metadata(
+    reader: &mut ParquetObjectReader,
+    file_size: usize,
+    options: ArrowReaderOptions,
+    page_index: bool,
+) -> Result<ArrowReaderMetadata> {
+    let mut metadata = match reader.get_metadata().await {
+        Ok(metadata) => metadata,
+        Err(error) => match without_unknown_logical_types(reader, file_size).await {
+            Some(metadata) => Arc::new(metadata),
+            None => return Err(error),
+        },
+    };
+    if page_index && metadata.column_index().is_none() && metadata.offset_index().is_none() {
+        let metadata_without_index =
+            Arc::try_unwrap(metadata).unwrap_or_else(|metadata| metadata.as_ref().clone());
+        let mut loader = MetadataLoader::new(&mut *reader, metadata_without_index);
+        loader.load_page_index(true, true).await?;
+        metadata = Arc::new(loader.finish());
+    }
+    ArrowReaderMetadata::try_new(metadata, options)
+}
+
+/// Reads the footer again with the logical types unknown to the parquet crate left out, if that
+/// is why it could not be read.
+async fn without_unknown_logical_types(
+    reader: &mut ParquetObjectReader,
+    file_size: usize,
+) -> Option<ParquetMetaData> {
+    let footer = reader
+        .get_bytes(file_size.checked_sub(FOOTER_SIZE)?..file_size)
+        .await
+        .ok()?;
+    let metadata_len = decode_footer(footer.as_ref().try_into().ok()?).ok()?;
+    let start = file_size.checked_sub(FOOTER_SIZE + metadata_len)?;
+    let encoded = reader
+        .get_bytes(start..file_size - FOOTER_SIZE)
+        .await
+        .ok()?;
+
+    let mut transcoder = Transcoder {
+        input: &encoded,
+        position: 0,
+        output: Vec::with_capacity(encoded.len()),
+        dropped: vec![],
+    };
+    transcoder.file_metadata()?;
+    if transcoder.dropped.is_empty() {
+        return None;
+    }
+    let metadata = decode_metadata(&transcoder.output).ok()?;
+    for (column, logical_type) in transcoder.dropped {
+        anomalies::report(
+            Anomaly::UnknownLogicalType,
+            1,
+            format!(
+                "column {} of logical type {} read as its converted or physical type",
+                column, logical_type
+            ),
+        );
+    }
+    Some(metadata)
+}
+
+/// Copies a FileMetaData struct encoded with the thrift compact protocol, leaving out the
+/// logical types of schema elements that are members of the LogicalType union unknown to the
+/// parquet crate. Returns `None` from any method once the input is malformed.
+struct Transcoder<'a> {
+    input: &'a [u8],
+    position: usize,
+    output: Vec<u8>,
+    /// Names of the schema elements whose logical type was left out, with the union member
+    dropped: Vec<(String, i16)>,
+}
+
+impl Transcoder<'_> {
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.input.get(self.position)?;
+        self.position += 1;
+        Some(byte)
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn binary(&mut self) -> Option<&[u8]> {
+        let length = usize::try_from(self.varint()?).ok()?;
+        let start = self.position;
+        self.position = start.checked_add(length)?;
+        self.input.get(start..self.position)
+    }
+
+    /// Reads the header of the next field of a struct, returning its id and type, or `None`
+    /// once the struct ends.
+    fn field_header(&mut self, last_id: i16) -> Option<Option<(i16, u8)>> {
+        let byte = self.byte()?;
+        if byte == 0 {
+            return Some(None);
+        }
+        let id = match byte >> 4 {
+            0 => {
+                let zigzag = self.varint()?;
+                ((zigzag >> 1) as i64 ^ -((zigzag & 1) as i64)) as i16
+            }
+            delta => last_id.checked_add(i16::from(delta))?,
+        };
+        Some(Some((id, byte & 0x0f)))
+    }
+
+    /// Reads the header of a list or set, returning its size and the type of its elements.
+    fn list_header(&mut self) -> Option<(usize, u8)> {
+        let byte = self.byte()?;
+        let size = match byte >> 4 {
+            15 => usize::try_from(self.varint()?).ok()?,
+            size => usize::from(size),
+        };
+        Some((size, byte & 0x0f))
+    }
+
+    fn write_field_header(&mut self, id: i16, field_type: u8, last_id: i16) {
+        match id.checked_sub(last_id) {
+            Some(delta @ 1..=15) => self.output.push(((delta as u8) << 4) | field_type),
+            _ => {
+                self.output.push(field_type);
+                let mut zigzag = ((id << 1) ^ (id >> 15)) as u16;
+                while zigzag >= 0x80 {
+                    self.output.push(zigzag as u8 | 0x80);
+                    zigzag >>= 7;
+                }
+                self.output.push(zigzag as u8);
+            }
+        }
+    }
+
+    /// Skips a value of the given type, whose booleans are held by its field header.
+    fn skip(&mut self, value_type: u8) -> Option<()> {
+        match value_type {
+            BOOLEAN_TRUE | BOOLEAN_FALSE => {}
+            BYTE => {
+                self.byte()?;
+            }
+            I16 | I32 | I64 => {
+                self.varint()?;
+            }
+            DOUBLE => {
+                self.position = self.position.checked_add(8)?;
+            }
+            BINARY => {
+                self.binary()?;
+            }
+            LIST | SET => {
+                let (size, element_type) = self.list_header()?;
+                for _ in 0..size {
+                    self.skip_element(element_type)?;
+                }
+            }
+            MAP => {
+                let size = self.varint()?;
+                if size > 0 {
+                    let types = self.byte()?;
+                    for _ in 0..size {
+                        self.skip_element(types >> 4)?;
+                        self.skip_element(types & 0x0f)?;
+                    }
+                }
+            }
+            STRUCT => {
+                let mut last_id = 0;
+                while let Some((id, field_type)) = self.field_header(last_id)? {
+                    self.skip(field_type)?;
+                    last_id = id;
+                }
+            }
+            _ => return None,
+        }
+        (self.position <= self.input.len()).then_some(())
+    }
+
+    /// Skips an element of a list, set or map, whose booleans are a byte each.
+    fn skip_element(&mut self, element_type: u8) -> Option<()> {
+        match element_type {
+            BOOLEAN_TRUE | BOOLEAN_FALSE => self.byte().map(|_| ()),
+            _ => self.skip(element_type),
+        }
+    }
+
+    /// Copies the FileMetaData struct, whose field 2 is the list of schema elements.
+    fn file_metadata(&mut self) -> Option<()> {
+        let mut last_id = 0;
+        loop {
+            let start = self.position;
+            let Some((id, field_type)) = self.field_header(last_id)? else {
+                break;
+            };
+            if id == 2 && field_type == LIST {
+                let (size, element_type) = self.list_header()?;
+                self.output
+                    .extend_from_slice(&self.input[start..self.position]);
+                if element_type != STRUCT {
+                    return None;
+                }
+                for _ in 0..size {
+                    self.schema_element()?;
+                }
+            } else {
+                self.skip(field_type)?;
+                self.output
+                    .extend_from_slice(&self.input[start..self.position]);
+            }
+            last_id = id;
+        }
+        self.output.push(0);
+        Some(())
+    }
+
+    /// Copies a SchemaElement struct, whose field 4 is its name and field 10 its logical type,
+    /// re-encoding the headers of its fields, which are relative to the previous one written.
+    fn schema_element(&mut self) -> Option<()> {
+        let (mut last_read, mut last_written) = (0, 0);
+        let mut name = String::new();
+        while let Some((id, field_type)) = self.field_header(last_read)? {
+            last_read = id;
+            let start = self.position;
+            if id == 4 && field_type == BINARY {
+                name = String::from_utf8_lossy(self.binary()?).into_owned();
+                self.position = start;
+            }
+            if id == 10 && field_type == STRUCT {
+                // A union is a struct of a single field, that of its member
+                let member = self.field_header(0)?.map(|(member, _)| member);
+                self.position = start;
+                self.skip(STRUCT)?;
+                if let Some(member) = member.filter(|member| !KNOWN_LOGICAL_TYPES.contains(member))
+                {
+                    self.dropped.push((name.clone(), member));
+                    continue;
+                }
+            } else {
+                self.skip(field_type)?;
+            }
+            self.write_field_header(id, field_type, last_written);
+            last_written = id;
+            self.output
+                .extend_from_slice(&self.input[start..self.position]);
+        }
+        self.output.push(0);
+        Some(())
+    }
+}
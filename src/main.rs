@@ -1,276 +1,1814 @@
+use std::io::Write;
 use std::ops::Add;
-use std::sync::Arc;
-
-use arrow_array::{Array, RecordBatch};
-use arrow_cast::display::FormatOptions;
-use arrow_cast::{cast_with_options, CastOptions};
-use arrow_json::writer::LineDelimited;
-use arrow_json::WriterBuilder;
-use arrow_schema::{DataType, Field, SchemaBuilder};
-use aws_config::profile::load;
-use aws_runtime::env_config::file::EnvConfigFiles;
-use aws_types::os_shim_internal::{Env, Fs};
-use cast::cast_binary_to_string;
-use clap::{Parser, Subcommand};
-use object_store::aws::AmazonS3Builder;
-use object_store::http::HttpBuilder;
-use object_store::local::LocalFileSystem;
+use std::sync::{Arc, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use advise::advise;
+use annotations::{describe, field_annotations};
+use anomalies::{non_finite_values, Anomaly, AnomalyArgs};
+use arrow_schema::SchemaRef;
+use arrow_select::filter::filter_record_batch;
+use bulk::BulkAction;
+use clap::error::ErrorKind;
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use clap_complete::{generate, Shell};
+use clap_mangen::Man;
+use clickhouse::create_table;
+use clock::Clock;
+use codecs::codecs_report;
+use coerce::{coerce_batch, widen_schemas};
+use csv_output::CsvQuoting;
+use dataset::{
+    glob_files, list_files, partition_values, prefetch_footers, read_manifest, split_glob,
+    with_partition_columns, Footer, METADATA_CONCURRENCY,
+};
+use derive::{derive_columns, Derivation};
+use duplicates::check_duplicates;
+use envelope::{Envelope, EnvelopeFormat, RowIndices};
+use filter::Predicate;
+use fingerprint::schema_fingerprint;
+use fk::{check_fk, Reference};
+use flightsql::is_flight_sql_location;
+use freq::freq;
+use gaps::check_gaps;
+use index::{index_location, index_url, SidecarIndex};
+use input_format::{convert_inputs, InputFormat};
+use join::Lookups;
+use mapping::{es_mapping, MappingOptions, StringMapping};
+use metrics::print_summary;
 use object_store::path::Path;
-use object_store::ObjectStore;
-use parquet::arrow::arrow_reader::ArrowReaderMetadata;
-use parquet::arrow::ParquetRecordBatchStreamBuilder;
+use object_store::{ObjectMeta, ObjectStore};
+use output::{estimated_bytes_per_row, Output, ReportWriter, TARGET_BATCH_BYTES};
+use parquet::arrow::arrow_reader::{ArrowReaderMetadata, RowSelection};
+use parquet::arrow::{arrow_to_parquet_schema, ParquetRecordBatchStreamBuilder};
 use parquet::arrow::{async_reader::ParquetObjectReader, ProjectionMask};
 use parquet::schema::printer::print_schema;
+use profile::{apply_profile, Profile};
+use progress::Progress;
+use rescale::{rescale_decimals, DecimalRescale, Rounding};
+use reshape::{Pivot, Unpivot};
+use rows::{
+    parse_row_indices, row_groups_and_selection, row_groups_from_offset,
+    rows_before_last_row_groups, rows_read_per_row_group,
+};
+use s3_flavor::S3Flavor;
+use serde_json::json;
+use source::{store_for_location, store_for_output};
+use spill::SpillArgs;
+use split::{split_timestamps, TimestampSplit};
+use style::Style;
+use temp::TempArgs;
 use tokio_stream::StreamExt;
-use url::Url;
-use urlencoding::decode;
+use update::self_update;
+use vecdb::VectorDb;
 
+mod access_point;
+#[cfg(feature = "adbc")]
+mod adbc;
+mod advise;
+mod annotations;
+mod anomalies;
+mod archive;
+#[cfg(feature = "avro")]
+mod avro;
+mod between;
+mod bulk;
 mod cast;
+mod clickhouse;
+mod clock;
+mod codecs;
+mod coerce;
+mod compress;
+mod credential_fallback;
+mod csv_output;
+mod dataset;
+mod derive;
+mod duplicates;
+mod encoder;
+mod envelope;
+mod filter;
+mod fingerprint;
+mod fk;
+mod flightsql;
+mod freq;
+mod full_download;
+mod gaps;
+mod index;
+mod input_format;
+mod join;
+mod jsonl;
+mod kafka;
+mod logical_types;
+mod mapping;
+mod markdown;
+mod metrics;
+mod object_version;
+mod output;
+mod parquet_output;
+mod partition;
+mod post;
+mod presigned;
+mod profile;
+mod progress;
+mod requester_pays;
+mod rescale;
+mod reshape;
+mod rows;
+mod s3_flavor;
+mod sdk_credentials;
+mod shard;
+mod sink;
+mod source;
+mod spill;
+mod split;
+mod sql;
+mod style;
+mod table;
+mod temp;
+mod timeout;
+mod transport;
+mod update;
+mod vecdb;
+mod yaml;
 
 #[derive(Parser, Clone)]
-#[clap(version, about, long_about = None)]
+#[clap(
+    version,
+    about,
+    long_about = None,
+    subcommand_negates_reqs = true,
+    subcommand_precedence_over_arg = true,
+    override_usage = "parquet2json [OPTIONS] <FILE>... <COMMAND>\n       parquet2json <completions|manpage|self-update>"
+)]
 struct Cli {
-    /// Location of Parquet input file (file path, HTTP or S3 URL)
-    file: String,
+    /// Location of Parquet input file (file path, HTTP, S3 or Azure URL) or Flight SQL query, glob patterns such as
+    /// data/part-*.parquet and multiple files are supported by cat
+    #[clap(required = true, value_name = "FILE")]
+    files: Vec<String>,
+
+    /// File listing input locations, one per line, read as a single dataset after any FILE
+    /// arguments, such as the manifest of an Athena UNLOAD (file path, URL or - for stdin)
+    #[clap(long, global = true)]
+    manifest: Option<String>,
+
+    /// Style of human-facing output such as schema trees and errors
+    #[clap(default_value_t, long, value_enum, global = true)]
+    style: Style,
+
+    /// Print request, byte, peak memory and time totals to stderr when done
+    #[clap(long, global = true)]
+    summary: bool,
+
+    /// Keeps output the same across runs for golden-file tests, leaving out peak memory and time
+    /// from --summary
+    #[clap(long, global = true)]
+    deterministic: bool,
+
+    /// Format of input files, files other than Parquet being converted as they are read
+    #[clap(default_value_t, long, value_enum, global = true)]
+    input_format: InputFormat,
+
+    #[clap(flatten)]
+    s3: S3Args,
+
+    #[clap(flatten)]
+    http: HttpArgs,
+
+    #[clap(flatten)]
+    temp: TempArgs,
 
     #[clap(subcommand)]
     command: Commands,
 }
 
+/// Settings for S3 locations, such as S3-compatible services like MinIO, R2 or Ceph.
+#[derive(Args, Clone, Default)]
+struct S3Args {
+    /// Named profile of the AWS credentials and config files to use
+    #[clap(long, env = "AWS_PROFILE", global = true)]
+    aws_profile: Option<String>,
+
+    /// Role to assume with STS for S3 access, using the profile or environment credentials
+    #[clap(long, global = true)]
+    role_arn: Option<String>,
+
+    /// External ID required by the role to assume
+    #[clap(long, global = true)]
+    external_id: Option<String>,
+
+    /// Session name of the assumed role [default: parquet2json]
+    #[clap(long, global = true)]
+    session_name: Option<String>,
+
+    /// Endpoint of an S3-compatible service (e.g. http://localhost:9000)
+    #[clap(long, env = "AWS_ENDPOINT_URL", global = true)]
+    endpoint_url: Option<String>,
+
+    /// Address buckets in the path rather than the host name of the endpoint
+    #[clap(long, global = true)]
+    force_path_style: bool,
+
+    /// Preset of the endpoint, signing region and bucket addressing of an S3-compatible service
+    #[clap(default_value_t, long, value_enum, global = true)]
+    s3_flavor: S3Flavor,
+
+    /// Read through the S3 Transfer Acceleration endpoint of the bucket, faster across continents
+    #[clap(
+        long,
+        global = true,
+        conflicts_with_all = ["endpoint_url", "force_path_style"]
+    )]
+    use_accelerate_endpoint: bool,
+
+    /// Read through the dual-stack endpoint of the bucket, reachable over IPv6
+    #[clap(
+        long,
+        env = "AWS_USE_DUALSTACK_ENDPOINT",
+        global = true,
+        conflicts_with = "endpoint_url"
+    )]
+    use_dualstack_endpoint: bool,
+
+    /// Read public buckets anonymously, without loading credentials or signing requests
+    #[clap(long, global = true, conflicts_with = "request_payer")]
+    no_sign_request: bool,
+
+    /// Read anonymously, without signing requests, once every source of AWS credentials has
+    /// failed or been denied access
+    #[clap(long, global = true, conflicts_with_all = ["no_sign_request", "request_payer"])]
+    anonymous_fallback: bool,
+
+    /// Accept the charges of reading from requester-pays buckets
+    #[clap(long, value_name = "PAYER", global = true)]
+    request_payer: Option<RequestPayer>,
+
+    /// Version of the S3 object to read from a versioned bucket, instead of the latest
+    #[clap(long, global = true)]
+    version_id: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum RequestPayer {
+    Requester,
+}
+
+static S3_ARGS: OnceLock<S3Args> = OnceLock::new();
+
+/// Settings for HTTP locations and connections.
+#[derive(Args, Clone, Default)]
+struct HttpArgs {
+    /// Authorization of requests to HTTP(S) files read, as bearer:<TOKEN> or
+    /// basic:<USER>:<PASSWORD>
+    #[clap(
+        long,
+        env = "PARQUET2JSON_HTTP_AUTH",
+        hide_env_values = true,
+        global = true
+    )]
+    http_auth: Option<String>,
+
+    /// Authorization of requests of post: and kafka: outputs, as bearer:<TOKEN> or
+    /// basic:<USER>:<PASSWORD>
+    #[clap(
+        long,
+        env = "PARQUET2JSON_OUTPUT_HTTP_AUTH",
+        hide_env_values = true,
+        global = true
+    )]
+    output_http_auth: Option<String>,
+
+    /// Proxy for HTTP, S3 and Azure requests, instead of HTTP_PROXY and HTTPS_PROXY, still
+    /// bypassed for the hosts in NO_PROXY
+    #[clap(long, global = true)]
+    proxy: Option<String>,
+
+    /// Size in megabytes of objects downloaded whole from HTTP servers that do not support
+    /// range requests, above which parquet2json stops with an error
+    #[clap(default_value_t = 1024, long, global = true)]
+    max_download_size: u64,
+
+    /// Seconds after which requests for object metadata, listings and footers fail, rather than
+    /// hang [default: no timeout]
+    #[clap(long, value_name = "SECONDS", global = true)]
+    metadata_timeout: Option<u64>,
+
+    /// Seconds after which each request reading data fails, rather than hang [default: no
+    /// timeout]
+    #[clap(long, value_name = "SECONDS", global = true)]
+    data_timeout: Option<u64>,
+}
+
+static HTTP_ARGS: OnceLock<HttpArgs> = OnceLock::new();
+
+#[derive(Args, Clone)]
+struct CatArgs {
+    /// Starts outputting from this row (first row: 0, last row: -1)
+    #[clap(default_value_t = 0, short, long)]
+    offset: i64,
+
+    /// Maximum number of rows to output
+    #[clap(short, long)]
+    limit: Option<usize>,
+
+    /// Reads only the last row groups of each file, e.g. to peek at recently appended data
+    #[clap(long, conflicts_with_all = ["offset", "rows", "rows_file"])]
+    last_row_groups: Option<usize>,
+
+    /// Select columns by name (comma,separated,?prefixed_optional)
+    #[clap(short, long)]
+    columns: Option<String>,
+
+    /// Outputs null values
+    #[clap(short, long)]
+    nulls: bool,
+
+    /// Indents each record over several lines, for reading rather than loading, with the jsonl
+    /// format
+    #[clap(long)]
+    pretty: bool,
+
+    /// Outputs only rows at these indices (comma,separated), reading only their row groups
+    #[clap(long, conflicts_with = "rows_file")]
+    rows: Option<String>,
+
+    /// Outputs only rows at indices listed in this file (one per line)
+    #[clap(long)]
+    rows_file: Option<String>,
+
+    /// Outputs only rows whose key is in this CSV or JSON lines file
+    #[clap(long, requires = "on", conflicts_with = "anti_join")]
+    semi_join: Option<String>,
+
+    /// Outputs only rows whose key is not in this CSV or JSON lines file
+    #[clap(long, requires = "on")]
+    anti_join: Option<String>,
+
+    /// Appends fields from the matching record in this CSV or JSON lines lookup file
+    #[clap(long, requires = "on")]
+    enrich: Option<String>,
+
+    /// Prefix for field names appended by --enrich
+    #[clap(default_value = "", long)]
+    prefix: String,
+
+    /// Key column to join on, in both the Parquet file and the key or lookup file
+    #[clap(long)]
+    on: Option<String>,
+
+    /// Outputs only rows matching all comparisons (e.g. "country = 'US' and id >= 10")
+    #[clap(long = "where")]
+    predicate: Option<String>,
+
+    /// Outputs only rows with values in all of the columns (comma,separated)
+    #[clap(long, value_name = "COLUMNS")]
+    drop_if_null: Option<String>,
+
+    /// Outputs only rows with a value in any of the columns (comma,separated)
+    #[clap(long, value_name = "COLUMNS")]
+    keep_if_any_nonnull: Option<String>,
+
+    /// Outputs only rows with timestamps from the start (inclusive) to the end (exclusive) of a
+    /// range of local dates or times (e.g. ts 2024-05-01..2024-06-01)
+    #[clap(long, num_args = 2, value_names = ["COLUMN", "RANGE"])]
+    between: Option<Vec<String>>,
+
+    /// Time zone of --between dates and times (e.g. Europe/Paris)
+    #[clap(default_value = "UTC", long, requires = "between")]
+    tz: String,
+
+    /// Location of the sidecar index used to skip row groups [default: <FILE>.p2jidx]
+    #[clap(long)]
+    index_file: Option<String>,
+
+    /// Rounds or pads a decimal column to a number of decimal places (e.g. amount=2)
+    #[clap(long, value_name = "COLUMN=SCALE")]
+    rescale_decimal: Vec<String>,
+
+    /// Rounding of decimal values rescaled to fewer decimal places
+    #[clap(default_value_t, long, value_enum, requires = "rescale_decimal")]
+    rounding: Rounding,
+
+    /// Outputs the date and/or time of a timestamp column as <COLUMN>_date and <COLUMN>_time
+    /// fields (e.g. ts=date,time), in the time zone of the timestamps
+    #[clap(long, value_name = "COLUMN=PARTS")]
+    split_timestamp: Vec<String>,
+
+    /// Do not output the original columns of --split-timestamp
+    #[clap(long, requires = "split_timestamp")]
+    drop_split_columns: bool,
+
+    /// Outputs a column computed from others with arithmetic and functions (e.g.
+    /// 'total=price*qty' or 'day=date_trunc(day, ts)'), replacing any column of the same name
+    #[clap(long, value_name = "NAME=EXPRESSION")]
+    derive: Vec<String>,
+
+    /// Outputs a row per column matching a pattern instead, with the other columns, the name of
+    /// the column and its value (e.g. 'metric_*:name,value')
+    #[clap(long, value_name = "PATTERN:NAME,VALUE")]
+    unpivot: Option<String>,
+
+    /// Outputs adjacent rows with the same other columns as one row instead, with a column per
+    /// distinct name holding its value, as the inverse of --unpivot (e.g. name,value)
+    #[clap(long, value_name = "NAME,VALUE")]
+    pivot: Option<String>,
+
+    /// Wraps each row in an envelope with the file it was read from, its index in the file and
+    /// the time of the run
+    #[clap(long, value_enum, conflicts_with_all = ["wrap", "pivot", "partition_by"])]
+    envelope: Option<EnvelopeFormat>,
+
+    /// Type of the events of --envelope cloudevents
+    #[clap(default_value = "parquet2json.row", long)]
+    event_type: String,
+
+    /// Outputs each row under a key instead, with the file it was read from, its index in the
+    /// file and the time of the run
+    #[clap(long, value_name = "KEY", conflicts_with_all = ["pivot", "partition_by"])]
+    wrap: Option<String>,
+
+    /// Output format
+    #[clap(default_value_t, long, value_enum)]
+    format: OutputFormat,
+
+    /// Delimiter of csv fields, a single character or \t for tabs
+    #[clap(default_value = ",", long)]
+    delimiter: String,
+
+    /// Fields quoted in csv output
+    #[clap(default_value_t, long, value_enum)]
+    quoting: CsvQuoting,
+
+    /// Number of characters values of markdown and table output are truncated to, with an
+    /// ellipsis [default: none for markdown, 40 for table]
+    #[clap(long)]
+    max_width: Option<usize>,
+
+    /// Option of the output format, as KEY=VALUE, overriding the flag of the same name (e.g.
+    /// delimiter=; for csv, see the formats in the README for the options each takes)
+    #[clap(long, value_name = "KEY=VALUE")]
+    format_opt: Vec<String>,
+
+    /// Adapts values to what a specific loader accepts
+    #[clap(long, value_enum)]
+    profile: Option<Profile>,
+
+    /// Index name for es-bulk action lines
+    #[clap(long)]
+    index: Option<String>,
+
+    /// Action of es-bulk action lines
+    #[clap(default_value_t, long, value_enum)]
+    bulk_action: BulkAction,
+
+    /// Column used as document ID for es-bulk action lines and vecdb records
+    #[clap(long)]
+    id_column: Option<String>,
+
+    /// Column of vectors (list of numbers) for vecdb records
+    #[clap(long, required_if_eq("format", "vecdb"))]
+    vector_column: Option<String>,
+
+    /// Vector database whose bulk upsert request bodies vecdb outputs
+    #[clap(default_value_t, long, value_enum)]
+    vector_db: VectorDb,
+
+    /// Number of records per vecdb request body
+    #[clap(default_value_t = 100, long)]
+    vecdb_batch_size: usize,
+
+    /// Number of rows read and serialized at a time, coalescing small row groups [default: 8192,
+    /// fewer for rows too wide for --target-batch-bytes]
+    #[clap(long)]
+    batch_size: Option<usize>,
+
+    /// Size in bytes in memory that batches of wide rows are shrunk to, estimated from the footer
+    /// and then the first batch, unless --batch-size is given
+    #[clap(default_value_t = TARGET_BATCH_BYTES, long)]
+    target_batch_bytes: usize,
+
+    /// Number of batches serialized in parallel, written in order
+    #[clap(default_value_t = 1, long)]
+    serialize_threads: usize,
+
+    /// Size in kilobytes of serialized output gathered before each write to stdout, or 0 to
+    /// write each batch once serialized
+    #[clap(default_value_t = 1024, long)]
+    write_buffer_size: usize,
+
+    /// Writes gzip-compressed shards (part-00000.json.gz, ...) under this directory or prefix,
+    /// named by content hash with a file name containing {hash} (e.g. out/part-{hash}.json.gz), or
+    /// to a sink: a single file with file:<path>, POST requests with post:<url>, a Kafka topic of
+    /// a REST Proxy with kafka:<url>, or a database with adbc:<uri> (e.g. adbc:postgresql://host/db)
+    #[clap(long)]
+    output: Option<String>,
+
+    /// Fails before outputting any rows unless the schema of each file has this fingerprint, as
+    /// output by schema --fingerprint, or the unified schema with --coerce-schemas
+    #[clap(long, value_name = "HASH")]
+    expect_fingerprint: Option<String>,
+
+    /// Compresses the output to stdout or a file: output as it is written, with gzip or zstd and
+    /// an optional level (e.g. zstd:19)
+    #[clap(long, value_name = "CODEC[:LEVEL]")]
+    compress: Option<String>,
+
+    /// Table that adbc: outputs ingest into, created if it does not exist, or that sql output
+    /// inserts into
+    #[clap(long)]
+    table: Option<String>,
+
+    /// Outputs field descriptions and annotations from pandas, Spark, Avro or Arrow metadata as a
+    /// first {"_annotations": {...}} line
+    #[clap(long)]
+    with_annotations: bool,
+
+    /// Casts rows of files with differing schemas under a directory or prefix to a unified schema,
+    /// widening types and filling missing columns with nulls
+    #[clap(long)]
+    coerce_schemas: bool,
+
+    /// Do not output key=value path segments of partitioned layouts (e.g. dt=2024-01-01/) as
+    /// columns
+    #[clap(long)]
+    no_partition_columns: bool,
+
+    /// Compressed size in megabytes at which shards are split [default: per --profile, or 128]
+    #[clap(long, requires = "output")]
+    max_file_size: Option<usize>,
+
+    /// Writes the shards of --output under a key=value directory per value of a column (e.g.
+    /// out/country=US/), leaving the column out of the rows, as Hive and Spark read them back
+    #[clap(long, requires = "output", value_name = "COLUMN")]
+    partition_by: Vec<String>,
+
+    /// Number of file footers fetched concurrently, ahead of the rows, with multiple input files
+    #[clap(default_value_t = METADATA_CONCURRENCY, long)]
+    metadata_concurrency: usize,
+
+    #[clap(flatten)]
+    anomalies: AnomalyArgs,
+
+    /// Writes JSON progress events (rows, bytes, percent, ETA) every second to a file descriptor
+    /// as fd:<N>, or to a file
+    #[clap(long, value_name = "TARGET")]
+    progress_json: Option<String>,
+}
+
+#[derive(Args, Clone)]
+struct ExtractArgs {
+    /// Starts extracting from this row (first row: 0, last row: -1)
+    #[clap(default_value_t = 0, short, long)]
+    offset: i64,
+
+    /// Maximum number of rows to extract
+    #[clap(short, long)]
+    limit: Option<usize>,
+
+    /// Select columns by name (comma,separated,?prefixed_optional)
+    #[clap(short, long)]
+    columns: Option<String>,
+
+    /// Path of the Parquet file to write instead of stdout
+    #[clap(long)]
+    output: Option<String>,
+
+    /// Compression codec of the file (e.g. snappy, zstd(3) or uncompressed)
+    #[clap(default_value = "snappy", long)]
+    compression: String,
+}
+
+impl ExtractArgs {
+    /// Returns the arguments of cat writing the rows as Parquet, with defaults for the rest.
+    fn cat_args(&self) -> CatArgs {
+        let command = CatArgs::augment_args(clap::Command::new("cat"));
+        let mut args = CatArgs::from_arg_matches(&command.get_matches_from(["cat"])).unwrap();
+        args.offset = self.offset;
+        args.limit = self.limit;
+        args.columns = self.columns.clone();
+        args.format = OutputFormat::Parquet;
+        args.format_opt = vec![format!("compression={}", self.compression)];
+        args.output = self
+            .output
+            .as_ref()
+            .map(|output| format!("file:{}", output));
+        args
+    }
+}
+
+#[derive(Args, Clone)]
+struct ReportArgs {
+    /// Location to write the output to instead of stdout, once complete (file path, S3 or Azure
+    /// URL)
+    #[clap(short, long)]
+    output: Option<String>,
+}
+
 #[derive(Subcommand, Clone)]
 enum Commands {
     /// Outputs data as JSON lines
-    Cat {
-        /// Starts outputting from this row (first row: 0, last row: -1)
-        #[clap(default_value_t = 0, short, long)]
-        offset: i64,
+    Cat(Box<CatArgs>),
 
-        /// Maximum number of rows to output
-        #[clap(short, long)]
-        limit: Option<usize>,
+    /// Writes rows as a Parquet file, such as a small sample of a huge remote file
+    Extract(ExtractArgs),
+
+    /// Outputs the Thrift schema
+    Schema {
+        /// Output format
+        #[clap(default_value_t, long, value_enum)]
+        format: SchemaFormat,
 
-        /// Select columns by name (comma,separated,?prefixed_optional)
+        /// Mapping of string fields for es-mapping (multi: text with a keyword sub-field)
+        #[clap(default_value_t, long, value_enum)]
+        es_strings: StringMapping,
+
+        /// String fields always mapped as text for es-mapping (comma,separated,nested.paths)
+        #[clap(long)]
+        es_text: Option<String>,
+
+        /// Table name for clickhouse-ddl [default: file name without extension]
+        #[clap(long)]
+        table: Option<String>,
+
+        /// Shows field descriptions and annotations from pandas, Spark, Avro or Arrow metadata
+        #[clap(long)]
+        with_annotations: bool,
+
+        /// Outputs a hash of the column names, types and nullability instead, to check with
+        /// cat --expect-fingerprint
+        #[clap(long)]
+        fingerprint: bool,
+
+        #[clap(flatten)]
+        report: ReportArgs,
+    },
+
+    /// Outputs only the total row count
+    Rowcount {
+        #[clap(flatten)]
+        report: ReportArgs,
+    },
+
+    /// Analyzes row group, page, dictionary and compression layout and recommends writer settings
+    Advise {
+        #[clap(flatten)]
+        report: ReportArgs,
+    },
+
+    /// Reports the codecs and encodings of each column across all files, and inconsistencies
+    Codecs {
+        #[clap(flatten)]
+        report: ReportArgs,
+    },
+
+    /// Outputs the distinct values of a column with their counts, most frequent first, as JSON lines
+    Freq {
+        /// Column name
+        column: String,
+
+        /// Minimum count of values to output
+        #[clap(default_value_t = 1, long)]
+        min_count: usize,
+
+        #[clap(flatten)]
+        report: ReportArgs,
+
+        /// Estimates counts from this number of row groups chosen at random across all files,
+        /// with a margin of error at 95% confidence, to keep huge datasets interactive
+        #[clap(long, value_name = "ROW_GROUPS")]
+        sample_row_groups: Option<usize>,
+
+        /// Seed of the random choice of sampled row groups [default: random, as reported]
+        #[clap(long, requires = "sample_row_groups")]
+        seed: Option<u64>,
+    },
+
+    /// Reports duplicate keys across all files under a directory or prefix
+    CheckDuplicates {
+        /// Key columns by name (comma,separated)
         #[clap(short, long)]
-        columns: Option<String>,
+        key: String,
+
+        /// Size of the probabilistic pre-filter in megabytes
+        #[clap(default_value_t = 64, long)]
+        bloom_size: usize,
 
-        /// Outputs null values
+        #[clap(flatten)]
+        spill: SpillArgs,
+
+        #[clap(flatten)]
+        report: ReportArgs,
+    },
+
+    /// Reports values of a column missing from the key column of a reference file, as a foreign
+    /// key check without a join
+    CheckFk {
+        /// Columns by name (comma,separated)
         #[clap(short, long)]
-        nulls: bool,
+        column: String,
+
+        /// Reference file or directory and its key columns, e.g. users.parquet:id
+        #[clap(long, value_name = "FILE:COLUMN")]
+        against: String,
+
+        /// Number of values without a match to output as samples
+        #[clap(default_value_t = 10, long)]
+        samples: usize,
+
+        #[clap(flatten)]
+        spill: SpillArgs,
+
+        #[clap(flatten)]
+        report: ReportArgs,
     },
 
-    /// Outputs the Thrift schema
-    Schema {},
+    /// Reports intervals without values of a time column, per group
+    CheckGaps {
+        /// Time column by name
+        #[clap(long)]
+        by: String,
 
-    /// Outputs only the total row count
-    Rowcount {},
+        /// Length of the intervals expected to have values, a number and a unit of ms, s, m, h, d
+        /// or w (e.g. 5m)
+        #[clap(long)]
+        interval: String,
+
+        /// Columns grouping rows by name (comma,separated)
+        #[clap(long)]
+        group_by: Option<String>,
+
+        #[clap(flatten)]
+        report: ReportArgs,
+    },
+
+    /// Manages the sidecar index of per row group min/max values used by cat --where
+    Index {
+        #[clap(subcommand)]
+        command: IndexCommands,
+    },
+
+    /// Outputs a shell completion script
+    Completions {
+        #[clap(value_enum)]
+        shell: Shell,
+
+        #[clap(flatten)]
+        report: ReportArgs,
+    },
+
+    /// Outputs a man page
+    Manpage {
+        #[clap(flatten)]
+        report: ReportArgs,
+    },
+
+    /// Updates this executable to the latest GitHub release
+    ///
+    /// The release is only installed once its minisign signature is verified by the release
+    /// public key built into this executable. Builds without that key, such as those of cargo
+    /// install, do not update themselves.
+    SelfUpdate {
+        /// Only checks whether an update is available
+        #[clap(long)]
+        check: bool,
+
+        /// Reinstalls even if already up to date
+        #[clap(long)]
+        force: bool,
+    },
 }
 
-async fn output_for_command(mut reader: ParquetObjectReader, command: &Commands) {
-    let metadata = ArrowReaderMetadata::load_async(&mut reader, Default::default())
-        .await
-        .unwrap();
-    let metadata_clone = metadata.clone();
-    let parquet_metadata = metadata_clone.metadata();
-    let mut async_reader_builder =
-        ParquetRecordBatchStreamBuilder::new_with_metadata(reader, metadata);
+impl Commands {
+    /// Location the output of commands other than cat and extract is written to, if not stdout.
+    fn report_output(&self) -> Option<&str> {
+        match self {
+            Commands::Schema { report, .. }
+            | Commands::Rowcount { report }
+            | Commands::Advise { report }
+            | Commands::Codecs { report }
+            | Commands::Freq { report, .. }
+            | Commands::CheckDuplicates { report, .. }
+            | Commands::CheckFk { report, .. }
+            | Commands::CheckGaps { report, .. }
+            | Commands::Completions { report, .. }
+            | Commands::Manpage { report } => report.output.as_deref(),
+            Commands::Cat(_)
+            | Commands::Extract(_)
+            | Commands::Index { .. }
+            | Commands::SelfUpdate { .. } => None,
+        }
+    }
+}
 
-    match command {
-        Commands::Cat {
-            offset,
-            limit,
-            columns,
-            nulls,
-        } => {
-            let absolute_offset: usize = if offset.is_negative() {
-                parquet_metadata
-                    .file_metadata()
-                    .num_rows()
-                    .add(offset)
-                    .try_into()
-                    .unwrap()
-            } else {
-                offset.abs().try_into().unwrap()
-            };
-            async_reader_builder = async_reader_builder.with_offset(absolute_offset);
+#[derive(ValueEnum, Clone, Copy, Default, PartialEq)]
+enum OutputFormat {
+    #[default]
+    Jsonl,
+    Csv,
+    Tsv,
+    EsBulk,
+    Vecdb,
+    Yaml,
+    Sql,
+    PgCopy,
+    Markdown,
+    Table,
+    // Written by extract rather than selected
+    #[value(hide = true)]
+    Parquet,
+}
+
+impl OutputFormat {
+    /// Returns how NaN and infinite floats are output, which JSON cannot represent.
+    fn non_finite_output(&self) -> &'static str {
+        match self {
+            OutputFormat::Jsonl
+            | OutputFormat::EsBulk
+            | OutputFormat::Vecdb
+            | OutputFormat::Yaml => "output as null",
+            OutputFormat::Csv
+            | OutputFormat::Tsv
+            | OutputFormat::Markdown
+            | OutputFormat::Table => "output as NaN or inf",
+            OutputFormat::PgCopy => "output as NaN or Infinity",
+            OutputFormat::Sql => "output as the sql dialect reads them",
+            OutputFormat::Parquet => "output as is",
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Default)]
+enum SchemaFormat {
+    #[default]
+    Thrift,
+    EsMapping,
+    ClickhouseDdl,
+}
+
+#[derive(Subcommand, Clone)]
+enum IndexCommands {
+    /// Builds the sidecar index for the given columns
+    Build {
+        /// Select columns by name (comma,separated)
+        #[clap(short, long)]
+        columns: String,
+
+        /// Location to write the index to [default: <FILE>.p2jidx]
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+}
+
+/// Outputs the rows of one file, cast to the given schema if any, and returns the number output,
+/// counted as --limit counts them, before they are reshaped.
+async fn cat(
+    storage_container: Arc<dyn ObjectStore>,
+    meta: ObjectMeta,
+    metadata: ArrowReaderMetadata,
+    args: &CatArgs,
+    lookups: &Lookups,
+    output: &mut Output,
+    mut schema: Option<SchemaRef>,
+) -> usize {
+    let parquet_metadata = metadata.metadata();
+
+    let CatArgs {
+        offset,
+        limit,
+        last_row_groups,
+        columns,
+        rows,
+        rows_file,
+        on,
+        index_file,
+        rescale_decimal,
+        rounding,
+        split_timestamp,
+        drop_split_columns,
+        derive,
+        unpivot,
+        pivot,
+        format,
+        profile,
+        with_annotations,
+        no_partition_columns,
+        partition_by,
+        ..
+    } = args;
+
+    // Partition values are added as columns, unless the file has columns of the same name
+    let mut partitions: Vec<(String, Option<String>)> = if *no_partition_columns {
+        vec![]
+    } else {
+        partition_values(&meta.location)
+            .into_iter()
+            .filter(|(key, _)| metadata.schema().field_with_name(key).is_err())
+            .collect()
+    };
+
+    let absolute_offset: usize = if let Some(count) = last_row_groups {
+        rows_before_last_row_groups(parquet_metadata, *count)
+    } else if offset.is_negative() {
+        parquet_metadata
+            .file_metadata()
+            .num_rows()
+            .add(offset)
+            .try_into()
+            .unwrap()
+    } else {
+        offset.abs().try_into().unwrap()
+    };
+    let row_indices = match (rows, rows_file) {
+        (Some(rows), _) => Some(parse_row_indices(rows)),
+        (_, Some(rows_file)) => Some(parse_row_indices(
+            &std::fs::read_to_string(rows_file).unwrap(),
+        )),
+        _ => None,
+    };
+
+    let join = lookups.join.as_ref();
+    let enrichment = lookups.enrichment.as_ref();
 
-            if let Some(limit) = limit {
-                async_reader_builder = async_reader_builder.with_limit(*limit)
+    let decimal_rescales: Vec<DecimalRescale> = rescale_decimal
+        .iter()
+        .map(|argument| DecimalRescale::parse(argument))
+        .collect();
+    let timestamp_splits: Vec<TimestampSplit> = split_timestamp
+        .iter()
+        .map(|argument| TimestampSplit::parse(argument))
+        .collect();
+    let derivations: Vec<Derivation> = derive
+        .iter()
+        .map(|argument| Derivation::parse(argument))
+        .collect();
+    let unpivot = unpivot.as_deref().map(Unpivot::parse);
+    let mut pivot = pivot.as_deref().map(Pivot::parse);
+
+    let predicate = Predicate::for_args(args);
+    let envelope = Envelope::for_args(args, meta.location.as_ref());
+
+    // Rows at given indices are counted from the offset, otherwise row groups before the offset
+    // are not read, nor those after the limit when rows are not filtered
+    let (mut row_groups, mut row_selection, mut skipped) = match &row_indices {
+        Some(row_indices) => {
+            let (row_groups, row_selection) =
+                row_groups_and_selection(parquet_metadata, row_indices);
+            (row_groups, Some(row_selection), absolute_offset)
+        }
+        None => {
+            let limit = limit.filter(|_| join.is_none() && predicate.is_none());
+            let (row_groups, skipped) =
+                row_groups_from_offset(parquet_metadata, absolute_offset, limit);
+            (row_groups, None, skipped)
+        }
+    };
+    if let (Some(predicate), None) = (&predicate, &row_indices) {
+        let (index_container, index_location) = match index_file {
+            Some(index_file) => store_for_output(index_file).await,
+            None => (storage_container.clone(), index_location(&meta.location)),
+        };
+        if let Some(index) =
+            SidecarIndex::load(&index_container, &index_location, &meta, parquet_metadata).await
+        {
+            let pruned = index.prune(predicate, metadata.schema());
+            // The rows to skip were in the first row group, unless it is pruned
+            if row_groups
+                .first()
+                .is_some_and(|first| !pruned.contains(first))
+            {
+                skipped = 0;
             }
+            row_groups.retain(|row_group| pruned.contains(row_group));
+        }
+    }
+    let mut envelope_rows = envelope.as_ref().map(|_| {
+        RowIndices::new(
+            parquet_metadata,
+            &row_groups,
+            row_indices.as_deref(),
+            skipped,
+        )
+    });
+
+    let mut remaining = *limit;
+    let mut stream_limit = None;
+    if join.is_none() && predicate.is_none() {
+        stream_limit = *limit;
+        remaining = None;
+    }
 
-            if let Some(columns) = columns {
-                let column_names = columns.split(',');
-
-                let schema_descr = parquet_metadata.file_metadata().schema_descr();
-                let root_schema = schema_descr.root_schema().get_fields();
-
-                let mut indices: Vec<usize> = vec![];
-                for column_name in column_names {
-                    let is_optional = column_name.starts_with('?');
-                    let found = root_schema.iter().position(|field| {
-                        field.name().eq(if is_optional {
-                            &column_name[1..]
-                        } else {
-                            column_name
-                        })
-                    });
-
-                    match found {
-                        Some(field) => indices.push(field),
-                        None => {
-                            if !is_optional {
-                                panic!("Column not found ({})", column_name)
-                            }
-                        }
+    let mut hidden: Vec<String> = vec![];
+    let mut projection_mask = ProjectionMask::all();
+    if let Some(columns) = columns {
+        let column_names = columns.split(',');
+
+        let schema_descr = parquet_metadata.file_metadata().schema_descr();
+        let root_schema = schema_descr.root_schema().get_fields();
+
+        // With a unified schema, columns missing from this file are output as nulls
+        let in_schema = |column_name: &str| {
+            schema
+                .as_ref()
+                .is_some_and(|schema| schema.field_with_name(column_name).is_ok())
+                || partitions.iter().any(|(key, _)| key == column_name)
+        };
+        let mut indices: Vec<usize> = vec![];
+        let mut selected: Vec<String> = vec![];
+        for column_name in column_names {
+            let is_optional = column_name.starts_with('?');
+            let column_name = if is_optional {
+                &column_name[1..]
+            } else {
+                column_name
+            };
+            let found = root_schema
+                .iter()
+                .position(|field| field.name().eq(column_name));
+
+            match found {
+                Some(field) => indices.push(field),
+                None => {
+                    if !is_optional && !in_schema(column_name) {
+                        panic!("Column not found ({})", column_name)
                     }
                 }
-                let projection_mask = ProjectionMask::roots(schema_descr, indices);
-                async_reader_builder = async_reader_builder.with_projection(projection_mask);
             }
+            selected.push(column_name.to_string());
+        }
 
-            let mut iter = async_reader_builder.build().unwrap();
-
-            let builder = WriterBuilder::new().with_explicit_nulls(*nulls);
-            let mut json_writer = builder.build::<_, LineDelimited>(std::io::stdout());
-
-            while let Some(rbt) = iter.next().await {
-                match rbt {
-                    Ok(batch) => {
-                        let schema = batch.schema();
-                        let json_batch = if schema.fields.iter().any(|field| {
-                            matches!(
-                                field.data_type(),
-                                DataType::Binary
-                                    | DataType::Decimal128(_, _)
-                                    | DataType::Decimal256(_, _)
-                            )
-                        }) {
-                            let mut columns: Vec<Arc<dyn Array>> = vec![];
-                            let mut builder = SchemaBuilder::new();
-                            schema
-                                .fields
-                                .iter()
-                                .for_each(|field| match field.data_type() {
-                                    DataType::Binary => {
-                                        builder.push(Field::new(
-                                            field.name(),
-                                            DataType::Utf8,
-                                            field.is_nullable(),
-                                        ));
-                                        let column = batch.column_by_name(field.name()).unwrap();
-                                        let new_column =
-                                            cast_binary_to_string::<i32>(column).unwrap();
-                                        columns.push(new_column);
-                                    }
-                                    DataType::Decimal128(_, _) | DataType::Decimal256(_, _) => {
-                                        builder.push(Field::new(
-                                            field.name(),
-                                            DataType::Utf8,
-                                            field.is_nullable(),
-                                        ));
-                                        let column = batch.column_by_name(field.name()).unwrap();
-                                        let new_column = cast_with_options(
-                                            column,
-                                            &DataType::Utf8,
-                                            &CastOptions {
-                                                safe: false,
-                                                format_options: FormatOptions::default(),
-                                            },
-                                        )
-                                        .unwrap();
-                                        columns.push(new_column);
-                                    }
-                                    _ => {
-                                        builder.push(field.clone());
-                                        columns.push(
-                                            batch.column_by_name(field.name()).unwrap().clone(),
-                                        );
-                                    }
-                                });
-                            let schema = builder.finish();
-                            RecordBatch::try_new(schema.into(), columns).unwrap()
-                        } else {
-                            batch
-                        };
-                        json_writer.write(&json_batch).unwrap();
-                    }
-                    Err(e) => println!("{}", e),
-                };
+        // Columns needed for filtering, joins and derived columns are read but not output
+        let mut needed_columns = predicate
+            .as_ref()
+            .map(|predicate| predicate.columns())
+            .unwrap_or_default();
+        needed_columns.extend(on.clone());
+        needed_columns.extend(
+            (partition_by.iter())
+                .filter(|column| {
+                    !derivations
+                        .iter()
+                        .any(|derivation| derivation.name == **column)
+                })
+                .cloned(),
+        );
+        for (index, derivation) in derivations.iter().enumerate() {
+            let derived_before = |column: &String| {
+                (derivations[..index].iter()).any(|derivation| derivation.name == *column)
+            };
+            needed_columns.extend(
+                (derivation.columns().into_iter()).filter(|column| !derived_before(column)),
+            );
+        }
+        for column_name in needed_columns {
+            if selected.contains(&column_name) {
+                continue;
+            }
+            let found = root_schema
+                .iter()
+                .position(|field| field.name().eq(&column_name));
+            match found {
+                Some(found) => indices.push(found),
+                None if in_schema(&column_name) => {}
+                None => panic!("Column not found ({})", column_name),
             }
-            json_writer.finish().unwrap();
+            selected.push(column_name.clone());
+            hidden.push(column_name);
         }
-        Commands::Schema {} => {
-            print_schema(
-                &mut std::io::stdout(),
-                parquet_metadata.file_metadata().schema(),
-            );
+        // Partition columns are left out of the rows by the sink, once it has read their values
+        hidden.retain(|column_name| !partition_by.contains(column_name));
+
+        partitions.retain(|(key, _)| selected.contains(key));
+        schema = schema.map(|schema| {
+            let indices: Vec<usize> = (0..schema.fields().len())
+                .filter(|index| selected.contains(schema.field(*index).name()))
+                .collect();
+            Arc::new(schema.project(&indices).unwrap())
+        });
+
+        projection_mask = ProjectionMask::roots(schema_descr, indices);
+    }
+
+    // Batches spanning whole output batches keep the dictionaries of a column chunk, so their
+    // encoded values are reused
+    if let Some(bytes_per_row) = estimated_bytes_per_row(parquet_metadata, &projection_mask) {
+        output.fit_batch_size(bytes_per_row);
+    }
+    let batch_size = output.batch_size();
+    // Streams are opened again after row groups that cannot be read
+    let open_stream = |row_groups: &[usize],
+                       row_selection: Option<RowSelection>,
+                       skipped: usize,
+                       limit: Option<usize>| {
+        let reader = ParquetObjectReader::new(storage_container.clone(), meta.clone());
+        let mut async_reader_builder =
+            ParquetRecordBatchStreamBuilder::new_with_metadata(reader, metadata.clone())
+                .with_row_groups(row_groups.to_vec())
+                .with_offset(skipped)
+                .with_projection(projection_mask.clone())
+                .with_batch_size(batch_size);
+        if let Some(row_selection) = row_selection {
+            async_reader_builder = async_reader_builder.with_row_selection(row_selection);
         }
-        Commands::Rowcount {} => {
-            println!("{}", parquet_metadata.file_metadata().num_rows());
+        if let Some(limit) = limit {
+            async_reader_builder = async_reader_builder.with_limit(limit);
         }
+        async_reader_builder.build().unwrap()
+    };
+    let mut iter = open_stream(&row_groups, row_selection.clone(), skipped, stream_limit);
+    let mut rows_per_row_group = rows_read_per_row_group(
+        parquet_metadata,
+        &row_groups,
+        row_selection.as_ref(),
+        skipped,
+    );
+    let mut rows_read_from_stream = 0;
+
+    if *with_annotations && !output.header_written {
+        if !matches!(format, OutputFormat::Jsonl) {
+            panic!("Annotations can only be output with the jsonl format");
+        }
+        let annotations = field_annotations(parquet_metadata.file_metadata(), metadata.schema());
+        let header = format!("{}\n", json!({ "_annotations": annotations }));
+        output.write(header.as_bytes()).await;
+        output.header_written = true;
+    }
+
+    let mut rows_output = 0;
+
+    while let Some(rbt) = iter.next().await {
+        match rbt {
+            Ok(batch) => {
+                let mut batch = batch;
+                let rows_read = batch.num_rows();
+                rows_read_from_stream += rows_read;
+                if let Some(schema) = &schema {
+                    batch = coerce_batch(&batch, schema);
+                }
+                if !partitions.is_empty() {
+                    batch = with_partition_columns(&batch, &partitions);
+                }
+                // Row indices are taken before rows are filtered, and carried to the envelope
+                if let Some(envelope_rows) = envelope_rows.as_mut() {
+                    batch = envelope_rows.add_column(&batch);
+                }
+                if let Some(predicate) = &predicate {
+                    batch = filter_record_batch(&batch, &predicate.evaluate(&batch)).unwrap();
+                }
+                if let Some(join) = &join {
+                    batch = join.filter(&batch);
+                }
+                if let Some(remaining) = remaining.as_mut() {
+                    batch = batch.slice(0, batch.num_rows().min(*remaining));
+                    *remaining -= batch.num_rows();
+                }
+                rows_output += batch.num_rows();
+                if let Some(enrichment) = &enrichment {
+                    batch = enrichment.enrich(&batch);
+                }
+                if !decimal_rescales.is_empty() {
+                    batch = rescale_decimals(&batch, &decimal_rescales, *rounding);
+                }
+                if !timestamp_splits.is_empty() {
+                    batch = split_timestamps(&batch, &timestamp_splits, *drop_split_columns);
+                }
+                if !derivations.is_empty() {
+                    batch = derive_columns(&batch, &derivations);
+                }
+                if let Some(unpivot) = &unpivot {
+                    batch = unpivot.unpivot(&batch);
+                }
+                if !hidden.is_empty() {
+                    let output_indices: Vec<usize> = batch
+                        .schema()
+                        .fields()
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, field)| !hidden.contains(field.name()))
+                        .map(|(index, _)| index)
+                        .collect();
+                    batch = batch.project(&output_indices).unwrap();
+                }
+                if anomalies::enabled(Anomaly::NaN) {
+                    let non_finite: usize = (batch.columns().iter())
+                        .map(|column| non_finite_values(column))
+                        .sum();
+                    anomalies::report(
+                        Anomaly::NaN,
+                        non_finite,
+                        format!("{} values {}", non_finite, format.non_finite_output()),
+                    );
+                }
+                if let Some(envelope) = &envelope {
+                    batch = envelope.wrap(&batch);
+                }
+                if let Some(profile) = profile {
+                    batch = apply_profile(&batch, *profile);
+                }
+                if let Some(pivot) = pivot.as_mut() {
+                    batch = pivot.pivot(batch);
+                }
+
+                output.write_batch(batch.clone()).await;
+                if let Some(progress) = output.progress.as_mut() {
+                    progress.advance(rows_read, batch.num_rows());
+                }
+
+                if remaining == Some(0) {
+                    break;
+                }
+            }
+            // The stream ends once a batch cannot be read, so the rest of its row group is
+            // skipped and reading goes on from the next one
+            Err(e) if anomalies::is_invalid_utf8(&e) => {
+                let mut rows_before = 0;
+                let Some(failed) = rows_per_row_group.iter().position(|rows| {
+                    rows_before += rows;
+                    rows_before > rows_read_from_stream
+                }) else {
+                    break;
+                };
+                let rows_skipped = rows_before - rows_read_from_stream;
+                anomalies::report(
+                    Anomaly::InvalidUtf8,
+                    rows_skipped,
+                    format!(
+                        "{} rows of row group {} skipped, {}",
+                        rows_skipped, row_groups[failed], e
+                    ),
+                );
+                if let Some(envelope_rows) = envelope_rows.as_mut() {
+                    envelope_rows.skip(rows_skipped);
+                }
+
+                let rows_through_failed: usize = (row_groups[..=failed].iter())
+                    .map(|row_group| parquet_metadata.row_group(*row_group).num_rows() as usize)
+                    .sum();
+                if let Some(row_selection) = row_selection.as_mut() {
+                    row_selection.split_off(rows_through_failed);
+                }
+                row_groups.drain(..=failed);
+                rows_per_row_group.drain(..=failed);
+                stream_limit = stream_limit.map(|limit| limit - rows_read_from_stream);
+                rows_read_from_stream = 0;
+                if row_groups.is_empty() || stream_limit == Some(0) {
+                    break;
+                }
+                iter = open_stream(&row_groups, row_selection.clone(), 0, stream_limit);
+            }
+            Err(e) => style::error(e),
+        };
+    }
+    // Rows of the last key of --pivot are kept back until the end of the file
+    if let Some(batch) = pivot.as_mut().and_then(Pivot::finish) {
+        output.write_batch(batch).await;
     }
+    rows_output
 }
 
-#[tokio::main]
-async fn main() {
-    let cli = Cli::parse();
-    let file = cli.file;
+/// Outputs the rows of several files as a single stream, with offset, limit and row indices
+/// applying across all of them.
+async fn cat_files(files: Vec<(Arc<dyn ObjectStore>, ObjectMeta)>, args: &CatArgs) {
+    anomalies::init(&args.anomalies);
+    let total_size: usize = files.iter().map(|(_, meta)| meta.size).sum();
+    let mut receiver = prefetch_footers(files, args.metadata_concurrency, args.offset != 0);
 
-    if file.as_str().starts_with("s3://") {
-        let mut s3_builder: AmazonS3Builder = AmazonS3Builder::from_env();
+    // Unifying schemas, checking their fingerprints and counting from the end need every footer
+    // before the first row, otherwise files are output while the footers of the next ones are
+    // fetched
+    let mut footers: Vec<Footer> = vec![];
+    if args.coerce_schemas || args.expect_fingerprint.is_some() || args.offset.is_negative() {
+        while let Some(footer) = receiver.recv().await {
+            footers.push(footer.unwrap());
+        }
+    }
+    let schema = if args.coerce_schemas {
+        let schemas: Vec<SchemaRef> = footers
+            .iter()
+            .map(|(_, _, metadata)| metadata.schema().clone())
+            .collect();
+        Some(widen_schemas(&schemas))
+    } else {
+        None
+    };
+    if let Some(expected) = &args.expect_fingerprint {
+        let schemas: Vec<(String, SchemaRef)> = match &schema {
+            Some(schema) => vec![("the unified schema".to_string(), schema.clone())],
+            None => footers
+                .iter()
+                .map(|(_, meta, metadata)| (meta.location.to_string(), metadata.schema().clone()))
+                .collect(),
+        };
+        for (location, schema) in schemas {
+            let fingerprint = schema_fingerprint(&schema);
+            if !fingerprint.eq_ignore_ascii_case(expected) {
+                panic!(
+                    "Schema fingerprint of {} ({}) differs from --expect-fingerprint ({})",
+                    location, fingerprint, expected
+                );
+            }
+        }
+    }
 
-        if let Ok(profile_set) = load(
-            &Fs::default(),
-            &Env::default(),
-            &EnvConfigFiles::default(),
-            None,
+    let total_rows: usize = footers
+        .iter()
+        .map(|(_, _, metadata)| metadata.metadata().file_metadata().num_rows() as usize)
+        .sum();
+    let mut offset = if args.offset.is_negative() {
+        total_rows.saturating_sub(args.offset.unsigned_abs() as usize)
+    } else {
+        args.offset as usize
+    };
+    let row_indices = match (&args.rows, &args.rows_file) {
+        (Some(rows), _) => Some(parse_row_indices(rows)),
+        (_, Some(rows_file)) => Some(parse_row_indices(
+            &std::fs::read_to_string(rows_file).unwrap(),
+        )),
+        _ => None,
+    };
+
+    let lookups = Lookups::for_args(args);
+
+    let mut output = Output::new(args).await;
+    output.progress = args
+        .progress_json
+        .as_deref()
+        .map(|target| Progress::new(target, total_size as u64));
+    let mut remaining = args.limit;
+    let mut first_row = 0;
+    let mut first_file: Option<(Path, SchemaRef)> = None;
+    let mut footers = footers.into_iter();
+    loop {
+        let (storage_container, meta, metadata) = match footers.next() {
+            Some(footer) => footer,
+            None => match receiver.recv().await {
+                Some(footer) => footer.unwrap(),
+                None => break,
+            },
+        };
+        if schema.is_none() {
+            match &first_file {
+                Some((location, first_schema))
+                    if first_schema.fields() != metadata.schema().fields() =>
+                {
+                    panic!(
+                        "Schema differs from {} ({}), use --coerce-schemas to unify",
+                        location, meta.location
+                    );
+                }
+                Some(_) => {}
+                None => first_file = Some((meta.location.clone(), metadata.schema().clone())),
+            }
+        }
+
+        let num_rows = metadata.metadata().file_metadata().num_rows() as usize;
+        if let Some(progress) = output.progress.as_mut() {
+            progress.start_file(meta.size, num_rows);
+        }
+        let file_rows = first_row..first_row + num_rows;
+        first_row += num_rows;
+        if remaining == Some(0) {
+            break;
+        }
+        if offset >= num_rows {
+            offset -= num_rows;
+            continue;
+        }
+
+        let mut file_args = args.clone();
+        file_args.offset = offset as i64;
+        file_args.limit = remaining;
+        offset = 0;
+        if let Some(row_indices) = &row_indices {
+            let rows: Vec<String> = row_indices
+                .iter()
+                .filter(|row| file_rows.contains(row))
+                .map(|row| (row - file_rows.start).to_string())
+                .collect();
+            if rows.is_empty() {
+                continue;
+            }
+            file_args.rows = Some(rows.join(","));
+            file_args.rows_file = None;
+        }
+
+        let written = cat(
+            storage_container,
+            meta,
+            metadata,
+            &file_args,
+            &lookups,
+            &mut output,
+            schema.clone(),
         )
-        .await
-        {
-            if let Some(aws_access_key_id) = profile_set.get("aws_access_key_id") {
-                s3_builder = s3_builder.with_access_key_id(aws_access_key_id);
+        .await;
+        if let Some(remaining) = remaining.as_mut() {
+            *remaining -= written;
+        }
+    }
+    output.finish().await;
+    anomalies::print_counts();
+}
+
+/// Outputs the command for one or more files, with schema and rowcount aggregated across them.
+async fn output_for_command(
+    files: Vec<(Arc<dyn ObjectStore>, ObjectMeta)>,
+    file: &str,
+    command: &Commands,
+) {
+    let files = convert_inputs(files).await;
+    if let Commands::Cat(args) = command {
+        match (&args.index_file, index_url(file)) {
+            (None, Some(index_url)) => {
+                let mut args = args.clone();
+                args.index_file = Some(index_url);
+                cat_files(files, &args).await;
             }
-            if let Some(aws_secret_access_key) = profile_set.get("aws_secret_access_key") {
-                s3_builder = s3_builder.with_secret_access_key(aws_secret_access_key);
+            _ => cat_files(files, args).await,
+        }
+        return;
+    }
+
+    let mut receiver = prefetch_footers(files, METADATA_CONCURRENCY, false);
+    let mut footers: Vec<Footer> = vec![];
+    while let Some(footer) = receiver.recv().await {
+        footers.push(footer.unwrap());
+    }
+    let metadata = &footers[0].2;
+    let parquet_metadata = metadata.metadata();
+    let differs = footers
+        .iter()
+        .any(|(_, _, other)| other.schema().fields() != metadata.schema().fields());
+    // Files with differing schemas are described by the schema they are unified to by cat
+    let (schema, parquet_schema) = if differs {
+        let schemas: Vec<SchemaRef> = footers
+            .iter()
+            .map(|(_, _, metadata)| metadata.schema().clone())
+            .collect();
+        let schema = widen_schemas(&schemas);
+        let parquet_schema = arrow_to_parquet_schema(&schema).unwrap().root_schema_ptr();
+        (schema, parquet_schema)
+    } else {
+        (
+            metadata.schema().clone(),
+            parquet_metadata
+                .file_metadata()
+                .schema_descr()
+                .root_schema_ptr(),
+        )
+    };
+
+    let mut out = ReportWriter::open(command.report_output()).await;
+    let mut report: Vec<u8> = vec![];
+    match command {
+        Commands::Schema {
+            fingerprint: true, ..
+        } => writeln!(report, "{}", schema_fingerprint(&schema)).unwrap(),
+        Commands::Schema {
+            format: SchemaFormat::EsMapping,
+            es_strings,
+            es_text,
+            ..
+        } => {
+            let options = MappingOptions {
+                strings: *es_strings,
+                text_fields: es_text
+                    .as_ref()
+                    .map(|fields| fields.split(',').map(String::from).collect())
+                    .unwrap_or_default(),
+            };
+            let mapping = es_mapping(&schema, &options);
+            writeln!(
+                report,
+                "{}",
+                serde_json::to_string_pretty(&mapping).unwrap()
+            )
+            .unwrap();
+        }
+        Commands::Schema {
+            format: SchemaFormat::ClickhouseDdl,
+            table,
+            ..
+        } => {
+            let table = table.clone().unwrap_or_else(|| {
+                let filename = file
+                    .split('?')
+                    .next()
+                    .unwrap_or_default()
+                    .trim_end_matches('/')
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or_default();
+                filename
+                    .strip_suffix(".parquet")
+                    .unwrap_or(filename)
+                    .to_string()
+            });
+            writeln!(report, "{}", create_table(&schema, &table)).unwrap();
+        }
+        Commands::Schema {
+            with_annotations, ..
+        } => {
+            let annotations = if *with_annotations {
+                field_annotations(parquet_metadata.file_metadata(), &schema)
+            } else {
+                Default::default()
+            };
+            if style::theme().style == Style::Plain {
+                print_schema(&mut report, &parquet_schema);
+                for path in annotations.keys() {
+                    let description = describe(&annotations, path).unwrap();
+                    writeln!(report, "// {}: {}", path, description).unwrap();
+                }
+            } else {
+                style::print_schema_tree(&mut report, &parquet_schema, &annotations);
             }
-            if let Some(aws_session_token) = profile_set.get("aws_session_token") {
-                s3_builder = s3_builder.with_token(aws_session_token);
+        }
+        Commands::Rowcount { .. } => {
+            let num_rows: i64 = footers
+                .iter()
+                .map(|(_, _, metadata)| metadata.metadata().file_metadata().num_rows())
+                .sum();
+            writeln!(report, "{}", num_rows).unwrap();
+        }
+        Commands::Codecs { .. } => codecs_report(&mut report, &footers),
+        Commands::Freq {
+            column,
+            min_count,
+            sample_row_groups,
+            seed,
+            ..
+        } => {
+            let sample = sample_row_groups.map(|row_groups| {
+                let seed = seed.unwrap_or_else(|| {
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap()
+                        .as_nanos() as u64
+                });
+                (row_groups, seed)
+            });
+            freq(&mut out, &footers, column, *min_count, sample).await
+        }
+        Commands::Cat(_)
+        | Commands::Extract(_)
+        | Commands::Advise { .. }
+        | Commands::CheckDuplicates { .. }
+        | Commands::CheckFk { .. }
+        | Commands::CheckGaps { .. }
+        | Commands::Completions { .. }
+        | Commands::Index { .. }
+        | Commands::Manpage { .. }
+        | Commands::SelfUpdate { .. } => unreachable!(),
+    }
+    out.write(&report).await;
+    out.finish().await;
+}
+
+/// Resolves input locations to files in the given order, expanding glob patterns as well as
+/// directories and prefixes to their files in lexicographic order.
+async fn resolve_files(locations: &[String]) -> Vec<(Arc<dyn ObjectStore>, ObjectMeta)> {
+    let mut files = vec![];
+    for location in locations {
+        let (storage_container, metas) = match split_glob(location) {
+            Some((base, pattern)) => {
+                let (storage_container, prefix) = store_for_location(&base).await;
+                let metas = glob_files(&storage_container, &prefix, &pattern).await;
+                (storage_container, metas)
             }
-            if let Some(region) = profile_set.get("region") {
-                s3_builder = s3_builder.with_region(region);
+            None => {
+                let (storage_container, path) = store_for_location(location).await;
+                let metas = list_files(&storage_container, &path).await;
+                (storage_container, metas)
             }
+        };
+        files.extend(
+            metas
+                .into_iter()
+                .map(|meta| (storage_container.clone(), meta)),
+        );
+    }
+    files
+}
+
+#[tokio::main]
+async fn main() {
+    let mut cli = Cli::parse();
+    style::init(cli.style, cli.command.report_output().is_none());
+    // Global arguments are not checked by clap for their requirements
+    if cli.s3.role_arn.is_none() && (cli.s3.external_id.is_some() || cli.s3.session_name.is_some())
+    {
+        Cli::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "the following required arguments were not provided:\n  --role-arn <ROLE_ARN>",
+            )
+            .exit()
+    }
+    if cli.s3.version_id.is_some()
+        && (cli.files.len() > 1
+            || cli.manifest.is_some()
+            || !cli.files.iter().all(|file| file.starts_with("s3://"))
+            || cli.files.iter().any(|file| split_glob(file).is_some()))
+    {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--version-id can only be used with a single S3 object",
+            )
+            .exit()
+    }
+    S3_ARGS.set(cli.s3.clone()).ok();
+    HTTP_ARGS.set(cli.http.clone()).ok();
+    temp::init(&cli.temp);
+    input_format::init(cli.input_format);
+    if let Some(manifest) = &cli.manifest {
+        let files = read_manifest(manifest).await;
+        cli.files.extend(files);
+    }
+    // Extracts are the rows of cat, written as Parquet
+    if let Commands::Extract(args) = &cli.command {
+        cli.command = Commands::Cat(Box::new(args.cat_args()));
+    }
+
+    let clock = Clock::start(cli.deterministic);
+    run(&cli).await;
+    if cli.summary {
+        print_summary(clock);
+    }
+}
+
+async fn run(cli: &Cli) {
+    match &cli.command {
+        Commands::Completions { shell, report } => {
+            let mut script = vec![];
+            generate(*shell, &mut Cli::command(), "parquet2json", &mut script);
+            let mut out = ReportWriter::open(report.output.as_deref()).await;
+            out.write(&script).await;
+            out.finish().await;
+            return;
+        }
+        Commands::Manpage { report } => {
+            let mut page = vec![];
+            Man::new(Cli::command()).render(&mut page).unwrap();
+            let mut out = ReportWriter::open(report.output.as_deref()).await;
+            out.write(&page).await;
+            out.finish().await;
+            return;
         }
+        Commands::SelfUpdate { check, force } => {
+            self_update(*check, *force).await;
+            return;
+        }
+        _ => {}
+    }
 
-        let url = Url::parse(file.as_ref()).unwrap();
+    let Some(file) = cli.files.first().map(String::as_str) else {
+        Cli::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "the following required arguments were not provided:\n  <FILE>",
+            )
+            .exit()
+    };
+    if is_flight_sql_location(file) {
+        let Commands::Cat(args) = &cli.command else {
+            panic!("Flight SQL queries are only supported by cat");
+        };
+        let mut output = Output::new(args).await;
+        flightsql::cat(file, args, &mut output).await;
+        output.finish().await;
+        return;
+    }
+    let reads_datasets = matches!(
+        cli.command,
+        Commands::Cat(_)
+            | Commands::Schema { .. }
+            | Commands::Rowcount { .. }
+            | Commands::Codecs { .. }
+            | Commands::Freq { .. }
+    );
+    if cli.files.len() > 1 || split_glob(file).is_some() {
+        if !reads_datasets {
+            panic!(
+                "Multiple files and glob patterns are only supported by cat, schema, rowcount, codecs and freq"
+            );
+        }
+        output_for_command(resolve_files(&cli.files).await, file, &cli.command).await;
+        return;
+    }
+    let (storage_container, location) = store_for_location(file).await;
 
-        let storage_container = Arc::new(
-            s3_builder
-                .with_bucket_name(decode(url.host_str().unwrap()).unwrap())
-                .build()
-                .unwrap(),
-        );
-        let location = Path::from(decode(url.path()).unwrap().as_ref());
-        let meta = storage_container.head(&location).await.unwrap();
-        let reader = ParquetObjectReader::new(storage_container, meta);
+    if let Commands::CheckDuplicates {
+        key,
+        bloom_size,
+        spill,
+        report,
+    } = &cli.command
+    {
+        let files = list_files(&storage_container, &location).await;
+        let mut out = ReportWriter::open(report.output.as_deref()).await;
+        let found =
+            check_duplicates(&mut out, storage_container, &files, key, *bloom_size, spill).await;
+        out.finish().await;
+        if found {
+            std::process::exit(1);
+        }
+        return;
+    }
 
-        output_for_command(reader, &cli.command).await;
-    } else if file.as_str().starts_with("http") {
-        let url = Url::parse(file.as_ref()).unwrap();
+    if let Commands::CheckFk {
+        column,
+        against,
+        samples,
+        spill,
+        report,
+    } = &cli.command
+    {
+        let files = list_files(&storage_container, &location).await;
+        let mut out = ReportWriter::open(report.output.as_deref()).await;
+        let found = check_fk(
+            &mut out,
+            storage_container,
+            &files,
+            column,
+            &Reference::parse(against),
+            *samples,
+            spill,
+        )
+        .await;
+        out.finish().await;
+        if found {
+            std::process::exit(1);
+        }
+        return;
+    }
 
-        let storage_container = Arc::new(HttpBuilder::new().with_url(url).build().unwrap());
-        let location = Path::from("");
-        let meta = storage_container.head(&location).await.unwrap();
-        let reader = ParquetObjectReader::new(storage_container, meta);
+    if let Commands::CheckGaps {
+        by,
+        interval,
+        group_by,
+        report,
+    } = &cli.command
+    {
+        let files = list_files(&storage_container, &location).await;
+        let mut out = ReportWriter::open(report.output.as_deref()).await;
+        let found = check_gaps(
+            &mut out,
+            storage_container,
+            &files,
+            by,
+            interval,
+            group_by.as_deref(),
+        )
+        .await;
+        out.finish().await;
+        if found {
+            std::process::exit(1);
+        }
+        return;
+    }
 
-        output_for_command(reader, &cli.command).await;
-    } else {
-        let storage_container = Arc::new(LocalFileSystem::new());
-        let str: &str = file.as_ref();
-        let file_path_buf = std::fs::canonicalize(str).unwrap();
-        let file_path = file_path_buf.to_str().unwrap();
-        let location = Path::from(file_path);
-        let meta = storage_container.head(&location).await.unwrap();
-        let reader = ParquetObjectReader::new(storage_container, meta);
-
-        output_for_command(reader, &cli.command).await;
+    let meta = match storage_container.head(&location).await {
+        Ok(meta) => meta,
+        Err(_) if reads_datasets => {
+            let files = list_files(&storage_container, &location).await;
+            let files = files
+                .into_iter()
+                .map(|meta| (storage_container.clone(), meta))
+                .collect();
+            output_for_command(files, file, &cli.command).await;
+            return;
+        }
+        Err(e) => panic!("{}", e),
     };
+
+    if let Commands::Advise { report } = &cli.command {
+        let mut advice = vec![];
+        advise(&mut advice, storage_container, meta, file).await;
+        let mut out = ReportWriter::open(report.output.as_deref()).await;
+        out.write(&advice).await;
+        out.finish().await;
+        return;
+    }
+
+    if let Commands::Index {
+        command: IndexCommands::Build { columns, output },
+    } = &cli.command
+    {
+        let column_names: Vec<&str> = columns.split(',').collect();
+        let index = SidecarIndex::build(storage_container.clone(), meta, &column_names).await;
+        let (output_container, output_location) = match output {
+            Some(output) => store_for_output(output).await,
+            None => match index_url(file) {
+                Some(index_url) => store_for_output(&index_url).await,
+                None => (storage_container, index_location(&location)),
+            },
+        };
+        output_container
+            .put(&output_location, index.to_json().to_string().into())
+            .await
+            .unwrap();
+        return;
+    }
+
+    output_for_command(vec![(storage_container, meta)], file, &cli.command).await;
 }
@@ -0,0 +1,102 @@
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+
+/// How string fields are mapped when not listed explicitly as text fields
+#[derive(ValueEnum, Clone, Copy, Default)]
+pub enum StringMapping {
+    #[default]
+    Keyword,
+    Text,
+    Multi,
+}
+
+pub struct MappingOptions {
+    pub strings: StringMapping,
+    pub text_fields: Vec<String>,
+}
+
+fn string_mapping(path: &str, options: &MappingOptions) -> Value {
+    let strings = if options.text_fields.iter().any(|field| field == path) {
+        StringMapping::Text
+    } else {
+        options.strings
+    };
+    match strings {
+        StringMapping::Keyword => json!({"type": "keyword"}),
+        StringMapping::Text => json!({"type": "text"}),
+        StringMapping::Multi => json!({
+            "type": "text",
+            "fields": {"keyword": {"type": "keyword", "ignore_above": 256}}
+        }),
+    }
+}
+
+fn field_mapping(field: &Field, path: &str, options: &MappingOptions) -> Value {
+    match field.data_type() {
+        DataType::Boolean => json!({"type": "boolean"}),
+        DataType::Int8 => json!({"type": "byte"}),
+        DataType::Int16 | DataType::UInt8 => json!({"type": "short"}),
+        DataType::Int32 | DataType::UInt16 => json!({"type": "integer"}),
+        DataType::Int64 | DataType::UInt32 => json!({"type": "long"}),
+        DataType::UInt64 => json!({"type": "unsigned_long"}),
+        DataType::Float16 => json!({"type": "half_float"}),
+        DataType::Float32 => json!({"type": "float"}),
+        DataType::Float64 => json!({"type": "double"}),
+        DataType::Decimal128(_, scale) | DataType::Decimal256(_, scale) => json!({
+            "type": "scaled_float",
+            "scaling_factor": 10f64.powi(*scale as i32)
+        }),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => string_mapping(path, options),
+        DataType::Binary | DataType::LargeBinary | DataType::FixedSizeBinary(_) => {
+            json!({"type": "binary"})
+        }
+        DataType::Date32 | DataType::Date64 => json!({"type": "date"}),
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => json!({"type": "date_nanos"}),
+        DataType::Timestamp(_, _) => json!({"type": "date"}),
+        DataType::FixedSizeList(item, dims)
+            if matches!(item.data_type(), DataType::Float32 | DataType::Float64) =>
+        {
+            json!({"type": "dense_vector", "dims": dims})
+        }
+        DataType::List(item) | DataType::LargeList(item) | DataType::FixedSizeList(item, _) => {
+            field_mapping(item, path, options)
+        }
+        DataType::Struct(fields) => json!({
+            "properties": properties(fields.iter().map(|field| field.as_ref()), path, options)
+        }),
+        DataType::Map(_, _) => json!({"type": "flattened"}),
+        DataType::Dictionary(_, value_type) => field_mapping(
+            &Field::new(field.name(), value_type.as_ref().clone(), true),
+            path,
+            options,
+        ),
+        _ => json!({"type": "keyword"}),
+    }
+}
+
+fn properties<'a>(
+    fields: impl Iterator<Item = &'a Field>,
+    parent_path: &str,
+    options: &MappingOptions,
+) -> Map<String, Value> {
+    fields
+        .map(|field| {
+            let path = if parent_path.is_empty() {
+                field.name().clone()
+            } else {
+                format!("{}.{}", parent_path, field.name())
+            };
+            (field.name().clone(), field_mapping(field, &path, options))
+        })
+        .collect()
+}
+
+/// Returns an Elasticsearch/OpenSearch index mapping matching the JSON output of the schema.
+pub fn es_mapping(schema: &Schema, options: &MappingOptions) -> Value {
+    json!({
+        "mappings": {
+            "properties": properties(schema.fields().iter().map(|field| field.as_ref()), "", options)
+        }
+    })
+}
@@ -0,0 +1,100 @@
+use std::io::Write;
+
+use arrow_array::RecordBatch;
+use arrow_schema::DataType;
+
+use crate::csv_output::Fields;
+use crate::encoder::{Encoder, EncoderKind, FormatOpts, Serialize};
+use crate::{CatArgs, OutputFormat};
+
+/// Returns a value as the text of a table cell, with pipes escaped and line breaks as <br>,
+/// truncated to a number of characters with an ellipsis.
+fn cell(value: &str, max_width: Option<usize>) -> String {
+    let value = match max_width {
+        Some(max_width) if value.chars().count() > max_width => {
+            let truncated: String = value.chars().take(max_width.saturating_sub(1)).collect();
+            format!("{}…", truncated)
+        }
+        _ => value.to_string(),
+    };
+    value
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace(['\n', '\r'], "<br>")
+}
+
+/// Writes rows as a GitHub-flavored Markdown table, after a header row of the column names of
+/// the first batch. Numbers are aligned right, values of nested types are written as JSON, and
+/// nulls as empty cells.
+#[derive(Clone)]
+pub struct MarkdownWriter {
+    pub max_width: Option<usize>,
+    pub header: bool,
+}
+
+impl MarkdownWriter {
+    fn write_row(&self, out: &mut Vec<u8>, values: impl Iterator<Item = String>) {
+        out.push(b'|');
+        for value in values {
+            write!(out, " {} |", cell(&value, self.max_width)).unwrap();
+        }
+        out.push(b'\n');
+    }
+
+    pub fn write_batch(&self, out: &mut Vec<u8>, batch: &RecordBatch) {
+        let schema = batch.schema();
+        if self.header {
+            self.write_row(
+                out,
+                schema.fields().iter().map(|field| field.name().clone()),
+            );
+            let alignments = schema.fields().iter().map(|field| match field.data_type() {
+                data_type if data_type.is_numeric() => "---:".to_string(),
+                DataType::Dictionary(_, value_type) if value_type.is_numeric() => {
+                    "---:".to_string()
+                }
+                _ => "---".to_string(),
+            });
+            self.write_row(out, alignments);
+        }
+        let fields = Fields::new(batch);
+        for row in 0..batch.num_rows() {
+            self.write_row(out, fields.row(row).into_iter());
+        }
+    }
+}
+
+/// GitHub-flavored Markdown table, taking max-width, the number of characters values are
+/// truncated to.
+pub struct MarkdownEncoderKind;
+
+impl EncoderKind for MarkdownEncoderKind {
+    fn format(&self) -> OutputFormat {
+        OutputFormat::Markdown
+    }
+
+    fn content_type(&self) -> &'static str {
+        "text/markdown"
+    }
+
+    fn open(&self, args: &CatArgs, options: &mut FormatOpts) -> Box<dyn Encoder> {
+        Box::new(MarkdownWriter {
+            max_width: options.value("max-width").or(args.max_width),
+            header: true,
+        })
+    }
+}
+
+// The header row is written before the first batch only
+impl Encoder for MarkdownWriter {
+    fn encode(&mut self, batch: RecordBatch) -> Serialize {
+        let markdown_writer = self.clone();
+        self.header = false;
+        Box::new(move || {
+            let mut lines = vec![];
+            markdown_writer.write_batch(&mut lines, &batch);
+            lines
+        })
+    }
+}
@@ -0,0 +1,175 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore, PutMultipartOpts,
+    PutOptions, PutPayload, PutResult, Result,
+};
+
+use crate::advise::format_bytes;
+use crate::clock::Clock;
+use crate::style::{accent, bold};
+
+static GET_REQUESTS: AtomicU64 = AtomicU64::new(0);
+static HEAD_REQUESTS: AtomicU64 = AtomicU64::new(0);
+static LIST_REQUESTS: AtomicU64 = AtomicU64::new(0);
+static BYTES_READ: AtomicU64 = AtomicU64::new(0);
+static ROWS_FILTERED: AtomicU64 = AtomicU64::new(0);
+static ROWS_MATCHED: AtomicU64 = AtomicU64::new(0);
+static FILTER_NANOS: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the number of bytes read so far.
+pub fn bytes_read() -> u64 {
+    BYTES_READ.load(Ordering::Relaxed)
+}
+
+/// Counts rows evaluated by filters, with the number matching and the time taken.
+pub fn count_filtered(rows: usize, matched: usize, elapsed: Duration) {
+    ROWS_FILTERED.fetch_add(rows as u64, Ordering::Relaxed);
+    ROWS_MATCHED.fetch_add(matched as u64, Ordering::Relaxed);
+    FILTER_NANOS.fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+}
+
+fn count_get(bytes: usize) {
+    GET_REQUESTS.fetch_add(1, Ordering::Relaxed);
+    BYTES_READ.fetch_add(bytes as u64, Ordering::Relaxed);
+}
+
+/// Object store counting the requests and bytes read through it, for the same numbers across
+/// local, HTTP, S3 and Azure locations.
+#[derive(Debug)]
+pub struct InstrumentedStore {
+    inner: Arc<dyn ObjectStore>,
+}
+
+impl InstrumentedStore {
+    pub fn new(inner: Arc<dyn ObjectStore>) -> Self {
+        InstrumentedStore { inner }
+    }
+}
+
+impl Display for InstrumentedStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Instrumented({})", self.inner)
+    }
+}
+
+#[async_trait::async_trait]
+impl ObjectStore for InstrumentedStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let result = self.inner.get_opts(location, options).await?;
+        count_get(result.range.len());
+        Ok(result)
+    }
+
+    // Ranges coalesced by get_ranges are requested through here, so each one is counted
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        let bytes = self.inner.get_range(location, range).await?;
+        count_get(bytes.len());
+        Ok(bytes)
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        HEAD_REQUESTS.fetch_add(1, Ordering::Relaxed);
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        LIST_REQUESTS.fetch_add(1, Ordering::Relaxed);
+        self.inner.list(prefix)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        LIST_REQUESTS.fetch_add(1, Ordering::Relaxed);
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+}
+
+/// Returns the peak resident set size of the process, where the platform reports it.
+fn peak_rss() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kilobytes: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+/// Prints request, byte, filtered row, memory and time totals of the run to stderr, leaving out
+/// peak memory and times, which vary between runs, for a fixed clock.
+pub fn print_summary(clock: Clock) {
+    let get_requests = GET_REQUESTS.load(Ordering::Relaxed);
+    let head_requests = HEAD_REQUESTS.load(Ordering::Relaxed);
+    let list_requests = LIST_REQUESTS.load(Ordering::Relaxed);
+
+    eprintln!("{}", bold("Summary"));
+    eprintln!(
+        "  {}: {} (get: {}, head: {}, list: {})",
+        accent("requests"),
+        get_requests + head_requests + list_requests,
+        get_requests,
+        head_requests,
+        list_requests
+    );
+    eprintln!("  {}: {}", accent("bytes read"), format_bytes(bytes_read()));
+    let rows_filtered = ROWS_FILTERED.load(Ordering::Relaxed);
+    if rows_filtered > 0 {
+        eprintln!(
+            "  {}: {} of {} matched",
+            accent("rows filtered"),
+            ROWS_MATCHED.load(Ordering::Relaxed),
+            rows_filtered
+        );
+    }
+    if let Some(elapsed) = clock.elapsed() {
+        // Time spent evaluating filters, leaving out reading and decoding the rows
+        let filter_seconds = FILTER_NANOS.load(Ordering::Relaxed) as f64 / 1e9;
+        if rows_filtered > 0 && filter_seconds > 0.0 {
+            eprintln!(
+                "  {}: {:.0} rows/s ({:.3} s)",
+                accent("filter throughput"),
+                rows_filtered as f64 / filter_seconds,
+                filter_seconds
+            );
+        }
+        eprintln!(
+            "  {}: {}",
+            accent("peak memory"),
+            peak_rss().map_or("unknown".to_string(), format_bytes)
+        );
+        eprintln!("  {}: {:.3} s", accent("elapsed"), elapsed.as_secs_f64());
+    }
+}
@@ -0,0 +1,94 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use futures::stream::BoxStream;
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore, PutMultipartOpts,
+    PutOptions, PutPayload, PutResult, Result,
+};
+
+/// Store reading a given version of an object in a versioned bucket, with other objects, such
+/// as sidecar indexes and outputs, left at their latest version.
+#[derive(Debug)]
+pub struct ObjectVersionStore {
+    inner: Arc<dyn ObjectStore>,
+    location: Path,
+    version: String,
+}
+
+impl ObjectVersionStore {
+    pub fn new(inner: Arc<dyn ObjectStore>, location: Path, version: String) -> Self {
+        ObjectVersionStore {
+            inner,
+            location,
+            version,
+        }
+    }
+}
+
+impl Display for ObjectVersionStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ObjectVersion({}, {})", self.inner, self.version)
+    }
+}
+
+#[async_trait::async_trait]
+impl ObjectStore for ObjectVersionStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let options = if *location == self.location {
+            GetOptions {
+                version: Some(self.version.clone()),
+                ..options
+            }
+        } else {
+            options
+        };
+        self.inner.get_opts(location, options).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        let options = GetOptions {
+            head: true,
+            ..Default::default()
+        };
+        Ok(self.get_opts(location, options).await?.meta)
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::io::{BufWriter, Stdout, Write};
+
+use arrow_array::RecordBatch;
+use arrow_select::concat::concat_batches;
+use object_store::{ObjectStore, WriteMultipart};
+use parquet::arrow::ProjectionMask;
+use parquet::file::metadata::ParquetMetaData;
+use tokio::task::JoinHandle;
+
+use crate::cast::json_compatible;
+use crate::encoder::{open_encoder, Encoder};
+use crate::profile::{escape_non_ascii, Profile};
+use crate::progress::Progress;
+use crate::sink::{open_sink, Sink};
+use crate::source::store_for_output;
+use crate::CatArgs;
+
+/// Default number of rows serialized at a time.
+pub const BATCH_SIZE: usize = 8192;
+
+/// Default size in bytes of batches in memory, to which batches of wide rows are shrunk.
+pub const TARGET_BATCH_BYTES: usize = 64 * 1024 * 1024;
+
+/// Returns the number of rows of batches within the target size in memory, for rows of the given
+/// size, up to the default batch size.
+fn adaptive_batch_size(bytes_per_row: usize, target_batch_bytes: usize) -> usize {
+    (target_batch_bytes / bytes_per_row.max(1)).clamp(1, BATCH_SIZE)
+}
+
+/// Estimates the size of rows of a file from the uncompressed size of the columns read in its
+/// first row group with rows, before reading any.
+pub fn estimated_bytes_per_row(
+    metadata: &ParquetMetaData,
+    projection: &ProjectionMask,
+) -> Option<usize> {
+    let row_group = metadata
+        .row_groups()
+        .iter()
+        .find(|row_group| row_group.num_rows() > 0)?;
+    let bytes: i64 = (0..row_group.num_columns())
+        .filter(|index| projection.leaf_included(*index))
+        .map(|index| row_group.column(index).uncompressed_size())
+        .sum();
+    Some(bytes as usize / row_group.num_rows() as usize)
+}
+
+/// Destination of cat output across one or more input files.
+pub struct Output {
+    profile: Option<Profile>,
+    sink: Box<dyn Sink>,
+    encoder: Box<dyn Encoder>,
+    pub header_written: bool,
+    pub progress: Option<Progress>,
+    batch_size: usize,
+    /// Size in memory batches are shrunk to, unless --batch-size sets their number of rows
+    target_batch_bytes: Option<usize>,
+    measured: bool,
+    pending: Vec<RecordBatch>,
+    pending_rows: usize,
+    serialize_threads: usize,
+    serializing: VecDeque<JoinHandle<Vec<u8>>>,
+}
+
+impl Output {
+    pub async fn new(args: &CatArgs) -> Self {
+        Output {
+            profile: args.profile,
+            sink: open_sink(args).await,
+            encoder: open_encoder(args),
+            header_written: false,
+            progress: None,
+            batch_size: args.batch_size.unwrap_or(BATCH_SIZE),
+            target_batch_bytes: args.batch_size.is_none().then_some(args.target_batch_bytes),
+            measured: false,
+            pending: vec![],
+            pending_rows: 0,
+            serialize_threads: args.serialize_threads.max(1),
+            serializing: VecDeque::new(),
+        }
+    }
+
+    /// Number of rows of batches read and serialized.
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Shrinks batches so that batches of rows of the given size stay within --target-batch-bytes.
+    pub fn fit_batch_size(&mut self, bytes_per_row: usize) {
+        if let Some(target_batch_bytes) = self.target_batch_bytes {
+            let batch_size = adaptive_batch_size(bytes_per_row, target_batch_bytes);
+            self.batch_size = self.batch_size.min(batch_size);
+        }
+    }
+
+    pub async fn write(&mut self, bytes: &[u8]) {
+        while !self.serializing.is_empty() {
+            self.write_serialized().await;
+        }
+        self.sink.write(bytes.to_vec()).await;
+    }
+
+    /// Writes the oldest batch being serialized, once done.
+    async fn write_serialized(&mut self) {
+        if let Some(serializing) = self.serializing.pop_front() {
+            // Raises a panic while serializing as is, rather than as a join error
+            let lines = serializing
+                .await
+                .unwrap_or_else(|error| std::panic::resume_unwind(error.into_panic()));
+            self.sink.write(lines).await;
+        }
+    }
+
+    /// Writes the batch once enough rows are buffered, so that the many tiny batches of small
+    /// row groups are serialized together.
+    pub async fn write_batch(&mut self, batch: RecordBatch) {
+        if batch.num_rows() == 0 {
+            return;
+        }
+        // The first batch read tells the size of rows better than the estimate of the footer
+        if !self.measured {
+            self.measured = true;
+            self.fit_batch_size(batch.get_array_memory_size() / batch.num_rows());
+        }
+        if self
+            .pending
+            .first()
+            .is_some_and(|pending| pending.schema() != batch.schema())
+        {
+            self.flush().await;
+        }
+        self.pending_rows += batch.num_rows();
+        self.pending.push(batch);
+        if self.pending_rows >= self.batch_size {
+            self.flush().await;
+        }
+    }
+
+    async fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let batch = if self.pending.len() == 1 {
+            self.pending.pop().unwrap()
+        } else {
+            concat_batches(&self.pending[0].schema(), &self.pending).unwrap()
+        };
+        self.pending.clear();
+        self.pending_rows = 0;
+        self.write_coalesced(batch).await;
+    }
+
+    async fn write_coalesced(&mut self, batch: RecordBatch) {
+        if self.sink.write_batch(&batch).await {
+            return;
+        }
+
+        let redshift = self.profile == Some(Profile::Redshift);
+        let batch = if self.encoder.json_types() {
+            json_compatible(batch)
+        } else {
+            batch
+        };
+        let serialize = self.encoder.encode(batch);
+        let serialize = move || {
+            let lines = serialize();
+            if redshift {
+                escape_non_ascii(&lines)
+            } else {
+                lines
+            }
+        };
+
+        if self.serialize_threads == 1 {
+            self.sink.write(serialize()).await;
+            return;
+        }
+        if self.serializing.len() >= self.serialize_threads {
+            self.write_serialized().await;
+        }
+        self.serializing
+            .push_back(tokio::task::spawn_blocking(serialize));
+    }
+
+    /// Writes any buffered records, then finishes the sink, such as with the last shard.
+    pub async fn finish(&mut self) {
+        self.flush().await;
+        let lines = self.encoder.finish();
+        self.write(&lines).await;
+        self.sink.finish().await;
+
+        if let Some(progress) = self.progress.as_mut() {
+            progress.finish();
+        }
+    }
+}
+
+/// Number of parts of the output of a command other than cat uploaded at a time.
+const REPORT_UPLOADS: usize = 4;
+
+/// Writes the output of a command other than cat as it is produced, to stdout, or to the
+/// --output location as a multipart upload completed once the report is, so that no half-written
+/// file is left behind on failure. Local files are written under a temporary name and renamed.
+pub enum ReportWriter {
+    Stdout(BufWriter<Stdout>),
+    Upload {
+        output: String,
+        upload: WriteMultipart,
+    },
+}
+
+impl ReportWriter {
+    pub async fn open(output: Option<&str>) -> Self {
+        let Some(output) = output else {
+            return ReportWriter::Stdout(BufWriter::new(std::io::stdout()));
+        };
+        let (storage_container, location) = store_for_output(output).await;
+        let upload = storage_container
+            .put_multipart(&location)
+            .await
+            .unwrap_or_else(|error| panic!("Cannot write output ({}: {})", output, error));
+        ReportWriter::Upload {
+            output: output.to_string(),
+            upload: WriteMultipart::new(upload),
+        }
+    }
+
+    /// Writes the next bytes of the report, once few enough parts are being uploaded.
+    pub async fn write(&mut self, bytes: &[u8]) {
+        match self {
+            ReportWriter::Stdout(writer) => writer.write_all(bytes).unwrap(),
+            ReportWriter::Upload { output, upload } => {
+                upload
+                    .wait_for_capacity(REPORT_UPLOADS)
+                    .await
+                    .unwrap_or_else(|error| panic!("Cannot write output ({}: {})", output, error));
+                upload.write(bytes);
+            }
+        }
+    }
+
+    /// Writes an entry of the report as a line.
+    pub async fn write_line(&mut self, line: impl Display) {
+        self.write(format!("{}\n", line).as_bytes()).await;
+    }
+
+    /// Flushes stdout, or uploads the last part and completes the upload.
+    pub async fn finish(self) {
+        match self {
+            ReportWriter::Stdout(mut writer) => writer.flush().unwrap(),
+            ReportWriter::Upload { output, upload } => {
+                upload
+                    .finish()
+                    .await
+                    .unwrap_or_else(|error| panic!("Cannot write output ({}: {})", output, error));
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use arrow_array::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+
+use crate::encoder::{Encoder, EncoderKind, FormatOpts, Serialize};
+use crate::{CatArgs, OutputFormat};
+
+/// Writes batches as a Parquet file, opened with the schema of the first batch. Row groups are
+/// encoded as they fill up rather than on serialize threads, being parts of one file, and the
+/// bytes written so far are output after each batch, with the footer last.
+struct ParquetEncoder {
+    compression: Compression,
+    writer: Option<ArrowWriter<Vec<u8>>>,
+}
+
+impl ParquetEncoder {
+    /// Takes the bytes the writer has written so far.
+    fn written(&mut self) -> Vec<u8> {
+        self.writer
+            .as_mut()
+            .map(|writer| std::mem::take(writer.inner_mut()))
+            .unwrap_or_default()
+    }
+}
+
+/// Parquet, as written by extract, taking compression (e.g. snappy, zstd(3) or uncompressed).
+pub struct ParquetEncoderKind;
+
+impl EncoderKind for ParquetEncoderKind {
+    fn format(&self) -> OutputFormat {
+        OutputFormat::Parquet
+    }
+
+    fn content_type(&self) -> &'static str {
+        "application/vnd.apache.parquet"
+    }
+
+    fn open(&self, _args: &CatArgs, options: &mut FormatOpts) -> Box<dyn Encoder> {
+        Box::new(ParquetEncoder {
+            compression: options.value("compression").unwrap_or(Compression::SNAPPY),
+            writer: None,
+        })
+    }
+}
+
+impl Encoder for ParquetEncoder {
+    fn json_types(&self) -> bool {
+        false
+    }
+
+    fn encode(&mut self, batch: RecordBatch) -> Serialize {
+        let compression = self.compression;
+        let writer = self.writer.get_or_insert_with(|| {
+            let properties = WriterProperties::builder()
+                .set_compression(compression)
+                .build();
+            ArrowWriter::try_new(vec![], batch.schema(), Some(properties)).unwrap()
+        });
+        writer.write(&batch).unwrap();
+        let bytes = self.written();
+        Box::new(move || bytes)
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        match self.writer.take() {
+            Some(writer) => writer.into_inner().unwrap(),
+            None => vec![],
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use arrow_array::{Array, ArrayRef, RecordBatch, UInt32Array};
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_select::take::take_record_batch;
+use object_store::path::Path;
+use object_store::ObjectStore;
+use urlencoding::encode;
+
+use crate::cast::json_compatible;
+use crate::encoder::{open_encoder, Encoder};
+use crate::profile::{escape_non_ascii, Profile};
+use crate::shard::{split_name_template, ShardWriter};
+use crate::sink::Sink;
+use crate::source::store_for_output;
+use crate::{CatArgs, OutputFormat};
+
+/// Directory name of null partition values, as read back by Hive and Spark.
+const DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// Writes rows as shards under a key=value directory per value of the --partition-by columns
+/// (e.g. out/country=US/part-00000.json.gz), in the layout of Hive, leaving the partition
+/// columns out of the rows as they are read back from the directories.
+pub struct PartitionSink {
+    storage_container: Arc<dyn ObjectStore>,
+    prefix: Path,
+    name_template: Option<String>,
+    columns: Vec<String>,
+    max_size: usize,
+    encoder: Box<dyn Encoder>,
+    redshift: bool,
+    shard_writers: BTreeMap<String, ShardWriter>,
+}
+
+impl PartitionSink {
+    pub async fn open(output: &str, args: &CatArgs) -> Self {
+        // Partitions are written as shards, so take the formats shards do
+        if matches!(
+            args.format,
+            OutputFormat::Csv | OutputFormat::Tsv | OutputFormat::Markdown | OutputFormat::Table
+        ) {
+            panic!("CSV, TSV, markdown and table output cannot be written as --partition-by partitions");
+        }
+        if args.pretty || matches!(args.format, OutputFormat::Yaml) {
+            panic!("Indented records cannot be written as --partition-by partitions");
+        }
+        let (output, name_template) = split_name_template(output);
+        let (storage_container, prefix) = store_for_output(output).await;
+        let max_file_size = args
+            .max_file_size
+            .or(args.profile.map(|profile| profile.max_file_size()))
+            .unwrap_or(128);
+        PartitionSink {
+            storage_container,
+            prefix,
+            name_template,
+            columns: args.partition_by.clone(),
+            max_size: max_file_size * 1024 * 1024,
+            encoder: open_encoder(args),
+            redshift: args.profile == Some(Profile::Redshift),
+            shard_writers: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the rows of a batch by the key=value directories of their partition values,
+    /// without the partition columns.
+    fn split(&self, batch: &RecordBatch) -> Vec<(String, RecordBatch)> {
+        let schema = batch.schema();
+        let options = FormatOptions::default();
+        let partitions: Vec<(&String, &ArrayRef, ArrayFormatter)> = self
+            .columns
+            .iter()
+            .map(|column| {
+                let array = batch
+                    .column_by_name(column)
+                    .unwrap_or_else(|| panic!("Column not found ({})", column));
+                let formatter = ArrayFormatter::try_new(array.as_ref(), &options).unwrap();
+                (column, array, formatter)
+            })
+            .collect();
+        let mut directories: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+        for row in 0..batch.num_rows() {
+            let directory = partitions
+                .iter()
+                .map(|(column, array, formatter)| {
+                    let value = match array.is_null(row) {
+                        true => DEFAULT_PARTITION.to_string(),
+                        false => encode(&formatter.value(row).to_string()).into_owned(),
+                    };
+                    format!("{}={}", encode(column), value)
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+            directories.entry(directory).or_default().push(row as u32);
+        }
+        let indices: Vec<usize> = (0..schema.fields().len())
+            .filter(|index| !self.columns.contains(schema.field(*index).name()))
+            .collect();
+        let batch = batch.project(&indices).unwrap();
+        directories
+            .into_iter()
+            .map(|(directory, rows)| {
+                let rows = take_record_batch(&batch, &UInt32Array::from(rows)).unwrap();
+                (directory, rows)
+            })
+            .collect()
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Sink for PartitionSink {
+    async fn write_batch(&mut self, batch: &RecordBatch) -> bool {
+        for (directory, batch) in self.split(batch) {
+            let batch = if self.encoder.json_types() {
+                json_compatible(batch)
+            } else {
+                batch
+            };
+            let mut lines = self.encoder.encode(batch)();
+            if self.redshift {
+                lines = escape_non_ascii(&lines);
+            }
+            if !self.shard_writers.contains_key(&directory) {
+                let prefix = (directory.split('/'))
+                    .fold(self.prefix.clone(), |prefix, part| prefix.child(part));
+                let shard_writer = ShardWriter::new(
+                    self.storage_container.clone(),
+                    prefix,
+                    self.name_template.clone(),
+                    self.max_size,
+                );
+                self.shard_writers.insert(directory.clone(), shard_writer);
+            }
+            let shard_writer = self.shard_writers.get_mut(&directory).unwrap();
+            shard_writer.write(&lines).await;
+        }
+        true
+    }
+
+    async fn write(&mut self, _bytes: Vec<u8>) {}
+
+    async fn finish(&mut self) {
+        for shard_writer in self.shard_writers.values_mut() {
+            shard_writer.finish().await;
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Client;
+
+use crate::encoder::encoder_kind;
+use crate::sink::{Sink, SinkKind};
+use crate::source::http_client;
+use crate::transport::send_with_retries;
+use crate::CatArgs;
+
+/// HTTP endpoint, as post:<url>, receiving the output in POST requests of whole batches of rows,
+/// such as an Elasticsearch `_bulk` endpoint for --format es-bulk.
+pub struct PostSinkKind;
+
+#[async_trait::async_trait(?Send)]
+impl SinkKind for PostSinkKind {
+    fn matches(&self, output: &str) -> bool {
+        output.starts_with("post:")
+    }
+
+    async fn open(&self, output: &str, args: &CatArgs) -> Box<dyn Sink> {
+        Box::new(PostSink {
+            client: http_client(),
+            url: output["post:".len()..].to_string(),
+            content_type: encoder_kind(args.format).content_type(),
+            max_body_size: (args.write_buffer_size * 1024).max(1),
+            body: vec![],
+        })
+    }
+}
+
+/// Gathers output up to --write-buffer-size, then posts it.
+struct PostSink {
+    client: Client,
+    url: String,
+    content_type: &'static str,
+    max_body_size: usize,
+    body: Vec<u8>,
+}
+
+impl PostSink {
+    async fn post(&mut self) {
+        if self.body.is_empty() {
+            return;
+        }
+        let body = std::mem::take(&mut self.body);
+        let request = self
+            .client
+            .post(&self.url)
+            .header(CONTENT_TYPE, self.content_type)
+            .body(body)
+            .build()
+            .unwrap();
+        let response = send_with_retries(&self.client, request)
+            .await
+            .unwrap_or_else(|error| panic!("Output request failed ({}: {})", self.url, error));
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            panic!("Output request failed ({}: {} {})", self.url, status, text);
+        }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Sink for PostSink {
+    async fn write(&mut self, bytes: Vec<u8>) {
+        self.body.extend_from_slice(&bytes);
+        if self.body.len() >= self.max_body_size {
+            self.post().await;
+        }
+    }
+
+    async fn finish(&mut self) {
+        self.post().await;
+    }
+}
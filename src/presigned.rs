@@ -0,0 +1,106 @@
+use std::fmt::{Display, Formatter};
+
+use futures::stream::BoxStream;
+use object_store::http::HttpStore;
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetRange, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    PutMultipartOpts, PutOptions, PutPayload, PutResult, Result,
+};
+use url::Url;
+
+/// Returns whether the URL is a presigned S3 URL, authorized by its query parameters.
+pub fn is_presigned_url(url: &Url) -> bool {
+    url.query_pairs().any(|(key, _)| {
+        // Signature Version 4, or 2 for older URLs
+        key.eq_ignore_ascii_case("X-Amz-Signature") || key == "Signature"
+    })
+}
+
+/// HTTP store for a presigned S3 URL, which is signed for GET requests only. Object metadata is
+/// read from a range GET of the first byte instead of a HEAD request, and every request is sent
+/// to the exact signed URL, with its query parameters.
+#[derive(Debug)]
+pub struct PresignedUrlStore {
+    inner: HttpStore,
+}
+
+impl PresignedUrlStore {
+    pub fn new(inner: HttpStore) -> Self {
+        PresignedUrlStore { inner }
+    }
+}
+
+impl Display for PresignedUrlStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Presigned({})", self.inner)
+    }
+}
+
+#[async_trait::async_trait]
+impl ObjectStore for PresignedUrlStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        // Objects next to the signed one, such as sidecar indexes, would not be signed
+        if location.as_ref() != "" {
+            return Err(object_store::Error::NotFound {
+                path: location.to_string(),
+                source: "Only the object of a presigned URL can be read".into(),
+            });
+        }
+        let options = if options.head {
+            GetOptions {
+                head: false,
+                range: Some(GetRange::Bounded(0..1)),
+                ..options
+            }
+        } else {
+            options
+        };
+        self.inner.get_opts(location, options).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        let options = GetOptions {
+            head: true,
+            ..Default::default()
+        };
+        Ok(self.get_opts(location, options).await?.meta)
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+}
@@ -0,0 +1,288 @@
+use std::sync::Arc;
+
+use arrow_array::cast::AsArray;
+use arrow_array::{
+    Array, ArrayRef, BooleanArray, GenericBinaryArray, ListArray, MapArray, OffsetSizeTrait,
+    RecordBatch, StringArray, StructArray,
+};
+use arrow_buffer::{NullBuffer, OffsetBuffer};
+use arrow_cast::base64::{b64_encode, BASE64_STANDARD};
+use arrow_cast::display::FormatOptions;
+use arrow_cast::{cast, cast_with_options, CastOptions};
+use arrow_schema::{DataType, Field, Fields, SchemaBuilder, TimeUnit};
+use arrow_select::filter::filter;
+use clap::ValueEnum;
+
+/// Output presets adapting values to what specific loaders accept
+#[derive(ValueEnum, Clone, Copy, PartialEq)]
+pub enum Profile {
+    Bigquery,
+    Clickhouse,
+    Snowflake,
+    Redshift,
+}
+
+impl Profile {
+    /// Compressed size at which output files are split, within the range recommended by the loader.
+    pub fn max_file_size(&self) -> usize {
+        match self {
+            Profile::Snowflake => 150,
+            Profile::Redshift => 256,
+            Profile::Bigquery | Profile::Clickhouse => 128,
+        }
+    }
+}
+
+/// Escapes non-ASCII characters as `\uXXXX` sequences, which only occur within JSON strings.
+pub fn escape_non_ascii(json: &[u8]) -> Vec<u8> {
+    if json.is_ascii() {
+        return json.to_vec();
+    }
+    let mut escaped = Vec::with_capacity(json.len());
+    for character in String::from_utf8_lossy(json).chars() {
+        if character.is_ascii() {
+            escaped.push(character as u8);
+        } else {
+            for unit in character.encode_utf16(&mut [0; 2]) {
+                escaped.extend(format!("\\u{:04x}", unit).bytes());
+            }
+        }
+    }
+    escaped
+}
+
+fn to_hex<O: OffsetSizeTrait>(array: &GenericBinaryArray<O>) -> ArrayRef {
+    Arc::new(StringArray::from_iter(array.iter().map(|value| {
+        value.map(|bytes| {
+            bytes
+                .iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect::<String>()
+        })
+    })))
+}
+
+fn to_string_with(array: &ArrayRef, format_options: FormatOptions) -> ArrayRef {
+    cast_with_options(
+        array,
+        &DataType::Utf8,
+        &CastOptions {
+            safe: false,
+            format_options,
+        },
+    )
+    .unwrap()
+}
+
+/// Replaces null lists with empty ones, optionally dropping null elements too (as REPEATED fields require).
+fn compact_offsets(
+    offsets: &OffsetBuffer<i32>,
+    nulls: Option<&NullBuffer>,
+    values: &ArrayRef,
+    drop_null_items: bool,
+) -> (OffsetBuffer<i32>, ArrayRef) {
+    let mut mask = vec![false; values.len()];
+    let mut compacted: Vec<i32> = vec![0];
+    for index in 0..offsets.len() - 1 {
+        let (start, end) = (offsets[index] as usize, offsets[index + 1] as usize);
+        let mut length = 0;
+        if nulls.map_or(true, |nulls| nulls.is_valid(index)) {
+            for (position, selected) in mask.iter_mut().enumerate().take(end).skip(start) {
+                *selected = !drop_null_items || values.is_valid(position);
+                length += *selected as i32;
+            }
+        }
+        compacted.push(compacted[index] + length);
+    }
+
+    let values = filter(values, &BooleanArray::from(mask)).unwrap();
+    (OffsetBuffer::new(compacted.into()), values)
+}
+
+fn compact_list(list: &ListArray, values: ArrayRef, drop_null_items: bool) -> ListArray {
+    let (offsets, values) = compact_offsets(list.offsets(), list.nulls(), &values, drop_null_items);
+    let field = Arc::new(Field::new(
+        "item",
+        values.data_type().clone(),
+        !drop_null_items,
+    ));
+    ListArray::new(field, offsets, values, None)
+}
+
+fn as_list(array: &ArrayRef) -> ArrayRef {
+    let item = Field::new("item", DataType::Null, true);
+    let list_type = match array.data_type() {
+        DataType::LargeList(field) | DataType::FixedSizeList(field, _) => {
+            DataType::List(Arc::new(item.with_data_type(field.data_type().clone())))
+        }
+        _ => unreachable!(),
+    };
+    cast(array, &list_type).unwrap()
+}
+
+fn transform_struct(
+    fields: &Fields,
+    columns: &[ArrayRef],
+    profile: Profile,
+) -> (Fields, Vec<ArrayRef>) {
+    let columns: Vec<ArrayRef> = columns
+        .iter()
+        .map(|column| transform_array(column, profile))
+        .collect();
+    let fields: Fields = fields
+        .iter()
+        .zip(&columns)
+        .map(|(field, column)| {
+            Field::new(
+                field.name(),
+                column.data_type().clone(),
+                field.is_nullable(),
+            )
+        })
+        .collect();
+    (fields, columns)
+}
+
+/// Converts an array to the representation expected by the profile, recursing into nested types.
+pub fn transform_array(array: &ArrayRef, profile: Profile) -> ArrayRef {
+    match (profile, array.data_type()) {
+        (Profile::Bigquery, DataType::Timestamp(_, Some(_))) => to_string_with(
+            array,
+            FormatOptions::default().with_timestamp_tz_format(Some("%Y-%m-%dT%H:%M:%S%.6f%:z")),
+        ),
+        (Profile::Bigquery, DataType::Timestamp(_, None)) => to_string_with(
+            array,
+            FormatOptions::default().with_timestamp_format(Some("%Y-%m-%dT%H:%M:%S%.6f")),
+        ),
+        (Profile::Bigquery, DataType::Decimal128(_, _) | DataType::Decimal256(_, _)) => {
+            to_string_with(array, FormatOptions::default())
+        }
+        (Profile::Bigquery, DataType::Binary) => {
+            Arc::new(b64_encode(&BASE64_STANDARD, array.as_binary::<i32>()))
+        }
+        (Profile::Bigquery, DataType::LargeBinary) => {
+            Arc::new(b64_encode(&BASE64_STANDARD, array.as_binary::<i64>()))
+        }
+        (Profile::Bigquery, DataType::FixedSizeBinary(_)) => {
+            let binary = cast(array, &DataType::Binary).unwrap();
+            Arc::new(b64_encode(&BASE64_STANDARD, binary.as_binary::<i32>()))
+        }
+        (_, DataType::LargeList(_) | DataType::FixedSizeList(_, _)) => {
+            transform_array(&as_list(array), profile)
+        }
+        (Profile::Bigquery, DataType::List(_)) => {
+            let list = array.as_list::<i32>();
+            let values = transform_array(list.values(), profile);
+            Arc::new(compact_list(list, values, true))
+        }
+        (Profile::Bigquery, DataType::Map(_, _)) => {
+            // Maps are loaded as repeated key/value records
+            let map = array.as_map();
+            let entries: ArrayRef = Arc::new(map.entries().clone());
+            let field = Arc::new(Field::new("item", entries.data_type().clone(), true));
+            let list = ListArray::new(field, map.offsets().clone(), entries, map.nulls().cloned());
+            transform_array(&(Arc::new(list) as ArrayRef), profile)
+        }
+        (Profile::Clickhouse, DataType::Timestamp(unit, Some(_))) => {
+            // DateTime64 columns parse wall-clock values, so zoned timestamps are shifted to UTC
+            let utc = cast(array, &DataType::Timestamp(*unit, Some("+00:00".into()))).unwrap();
+            to_string_with(
+                &utc,
+                FormatOptions::default().with_timestamp_tz_format(Some("%Y-%m-%d %H:%M:%S%.6f")),
+            )
+        }
+        (Profile::Clickhouse, DataType::Timestamp(_, None)) => to_string_with(
+            array,
+            FormatOptions::default().with_timestamp_format(Some("%Y-%m-%d %H:%M:%S%.6f")),
+        ),
+        (Profile::Clickhouse, DataType::Date64) => transform_array(
+            &cast(array, &DataType::Timestamp(TimeUnit::Millisecond, None)).unwrap(),
+            profile,
+        ),
+        (Profile::Clickhouse, DataType::Decimal128(_, _) | DataType::Decimal256(_, _)) => {
+            to_string_with(array, FormatOptions::default())
+        }
+        (Profile::Clickhouse, DataType::Dictionary(_, value_type)) => {
+            transform_array(&cast(array, value_type).unwrap(), profile)
+        }
+        (Profile::Clickhouse, DataType::List(_)) => {
+            // Array columns cannot be Nullable, so null lists become empty ones
+            let list = array.as_list::<i32>();
+            let values = transform_array(list.values(), profile);
+            Arc::new(compact_list(list, values, false))
+        }
+        (Profile::Clickhouse, DataType::Map(field, ordered)) => {
+            let map = array.as_map();
+            let entries: ArrayRef = Arc::new(map.entries().clone());
+            let (offsets, entries) = compact_offsets(map.offsets(), map.nulls(), &entries, false);
+            let entries = transform_array(&entries, profile);
+            let field = Arc::new(Field::new(field.name(), entries.data_type().clone(), false));
+            Arc::new(MapArray::new(
+                field,
+                offsets,
+                entries.as_struct().clone(),
+                None,
+                *ordered,
+            ))
+        }
+        (Profile::Snowflake, DataType::Timestamp(_, Some(_))) => to_string_with(
+            array,
+            FormatOptions::default().with_timestamp_tz_format(Some("%Y-%m-%dT%H:%M:%S%.9f%:z")),
+        ),
+        (Profile::Snowflake, DataType::Timestamp(_, None)) => to_string_with(
+            array,
+            FormatOptions::default().with_timestamp_format(Some("%Y-%m-%dT%H:%M:%S%.9f")),
+        ),
+        (Profile::Redshift, DataType::Timestamp(_, Some(_))) => to_string_with(
+            array,
+            FormatOptions::default().with_timestamp_tz_format(Some("%Y-%m-%d %H:%M:%S%.6f%:z")),
+        ),
+        (Profile::Redshift, DataType::Timestamp(_, None)) => to_string_with(
+            array,
+            FormatOptions::default().with_timestamp_format(Some("%Y-%m-%d %H:%M:%S%.6f")),
+        ),
+        (
+            Profile::Snowflake | Profile::Redshift,
+            DataType::Decimal128(_, _) | DataType::Decimal256(_, _),
+        ) => to_string_with(array, FormatOptions::default()),
+        // BINARY and VARBYTE columns load hexadecimal strings by default
+        (Profile::Snowflake | Profile::Redshift, DataType::Binary) => {
+            to_hex(array.as_binary::<i32>())
+        }
+        (Profile::Snowflake | Profile::Redshift, DataType::LargeBinary) => {
+            to_hex(array.as_binary::<i64>())
+        }
+        (Profile::Snowflake | Profile::Redshift, DataType::FixedSizeBinary(_)) => {
+            to_hex(cast(array, &DataType::Binary).unwrap().as_binary::<i32>())
+        }
+        (Profile::Snowflake | Profile::Redshift, DataType::List(_)) => {
+            let list = array.as_list::<i32>();
+            let values = transform_array(list.values(), profile);
+            let field = Arc::new(Field::new("item", values.data_type().clone(), true));
+            Arc::new(ListArray::new(
+                field,
+                list.offsets().clone(),
+                values,
+                list.nulls().cloned(),
+            ))
+        }
+        (_, DataType::Struct(fields)) => {
+            let struct_array = array.as_struct();
+            let (fields, columns) = transform_struct(fields, struct_array.columns(), profile);
+            Arc::new(StructArray::new(
+                fields,
+                columns,
+                struct_array.nulls().cloned(),
+            ))
+        }
+        _ => array.clone(),
+    }
+}
+
+pub fn apply_profile(batch: &RecordBatch, profile: Profile) -> RecordBatch {
+    let schema = batch.schema();
+    let (fields, columns) = transform_struct(schema.fields(), batch.columns(), profile);
+    let mut builder = SchemaBuilder::from(fields);
+    *builder.metadata_mut() = schema.metadata().clone();
+    RecordBatch::try_new(Arc::new(builder.finish()), columns).unwrap()
+}
@@ -0,0 +1,106 @@
+use std::fs::File;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use crate::metrics::bytes_read;
+
+/// Minimum time between progress events.
+const INTERVAL: Duration = Duration::from_secs(1);
+
+/// Opens the target of --progress-json, a file descriptor as fd:<N> or a file path.
+fn open_target(target: &str) -> File {
+    match target.strip_prefix("fd:") {
+        #[cfg(unix)]
+        Some(fd) => {
+            use std::os::fd::FromRawFd;
+            let fd: i32 = fd
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid progress file descriptor ({})", fd));
+            // The descriptor is opened for parquet2json by the calling process
+            unsafe { File::from_raw_fd(fd) }
+        }
+        #[cfg(not(unix))]
+        Some(_) => panic!("Progress file descriptors are only supported on Unix"),
+        None => File::create(target)
+            .unwrap_or_else(|error| panic!("Cannot create progress file ({})", error)),
+    }
+}
+
+/// Progress of cat through its input files, written as JSON lines at most once a second, with a
+/// last event when done. Percent and ETA go by the size of the files read, with the part of the
+/// current file estimated from the rows read of it.
+pub struct Progress {
+    writer: File,
+    started: Instant,
+    emitted: Instant,
+    total_size: u64,
+    files_size: u64,
+    file_size: u64,
+    file_rows: usize,
+    file_rows_read: usize,
+    rows: usize,
+}
+
+impl Progress {
+    pub fn new(target: &str, total_size: u64) -> Self {
+        let started = Instant::now();
+        Progress {
+            writer: open_target(target),
+            started,
+            emitted: started,
+            total_size,
+            files_size: 0,
+            file_size: 0,
+            file_rows: 0,
+            file_rows_read: 0,
+            rows: 0,
+        }
+    }
+
+    /// Moves on to the next file, with the previous one counted as read.
+    pub fn start_file(&mut self, size: usize, num_rows: usize) {
+        self.files_size += self.file_size;
+        self.file_size = size as u64;
+        self.file_rows = num_rows;
+        self.file_rows_read = 0;
+    }
+
+    /// Adds the rows read from the current file and the rows output of them.
+    pub fn advance(&mut self, rows_read: usize, rows_output: usize) {
+        self.file_rows_read += rows_read;
+        self.rows += rows_output;
+        if self.emitted.elapsed() >= INTERVAL {
+            self.emit(false);
+        }
+    }
+
+    pub fn finish(&mut self) {
+        self.emit(true);
+    }
+
+    fn emit(&mut self, done: bool) {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let percent = if done || self.total_size == 0 {
+            100.0
+        } else {
+            let file_part = self.file_rows_read.min(self.file_rows) as f64
+                / self.file_rows.max(1) as f64
+                * self.file_size as f64;
+            (self.files_size as f64 + file_part) / self.total_size as f64 * 100.0
+        };
+        let eta = (percent > 0.0).then(|| elapsed * (100.0 - percent) / percent);
+        let event = json!({
+            "rows": self.rows,
+            "bytes": bytes_read(),
+            "percent": (percent * 10.0).round() / 10.0,
+            "elapsed_seconds": (elapsed * 1000.0).round() / 1000.0,
+            "eta_seconds": eta.map(|eta| (eta * 10.0).round() / 10.0),
+            "done": done,
+        });
+        writeln!(self.writer, "{}", event)
+            .unwrap_or_else(|error| panic!("Cannot write progress ({})", error));
+        self.emitted = Instant::now();
+    }
+}
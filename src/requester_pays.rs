@@ -0,0 +1,196 @@
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use object_store::aws::{AmazonS3, AwsAuthorizer};
+use object_store::path::Path;
+use object_store::{
+    Attributes, GetOptions, GetResult, GetResultPayload, ListResult, MultipartUpload, ObjectMeta,
+    ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult, Result,
+};
+use reqwest::header::{
+    CONTENT_LENGTH, CONTENT_RANGE, ETAG, IF_MATCH, IF_NONE_MATCH, LAST_MODIFIED, RANGE,
+};
+use reqwest::{Client, Method, Response, StatusCode};
+use urlencoding::encode;
+
+use crate::transport::{parse_content_range, send_with_retries};
+
+fn error(source: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> object_store::Error {
+    object_store::Error::Generic {
+        store: "S3",
+        source: source.into(),
+    }
+}
+
+fn object_meta(location: &Path, response: &Response) -> Result<(ObjectMeta, Range<usize>)> {
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+    // Partial responses are described by Content-Range: bytes <start>-<end>/<size>
+    let (range, size) = match header(CONTENT_RANGE) {
+        Some(content_range) => parse_content_range(content_range),
+        None => header(CONTENT_LENGTH)
+            .and_then(|length| length.parse().ok())
+            .map(|size| (0..size, size)),
+    }
+    .ok_or_else(|| error("Missing object size in response"))?;
+    let last_modified = header(LAST_MODIFIED)
+        .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+        .map(|value| value.with_timezone(&Utc))
+        .unwrap_or_default();
+
+    let meta = ObjectMeta {
+        location: location.clone(),
+        last_modified,
+        size,
+        e_tag: header(ETAG).map(str::to_string),
+        version: None,
+    };
+    Ok((meta, range))
+}
+
+/// S3 store for requester-pays buckets, sending object reads and metadata requests with the
+/// `x-amz-request-payer` header, which has to be signed and so cannot be a default client
+/// header. Listing is left to the object_store client, so only single files can be read.
+#[derive(Debug)]
+pub struct RequesterPaysStore {
+    inner: AmazonS3,
+    client: Client,
+    bucket_endpoint: String,
+    region: String,
+}
+
+impl RequesterPaysStore {
+    pub fn new(inner: AmazonS3, client: Client, bucket_endpoint: String, region: String) -> Self {
+        RequesterPaysStore {
+            inner,
+            client,
+            bucket_endpoint,
+            region,
+        }
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        location: &Path,
+        options: &GetOptions,
+    ) -> Result<Response> {
+        let path: Vec<String> = location
+            .parts()
+            .map(|part| encode(part.as_ref()).into_owned())
+            .collect();
+        let mut url = format!("{}/{}", self.bucket_endpoint, path.join("/"));
+        if let Some(version) = &options.version {
+            url = format!("{}?versionId={}", url, encode(version));
+        }
+
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("x-amz-request-payer", "requester");
+        if let Some(range) = &options.range {
+            request = request.header(RANGE, range.to_string());
+        }
+        if let Some(if_match) = &options.if_match {
+            request = request.header(IF_MATCH, if_match);
+        }
+        if let Some(if_none_match) = &options.if_none_match {
+            request = request.header(IF_NONE_MATCH, if_none_match);
+        }
+        let mut request = request.build().map_err(error)?;
+        let credential = self.inner.credentials().get_credential().await?;
+        AwsAuthorizer::new(&credential, "s3", &self.region).authorize(&mut request, None);
+
+        let response = send_with_retries(&self.client, request)
+            .await
+            .map_err(error)?;
+        match response.status() {
+            status if status.is_success() => Ok(response),
+            StatusCode::NOT_FOUND => Err(object_store::Error::NotFound {
+                path: location.to_string(),
+                source: "Object not found".into(),
+            }),
+            status => {
+                let body = response.text().await.unwrap_or_default();
+                Err(error(format!("{} ({})", status, body.trim())))
+            }
+        }
+    }
+}
+
+impl Display for RequesterPaysStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RequesterPays({})", self.inner)
+    }
+}
+
+#[async_trait::async_trait]
+impl ObjectStore for RequesterPaysStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let method = if options.head {
+            Method::HEAD
+        } else {
+            Method::GET
+        };
+        let response = self.send(method, location, &options).await?;
+        let (meta, range) = object_meta(location, &response)?;
+        let stream = response.bytes_stream().map_err(error).boxed();
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(stream),
+            meta,
+            range,
+            attributes: Attributes::default(),
+        })
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        let response = self
+            .send(Method::HEAD, location, &GetOptions::default())
+            .await?;
+        Ok(object_meta(location, &response)?.0)
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+}
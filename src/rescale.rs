@@ -0,0 +1,165 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Decimal128Type, Decimal256Type, DecimalType};
+use arrow_array::{Array, ArrayRef, ArrowNativeTypeOp, PrimitiveArray, RecordBatch};
+use arrow_buffer::ArrowNativeType;
+use arrow_schema::{DataType, Field, FieldRef, Schema};
+use clap::ValueEnum;
+
+/// Rounding of decimal values to fewer decimal places.
+#[derive(ValueEnum, Clone, Copy, Default)]
+pub enum Rounding {
+    /// Ties away from zero
+    #[default]
+    HalfUp,
+    /// Ties to the even neighbor (banker's rounding)
+    HalfEven,
+    /// Towards zero (truncation)
+    Down,
+    /// Away from zero
+    Up,
+    /// Towards negative infinity
+    Floor,
+    /// Towards positive infinity
+    Ceiling,
+}
+
+/// Divides a value by a power of ten, rounding the quotient.
+fn round<N: ArrowNativeTypeOp>(value: N, divisor: N, rounding: Rounding) -> N {
+    let quotient = value.div_wrapping(divisor);
+    let remainder = value.mod_wrapping(divisor);
+    if remainder.is_zero() {
+        return quotient;
+    }
+    let negative = value.is_lt(N::ZERO);
+    let (away, remainder) = if negative {
+        (quotient.sub_wrapping(N::ONE), remainder.neg_wrapping())
+    } else {
+        (quotient.add_wrapping(N::ONE), remainder)
+    };
+    // Compared with its complement rather than doubled, which could overflow
+    let half = remainder.compare(divisor.sub_wrapping(remainder));
+    match (rounding, half) {
+        (Rounding::Down, _) => quotient,
+        (Rounding::Up, _) => away,
+        (Rounding::Floor, _) if negative => away,
+        (Rounding::Floor, _) => quotient,
+        (Rounding::Ceiling, _) if negative => quotient,
+        (Rounding::Ceiling, _) => away,
+        (Rounding::HalfUp | Rounding::HalfEven, Ordering::Less) => quotient,
+        (Rounding::HalfUp | Rounding::HalfEven, Ordering::Greater) => away,
+        (Rounding::HalfUp, Ordering::Equal) => away,
+        (Rounding::HalfEven, Ordering::Equal) => {
+            if quotient.mod_wrapping(N::usize_as(2)).is_zero() {
+                quotient
+            } else {
+                away
+            }
+        }
+    }
+}
+
+fn rescale_array<T: DecimalType>(
+    column_name: &str,
+    array: &ArrayRef,
+    precision: u8,
+    scale: i8,
+    new_scale: i8,
+    rounding: Rounding,
+) -> ArrayRef {
+    let array = array.as_primitive::<T>();
+    // One more digit for values rounded up to the next power of ten
+    let new_precision =
+        (precision as i16 - scale as i16 + new_scale as i16 + 1).clamp(1, T::MAX_PRECISION as i16);
+    let factor = T::Native::usize_as(10).pow_wrapping(scale.abs_diff(new_scale) as u32);
+
+    let rescaled: PrimitiveArray<T> = if new_scale < scale {
+        array.unary(|value| round(value, factor, rounding))
+    } else {
+        array
+            .try_unary(|value| value.mul_checked(factor))
+            .unwrap_or_else(|_| panic!("Decimal value out of range ({})", column_name))
+    };
+    let rescaled = rescaled
+        .with_precision_and_scale(new_precision as u8, new_scale)
+        .unwrap_or_else(|error| panic!("Invalid decimal scale ({}: {})", column_name, error));
+    rescaled
+        .validate_decimal_precision(new_precision as u8)
+        .unwrap_or_else(|_| panic!("Decimal value out of range ({})", column_name));
+    Arc::new(rescaled)
+}
+
+/// Number of decimal places output for a decimal column, from a `column=scale` argument.
+pub struct DecimalRescale {
+    column: String,
+    scale: i8,
+}
+
+impl DecimalRescale {
+    pub fn parse(argument: &str) -> Self {
+        let (column, scale) = argument
+            .split_once('=')
+            .and_then(|(column, scale)| Some((column.trim(), scale.trim().parse().ok()?)))
+            .unwrap_or_else(|| {
+                panic!(
+                    "Invalid decimal rescale, expected column=scale ({})",
+                    argument
+                )
+            });
+        DecimalRescale {
+            column: column.to_string(),
+            scale,
+        }
+    }
+}
+
+/// Rounds or pads decimal columns to the given number of decimal places.
+pub fn rescale_decimals(
+    batch: &RecordBatch,
+    rescales: &[DecimalRescale],
+    rounding: Rounding,
+) -> RecordBatch {
+    let schema = batch.schema();
+    let mut fields: Vec<FieldRef> = schema.fields().iter().cloned().collect();
+    let mut columns = batch.columns().to_vec();
+    for rescale in rescales {
+        let (index, field) = schema
+            .column_with_name(&rescale.column)
+            .unwrap_or_else(|| panic!("Column not found ({})", rescale.column));
+        let column = &columns[index];
+        let new_scale = rescale.scale;
+        let new_column = match field.data_type() {
+            DataType::Decimal128(_, scale) | DataType::Decimal256(_, scale)
+                if *scale == new_scale =>
+            {
+                continue
+            }
+            DataType::Decimal128(precision, scale) => rescale_array::<Decimal128Type>(
+                field.name(),
+                column,
+                *precision,
+                *scale,
+                new_scale,
+                rounding,
+            ),
+            DataType::Decimal256(precision, scale) => rescale_array::<Decimal256Type>(
+                field.name(),
+                column,
+                *precision,
+                *scale,
+                new_scale,
+                rounding,
+            ),
+            _ => panic!("Column is not a decimal ({})", rescale.column),
+        };
+        fields[index] = Arc::new(Field::new(
+            field.name(),
+            new_column.data_type().clone(),
+            field.is_nullable(),
+        ));
+        columns[index] = new_column;
+    }
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap()
+}
@@ -0,0 +1,227 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use arrow_array::cast::AsArray;
+use arrow_array::{
+    new_null_array, Array, ArrayRef, RecordBatch, RecordBatchOptions, StringArray, UInt32Array,
+};
+use arrow_cast::cast;
+use arrow_ord::partition::partition;
+use arrow_schema::{DataType, Field, FieldRef, Schema};
+use arrow_select::concat::concat_batches;
+use arrow_select::interleave::interleave;
+use arrow_select::take::take;
+use glob::Pattern;
+
+use crate::coerce::widen_type;
+
+/// Most distinct names --pivot outputs as columns.
+const MAX_PIVOT_COLUMNS: usize = 1000;
+
+/// Parses the two column names of a `name,value` argument.
+fn name_and_value(argument: &str, names: &str) -> (String, String) {
+    match names.split_once(',') {
+        Some((name, value)) if !name.trim().is_empty() && !value.trim().is_empty() => {
+            (name.trim().to_string(), value.trim().to_string())
+        }
+        _ => panic!(
+            "Invalid reshape, expected name and value columns as name,value ({})",
+            argument
+        ),
+    }
+}
+
+/// Columns melted into rows of a name and a value column, from a `pattern:name,value`
+/// argument.
+pub struct Unpivot {
+    pattern: Pattern,
+    name: String,
+    value: String,
+}
+
+impl Unpivot {
+    pub fn parse(argument: &str) -> Self {
+        let (pattern, names) = argument.split_once(':').unwrap_or_else(|| {
+            panic!(
+                "Invalid unpivot, expected pattern:name,value (e.g. metric_*:name,value) ({})",
+                argument
+            )
+        });
+        let (name, value) = name_and_value(argument, names);
+        Unpivot {
+            pattern: Pattern::new(pattern.trim())
+                .unwrap_or_else(|error| panic!("Invalid unpivot pattern ({}: {})", pattern, error)),
+            name,
+            value,
+        }
+    }
+
+    /// Returns a row per row and column matching the pattern, with the other columns, the name of
+    /// the column and its value. Values of differing types are widened to a common type, or cast
+    /// to strings if there is none.
+    pub fn unpivot(&self, batch: &RecordBatch) -> RecordBatch {
+        let schema = batch.schema();
+        let (melted, kept): (Vec<usize>, Vec<usize>) = (0..schema.fields().len())
+            .partition(|index| self.pattern.matches(schema.field(*index).name()));
+        if melted.is_empty() {
+            return batch.clone();
+        }
+        let value_type = (melted.iter())
+            .map(|index| Some(schema.field(*index).data_type().clone()))
+            .reduce(|a, b| widen_type(&a?, &b?))
+            .flatten()
+            .unwrap_or(DataType::Utf8);
+        let values: Vec<ArrayRef> = (melted.iter())
+            .map(|index| cast(batch.column(*index), &value_type).unwrap())
+            .collect();
+
+        let rows = batch.num_rows();
+        let melted_count = melted.len() as u32;
+        let repeated: UInt32Array = (0..rows as u32 * melted_count)
+            .map(|index| index / melted_count)
+            .collect();
+        let mut fields: Vec<FieldRef> = vec![];
+        let mut columns: Vec<ArrayRef> = vec![];
+        for index in kept {
+            fields.push(schema.fields()[index].clone());
+            columns.push(take(batch.column(index), &repeated, None).unwrap());
+        }
+        fields.push(Arc::new(Field::new(&self.name, DataType::Utf8, false)));
+        let names: Vec<&str> = (0..rows)
+            .flat_map(|_| {
+                melted
+                    .iter()
+                    .map(|index| schema.field(*index).name().as_str())
+            })
+            .collect();
+        columns.push(Arc::new(StringArray::from(names)));
+        let indices: Vec<(usize, usize)> = (0..rows)
+            .flat_map(|row| (0..melted.len()).map(move |column| (column, row)))
+            .collect();
+        let values: Vec<&dyn Array> = values.iter().map(|values| values.as_ref()).collect();
+        fields.push(Arc::new(Field::new(&self.value, value_type, true)));
+        columns.push(interleave(&values, &indices).unwrap());
+        RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap()
+    }
+}
+
+/// Rows of a name and a value column gathered into a row with a column per name, from a
+/// `name,value` argument, as the inverse of --unpivot. Adjacent rows with the same values of the
+/// other columns are gathered, so rows are expected in order, as --unpivot outputs them.
+pub struct Pivot {
+    name: String,
+    value: String,
+    /// Rows of the last key of the previous batch, which may continue in the next one
+    pending: Option<RecordBatch>,
+}
+
+impl Pivot {
+    pub fn parse(argument: &str) -> Self {
+        let (name, value) = name_and_value(argument, argument);
+        Pivot {
+            name,
+            value,
+            pending: None,
+        }
+    }
+
+    /// Returns the rows of a batch gathered by key, keeping back those of the last key.
+    pub fn pivot(&mut self, batch: RecordBatch) -> RecordBatch {
+        let batch = match self.pending.take() {
+            Some(pending) => concat_batches(&pending.schema(), &[pending, batch]).unwrap(),
+            None => batch,
+        };
+        let ranges = self.ranges(&batch);
+        let Some(last) = ranges.last() else {
+            return self.gather(&batch, &ranges);
+        };
+        self.pending = Some(batch.slice(last.start, last.len()));
+        self.gather(&batch, &ranges[..ranges.len() - 1])
+    }
+
+    /// Returns the rows kept back of the last key, once all batches are read.
+    pub fn finish(&mut self) -> Option<RecordBatch> {
+        let batch = self.pending.take()?;
+        let ranges = self.ranges(&batch);
+        Some(self.gather(&batch, &ranges))
+    }
+
+    fn key_columns(&self, batch: &RecordBatch) -> Vec<usize> {
+        let schema = batch.schema();
+        for column in [&self.name, &self.value] {
+            if schema.index_of(column).is_err() {
+                panic!("Column not found ({})", column);
+            }
+        }
+        (0..schema.fields().len())
+            .filter(|index| ![&self.name, &self.value].contains(&schema.field(*index).name()))
+            .collect()
+    }
+
+    /// Returns the ranges of adjacent rows with the same key.
+    fn ranges(&self, batch: &RecordBatch) -> Vec<Range<usize>> {
+        let keys: Vec<ArrayRef> = (self.key_columns(batch).into_iter())
+            .map(|index| batch.column(index).clone())
+            .collect();
+        match (keys.is_empty(), batch.num_rows()) {
+            (_, 0) => vec![],
+            (true, rows) => std::iter::once(0..rows).collect(),
+            (false, _) => partition(&keys).unwrap().ranges(),
+        }
+    }
+
+    /// Returns a row per range, with the key and a column per distinct name, in order of first
+    /// appearance.
+    fn gather(&self, batch: &RecordBatch, ranges: &[Range<usize>]) -> RecordBatch {
+        let schema = batch.schema();
+        let names = cast(batch.column_by_name(&self.name).unwrap(), &DataType::Utf8).unwrap();
+        let names = names.as_string::<i32>();
+        let values = batch.column_by_name(&self.value).unwrap();
+
+        let mut columns_names: Vec<&str> = vec![];
+        let rows = ranges.iter().flat_map(|range| range.clone());
+        for row in rows.filter(|row| names.is_valid(*row)) {
+            if !columns_names.contains(&names.value(row)) {
+                columns_names.push(names.value(row));
+            }
+        }
+        if columns_names.len() > MAX_PIVOT_COLUMNS {
+            panic!(
+                "Too many distinct names to pivot into columns ({}, at most {})",
+                columns_names.len(),
+                MAX_PIVOT_COLUMNS
+            );
+        }
+
+        let first_rows: UInt32Array = ranges.iter().map(|range| range.start as u32).collect();
+        let mut fields: Vec<FieldRef> = vec![];
+        let mut columns: Vec<ArrayRef> = vec![];
+        for index in self.key_columns(batch) {
+            fields.push(schema.fields()[index].clone());
+            columns.push(take(batch.column(index), &first_rows, None).unwrap());
+        }
+        // Values are taken from the last row of each name in a range, or from a null
+        let null = new_null_array(values.data_type(), 1);
+        let sources: [&dyn Array; 2] = [values.as_ref(), null.as_ref()];
+        for column_name in columns_names {
+            let indices: Vec<(usize, usize)> = ranges
+                .iter()
+                .map(|range| {
+                    range
+                        .clone()
+                        .rev()
+                        .find(|row| names.is_valid(*row) && names.value(*row) == column_name)
+                        .map_or((1, 0), |row| (0, row))
+                })
+                .collect();
+            fields.push(Arc::new(Field::new(
+                column_name,
+                values.data_type().clone(),
+                true,
+            )));
+            columns.push(interleave(&sources, &indices).unwrap());
+        }
+        let options = RecordBatchOptions::new().with_row_count(Some(ranges.len()));
+        RecordBatch::try_new_with_options(Arc::new(Schema::new(fields)), columns, &options).unwrap()
+    }
+}
@@ -0,0 +1,119 @@
+use parquet::arrow::arrow_reader::{RowSelection, RowSelector};
+use parquet::file::metadata::ParquetMetaData;
+
+/// Parses row indices separated by commas or whitespace, e.g. from `--rows` or `--rows-file`.
+pub fn parse_row_indices(input: &str) -> Vec<usize> {
+    input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|index| !index.is_empty())
+        .map(|index| {
+            index
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid row index ({})", index))
+        })
+        .collect()
+}
+
+/// Returns the row groups containing the given global row indices, with a selection of exactly
+/// those rows relative to the selected row groups.
+pub fn row_groups_and_selection(
+    parquet_metadata: &ParquetMetaData,
+    rows: &[usize],
+) -> (Vec<usize>, RowSelection) {
+    let mut rows = rows.to_vec();
+    rows.sort_unstable();
+    rows.dedup();
+
+    let mut row_groups: Vec<usize> = vec![];
+    let mut selectors: Vec<RowSelector> = vec![];
+    let mut rows_iter = rows.iter().peekable();
+    let mut row_group_start = 0;
+
+    for (index, row_group) in parquet_metadata.row_groups().iter().enumerate() {
+        let row_group_end = row_group_start + row_group.num_rows() as usize;
+        let mut position = row_group_start;
+
+        while let Some(row) = rows_iter.next_if(|row| **row < row_group_end) {
+            if position == row_group_start {
+                row_groups.push(index);
+            }
+            if *row > position {
+                selectors.push(RowSelector::skip(row - position));
+            }
+            selectors.push(RowSelector::select(1));
+            position = row + 1;
+        }
+        if position > row_group_start && position < row_group_end {
+            selectors.push(RowSelector::skip(row_group_end - position));
+        }
+
+        row_group_start = row_group_end;
+    }
+
+    if let Some(row) = rows_iter.next() {
+        panic!("Row index out of range ({})", row);
+    }
+
+    (row_groups, selectors.into())
+}
+
+/// Returns the row groups holding the rows from the offset on, up to the limit if given, and the
+/// number of rows to skip in the first one, so that preceding row groups are not fetched.
+pub fn row_groups_from_offset(
+    parquet_metadata: &ParquetMetaData,
+    offset: usize,
+    limit: Option<usize>,
+) -> (Vec<usize>, usize) {
+    let end = limit.map(|limit| offset.saturating_add(limit));
+    let mut row_groups: Vec<usize> = vec![];
+    let mut skipped = 0;
+    let mut row_group_start = 0;
+
+    for (index, row_group) in parquet_metadata.row_groups().iter().enumerate() {
+        let row_group_end = row_group_start + row_group.num_rows() as usize;
+        if end.is_some_and(|end| row_group_start >= end) {
+            break;
+        }
+        if row_group_end > offset {
+            if row_groups.is_empty() {
+                skipped = offset - row_group_start;
+            }
+            row_groups.push(index);
+        }
+        row_group_start = row_group_end;
+    }
+    (row_groups, skipped)
+}
+
+/// Returns the number of rows read from each of the given row groups, those of the selection if
+/// given, once a number of them are skipped.
+pub fn rows_read_per_row_group(
+    parquet_metadata: &ParquetMetaData,
+    row_groups: &[usize],
+    selection: Option<&RowSelection>,
+    mut skipped: usize,
+) -> Vec<usize> {
+    let mut selection = selection.cloned();
+    row_groups
+        .iter()
+        .map(|row_group| {
+            let num_rows = parquet_metadata.row_group(*row_group).num_rows() as usize;
+            let rows = match selection.as_mut() {
+                Some(selection) => selection.split_off(num_rows).row_count(),
+                None => num_rows,
+            };
+            let skipped_here = skipped.min(rows);
+            skipped -= skipped_here;
+            rows - skipped_here
+        })
+        .collect()
+}
+
+/// Returns the number of rows before the last row groups, from which they are read.
+pub fn rows_before_last_row_groups(parquet_metadata: &ParquetMetaData, count: usize) -> usize {
+    let row_groups = parquet_metadata.row_groups();
+    row_groups[..row_groups.len().saturating_sub(count)]
+        .iter()
+        .map(|row_group| row_group.num_rows() as usize)
+        .sum()
+}
@@ -0,0 +1,109 @@
+use clap::ValueEnum;
+use object_store::aws::{AmazonS3Builder, AmazonS3ConfigKey};
+use url::Url;
+
+use crate::S3Args;
+
+/// S3-compatible service, configuring its quirks together.
+#[derive(ValueEnum, Clone, Copy, Default, PartialEq)]
+pub enum S3Flavor {
+    /// Amazon S3
+    #[default]
+    Aws,
+    /// Cloudflare R2, at https://<ACCOUNT_ID>.r2.cloudflarestorage.com
+    R2,
+    /// Backblaze B2, at https://s3.<REGION>.backblazeb2.com
+    B2,
+    /// DigitalOcean Spaces, at https://<REGION>.digitaloceanspaces.com
+    Spaces,
+    /// MinIO, at the URL of the server
+    Minio,
+}
+
+impl S3Flavor {
+    fn name(&self) -> &'static str {
+        match self {
+            S3Flavor::Aws => "aws",
+            S3Flavor::R2 => "r2",
+            S3Flavor::B2 => "b2",
+            S3Flavor::Spaces => "spaces",
+            S3Flavor::Minio => "minio",
+        }
+    }
+
+    /// Configures the endpoint, signing region and bucket addressing of the service, stopping
+    /// on options of Amazon S3 that it does not support.
+    pub fn configure(self, s3_builder: AmazonS3Builder, s3_args: &S3Args) -> AmazonS3Builder {
+        if self == S3Flavor::Aws {
+            return s3_builder;
+        }
+        let unsupported = [
+            (s3_args.request_payer.is_some(), "--request-payer"),
+            (s3_args.use_accelerate_endpoint, "--use-accelerate-endpoint"),
+            (s3_args.use_dualstack_endpoint, "--use-dualstack-endpoint"),
+            (s3_args.role_arn.is_some(), "--role-arn"),
+            (
+                s3_args.version_id.is_some() && self == S3Flavor::R2,
+                "--version-id",
+            ),
+            (
+                s3_builder
+                    .get_config_value(&AmazonS3ConfigKey::Checksum)
+                    .is_some()
+                    && matches!(self, S3Flavor::B2 | S3Flavor::Spaces),
+                "AWS_CHECKSUM_ALGORITHM",
+            ),
+        ];
+        if let Some((_, option)) = unsupported.iter().find(|(used, _)| *used) {
+            panic!("{} is not supported by --s3-flavor {}", option, self.name());
+        }
+
+        let region = s3_builder.get_config_value(&AmazonS3ConfigKey::Region);
+        let endpoint = match (&s3_args.endpoint_url, self) {
+            (Some(endpoint_url), _) => endpoint_url.clone(),
+            (None, S3Flavor::B2) => format!(
+                "https://s3.{}.backblazeb2.com",
+                region_of(self, region.as_deref())
+            ),
+            (None, S3Flavor::Spaces) => format!(
+                "https://{}.digitaloceanspaces.com",
+                region_of(self, region.as_deref())
+            ),
+            (None, _) => panic!("--s3-flavor {} requires --endpoint-url", self.name()),
+        };
+        // Services sign with the region in their host name, or any region for R2 and MinIO
+        let host = Url::parse(&endpoint)
+            .ok()
+            .and_then(|url| url.host_str().map(String::from))
+            .unwrap_or_default();
+        let region = match self {
+            S3Flavor::R2 => Some("auto".to_string()),
+            S3Flavor::B2 => host
+                .strip_prefix("s3.")
+                .and_then(|host| host.strip_suffix(".backblazeb2.com"))
+                .map(String::from),
+            S3Flavor::Spaces => host
+                .strip_suffix(".digitaloceanspaces.com")
+                .map(String::from),
+            _ => None,
+        }
+        .or(region)
+        .unwrap_or("us-east-1".to_string());
+
+        s3_builder
+            .with_endpoint(&endpoint)
+            .with_allow_http(endpoint.starts_with("http://"))
+            .with_region(region)
+            .with_virtual_hosted_style_request(false)
+    }
+}
+
+/// Returns the region an endpoint is derived from, required without --endpoint-url.
+fn region_of(flavor: S3Flavor, region: Option<&str>) -> &str {
+    region.unwrap_or_else(|| {
+        panic!(
+            "--s3-flavor {} requires --endpoint-url or a region (AWS_DEFAULT_REGION)",
+            flavor.name()
+        )
+    })
+}
@@ -0,0 +1,210 @@
+use std::error::Error;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use aws_config::ecs::EcsCredentialsProvider;
+use aws_config::environment::EnvironmentVariableCredentialsProvider;
+use aws_config::imds::credentials::ImdsCredentialsProvider;
+use aws_config::profile::ProfileFileCredentialsProvider;
+use aws_config::provider_config::ProviderConfig;
+use aws_config::sts::AssumeRoleProvider;
+use aws_config::web_identity_token::WebIdentityTokenCredentialsProvider;
+use aws_config::{BehaviorVersion, ConfigLoader};
+use aws_credential_types::provider::{ProvideCredentials, SharedCredentialsProvider};
+use object_store::aws::AwsCredential;
+use object_store::CredentialProvider;
+use tokio::sync::Mutex;
+
+/// Credentials are renewed this long before they expire.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+fn config_loader(aws_profile: Option<&str>) -> ConfigLoader {
+    let loader = aws_config::defaults(BehaviorVersion::latest());
+    match aws_profile {
+        Some(aws_profile) => loader.profile_name(aws_profile),
+        None => loader,
+    }
+}
+
+/// A source of credentials, named in diagnostics.
+#[derive(Debug)]
+struct CredentialSource {
+    name: String,
+    provider: SharedCredentialsProvider,
+}
+
+#[derive(Debug, Default)]
+struct ChainState {
+    /// Index of the source credentials are taken from, past the end once all have failed
+    current: usize,
+    cached: Option<(Arc<AwsCredential>, Option<SystemTime>)>,
+    /// Sources passed over, with the reason
+    rejected: Vec<(String, String)>,
+}
+
+/// Returns the error with its sources, which carry the reason of most credential errors.
+pub fn describe(error: &dyn Error) -> String {
+    let mut description = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        description.push_str(&format!(": {}", error));
+        source = error.source();
+    }
+    description
+}
+
+/// Credentials of AWS SDK providers tried in turn, renewed as they expire so long reads outlast
+/// temporary credentials. Sources that fail, or whose credentials are denied access, are passed
+/// over for the next, and once none is left the error lists why each was rejected.
+#[derive(Debug)]
+pub struct SdkCredentials {
+    sources: Vec<CredentialSource>,
+    state: Mutex<ChainState>,
+}
+
+impl SdkCredentials {
+    fn new(sources: Vec<(String, SharedCredentialsProvider)>) -> Self {
+        SdkCredentials {
+            sources: sources
+                .into_iter()
+                .map(|(name, provider)| CredentialSource { name, provider })
+                .collect(),
+            state: Mutex::new(ChainState::default()),
+        }
+    }
+
+    /// Resolves credentials like the SDK chain of the given or default profile: environment
+    /// variables, then the profile, including SSO (IAM Identity Center) sessions cached by
+    /// `aws sso login` and `credential_process` helpers, web identity tokens, and container and
+    /// instance metadata.
+    pub async fn from_profile(aws_profile: Option<&str>) -> Self {
+        let sdk_config = config_loader(aws_profile).load().await;
+        let provider_config = ProviderConfig::default().with_region(sdk_config.region().cloned());
+
+        let mut profile = ProfileFileCredentialsProvider::builder().configure(&provider_config);
+        if let Some(aws_profile) = aws_profile {
+            profile = profile.profile_name(aws_profile);
+        }
+        SdkCredentials::new(vec![
+            (
+                "environment".to_string(),
+                SharedCredentialsProvider::new(EnvironmentVariableCredentialsProvider::new()),
+            ),
+            (
+                format!("profile {}", aws_profile.unwrap_or("default")),
+                SharedCredentialsProvider::new(profile.build()),
+            ),
+            (
+                "web identity".to_string(),
+                SharedCredentialsProvider::new(
+                    WebIdentityTokenCredentialsProvider::builder()
+                        .configure(&provider_config)
+                        .build(),
+                ),
+            ),
+            (
+                "container".to_string(),
+                SharedCredentialsProvider::new(
+                    EcsCredentialsProvider::builder()
+                        .configure(&provider_config)
+                        .build(),
+                ),
+            ),
+            (
+                "instance metadata".to_string(),
+                SharedCredentialsProvider::new(
+                    ImdsCredentialsProvider::builder()
+                        .configure(&provider_config)
+                        .build(),
+                ),
+            ),
+        ])
+    }
+
+    /// Assumes the role with the credentials of the given or default profile (or environment)
+    /// as source.
+    pub async fn assume_role(
+        role_arn: &str,
+        external_id: Option<&str>,
+        session_name: &str,
+        aws_profile: Option<&str>,
+    ) -> Self {
+        let sdk_config = config_loader(aws_profile).load().await;
+
+        let mut builder = AssumeRoleProvider::builder(role_arn)
+            .session_name(session_name)
+            .configure(&sdk_config);
+        if let Some(external_id) = external_id {
+            builder = builder.external_id(external_id);
+        }
+        SdkCredentials::new(vec![(
+            format!("role {}", role_arn),
+            SharedCredentialsProvider::new(builder.build().await),
+        )])
+    }
+
+    /// Returns the index of the source credentials are taken from, if any is left.
+    pub async fn current(&self) -> Option<usize> {
+        let current = self.state.lock().await.current;
+        (current < self.sources.len()).then_some(current)
+    }
+
+    /// Passes over the source, such as once its credentials are denied access, unless it was
+    /// already passed over by a concurrent request.
+    pub async fn reject(&self, source: usize, reason: String) {
+        let mut state = self.state.lock().await;
+        if state.current != source {
+            return;
+        }
+        state
+            .rejected
+            .push((self.sources[source].name.clone(), reason));
+        state.current += 1;
+        state.cached = None;
+    }
+
+    fn error(&self, state: &ChainState) -> object_store::Error {
+        let mut message = "No AWS credentials accepted, tried:".to_string();
+        for (name, reason) in &state.rejected {
+            message.push_str(&format!("\n  {}: {}", name, reason));
+        }
+        object_store::Error::Generic {
+            store: "S3",
+            source: message.into(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl CredentialProvider for SdkCredentials {
+    type Credential = AwsCredential;
+
+    async fn get_credential(&self) -> object_store::Result<Arc<AwsCredential>> {
+        let mut state = self.state.lock().await;
+        if let Some((credential, expiry)) = state.cached.as_ref() {
+            if expiry.map_or(true, |expiry| SystemTime::now() + EXPIRY_MARGIN < expiry) {
+                return Ok(credential.clone());
+            }
+        }
+
+        while let Some(source) = self.sources.get(state.current) {
+            match source.provider.provide_credentials().await {
+                Ok(credentials) => {
+                    let credential = Arc::new(AwsCredential {
+                        key_id: credentials.access_key_id().to_string(),
+                        secret_key: credentials.secret_access_key().to_string(),
+                        token: credentials.session_token().map(str::to_string),
+                    });
+                    state.cached = Some((credential.clone(), credentials.expiry()));
+                    return Ok(credential);
+                }
+                Err(error) => {
+                    state.rejected.push((source.name.clone(), describe(&error)));
+                    state.current += 1;
+                    state.cached = None;
+                }
+            }
+        }
+        Err(self.error(&state))
+    }
+}
@@ -0,0 +1,193 @@
+use std::io::Write;
+use std::sync::Arc;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use object_store::path::Path;
+use object_store::ObjectStore;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::anomalies::{self, Anomaly};
+use crate::profile::Profile;
+use crate::sink::{Sink, SinkKind};
+use crate::source::store_for_output;
+use crate::{CatArgs, OutputFormat};
+
+/// Placeholder of the content hash in the file names of --output shards.
+const HASH_PLACEHOLDER: &str = "{hash}";
+
+/// Writes JSON lines as numbered gzip files under a prefix, starting a new file whenever the
+/// compressed size reaches the limit. Lines are never split across files. With a name template,
+/// files are instead named by the SHA-256 of their content, so re-runs write the same files.
+pub struct ShardWriter {
+    storage_container: Arc<dyn ObjectStore>,
+    prefix: Path,
+    name_template: Option<String>,
+    max_size: usize,
+    encoder: GzEncoder<Vec<u8>>,
+    lines: usize,
+    shards: Vec<(Path, usize)>,
+}
+
+impl ShardWriter {
+    pub fn new(
+        storage_container: Arc<dyn ObjectStore>,
+        prefix: Path,
+        name_template: Option<String>,
+        max_size: usize,
+    ) -> Self {
+        ShardWriter {
+            storage_container,
+            prefix,
+            name_template,
+            max_size,
+            encoder: GzEncoder::new(vec![], Compression::default()),
+            lines: 0,
+            shards: vec![],
+        }
+    }
+
+    pub async fn write(&mut self, bytes: &[u8]) {
+        for line in bytes.split_inclusive(|byte| *byte == b'\n') {
+            if line.len() > self.max_size {
+                anomalies::report(
+                    Anomaly::OversizedRecord,
+                    1,
+                    format!("line of {} bytes", line.len()),
+                );
+            }
+            self.encoder.write_all(line).unwrap();
+            self.lines += 1;
+            // Only counts output the encoder has flushed so far, which lags by at most its window
+            if self.encoder.get_ref().len() >= self.max_size {
+                self.flush_shard().await;
+            }
+        }
+    }
+
+    async fn flush_shard(&mut self) {
+        let encoder = std::mem::replace(
+            &mut self.encoder,
+            GzEncoder::new(vec![], Compression::default()),
+        );
+        let bytes = encoder.finish().unwrap();
+        let name = match &self.name_template {
+            Some(name_template) => {
+                let hash: String = Sha256::digest(&bytes)
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect();
+                name_template.replace(HASH_PLACEHOLDER, &hash)
+            }
+            None => format!("part-{:05}.json.gz", self.shards.len()),
+        };
+        let location = self.prefix.child(name);
+        self.shards.push((location.clone(), bytes.len()));
+        self.storage_container
+            .put(&location, bytes.into())
+            .await
+            .unwrap();
+        self.lines = 0;
+    }
+
+    /// Writes the last file.
+    pub async fn finish(&mut self) {
+        if self.lines > 0 || self.shards.is_empty() {
+            self.flush_shard().await;
+        }
+    }
+
+    /// Writes a Redshift COPY manifest listing the files, with URLs under the given output location.
+    pub async fn write_manifest(&self, output: &str) {
+        let entries: Vec<_> = self
+            .shards
+            .iter()
+            .map(|(location, size)| {
+                let filename = location.filename().unwrap();
+                json!({
+                    "url": format!("{}/{}", output.trim_end_matches('/'), filename),
+                    "mandatory": true,
+                    "meta": {"content_length": size}
+                })
+            })
+            .collect();
+        let manifest = serde_json::to_vec_pretty(&json!({ "entries": entries })).unwrap();
+        self.storage_container
+            .put(&self.prefix.child("manifest"), manifest.into())
+            .await
+            .unwrap();
+    }
+}
+
+/// Returns the directory or prefix of shards and the template of their file names, for an
+/// --output with a file name containing {hash}, which names each shard by its content.
+pub fn split_name_template(output: &str) -> (&str, Option<String>) {
+    match output.rsplit_once('/') {
+        Some((output, name)) if name.contains(HASH_PLACEHOLDER) => (output, Some(name.to_string())),
+        None if output.contains(HASH_PLACEHOLDER) => (".", Some(output.to_string())),
+        _ => (output, None),
+    }
+}
+
+/// Shards under a local directory or object store prefix, for --output locations of no other
+/// sink.
+pub struct ShardSinkKind;
+
+#[async_trait::async_trait(?Send)]
+impl SinkKind for ShardSinkKind {
+    fn matches(&self, _output: &str) -> bool {
+        true
+    }
+
+    async fn open(&self, output: &str, args: &CatArgs) -> Box<dyn Sink> {
+        // Shards are split anywhere in the output, so would lack the header row
+        if matches!(
+            args.format,
+            OutputFormat::Csv | OutputFormat::Tsv | OutputFormat::Markdown | OutputFormat::Table
+        ) {
+            panic!("CSV, TSV, markdown and table output cannot be written as --output shards");
+        }
+        // Shards are split between lines, which would split records indented over several, as
+        // with --pretty or yaml
+        if args.pretty || matches!(args.format, OutputFormat::Yaml) {
+            panic!("Indented records cannot be written as --output shards");
+        }
+        let (output, name_template) = split_name_template(output);
+        let (storage_container, prefix) = store_for_output(output).await;
+        let max_file_size = args
+            .max_file_size
+            .or(args.profile.map(|profile| profile.max_file_size()))
+            .unwrap_or(128);
+        Box::new(ShardSink {
+            shard_writer: ShardWriter::new(
+                storage_container,
+                prefix,
+                name_template,
+                max_file_size * 1024 * 1024,
+            ),
+            manifest_location: (args.profile == Some(Profile::Redshift))
+                .then(|| output.to_string()),
+        })
+    }
+}
+
+/// Shards, followed by a Redshift COPY manifest with --profile redshift.
+struct ShardSink {
+    shard_writer: ShardWriter,
+    manifest_location: Option<String>,
+}
+
+#[async_trait::async_trait(?Send)]
+impl Sink for ShardSink {
+    async fn write(&mut self, bytes: Vec<u8>) {
+        self.shard_writer.write(&bytes).await;
+    }
+
+    async fn finish(&mut self) {
+        self.shard_writer.finish().await;
+        if let Some(location) = &self.manifest_location {
+            self.shard_writer.write_manifest(location).await;
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::fs::File;
+use std::io::{BufWriter, IoSlice, Write};
+use std::path::PathBuf;
+
+use arrow_array::RecordBatch;
+
+#[cfg(feature = "adbc")]
+use crate::adbc::AdbcSinkKind;
+use crate::compress::{CompressedSink, OutputCompression};
+use crate::kafka::KafkaSinkKind;
+use crate::partition::PartitionSink;
+use crate::post::PostSinkKind;
+use crate::shard::ShardSinkKind;
+use crate::CatArgs;
+
+/// Destination of cat output, such as stdout or the shards of --output.
+#[async_trait::async_trait(?Send)]
+pub trait Sink {
+    /// Takes the rows of a batch as Arrow data rather than serialized, returning whether it did.
+    async fn write_batch(&mut self, _batch: &RecordBatch) -> bool {
+        false
+    }
+
+    /// Writes serialized rows, in order.
+    async fn write(&mut self, bytes: Vec<u8>);
+
+    /// Writes anything buffered, once all rows are written.
+    async fn finish(&mut self);
+}
+
+/// Kind of sink, opened for the --output locations it matches.
+#[async_trait::async_trait(?Send)]
+pub trait SinkKind: Sync {
+    fn matches(&self, output: &str) -> bool;
+
+    async fn open(&self, output: &str, args: &CatArgs) -> Box<dyn Sink>;
+}
+
+/// Sinks by the scheme prefixed to --output, tried in order. The last one takes any other
+/// location as a directory or prefix of shards.
+static SINKS: &[&dyn SinkKind] = &[
+    #[cfg(feature = "adbc")]
+    &AdbcSinkKind,
+    &FileSinkKind,
+    &PostSinkKind,
+    &KafkaSinkKind,
+    &ShardSinkKind,
+];
+
+/// Opens the sink of the --output location, or stdout without one, compressed with --compress.
+pub async fn open_sink(args: &CatArgs) -> Box<dyn Sink> {
+    let sink = open_uncompressed_sink(args).await;
+    match &args.compress {
+        Some(compression) => {
+            if args
+                .output
+                .as_ref()
+                .is_some_and(|output| !FileSinkKind.matches(output))
+            {
+                panic!("--compress only applies to stdout and file: outputs");
+            }
+            Box::new(CompressedSink::new(
+                sink,
+                OutputCompression::parse(compression),
+            ))
+        }
+        None => sink,
+    }
+}
+
+async fn open_uncompressed_sink(args: &CatArgs) -> Box<dyn Sink> {
+    let Some(output) = &args.output else {
+        return Box::new(StdoutSink::new(args.write_buffer_size * 1024));
+    };
+    if !args.partition_by.is_empty() {
+        return Box::new(PartitionSink::open(output, args).await);
+    }
+    #[cfg(not(feature = "adbc"))]
+    if output.starts_with("adbc:") {
+        panic!("adbc: outputs need parquet2json to be built with the adbc feature");
+    }
+    let kind = SINKS.iter().find(|kind| kind.matches(output)).unwrap();
+    kind.open(output, args).await
+}
+
+/// Writes to stdout, gathering output up to a size before each write.
+struct StdoutSink {
+    write_buffer_size: usize,
+    unwritten: Vec<Vec<u8>>,
+    unwritten_bytes: usize,
+}
+
+impl StdoutSink {
+    fn new(write_buffer_size: usize) -> Self {
+        StdoutSink {
+            write_buffer_size,
+            unwritten: vec![],
+            unwritten_bytes: 0,
+        }
+    }
+
+    /// Writes the gathered output, with as few vectored writes as stdout accepts.
+    fn write_unwritten(&mut self) {
+        let mut stdout = std::io::stdout().lock();
+        let mut slices: Vec<IoSlice> = self
+            .unwritten
+            .iter()
+            .map(|bytes| IoSlice::new(bytes))
+            .collect();
+        let mut slices = &mut slices[..];
+        while !slices.is_empty() {
+            let written = stdout.write_vectored(slices).unwrap();
+            if written == 0 {
+                panic!("Failed to write to stdout");
+            }
+            IoSlice::advance_slices(&mut slices, written);
+        }
+        stdout.flush().unwrap();
+        self.unwritten.clear();
+        self.unwritten_bytes = 0;
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Sink for StdoutSink {
+    async fn write(&mut self, bytes: Vec<u8>) {
+        if bytes.is_empty() {
+            return;
+        }
+        self.unwritten_bytes += bytes.len();
+        self.unwritten.push(bytes);
+        if self.unwritten_bytes >= self.write_buffer_size {
+            self.write_unwritten();
+        }
+    }
+
+    async fn finish(&mut self) {
+        self.write_unwritten();
+    }
+}
+
+/// Single local file, as file:<PATH>, in any format.
+struct FileSinkKind;
+
+#[async_trait::async_trait(?Send)]
+impl SinkKind for FileSinkKind {
+    fn matches(&self, output: &str) -> bool {
+        output.starts_with("file:")
+    }
+
+    async fn open(&self, output: &str, _args: &CatArgs) -> Box<dyn Sink> {
+        let path = PathBuf::from(output.trim_start_matches("file:").trim_start_matches("//"));
+        let mut partial = path.clone().into_os_string();
+        partial.push(".partial");
+        let partial = PathBuf::from(partial);
+        let file = File::create(&partial).unwrap_or_else(|error| {
+            panic!("Cannot create output file ({}: {})", path.display(), error)
+        });
+        Box::new(FileSink {
+            writer: BufWriter::new(file),
+            partial,
+            path,
+        })
+    }
+}
+
+/// Writes to a .partial file next to the output file, renamed to it once complete, so that the
+/// output file is never left half-written.
+struct FileSink {
+    writer: BufWriter<File>,
+    partial: PathBuf,
+    path: PathBuf,
+}
+
+#[async_trait::async_trait(?Send)]
+impl Sink for FileSink {
+    async fn write(&mut self, bytes: Vec<u8>) {
+        self.writer.write_all(&bytes).unwrap();
+    }
+
+    async fn finish(&mut self) {
+        self.writer.flush().unwrap();
+        std::fs::rename(&self.partial, &self.path).unwrap_or_else(|error| {
+            panic!(
+                "Cannot write output file ({}: {})",
+                self.path.display(),
+                error
+            )
+        });
+    }
+}
@@ -0,0 +1,459 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use arrow_cast::base64::{Engine, BASE64_STANDARD};
+use aws_config::profile::load;
+use aws_runtime::env_config::file::EnvConfigFiles;
+use aws_types::os_shim_internal::{Env, Fs};
+use object_store::aws::{AmazonS3Builder, AmazonS3ConfigKey};
+use object_store::azure::MicrosoftAzureBuilder;
+use object_store::http::HttpBuilder;
+use object_store::local::LocalFileSystem;
+use object_store::path::Path;
+use object_store::{ClientOptions, ObjectStore};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use reqwest::{Client, NoProxy, Proxy};
+use url::Url;
+use urlencoding::decode;
+
+use crate::access_point::parse_access_point;
+use crate::archive::{split_archive, ArchiveStore};
+use crate::credential_fallback::CredentialFallbackStore;
+use crate::full_download::FullDownloadStore;
+use crate::metrics::InstrumentedStore;
+use crate::object_version::ObjectVersionStore;
+use crate::presigned::{is_presigned_url, PresignedUrlStore};
+use crate::requester_pays::RequesterPaysStore;
+use crate::s3_flavor::S3Flavor;
+use crate::sdk_credentials::SdkCredentials;
+use crate::timeout::TimeoutStore;
+use crate::{RequestPayer, HTTP_ARGS, S3_ARGS};
+
+/// Backend of input and output locations, building the object store of the locations it
+/// matches.
+#[async_trait::async_trait]
+pub trait Source: Sync {
+    fn matches(&self, file: &str) -> bool;
+
+    async fn store(&self, file: &str) -> (Arc<dyn ObjectStore>, Path);
+}
+
+/// Sources tried in turn for a location, the first matching one reading it. Further backends,
+/// such as those behind features, are registered here.
+static SOURCES: &[&dyn Source] = &[&S3Source, &AzureSource, &HttpSource, &LocalSource];
+
+/// Returns the object store of a location, counting its requests for --summary. Members of zip
+/// and tar archives are read from the archive at the location of any source.
+pub async fn store_for_location(file: &str) -> (Arc<dyn ObjectStore>, Path) {
+    let (file, member) = match split_archive(file) {
+        Some((archive, member)) => (archive, Some(member)),
+        None => (file, None),
+    };
+    let source = SOURCES.iter().find(|source| source.matches(file)).unwrap();
+    let (storage_container, location) = source.store(file).await;
+    let storage_container = Arc::new(InstrumentedStore::new(Arc::new(TimeoutStore::new(
+        storage_container,
+    ))));
+
+    match member {
+        Some(member) => {
+            let archive = ArchiveStore::open(storage_container, &location).await;
+            let member = Path::from(member);
+            if !archive.contains(&member) {
+                panic!("Not found in archive ({}!{})", file, member);
+            }
+            (Arc::new(archive), member)
+        }
+        None => (storage_container, location),
+    }
+}
+
+/// Like `store_for_location`, but for locations that may not exist yet.
+pub async fn store_for_output(output: &str) -> (Arc<dyn ObjectStore>, Path) {
+    if output.contains("://") {
+        store_for_location(output).await
+    } else {
+        let file_path_buf = std::path::absolute(output).unwrap();
+        let location = Path::from_absolute_path(file_path_buf).unwrap();
+
+        (Arc::new(LocalFileSystem::new()), location)
+    }
+}
+
+/// S3 and S3-compatible services, as s3://<bucket>/<key> URLs.
+struct S3Source;
+
+#[async_trait::async_trait]
+impl Source for S3Source {
+    fn matches(&self, file: &str) -> bool {
+        file.starts_with("s3://")
+    }
+
+    async fn store(&self, file: &str) -> (Arc<dyn ObjectStore>, Path) {
+        let mut s3_builder: AmazonS3Builder = AmazonS3Builder::from_env();
+        let s3_args = S3_ARGS.get_or_init(Default::default);
+
+        let profile_set = load(
+            &Fs::default(),
+            &Env::default(),
+            &EnvConfigFiles::default(),
+            s3_args.aws_profile.clone().map(Cow::Owned),
+        )
+        .await
+        .ok();
+        if let Some(aws_profile) = &s3_args.aws_profile {
+            if profile_set
+                .as_ref()
+                .and_then(|profile_set| profile_set.get_profile(aws_profile))
+                .is_none()
+            {
+                panic!("AWS profile not found ({})", aws_profile);
+            }
+        }
+        if let Some(region) = profile_set
+            .as_ref()
+            .and_then(|profile_set| profile_set.get("region"))
+        {
+            s3_builder = s3_builder.with_region(region);
+        }
+
+        // Credentials come from a chain like that of the AWS CLI: keys of the environment or
+        // profile, SSO sessions of `aws sso login`, `credential_process` helpers, web identity
+        // tokens (e.g. IRSA on EKS), then container (ECS, EKS Pod Identity) and instance (IMDSv2)
+        // metadata, passing over those denied access
+        let credentials = if let Some(role_arn) = &s3_args.role_arn {
+            let credentials = SdkCredentials::assume_role(
+                role_arn,
+                s3_args.external_id.as_deref(),
+                s3_args.session_name.as_deref().unwrap_or("parquet2json"),
+                s3_args.aws_profile.as_deref(),
+            )
+            .await;
+            Some(Arc::new(credentials))
+        } else if !s3_args.no_sign_request {
+            let credentials = SdkCredentials::from_profile(s3_args.aws_profile.as_deref()).await;
+            Some(Arc::new(credentials))
+        } else {
+            None
+        };
+        if let Some(credentials) = &credentials {
+            s3_builder = s3_builder.with_credentials(credentials.clone());
+        }
+        if let Some((proxy, proxy_excludes)) = proxy_options() {
+            s3_builder = s3_builder.with_proxy_url(proxy);
+            if let Some(proxy_excludes) = proxy_excludes {
+                s3_builder = s3_builder.with_proxy_excludes(proxy_excludes);
+            }
+        }
+        if let Some(endpoint_url) = &s3_args.endpoint_url {
+            s3_builder = s3_builder
+                .with_endpoint(endpoint_url)
+                .with_allow_http(endpoint_url.starts_with("http://"));
+        }
+        if s3_args.force_path_style {
+            s3_builder = s3_builder.with_virtual_hosted_style_request(false);
+        }
+        if s3_args.no_sign_request {
+            s3_builder = s3_builder.with_skip_signature(true);
+        }
+        s3_builder = s3_args.s3_flavor.configure(s3_builder, s3_args);
+
+        // Access point ARNs are not valid URL hosts, and are addressed through endpoints of their own
+        let (bucket, key) = match parse_access_point(file, s3_args.use_dualstack_endpoint) {
+            Some(_) if s3_args.s3_flavor != S3Flavor::Aws => {
+                panic!("Access points are only supported by Amazon S3")
+            }
+            Some(_) if s3_args.use_accelerate_endpoint => {
+                panic!("Transfer acceleration is not supported with access points")
+            }
+            Some(access_point) => {
+                match access_point.region {
+                    Some(region) => s3_builder = s3_builder.with_region(region),
+                    None if !s3_args.no_sign_request => panic!(
+                        "Multi-Region Access Points require SigV4A signing, which is not supported ({})",
+                        access_point.name
+                    ),
+                    None => {}
+                }
+                s3_builder = s3_builder
+                    .with_endpoint(access_point.endpoint)
+                    .with_virtual_hosted_style_request(true);
+                (access_point.name, format!("/{}", access_point.key))
+            }
+            None => {
+                let url = Url::parse(file).unwrap();
+                let bucket = decode(url.host_str().unwrap()).unwrap().into_owned();
+                // Directory buckets of S3 Express One Zone, named <name>--<zone>--x-s3, are read
+                // through the endpoint of their zone with credentials of a session created there
+                if bucket.ends_with("--x-s3") && s3_args.s3_flavor == S3Flavor::Aws {
+                    if s3_args.use_accelerate_endpoint || s3_args.use_dualstack_endpoint {
+                        panic!(
+                            "Directory buckets have no accelerated or dual-stack endpoints ({})",
+                            bucket
+                        );
+                    }
+                    if s3_args.request_payer.is_some() {
+                        panic!(
+                            "Requester pays is not supported with directory buckets ({})",
+                            bucket
+                        );
+                    }
+                    s3_builder = s3_builder.with_s3_express(true);
+                }
+                // Accelerated endpoints are global, only reached with the bucket in the host name
+                if s3_args.use_accelerate_endpoint || s3_args.use_dualstack_endpoint {
+                    let region = s3_builder
+                        .get_config_value(&AmazonS3ConfigKey::Region)
+                        .unwrap_or("us-east-1".to_string());
+                    let host = match (
+                        s3_args.use_accelerate_endpoint,
+                        s3_args.use_dualstack_endpoint,
+                    ) {
+                        (true, false) => "s3-accelerate.amazonaws.com".to_string(),
+                        (true, true) => "s3-accelerate.dualstack.amazonaws.com".to_string(),
+                        (false, _) => format!("s3.dualstack.{}.amazonaws.com", region),
+                    };
+                    s3_builder = if s3_args.force_path_style {
+                        s3_builder.with_endpoint(format!("https://{}", host))
+                    } else {
+                        s3_builder
+                            .with_endpoint(format!("https://{}.{}", bucket, host))
+                            .with_virtual_hosted_style_request(true)
+                    };
+                }
+                (bucket, url.path().to_string())
+            }
+        };
+        let s3_builder = s3_builder.with_bucket_name(&bucket);
+        let anonymous: Option<Arc<dyn ObjectStore>> = s3_args.anonymous_fallback.then(|| {
+            Arc::new(
+                s3_builder
+                    .clone()
+                    .with_skip_signature(true)
+                    .build()
+                    .unwrap(),
+            ) as Arc<dyn ObjectStore>
+        });
+
+        let storage_container: Arc<dyn ObjectStore> = match s3_args.request_payer {
+            Some(RequestPayer::Requester) => {
+                let config = |key| s3_builder.get_config_value(&key);
+                let region = config(AmazonS3ConfigKey::Region).unwrap_or("us-east-1".to_string());
+                let virtual_hosted = config(AmazonS3ConfigKey::VirtualHostedStyleRequest)
+                    .is_some_and(|value| value == "true");
+                // Same bucket addressing as the object_store client
+                let bucket_endpoint = match (config(AmazonS3ConfigKey::Endpoint), virtual_hosted) {
+                    (Some(endpoint), true) => endpoint,
+                    (Some(endpoint), false) => format!("{}/{}", endpoint, bucket),
+                    (None, true) => format!("https://{}.s3.{}.amazonaws.com", bucket, region),
+                    (None, false) => format!("https://s3.{}.amazonaws.com/{}", region, bucket),
+                };
+                let client = match proxy_options() {
+                    Some((proxy, proxy_excludes)) => {
+                        let proxy = Proxy::all(proxy)
+                            .unwrap()
+                            .no_proxy(proxy_excludes.as_deref().and_then(NoProxy::from_string));
+                        Client::builder().proxy(proxy).build().unwrap()
+                    }
+                    None => Client::new(),
+                };
+                Arc::new(RequesterPaysStore::new(
+                    s3_builder.build().unwrap(),
+                    client,
+                    bucket_endpoint,
+                    region,
+                ))
+            }
+            None => Arc::new(s3_builder.build().unwrap()),
+        };
+        let location = Path::from(decode(&key).unwrap().as_ref());
+        let versioned = |storage_container: Arc<dyn ObjectStore>| -> Arc<dyn ObjectStore> {
+            match &s3_args.version_id {
+                Some(version_id) => Arc::new(ObjectVersionStore::new(
+                    storage_container,
+                    location.clone(),
+                    version_id.clone(),
+                )),
+                None => storage_container,
+            }
+        };
+        let storage_container = versioned(storage_container);
+        let storage_container: Arc<dyn ObjectStore> = match credentials {
+            Some(credentials) => Arc::new(CredentialFallbackStore::new(
+                storage_container,
+                credentials,
+                anonymous.map(versioned),
+            )),
+            None => storage_container,
+        };
+
+        (storage_container, location)
+    }
+}
+
+/// Azure Blob Storage and ADLS Gen2.
+struct AzureSource;
+
+#[async_trait::async_trait]
+impl Source for AzureSource {
+    fn matches(&self, file: &str) -> bool {
+        is_azure_location(file)
+    }
+
+    async fn store(&self, file: &str) -> (Arc<dyn ObjectStore>, Path) {
+        let url = Url::parse(file).unwrap();
+        // Account key, SAS token and managed identity are configured from AZURE_* variables
+        let mut azure_builder = MicrosoftAzureBuilder::from_env().with_url(file);
+        let query_pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        if !query_pairs.is_empty() {
+            azure_builder = azure_builder.with_sas_authorization(query_pairs);
+        }
+        if let Some((proxy, proxy_excludes)) = proxy_options() {
+            azure_builder = azure_builder.with_proxy_url(proxy);
+            if let Some(proxy_excludes) = proxy_excludes {
+                azure_builder = azure_builder.with_proxy_excludes(proxy_excludes);
+            }
+        }
+
+        let storage_container = Arc::new(azure_builder.build().unwrap());
+        let path = decode(url.path()).unwrap();
+        let path = match url.scheme() {
+            // The container is the first path segment of blob and dfs endpoint URLs
+            "https" => path
+                .trim_start_matches('/')
+                .split_once('/')
+                .map(|(_, path)| path.to_string())
+                .unwrap_or_default(),
+            _ => path.to_string(),
+        };
+        let location = Path::from(path.as_str());
+
+        (storage_container, location)
+    }
+}
+
+/// Files served over HTTP(S), including presigned S3 URLs.
+struct HttpSource;
+
+#[async_trait::async_trait]
+impl Source for HttpSource {
+    fn matches(&self, file: &str) -> bool {
+        file.starts_with("http")
+    }
+
+    async fn store(&self, file: &str) -> (Arc<dyn ObjectStore>, Path) {
+        let url = Url::parse(file).unwrap();
+
+        let presigned = is_presigned_url(&url);
+
+        let mut client_options = ClientOptions::new().with_allow_http(file.starts_with("http://"));
+        // A presigned URL is authorized by its query, and S3 rejects requests with both
+        if let Some(http_auth) = HTTP_ARGS
+            .get_or_init(Default::default)
+            .http_auth
+            .as_ref()
+            .filter(|_| !presigned)
+        {
+            let mut headers = HeaderMap::new();
+            headers.insert(AUTHORIZATION, http_authorization(http_auth));
+            client_options = client_options.with_default_headers(headers);
+        }
+        if let Some((proxy, proxy_excludes)) = proxy_options() {
+            client_options = client_options.with_proxy_url(proxy);
+            if let Some(proxy_excludes) = proxy_excludes {
+                client_options = client_options.with_proxy_excludes(proxy_excludes);
+            }
+        }
+        let http_store = HttpBuilder::new()
+            .with_url(url)
+            .with_client_options(client_options)
+            .build()
+            .unwrap();
+        let storage_container: Arc<dyn ObjectStore> = if presigned {
+            Arc::new(PresignedUrlStore::new(http_store))
+        } else {
+            Arc::new(http_store)
+        };
+        let storage_container = Arc::new(FullDownloadStore::new(storage_container));
+        let location = Path::from("");
+
+        (storage_container, location)
+    }
+}
+
+/// Local files, matching any location not matched by other sources.
+struct LocalSource;
+
+#[async_trait::async_trait]
+impl Source for LocalSource {
+    fn matches(&self, _file: &str) -> bool {
+        true
+    }
+
+    async fn store(&self, file: &str) -> (Arc<dyn ObjectStore>, Path) {
+        let storage_container = Arc::new(LocalFileSystem::new());
+        let file_path_buf = std::fs::canonicalize(file).unwrap();
+        let file_path = file_path_buf.to_str().unwrap();
+        let location = Path::from(file_path);
+
+        (storage_container, location)
+    }
+}
+
+/// Returns an HTTP client for requests of sinks, authorized with --output-http-auth and sent
+/// through --proxy if given. The --http-auth of sources is never sent to outputs.
+pub fn http_client() -> Client {
+    let mut builder = Client::builder();
+    if let Some(http_auth) = &HTTP_ARGS.get_or_init(Default::default).output_http_auth {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, http_authorization(http_auth));
+        builder = builder.default_headers(headers);
+    }
+    if let Some((proxy, proxy_excludes)) = proxy_options() {
+        let proxy = Proxy::all(proxy)
+            .unwrap()
+            .no_proxy(proxy_excludes.as_deref().and_then(NoProxy::from_string));
+        builder = builder.proxy(proxy);
+    }
+    builder.build().unwrap()
+}
+
+/// Returns the `--proxy` URL and the hosts to bypass it for. The HTTP_PROXY, HTTPS_PROXY and
+/// NO_PROXY variables are otherwise read by the HTTP client, but an explicit proxy replaces them.
+fn proxy_options() -> Option<(&'static str, Option<String>)> {
+    let proxy = HTTP_ARGS.get_or_init(Default::default).proxy.as_deref()?;
+    let proxy_excludes = std::env::var("NO_PROXY")
+        .or_else(|_| std::env::var("no_proxy"))
+        .ok();
+    Some((proxy, proxy_excludes))
+}
+
+/// Returns the Authorization header of a `bearer:<token>` or `basic:<user>:<password>` argument.
+fn http_authorization(http_auth: &str) -> HeaderValue {
+    let authorization = match http_auth.split_once(':') {
+        Some(("bearer", token)) => format!("Bearer {}", token),
+        Some(("basic", credentials)) if credentials.contains(':') => {
+            format!("Basic {}", BASE64_STANDARD.encode(credentials))
+        }
+        _ => {
+            panic!("Invalid HTTP authorization, expected bearer:<TOKEN> or basic:<USER>:<PASSWORD>")
+        }
+    };
+    let mut value = HeaderValue::from_str(&authorization)
+        .unwrap_or_else(|_| panic!("Invalid HTTP authorization, with non-ASCII characters"));
+    value.set_sensitive(true);
+    value
+}
+
+fn is_azure_location(file: &str) -> bool {
+    match Url::parse(file) {
+        Ok(url) => match url.scheme() {
+            "az" | "azure" | "adl" | "abfs" | "abfss" => true,
+            "https" => url.host_str().is_some_and(|host| {
+                host.ends_with(".blob.core.windows.net") || host.ends_with(".dfs.core.windows.net")
+            }),
+            _ => false,
+        },
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,195 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::temp::{TempDir, TempFile};
+
+#[derive(ValueEnum, Clone, Copy, Default)]
+pub enum SpillCompression {
+    #[default]
+    None,
+    Gzip,
+}
+
+/// Settings of operators that spill to disk beyond a memory budget.
+#[derive(Args, Clone)]
+pub struct SpillArgs {
+    /// Memory in megabytes held before spilling to disk
+    #[clap(default_value_t = 1024, long)]
+    pub memory_budget: usize,
+
+    /// Directory of spill files [default: --temp-dir]
+    #[clap(long)]
+    pub spill_dir: Option<PathBuf>,
+
+    /// Compression of spill files
+    #[clap(default_value = "none", long)]
+    pub spill_compression: SpillCompression,
+}
+
+impl SpillArgs {
+    pub fn budget(&self) -> usize {
+        self.memory_budget.max(1) * 1024 * 1024
+    }
+}
+
+enum SpillWriter {
+    Plain(BufWriter<TempFile>),
+    Gzip(GzEncoder<BufWriter<TempFile>>),
+}
+
+impl SpillWriter {
+    fn write<T: Serialize>(&mut self, record: &T) {
+        let writer: &mut dyn Write = match self {
+            SpillWriter::Plain(writer) => writer,
+            SpillWriter::Gzip(writer) => writer,
+        };
+        serde_json::to_writer(&mut *writer, record).unwrap();
+        writer.write_all(b"\n").unwrap();
+    }
+
+    fn finish(self) {
+        match self {
+            SpillWriter::Plain(mut writer) => writer.flush().unwrap(),
+            SpillWriter::Gzip(writer) => writer.finish().unwrap().flush().unwrap(),
+        }
+    }
+}
+
+/// Directory of spill files as JSON lines, removed with its files when dropped.
+struct SpillDir {
+    dir: TempDir,
+    compression: SpillCompression,
+}
+
+impl SpillDir {
+    fn new(args: &SpillArgs) -> Self {
+        SpillDir {
+            dir: TempDir::new("spill", args.spill_dir.as_deref()),
+            compression: args.spill_compression,
+        }
+    }
+
+    fn create(&self, index: usize) -> SpillWriter {
+        let file = BufWriter::new(self.dir.create(&index.to_string()));
+        match self.compression {
+            SpillCompression::None => SpillWriter::Plain(file),
+            SpillCompression::Gzip => SpillWriter::Gzip(GzEncoder::new(file, Compression::fast())),
+        }
+    }
+
+    fn open<T: DeserializeOwned>(&self, index: usize) -> impl Iterator<Item = T> {
+        let file = File::open(self.dir.path().join(index.to_string())).unwrap();
+        let reader: Box<dyn Read> = match self.compression {
+            SpillCompression::None => Box::new(file),
+            SpillCompression::Gzip => Box::new(GzDecoder::new(file)),
+        };
+        BufReader::new(reader)
+            .lines()
+            .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+    }
+}
+
+/// Records hash partitioned into spill files, each small enough to be processed within the
+/// memory budget.
+pub struct SpillPartitions {
+    spill_dir: SpillDir,
+    writers: Vec<SpillWriter>,
+}
+
+impl SpillPartitions {
+    /// Creates enough partitions for records taking about the given memory once read.
+    pub fn new(args: &SpillArgs, bytes: usize) -> Self {
+        let spill_dir = SpillDir::new(args);
+        let count = bytes.div_ceil(args.budget()).max(1);
+        let writers = (0..count).map(|index| spill_dir.create(index)).collect();
+        SpillPartitions { spill_dir, writers }
+    }
+
+    pub fn write<T: Serialize>(&mut self, hash: u64, record: &T) {
+        let index = (hash % self.writers.len() as u64) as usize;
+        self.writers[index].write(record);
+    }
+
+    /// Returns the records of each partition in turn, once all are written.
+    pub fn partitions<T: DeserializeOwned + 'static>(
+        &mut self,
+    ) -> impl Iterator<Item = Vec<T>> + '_ {
+        self.streams().map(Iterator::collect)
+    }
+
+    /// Returns the records of each partition in turn as they are read, for partitions too large
+    /// to be held in memory.
+    pub fn streams<T: DeserializeOwned + 'static>(
+        &mut self,
+    ) -> impl Iterator<Item = impl Iterator<Item = T>> + '_ {
+        let count = self.writers.len();
+        for writer in self.writers.drain(..) {
+            writer.finish();
+        }
+        (0..count).map(|index| self.spill_dir.open(index))
+    }
+}
+
+/// Sorted runs of records spilled to disk, merged back into a single sorted stream.
+pub struct SpillRuns {
+    spill_dir: SpillDir,
+    count: usize,
+}
+
+impl SpillRuns {
+    pub fn new(args: &SpillArgs) -> Self {
+        SpillRuns {
+            spill_dir: SpillDir::new(args),
+            count: 0,
+        }
+    }
+
+    /// Writes a run of records, sorted by the key later merged on.
+    pub fn write_run<T: Serialize>(&mut self, records: &[T]) {
+        let mut writer = self.spill_dir.create(self.count);
+        for record in records {
+            writer.write(record);
+        }
+        writer.finish();
+        self.count += 1;
+    }
+
+    /// Returns the records of all runs in the order of their keys.
+    pub fn merge<T: DeserializeOwned, K: Ord>(
+        self,
+        key: impl Fn(&T) -> K,
+    ) -> impl Iterator<Item = T> {
+        let mut runs: Vec<_> = (0..self.count)
+            .map(|index| self.spill_dir.open::<T>(index))
+            .collect();
+        let mut heads: Vec<Option<T>> = runs.iter_mut().map(Iterator::next).collect();
+        let mut heap: BinaryHeap<Reverse<(K, usize)>> = heads
+            .iter()
+            .enumerate()
+            .filter_map(|(index, head)| Some(Reverse((key(head.as_ref()?), index))))
+            .collect();
+
+        // The spill directory is kept until the runs are read
+        let spill_dir = self.spill_dir;
+        std::iter::from_fn(move || {
+            let _ = &spill_dir;
+            let Reverse((_, index)) = heap.pop()?;
+            let record = heads[index].take();
+            heads[index] = runs[index].next();
+            if let Some(head) = &heads[index] {
+                heap.push(Reverse((key(head), index)));
+            }
+            record
+        })
+    }
+}
@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_cast::cast;
+use arrow_schema::{DataType, Field, FieldRef, Schema, TimeUnit};
+
+/// Date and time parts output for a timestamp column, from a `column=date,time` argument.
+pub struct TimestampSplit {
+    column: String,
+    date: bool,
+    time: bool,
+}
+
+impl TimestampSplit {
+    pub fn parse(argument: &str) -> Self {
+        let (column, parts) = argument.split_once('=').unwrap_or_else(|| {
+            panic!(
+                "Invalid timestamp split, expected column=date,time ({})",
+                argument
+            )
+        });
+        let (mut date, mut time) = (false, false);
+        for part in parts.split(',') {
+            match part.trim() {
+                "date" => date = true,
+                "time" => time = true,
+                _ => panic!("Invalid timestamp part, expected date or time ({})", part),
+            }
+        }
+        TimestampSplit {
+            column: column.trim().to_string(),
+            date,
+            time,
+        }
+    }
+
+    /// Returns the <column>_date and <column>_time fields of the column, in the time zone of its
+    /// timestamps if they have one.
+    fn parts(&self, field: &Field, column: &ArrayRef) -> Vec<(FieldRef, ArrayRef)> {
+        let unit = match field.data_type() {
+            DataType::Timestamp(TimeUnit::Second | TimeUnit::Millisecond, _) => {
+                TimeUnit::Microsecond
+            }
+            DataType::Timestamp(unit, _) => *unit,
+            _ => panic!("Column is not a timestamp ({})", self.column),
+        };
+        let mut parts = vec![];
+        if self.date {
+            parts.push((format!("{}_date", self.column), DataType::Date32));
+        }
+        if self.time {
+            parts.push((format!("{}_time", self.column), DataType::Time64(unit)));
+        }
+        parts
+            .into_iter()
+            .map(|(name, data_type)| {
+                let array = cast(column, &data_type).unwrap();
+                let field = Field::new(name, data_type, field.is_nullable());
+                (Arc::new(field), array)
+            })
+            .collect()
+    }
+}
+
+/// Adds the date and time fields of split timestamp columns after each column, or in its place
+/// if the original is dropped.
+pub fn split_timestamps(
+    batch: &RecordBatch,
+    splits: &[TimestampSplit],
+    drop_original: bool,
+) -> RecordBatch {
+    let schema = batch.schema();
+    for split in splits {
+        if schema.field_with_name(&split.column).is_err() {
+            panic!("Column not found ({})", split.column);
+        }
+    }
+
+    let mut fields: Vec<FieldRef> = vec![];
+    let mut columns: Vec<ArrayRef> = vec![];
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        let split = splits.iter().find(|split| &split.column == field.name());
+        if split.is_none() || !drop_original {
+            fields.push(field.clone());
+            columns.push(column.clone());
+        }
+        if let Some(split) = split {
+            for (field, column) in split.parts(field, column) {
+                fields.push(field);
+                columns.push(column);
+            }
+        }
+    }
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap()
+}
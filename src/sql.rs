@@ -0,0 +1,291 @@
+use std::io::Write;
+
+use arrow_array::{Array, ArrayRef, RecordBatch};
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_schema::DataType;
+use clap::ValueEnum;
+
+use crate::encoder::{Encoder, EncoderKind, FormatOpts, Serialize};
+use crate::jsonl::EncodedValues;
+use crate::{CatArgs, OutputFormat};
+
+/// Database the literals of sql output are written for
+#[derive(ValueEnum, Clone, Copy, Default)]
+pub enum SqlDialect {
+    /// Binary as X'...' blobs, infinite floats as 9e999, and NaN as NULL
+    #[default]
+    Sqlite,
+    /// Binary as '\x...' bytea, and infinite floats and NaN as 'Infinity' and 'NaN'
+    Postgres,
+}
+
+/// Returns an SQL string literal, with single quotes doubled.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Returns a quoted SQL identifier, with double quotes doubled.
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Returns a float literal, with infinities and NaN, which SQL has no literals of, written as
+/// the dialect reads them.
+fn float(value: String, dialect: SqlDialect) -> String {
+    let float: f64 = value.parse().unwrap_or_default();
+    let sign = if float < 0.0 { "-" } else { "" };
+    match dialect {
+        _ if float.is_finite() => value,
+        SqlDialect::Sqlite if float.is_nan() => "NULL".to_string(),
+        SqlDialect::Sqlite => format!("{}9e999", sign),
+        SqlDialect::Postgres if float.is_nan() => "'NaN'".to_string(),
+        SqlDialect::Postgres => format!("'{}Infinity'", sign),
+    }
+}
+
+/// How the values of a column are written as SQL literals.
+enum Literal<'a> {
+    Boolean(ArrayFormatter<'a>),
+    Number(ArrayFormatter<'a>),
+    Float(ArrayFormatter<'a>),
+    Binary(ArrayFormatter<'a>),
+    Json(EncodedValues),
+    Text(ArrayFormatter<'a>),
+}
+
+impl<'a> Literal<'a> {
+    fn new(name: &str, array: &'a ArrayRef, options: &'a FormatOptions) -> Self {
+        let data_type = match array.data_type() {
+            DataType::Dictionary(_, value_type) => value_type.as_ref(),
+            data_type => data_type,
+        };
+        let formatter = || ArrayFormatter::try_new(array.as_ref(), options).unwrap();
+        match data_type {
+            DataType::Boolean => Literal::Boolean(formatter()),
+            DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Decimal128(_, _)
+            | DataType::Decimal256(_, _) => Literal::Number(formatter()),
+            DataType::Float16 | DataType::Float32 | DataType::Float64 => {
+                Literal::Float(formatter())
+            }
+            // Binary values are displayed as hex
+            DataType::Binary | DataType::LargeBinary | DataType::FixedSizeBinary(_) => {
+                Literal::Binary(formatter())
+            }
+            DataType::Struct(_)
+            | DataType::List(_)
+            | DataType::LargeList(_)
+            | DataType::FixedSizeList(_, _)
+            | DataType::Map(_, _) => Literal::Json(EncodedValues::new(name, array, false)),
+            _ => Literal::Text(formatter()),
+        }
+    }
+
+    fn value(&self, row: usize, dialect: SqlDialect) -> String {
+        match self {
+            Literal::Boolean(formatter) => formatter.value(row).to_string().to_uppercase(),
+            Literal::Number(formatter) => formatter.value(row).to_string(),
+            Literal::Float(formatter) => float(formatter.value(row).to_string(), dialect),
+            Literal::Binary(formatter) => match dialect {
+                SqlDialect::Sqlite => format!("X'{}'", formatter.value(row)),
+                SqlDialect::Postgres => format!("'\\x{}'", formatter.value(row)),
+            },
+            Literal::Json(encoded) => quote(&String::from_utf8_lossy(
+                encoded.value(row).unwrap_or(b"null"),
+            )),
+            Literal::Text(formatter) => quote(&formatter.value(row).to_string()),
+        }
+    }
+
+    /// Returns the value as Postgres reads it from the text format of COPY, before escaping.
+    fn copy_value(&self, row: usize) -> String {
+        match self {
+            Literal::Boolean(formatter) => match formatter.value(row).to_string().as_str() {
+                "true" => "t".to_string(),
+                _ => "f".to_string(),
+            },
+            Literal::Number(formatter) => formatter.value(row).to_string(),
+            Literal::Float(formatter) => {
+                let value = formatter.value(row).to_string();
+                let float: f64 = value.parse().unwrap_or_default();
+                if float.is_finite() {
+                    value
+                } else if float.is_nan() {
+                    "NaN".to_string()
+                } else if float < 0.0 {
+                    "-Infinity".to_string()
+                } else {
+                    "Infinity".to_string()
+                }
+            }
+            Literal::Binary(formatter) => format!("\\x{}", formatter.value(row)),
+            Literal::Json(encoded) => {
+                String::from_utf8_lossy(encoded.value(row).unwrap_or(b"null")).into_owned()
+            }
+            Literal::Text(formatter) => formatter.value(row).to_string(),
+        }
+    }
+}
+
+/// Writes rows as INSERT statements into a table, one per row. Strings, temporal values and
+/// values of nested types, as JSON, are quoted, and binary values are written as hex literals.
+#[derive(Clone)]
+pub struct SqlWriter {
+    pub table: String,
+    pub dialect: SqlDialect,
+}
+
+impl SqlWriter {
+    pub fn write_batch(&self, out: &mut Vec<u8>, batch: &RecordBatch) {
+        let schema = batch.schema();
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|field| quote_identifier(field.name()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let options = FormatOptions::default();
+        let literals: Vec<Literal> = schema
+            .fields()
+            .iter()
+            .zip(batch.columns())
+            .map(|(field, column)| Literal::new(field.name(), column, &options))
+            .collect();
+        for row in 0..batch.num_rows() {
+            let values = literals
+                .iter()
+                .zip(batch.columns())
+                .map(|(literal, column)| match column.is_null(row) {
+                    true => "NULL".to_string(),
+                    false => literal.value(row, self.dialect),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(
+                out,
+                "INSERT INTO {} ({}) VALUES ({});",
+                self.table, columns, values
+            )
+            .unwrap();
+        }
+    }
+}
+
+/// Writes rows in the text format of Postgres COPY, to be loaded with `COPY table FROM STDIN`:
+/// a line per row of tab-separated values, with nulls as \N, booleans as t and f, binary values
+/// as bytea hex and values of nested types as JSON. Backslashes, tabs and line breaks within
+/// values are escaped as \\, \t, \n and \r.
+pub struct CopyWriter;
+
+impl CopyWriter {
+    pub fn write_batch(&self, out: &mut Vec<u8>, batch: &RecordBatch) {
+        let schema = batch.schema();
+        let options = FormatOptions::default();
+        let literals: Vec<Literal> = schema
+            .fields()
+            .iter()
+            .zip(batch.columns())
+            .map(|(field, column)| Literal::new(field.name(), column, &options))
+            .collect();
+        for row in 0..batch.num_rows() {
+            for (index, (literal, column)) in literals.iter().zip(batch.columns()).enumerate() {
+                if index > 0 {
+                    out.push(b'\t');
+                }
+                if column.is_null(row) {
+                    out.extend_from_slice(b"\\N");
+                    continue;
+                }
+                for byte in literal.copy_value(row).bytes() {
+                    match byte {
+                        b'\\' => out.extend_from_slice(b"\\\\"),
+                        b'\t' => out.extend_from_slice(b"\\t"),
+                        b'\n' => out.extend_from_slice(b"\\n"),
+                        b'\r' => out.extend_from_slice(b"\\r"),
+                        byte => out.push(byte),
+                    }
+                }
+            }
+            out.push(b'\n');
+        }
+    }
+}
+
+/// SQL INSERT statements, taking table and dialect.
+pub struct SqlEncoderKind;
+
+impl EncoderKind for SqlEncoderKind {
+    fn format(&self) -> OutputFormat {
+        OutputFormat::Sql
+    }
+
+    fn content_type(&self) -> &'static str {
+        "application/sql"
+    }
+
+    fn open(&self, args: &CatArgs, options: &mut FormatOpts) -> Box<dyn Encoder> {
+        let table = options
+            .string("table")
+            .or(args.table.clone())
+            .unwrap_or_else(|| panic!("A --table is needed for the sql format"));
+        Box::new(SqlWriter {
+            table,
+            dialect: options.value_enum("dialect").unwrap_or_default(),
+        })
+    }
+}
+
+// Binary and decimal values are written as literals of their own rather than as JSON strings
+impl Encoder for SqlWriter {
+    fn encode(&mut self, batch: RecordBatch) -> Serialize {
+        let sql_writer = self.clone();
+        Box::new(move || {
+            let mut lines = vec![];
+            sql_writer.write_batch(&mut lines, &batch);
+            lines
+        })
+    }
+
+    fn json_types(&self) -> bool {
+        false
+    }
+}
+
+/// Text format of Postgres COPY.
+pub struct CopyEncoderKind;
+
+impl EncoderKind for CopyEncoderKind {
+    fn format(&self) -> OutputFormat {
+        OutputFormat::PgCopy
+    }
+
+    fn content_type(&self) -> &'static str {
+        "text/plain"
+    }
+
+    fn open(&self, _args: &CatArgs, _options: &mut FormatOpts) -> Box<dyn Encoder> {
+        Box::new(CopyWriter)
+    }
+}
+
+// Binary, decimal and float values are written as Postgres reads them rather than as JSON
+impl Encoder for CopyWriter {
+    fn encode(&mut self, batch: RecordBatch) -> Serialize {
+        Box::new(move || {
+            let mut lines = vec![];
+            CopyWriter.write_batch(&mut lines, &batch);
+            lines
+        })
+    }
+
+    fn json_types(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,240 @@
+use std::fmt::Display;
+use std::io::{IsTerminal, Write};
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+use parquet::basic::{ConvertedType, LogicalType};
+use parquet::schema::types::Type;
+
+use crate::annotations::{describe, Annotations};
+
+/// Rendering style for human-facing output
+#[derive(ValueEnum, Clone, Copy, Default, PartialEq)]
+pub enum Style {
+    #[default]
+    Plain,
+    Unicode,
+    Ascii,
+}
+
+pub struct Theme {
+    pub style: Style,
+    pub color: bool,
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Sets the theme from the selected style and the NO_COLOR/CLICOLOR/CLICOLOR_FORCE conventions,
+/// without color by default for output written to a file rather than stdout.
+pub fn init(style: Style, to_stdout: bool) {
+    let env_set = |name: &str| std::env::var_os(name).is_some_and(|value| !value.is_empty());
+    let color = if env_set("NO_COLOR") {
+        false
+    } else if std::env::var_os("CLICOLOR_FORCE").is_some_and(|value| value != "0") {
+        true
+    } else if std::env::var_os("CLICOLOR").is_some_and(|value| value == "0") {
+        false
+    } else {
+        to_stdout && std::io::stdout().is_terminal() && std::io::stderr().is_terminal()
+    };
+    let _ = THEME.set(Theme { style, color });
+
+    if std::env::var_os("RUST_BACKTRACE").map_or(true, |value| value == "0") {
+        std::panic::set_hook(Box::new(|info| {
+            let payload = info.payload();
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| info.to_string());
+            error(message);
+        }));
+    }
+}
+
+pub fn theme() -> &'static Theme {
+    THEME.get_or_init(|| Theme {
+        style: Style::Plain,
+        color: false,
+    })
+}
+
+fn paint(code: &str, text: impl Display) -> String {
+    if theme().color {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+pub fn bold(text: impl Display) -> String {
+    paint("1", text)
+}
+
+pub fn dim(text: impl Display) -> String {
+    paint("2", text)
+}
+
+pub fn accent(text: impl Display) -> String {
+    paint("36", text)
+}
+
+/// Prints an error message to stderr.
+pub fn error(message: impl Display) {
+    eprintln!("{} {}", paint("1;31", "error:"), message);
+}
+
+/// Prints a warning message to stderr.
+pub fn warning(message: impl Display) {
+    eprintln!("{} {}", paint("1;33", "warning:"), message);
+}
+
+struct TreeGlyphs {
+    branch: &'static str,
+    last: &'static str,
+    pipe: &'static str,
+    space: &'static str,
+}
+
+fn tree_glyphs() -> TreeGlyphs {
+    match theme().style {
+        Style::Ascii => TreeGlyphs {
+            branch: "|-- ",
+            last: "`-- ",
+            pipe: "|   ",
+            space: "    ",
+        },
+        _ => TreeGlyphs {
+            branch: "├── ",
+            last: "└── ",
+            pipe: "│   ",
+            space: "    ",
+        },
+    }
+}
+
+fn describe_type(schema_type: &Type) -> String {
+    let info = schema_type.get_basic_info();
+    let mut description = if schema_type.is_primitive() {
+        schema_type.get_physical_type().to_string()
+    } else {
+        "group".to_string()
+    };
+    let logical_type = match info.logical_type() {
+        Some(LogicalType::Decimal { scale, precision }) => {
+            Some(format!("DECIMAL({},{})", precision, scale))
+        }
+        Some(LogicalType::Integer {
+            bit_width,
+            is_signed,
+        }) => Some(format!("INTEGER({},{})", bit_width, is_signed)),
+        Some(LogicalType::Time {
+            is_adjusted_to_u_t_c,
+            unit,
+        })
+        | Some(LogicalType::Timestamp {
+            is_adjusted_to_u_t_c,
+            unit,
+        }) => Some(format!(
+            "{}({},{})",
+            if matches!(info.logical_type(), Some(LogicalType::Time { .. })) {
+                "TIME"
+            } else {
+                "TIMESTAMP"
+            },
+            format!("{:?}", unit).split('(').next().unwrap(),
+            is_adjusted_to_u_t_c
+        )),
+        Some(logical_type) => Some(format!("{:?}", logical_type).to_uppercase()),
+        None if info.converted_type() != ConvertedType::NONE => {
+            Some(info.converted_type().to_string())
+        }
+        None => None,
+    };
+    if let Some(logical_type) = logical_type {
+        description.push(' ');
+        description.push_str(&logical_type);
+    }
+    description
+}
+
+struct TreeContext<'a> {
+    glyphs: TreeGlyphs,
+    annotations: &'a Annotations,
+}
+
+fn write_tree_node(
+    out: &mut dyn Write,
+    schema_type: &Type,
+    prefix: &str,
+    path: &str,
+    is_last: bool,
+    context: &TreeContext,
+) {
+    let info = schema_type.get_basic_info();
+    let path = if path.is_empty() {
+        info.name().to_string()
+    } else {
+        format!("{}.{}", path, info.name())
+    };
+    let repetition = if info.has_repetition() {
+        format!(" {}", dim(info.repetition()))
+    } else {
+        String::new()
+    };
+    let annotation = match describe(context.annotations, &path) {
+        Some(annotation) => format!("  {}", dim(annotation)),
+        None => String::new(),
+    };
+    writeln!(
+        out,
+        "{}{}{}: {}{}{}",
+        prefix,
+        if is_last {
+            context.glyphs.last
+        } else {
+            context.glyphs.branch
+        },
+        bold(info.name()),
+        accent(describe_type(schema_type)),
+        repetition,
+        annotation
+    )
+    .unwrap();
+
+    if !schema_type.is_primitive() {
+        let child_prefix = format!(
+            "{}{}",
+            prefix,
+            if is_last {
+                context.glyphs.space
+            } else {
+                context.glyphs.pipe
+            }
+        );
+        write_tree_children(out, schema_type, &child_prefix, &path, context);
+    }
+}
+
+fn write_tree_children(
+    out: &mut dyn Write,
+    schema_type: &Type,
+    prefix: &str,
+    path: &str,
+    context: &TreeContext,
+) {
+    let fields = schema_type.get_fields();
+    for (index, field) in fields.iter().enumerate() {
+        write_tree_node(out, field, prefix, path, index == fields.len() - 1, context);
+    }
+}
+
+/// Prints the schema as a tree using the selected style's glyphs.
+pub fn print_schema_tree(out: &mut dyn Write, schema: &Type, annotations: &Annotations) {
+    writeln!(out, "{}", bold(schema.name())).unwrap();
+    let context = TreeContext {
+        glyphs: tree_glyphs(),
+        annotations,
+    };
+    write_tree_children(out, schema, "", "", &context);
+}
@@ -0,0 +1,157 @@
+use arrow_array::{Array, RecordBatch};
+use arrow_schema::{DataType, SchemaRef};
+
+use crate::csv_output::Fields;
+use crate::encoder::{Encoder, EncoderKind, FormatOpts, Serialize};
+use crate::{CatArgs, OutputFormat};
+
+/// Default number of characters values of table output are truncated to.
+const MAX_WIDTH: usize = 40;
+
+/// Returns a value as a single line of at most a number of characters, with control characters
+/// escaped as in tsv output, and an ellipsis where truncated.
+fn cell(value: &str, max_width: usize) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r");
+    if escaped.chars().count() <= max_width {
+        return escaped;
+    }
+    let truncated: String = escaped.chars().take(max_width.saturating_sub(1)).collect();
+    format!("{}…", truncated)
+}
+
+fn is_numeric(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::Dictionary(_, value_type) => value_type.is_numeric(),
+        data_type => data_type.is_numeric(),
+    }
+}
+
+/// Rows gathered to be output as a table of columns as wide as their widest value.
+struct Table {
+    schema: SchemaRef,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn render(&self, out: &mut Vec<u8>) {
+        let names: Vec<&String> = self
+            .schema
+            .fields()
+            .iter()
+            .map(|field| field.name())
+            .collect();
+        let right_aligned: Vec<bool> = (self.schema.fields().iter())
+            .map(|field| is_numeric(field.data_type()))
+            .collect();
+        let widths: Vec<usize> = (0..names.len())
+            .map(|column| {
+                let values = self.rows.iter().map(|row| row[column].chars().count());
+                values.fold(names[column].chars().count(), usize::max)
+            })
+            .collect();
+
+        let line = |left: &str, middle: &str, right: &str| {
+            let bars: Vec<String> = widths.iter().map(|width| "─".repeat(width + 2)).collect();
+            format!("{}{}{}\n", left, bars.join(middle), right)
+        };
+        let row = |values: &[&str], align: bool| {
+            let cells: Vec<String> = (values.iter().zip(&widths).enumerate())
+                .map(|(column, (value, width))| {
+                    let padding = " ".repeat(width - value.chars().count());
+                    match align && right_aligned[column] {
+                        true => format!(" {}{} ", padding, value),
+                        false => format!(" {}{} ", value, padding),
+                    }
+                })
+                .collect();
+            format!("│{}│\n", cells.join("│"))
+        };
+
+        out.extend_from_slice(line("┌", "┬", "┐").as_bytes());
+        let names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+        out.extend_from_slice(row(&names, false).as_bytes());
+        out.extend_from_slice(line("├", "┼", "┤").as_bytes());
+        for values in &self.rows {
+            let values: Vec<&str> = values.iter().map(|value| value.as_str()).collect();
+            out.extend_from_slice(row(&values, true).as_bytes());
+        }
+        out.extend_from_slice(line("└", "┴", "┘").as_bytes());
+    }
+}
+
+/// Gathers rows into a table with aligned columns, written once all rows are read, as it needs
+/// the widest value of each column. Numbers are aligned right, values of nested types are
+/// written as JSON, and nulls as NULL. Rows of batches of another schema start another table.
+pub struct TableEncoder {
+    max_width: usize,
+    rendered: Vec<u8>,
+    table: Option<Table>,
+}
+
+impl TableEncoder {
+    fn gather(&mut self, batch: &RecordBatch) {
+        if self
+            .table
+            .as_ref()
+            .is_some_and(|table| table.schema != batch.schema())
+        {
+            self.table.take().unwrap().render(&mut self.rendered);
+        }
+        let table = self.table.get_or_insert_with(|| Table {
+            schema: batch.schema(),
+            rows: vec![],
+        });
+        let fields = Fields::new(batch);
+        for row in 0..batch.num_rows() {
+            let values = (fields.row(row).into_iter().zip(batch.columns()))
+                .map(|(value, column)| match column.is_null(row) {
+                    true => "NULL".to_string(),
+                    false => cell(&value, self.max_width),
+                })
+                .collect();
+            table.rows.push(values);
+        }
+    }
+}
+
+/// Aligned table for terminals, taking max-width, the number of characters values are
+/// truncated to.
+pub struct TableEncoderKind;
+
+impl EncoderKind for TableEncoderKind {
+    fn format(&self) -> OutputFormat {
+        OutputFormat::Table
+    }
+
+    fn content_type(&self) -> &'static str {
+        "text/plain"
+    }
+
+    fn open(&self, args: &CatArgs, options: &mut FormatOpts) -> Box<dyn Encoder> {
+        Box::new(TableEncoder {
+            max_width: (options.value("max-width").or(args.max_width)).unwrap_or(MAX_WIDTH),
+            rendered: vec![],
+            table: None,
+        })
+    }
+}
+
+// Tables are written once complete, from finish
+impl Encoder for TableEncoder {
+    fn encode(&mut self, batch: RecordBatch) -> Serialize {
+        self.gather(&batch);
+        let rendered = std::mem::take(&mut self.rendered);
+        Box::new(move || rendered)
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        if let Some(table) = self.table.take() {
+            table.render(&mut self.rendered);
+        }
+        std::mem::take(&mut self.rendered)
+    }
+}
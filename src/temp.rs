@@ -0,0 +1,137 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::Args;
+
+/// Settings of temporary files, such as spill files.
+#[derive(Args, Clone, Default)]
+pub struct TempArgs {
+    /// Directory of temporary files such as spill files [default: the system temporary directory]
+    #[clap(long, env = "PARQUET2JSON_TEMP_DIR", global = true)]
+    pub temp_dir: Option<PathBuf>,
+
+    /// Size in megabytes of temporary files written at which parquet2json stops with an error
+    #[clap(long, global = true)]
+    pub temp_size_limit: Option<u64>,
+}
+
+static TEMP_ARGS: OnceLock<TempArgs> = OnceLock::new();
+
+/// Directories of temporary files not yet removed.
+static DIRS: Mutex<Vec<PathBuf>> = Mutex::new(vec![]);
+
+static BYTES_WRITTEN: AtomicU64 = AtomicU64::new(0);
+
+static LIMIT_EXCEEDED: AtomicBool = AtomicBool::new(false);
+
+/// Sets the temporary file settings and removes temporary files when parquet2json stops on an
+/// error or is interrupted, which skips the cleanup of dropped directories.
+pub fn init(args: &TempArgs) {
+    let _ = TEMP_ARGS.set(args.clone());
+
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        cleanup();
+        hook(info);
+    }));
+
+    tokio::spawn(async {
+        tokio::signal::ctrl_c().await.ok();
+        cleanup();
+        std::process::exit(130);
+    });
+    #[cfg(unix)]
+    tokio::spawn(async {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            terminate.recv().await;
+            cleanup();
+            std::process::exit(143);
+        }
+    });
+}
+
+/// Removes all temporary directories.
+pub fn cleanup() {
+    let dirs = std::mem::take(&mut *DIRS.lock().unwrap_or_else(|error| error.into_inner()));
+    for dir in dirs {
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
+
+/// Directory of temporary files, removed with its files when dropped.
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates a directory for temporary files of the given kind under the given directory,
+    /// --temp-dir or the system temporary directory.
+    pub fn new(kind: &str, parent: Option<&Path>) -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .subsec_nanos();
+        let parent = parent
+            .map(Path::to_path_buf)
+            .or_else(|| TEMP_ARGS.get_or_init(Default::default).temp_dir.clone())
+            .unwrap_or_else(std::env::temp_dir);
+        let path = parent.join(format!(
+            "parquet2json-{}-{}-{}",
+            kind,
+            std::process::id(),
+            nanos
+        ));
+        std::fs::create_dir_all(&path)
+            .unwrap_or_else(|error| panic!("Cannot create temporary directory ({})", error));
+        DIRS.lock().unwrap().push(path.clone());
+        TempDir { path }
+    }
+
+    /// Creates a temporary file in the directory.
+    pub fn create(&self, name: &str) -> TempFile {
+        TempFile(File::create(self.path.join(name)).unwrap())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.path).ok();
+        DIRS.lock().unwrap().retain(|path| *path != self.path);
+    }
+}
+
+/// Temporary file counting the bytes written to it towards --temp-size-limit.
+pub struct TempFile(File);
+
+impl Write for TempFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if let Some(limit) = TEMP_ARGS.get_or_init(Default::default).temp_size_limit {
+            let total = BYTES_WRITTEN.fetch_add(buf.len() as u64, Ordering::Relaxed);
+            if total + buf.len() as u64 > limit * 1024 * 1024 {
+                // Writes flushing buffers while unwinding fail without panicking again
+                if LIMIT_EXCEEDED.swap(true, Ordering::Relaxed) {
+                    return Err(std::io::Error::other("temporary file size limit exceeded"));
+                }
+                panic!(
+                    "Temporary files exceed {} MB, raise --temp-size-limit or --memory-budget",
+                    limit
+                );
+            }
+        }
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
@@ -0,0 +1,187 @@
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore, PutMultipartOpts,
+    PutOptions, PutPayload, PutResult, Result,
+};
+
+use crate::HTTP_ARGS;
+
+/// Returns the timeout of --metadata-timeout, bounding object metadata requests, listings and
+/// footer reads.
+pub fn metadata_timeout() -> Option<Duration> {
+    HTTP_ARGS
+        .get_or_init(Default::default)
+        .metadata_timeout
+        .map(Duration::from_secs)
+}
+
+/// Returns the timeout of --data-timeout, bounding each request reading data.
+fn data_timeout() -> Option<Duration> {
+    HTTP_ARGS
+        .get_or_init(Default::default)
+        .data_timeout
+        .map(Duration::from_secs)
+}
+
+/// Returns the error of a request taking longer than the timeout of --<PHASE>-timeout.
+pub fn timed_out(phase: &str, timeout: Duration, location: &Path) -> object_store::Error {
+    object_store::Error::Generic {
+        store: "Timeout",
+        source: format!(
+            "Timed out after {}s reading {} of {} (raise --{}-timeout)",
+            timeout.as_secs(),
+            phase,
+            location,
+            phase
+        )
+        .into(),
+    }
+}
+
+/// Awaits an object store request, failing once it takes longer than the timeout, if any.
+async fn within<T>(
+    phase: &str,
+    timeout: Option<Duration>,
+    location: &Path,
+    request: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, request)
+            .await
+            .unwrap_or_else(|_| Err(timed_out(phase, timeout, location))),
+        None => request.await,
+    }
+}
+
+/// Object store failing requests that hang, with --metadata-timeout for heads and listings, and
+/// --data-timeout for reads. Footers are read as data, and bounded as a whole as metadata by the
+/// reader of the footer.
+#[derive(Debug)]
+pub struct TimeoutStore {
+    inner: Arc<dyn ObjectStore>,
+    metadata_timeout: Option<Duration>,
+    data_timeout: Option<Duration>,
+}
+
+impl TimeoutStore {
+    pub fn new(inner: Arc<dyn ObjectStore>) -> Self {
+        TimeoutStore {
+            inner,
+            metadata_timeout: metadata_timeout(),
+            data_timeout: data_timeout(),
+        }
+    }
+}
+
+impl Display for TimeoutStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Timeout({})", self.inner)
+    }
+}
+
+#[async_trait::async_trait]
+impl ObjectStore for TimeoutStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let (phase, timeout) = if options.head {
+            ("metadata", self.metadata_timeout)
+        } else {
+            ("data", self.data_timeout)
+        };
+        within(
+            phase,
+            timeout,
+            location,
+            self.inner.get_opts(location, options),
+        )
+        .await
+    }
+
+    // Bounds the body along with the response, as get_opts cannot
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        within(
+            "data",
+            self.data_timeout,
+            location,
+            self.inner.get_range(location, range),
+        )
+        .await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        within(
+            "metadata",
+            self.metadata_timeout,
+            location,
+            self.inner.head(location),
+        )
+        .await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inner.delete(location).await
+    }
+
+    // Each page of the listing is bounded rather than the whole, which may be long
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        let Some(timeout) = self.metadata_timeout else {
+            return self.inner.list(prefix);
+        };
+        let location = prefix.cloned().unwrap_or_default();
+        stream::unfold(Some(self.inner.list(prefix)), move |list| {
+            let location = location.clone();
+            async move {
+                let mut list = list?;
+                match tokio::time::timeout(timeout, list.next()).await {
+                    Ok(meta) => meta.map(|meta| (meta, Some(list))),
+                    Err(_) => Some((Err(timed_out("metadata", timeout, &location)), None)),
+                }
+            }
+        })
+        .boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let location = prefix.cloned().unwrap_or_default();
+        within(
+            "metadata",
+            self.metadata_timeout,
+            &location,
+            self.inner.list_with_delimiter(prefix),
+        )
+        .await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+}
@@ -0,0 +1,71 @@
+use std::ops::Range;
+use std::time::Duration;
+
+use object_store::GetRange;
+use reqwest::{Client, Method, Request, Response, StatusCode};
+
+/// Attempts of a request sent with `send_with_retries`, the first included.
+const ATTEMPTS: u32 = 4;
+
+/// Delay before the first retry, doubled before each further one.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Returns the byte range a requested range refers to in an object of the given size, the
+/// whole object without one. Ranges past the end are cut short at the end.
+pub fn resolve_range(range: Option<&GetRange>, size: usize) -> Range<usize> {
+    match range {
+        Some(GetRange::Bounded(range)) => range.start.min(size)..range.end.min(size),
+        Some(GetRange::Offset(offset)) => (*offset).min(size)..size,
+        Some(GetRange::Suffix(length)) => size.saturating_sub(*length)..size,
+        None => 0..size,
+    }
+}
+
+/// Parses a Content-Range header of a partial response, `bytes <start>-<end>/<size>`, into the
+/// byte range of the response and the size of the object.
+pub fn parse_content_range(value: &str) -> Option<(Range<usize>, usize)> {
+    let (range, size) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    let (start, end): (usize, usize) = (start.parse().ok()?, end.parse().ok()?);
+    if end < start {
+        return None;
+    }
+    Some((start..end + 1, size.parse().ok()?))
+}
+
+/// Returns whether a failed request may succeed when sent again. Requests that may have been
+/// processed, such as those of output, are only sent again when they could not have been.
+fn is_transient(method: &Method, result: &reqwest::Result<Response>) -> bool {
+    let idempotent = matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE
+    );
+    match result {
+        Ok(response) => match response.status() {
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => true,
+            status => idempotent && status.is_server_error(),
+        },
+        Err(error) => error.is_connect() || (idempotent && error.is_timeout()),
+    }
+}
+
+/// Sends a request, sending it again with exponential backoff after transient failures, such as
+/// refused connections and throttling. Requests with streaming bodies are sent once.
+pub async fn send_with_retries(client: &Client, request: Request) -> reqwest::Result<Response> {
+    let mut backoff = INITIAL_BACKOFF;
+    let mut request = request;
+    for _ in 1..ATTEMPTS {
+        let Some(retry) = request.try_clone() else {
+            break;
+        };
+        let method = request.method().clone();
+        let result = client.execute(request).await;
+        if !is_transient(&method, &result) {
+            return result;
+        }
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+        request = retry;
+    }
+    client.execute(request).await
+}
@@ -0,0 +1,274 @@
+use std::cmp::Ordering;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+use arrow_cast::base64::{Engine, BASE64_STANDARD};
+use flate2::read::GzDecoder;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Path of the latest release under the GitHub API, which is api.github.com unless GITHUB_API_URL
+/// is set, as for a GitHub Enterprise mirror.
+const LATEST_RELEASE_PATH: &str = "/repos/jupiter/parquet2json/releases/latest";
+
+/// Minisign public key that release archives are signed with, as the base64 line of its
+/// minisign.pub file. It is built into release binaries from PARQUET2JSON_RELEASE_PUBLIC_KEY, and
+/// builds without it do not update themselves.
+const RELEASE_PUBLIC_KEY: Option<&str> = option_env!("PARQUET2JSON_RELEASE_PUBLIC_KEY");
+
+/// Part of the pre-release of a version, compared as semantic versioning does: numbers by value
+/// and before text.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum Identifier {
+    Numeric(u64),
+    Alphanumeric(String),
+}
+
+/// Semantic version of a release, such as v4.1.0 or v4.1.0-rc.1, leaving out build metadata.
+#[derive(PartialEq, Eq)]
+struct Version {
+    core: [u64; 3],
+    pre_release: Vec<Identifier>,
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // A pre-release comes before the release of the same version
+        self.core.cmp(&other.core).then_with(|| {
+            match (self.pre_release.is_empty(), other.pre_release.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre_release.cmp(&other.pre_release),
+            }
+        })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn parse_version(version: &str) -> Version {
+    let parse = || {
+        let version = version.strip_prefix('v').unwrap_or(version);
+        let version = version
+            .split_once('+')
+            .map_or(version, |(version, _)| version);
+        let (core, pre_release) = match version.split_once('-') {
+            Some((core, pre_release)) => (core, Some(pre_release)),
+            None => (version, None),
+        };
+        let core: Vec<u64> = core
+            .split('.')
+            .map(|part| part.parse().ok())
+            .collect::<Option<_>>()?;
+        let pre_release = pre_release.map_or(Some(vec![]), |pre_release| {
+            pre_release
+                .split('.')
+                .map(|part| match part.parse() {
+                    Ok(number) => Some(Identifier::Numeric(number)),
+                    Err(_) if !part.is_empty() => Some(Identifier::Alphanumeric(part.to_string())),
+                    Err(_) => None,
+                })
+                .collect()
+        })?;
+        Some(Version {
+            core: core.try_into().ok()?,
+            pre_release,
+        })
+    };
+    parse().unwrap_or_else(|| panic!("Invalid release version ({})", version))
+}
+
+fn binary_name() -> &'static str {
+    if cfg!(windows) {
+        "parquet2json.exe"
+    } else {
+        "parquet2json"
+    }
+}
+
+fn asset_name() -> String {
+    let extension = if cfg!(windows) { "zip" } else { "tar.gz" };
+    format!("parquet2json-{}.{}", env!("TARGET"), extension)
+}
+
+async fn download(client: &reqwest::Client, url: &str) -> Vec<u8> {
+    client
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .unwrap_or_else(|e| panic!("Download failed ({}): {}", url, e))
+        .bytes()
+        .await
+        .unwrap()
+        .to_vec()
+}
+
+fn extract_binary(archive: &[u8]) -> Vec<u8> {
+    let mut binary = vec![];
+    if cfg!(windows) {
+        let mut zip = zip::ZipArchive::new(Cursor::new(archive)).unwrap();
+        zip.by_name(binary_name())
+            .unwrap()
+            .read_to_end(&mut binary)
+            .unwrap();
+    } else {
+        let mut tar = tar::Archive::new(GzDecoder::new(archive));
+        let mut entry = tar
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap())
+            .find(|entry| entry.path().unwrap().ends_with(binary_name()))
+            .unwrap_or_else(|| panic!("Binary not found in release archive"));
+        entry.read_to_end(&mut binary).unwrap();
+    }
+    binary
+}
+
+/// Verifies a minisign signature of the archive by the public key. Only legacy signatures, of
+/// the archive itself as `minisign -S -l` makes them, are accepted, and their trusted comment is
+/// verified too.
+fn verify_signature(public_key: &str, archive: &[u8], signature: &str) -> Result<(), &'static str> {
+    let public_key = BASE64_STANDARD
+        .decode(public_key.trim())
+        .map_err(|_| "invalid public key")?;
+    if public_key.len() != 42 || &public_key[..2] != b"Ed" {
+        return Err("invalid public key");
+    }
+    let key_id = &public_key[2..10];
+    let public_key = UnparsedPublicKey::new(&ED25519, &public_key[10..]);
+
+    let mut lines = signature
+        .lines()
+        .skip_while(|line| line.starts_with("untrusted comment:"));
+    let signature = lines
+        .next()
+        .and_then(|line| BASE64_STANDARD.decode(line.trim()).ok())
+        .filter(|signature| signature.len() == 74)
+        .ok_or("invalid signature file")?;
+    match &signature[..2] {
+        b"Ed" => {}
+        b"ED" => return Err("prehashed signature, rather than one made with minisign -l"),
+        _ => return Err("unknown signature algorithm"),
+    }
+    if &signature[2..10] != key_id {
+        return Err("signed with another key");
+    }
+    public_key
+        .verify(archive, &signature[10..])
+        .map_err(|_| "signature does not match")?;
+
+    let trusted_comment = lines
+        .next()
+        .and_then(|line| line.strip_prefix("trusted comment: "))
+        .ok_or("invalid signature file")?;
+    let global_signature = lines
+        .next()
+        .and_then(|line| BASE64_STANDARD.decode(line.trim()).ok())
+        .ok_or("invalid signature file")?;
+    let signed = [&signature[10..], trusted_comment.as_bytes()].concat();
+    public_key
+        .verify(&signed, &global_signature)
+        .map_err(|_| "trusted comment signature does not match")
+}
+
+fn replace_executable(current_exe: &Path, binary: &[u8]) {
+    let new_exe = current_exe.with_extension("new");
+    let old_exe = current_exe.with_extension("old");
+
+    std::fs::write(&new_exe, binary).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&new_exe, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    // Running executables can be renamed but not overwritten on Windows
+    std::fs::rename(current_exe, &old_exe).unwrap();
+    if let Err(e) = std::fs::rename(&new_exe, current_exe) {
+        std::fs::rename(&old_exe, current_exe).unwrap();
+        panic!("Could not replace executable: {}", e);
+    }
+    let _ = std::fs::remove_file(&old_exe);
+}
+
+/// Replaces the current executable with the binary from the latest GitHub release, after
+/// verifying its minisign signature by the public key built into this executable, and the SHA-256
+/// checksum published alongside it.
+pub async fn self_update(check: bool, force: bool) {
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("parquet2json/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .unwrap();
+
+    let api_url = std::env::var("GITHUB_API_URL");
+    let api_url = api_url.as_deref().unwrap_or("https://api.github.com");
+    let releases_url = format!("{}{}", api_url.trim_end_matches('/'), LATEST_RELEASE_PATH);
+    let release: Value = serde_json::from_slice(&download(&client, &releases_url).await).unwrap();
+    let latest_version = release["tag_name"].as_str().unwrap();
+    let current_version = env!("CARGO_PKG_VERSION");
+
+    if !force && parse_version(latest_version) <= parse_version(current_version) {
+        println!("Already up to date ({})", current_version);
+        return;
+    }
+    if check {
+        println!(
+            "Update available ({} -> {})",
+            current_version, latest_version
+        );
+        return;
+    }
+    let Some(public_key) = RELEASE_PUBLIC_KEY else {
+        panic!(
+            "This build has no release signing key to verify updates with, update it as it was installed (e.g. cargo install parquet2json)"
+        );
+    };
+
+    let asset_url = |name: &str| {
+        release["assets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|asset| asset["name"] == name)
+            .and_then(|asset| asset["browser_download_url"].as_str())
+            .map(|url| url.to_string())
+            .unwrap_or_else(|| panic!("Release asset not found ({})", name))
+    };
+    let asset_name = asset_name();
+    let archive = download(&client, &asset_url(&asset_name)).await;
+    let checksum = download(&client, &asset_url(&format!("{}.sha256", asset_name))).await;
+    let signature = download(&client, &asset_url(&format!("{}.minisig", asset_name))).await;
+
+    if let Err(reason) =
+        verify_signature(public_key, &archive, &String::from_utf8_lossy(&signature))
+    {
+        panic!(
+            "Signature verification failed for {} ({})",
+            asset_name, reason
+        );
+    }
+
+    let expected_checksum = String::from_utf8(checksum).unwrap();
+    let expected_checksum = expected_checksum.split_whitespace().next().unwrap_or("");
+    let actual_checksum: String = Sha256::digest(&archive)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    if !actual_checksum.eq_ignore_ascii_case(expected_checksum) {
+        panic!(
+            "Checksum mismatch for {} (expected {}, got {})",
+            asset_name, expected_checksum, actual_checksum
+        );
+    }
+
+    let current_exe = std::env::current_exe().unwrap();
+    replace_executable(&current_exe, &extract_binary(&archive));
+    println!("Updated {} -> {}", current_version, latest_version);
+}
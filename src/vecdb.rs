@@ -0,0 +1,195 @@
+use std::io::Write;
+
+use arrow_array::RecordBatch;
+use arrow_json::writer::LineDelimited;
+use arrow_json::WriterBuilder;
+use arrow_schema::DataType;
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+
+use crate::encoder::{Encoder, EncoderKind, FormatOpts, Serialize};
+use crate::{CatArgs, OutputFormat};
+
+/// Shape of the bulk upsert request bodies
+#[derive(ValueEnum, Clone, Copy, Default)]
+pub enum VectorDb {
+    #[default]
+    Pinecone,
+    Qdrant,
+    Weaviate,
+}
+
+/// Writes rows as vector database bulk upsert request bodies, one JSON line per batch of records.
+pub struct VectorWriter {
+    pub vector_db: VectorDb,
+    pub vector_column: String,
+    pub id_column: Option<String>,
+    pub batch_size: usize,
+    pub explicit_nulls: bool,
+    dims: Option<usize>,
+    rows: usize,
+    records: Vec<Value>,
+}
+
+impl VectorWriter {
+    pub fn new(
+        vector_db: VectorDb,
+        vector_column: String,
+        id_column: Option<String>,
+        batch_size: usize,
+        explicit_nulls: bool,
+    ) -> Self {
+        VectorWriter {
+            vector_db,
+            vector_column,
+            id_column,
+            batch_size,
+            explicit_nulls,
+            dims: None,
+            rows: 0,
+            records: vec![],
+        }
+    }
+
+    fn record(&self, id: Option<Value>, vector: Value, metadata: Map<String, Value>) -> Value {
+        let id = id.unwrap_or_else(|| json!(self.rows));
+        // Pinecone metadata values cannot be null
+        let explicit_nulls = self.explicit_nulls && !matches!(self.vector_db, VectorDb::Pinecone);
+        let metadata: Map<String, Value> = metadata
+            .into_iter()
+            .filter(|(_, value)| explicit_nulls || !value.is_null())
+            .collect();
+        match self.vector_db {
+            VectorDb::Pinecone => {
+                // Pinecone IDs are strings
+                let id = match id {
+                    Value::String(id) => id,
+                    id => id.to_string(),
+                };
+                json!({"id": id, "values": vector, "metadata": metadata})
+            }
+            VectorDb::Qdrant => json!({"id": id, "vector": vector, "payload": metadata}),
+            VectorDb::Weaviate => json!({"id": id, "vector": vector, "properties": metadata}),
+        }
+    }
+
+    fn check_dims(&mut self, vector: &Value) {
+        let values = vector
+            .as_array()
+            .unwrap_or_else(|| panic!("Missing vector (row {})", self.rows));
+        if let Some(position) = values.iter().position(|value| !value.is_number()) {
+            panic!(
+                "Invalid vector element (row {}, position {})",
+                self.rows, position
+            );
+        }
+        let dims = *self.dims.get_or_insert(values.len());
+        if values.len() != dims {
+            panic!(
+                "Vector dimension mismatch (row {}: expected {}, got {})",
+                self.rows,
+                dims,
+                values.len()
+            );
+        }
+    }
+
+    fn flush(&mut self, out: &mut impl Write) {
+        let records = std::mem::take(&mut self.records);
+        let body = match self.vector_db {
+            VectorDb::Pinecone => json!({ "vectors": records }),
+            VectorDb::Qdrant => json!({ "points": records }),
+            VectorDb::Weaviate => json!({ "objects": records }),
+        };
+        writeln!(out, "{}", body).unwrap();
+    }
+
+    pub fn write_batch(&mut self, out: &mut impl Write, batch: &RecordBatch) {
+        let column = batch
+            .column_by_name(&self.vector_column)
+            .unwrap_or_else(|| panic!("Column not selected ({})", self.vector_column));
+        match column.data_type() {
+            DataType::FixedSizeList(_, width) => {
+                self.dims.get_or_insert(*width as usize);
+            }
+            DataType::List(_) | DataType::LargeList(_) => {}
+            data_type => panic!(
+                "Vector column must be a list ({}: {})",
+                self.vector_column, data_type
+            ),
+        }
+
+        let mut writer = WriterBuilder::new()
+            .with_explicit_nulls(true)
+            .build::<_, LineDelimited>(vec![]);
+        writer.write(batch).unwrap();
+        writer.finish().unwrap();
+        let documents = writer.into_inner();
+
+        for document in documents.split(|byte| *byte == b'\n') {
+            if document.is_empty() {
+                continue;
+            }
+            let mut metadata: Map<String, Value> = serde_json::from_slice(document).unwrap();
+            let vector = metadata.remove(&self.vector_column).unwrap_or_default();
+            self.check_dims(&vector);
+            let id = self
+                .id_column
+                .as_ref()
+                .and_then(|id_column| metadata.remove(id_column))
+                .filter(|id| !id.is_null());
+
+            let record = self.record(id, vector, metadata);
+            self.records.push(record);
+            self.rows += 1;
+            if self.records.len() >= self.batch_size {
+                self.flush(out);
+            }
+        }
+    }
+
+    /// Writes the last, possibly partial, batch of records.
+    pub fn finish(&mut self, out: &mut impl Write) {
+        if !self.records.is_empty() {
+            self.flush(out);
+        }
+    }
+}
+
+/// Vector database request bodies, taking vector-db and id-column.
+pub struct VectorEncoderKind;
+
+impl EncoderKind for VectorEncoderKind {
+    fn format(&self) -> OutputFormat {
+        OutputFormat::Vecdb
+    }
+
+    fn content_type(&self) -> &'static str {
+        "application/json"
+    }
+
+    fn open(&self, args: &CatArgs, options: &mut FormatOpts) -> Box<dyn Encoder> {
+        Box::new(VectorWriter::new(
+            options.value_enum("vector-db").unwrap_or(args.vector_db),
+            args.vector_column.clone().unwrap_or_default(),
+            options.string("id-column").or(args.id_column.clone()),
+            args.vecdb_batch_size,
+            args.nulls,
+        ))
+    }
+}
+
+// Request bodies span batches, so are built here in order
+impl Encoder for VectorWriter {
+    fn encode(&mut self, batch: RecordBatch) -> Serialize {
+        let mut lines = vec![];
+        self.write_batch(&mut lines, &batch);
+        Box::new(move || lines)
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        let mut lines = vec![];
+        VectorWriter::finish(self, &mut lines);
+        lines
+    }
+}
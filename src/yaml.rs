@@ -0,0 +1,52 @@
+use arrow_array::RecordBatch;
+use serde_json::Value;
+
+use crate::encoder::{Encoder, EncoderKind, FormatOpts, Serialize};
+use crate::jsonl::JsonLinesWriter;
+use crate::{CatArgs, OutputFormat};
+
+/// Returns the records of JSON lines as YAML documents, one per record, so that values are
+/// those of the jsonl format.
+fn yaml_documents(lines: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(lines.len() * 2);
+    for line in lines.split(|byte| *byte == b'\n') {
+        if line.is_empty() {
+            continue;
+        }
+        let record: Value = serde_json::from_slice(line).unwrap();
+        out.extend_from_slice(b"---\n");
+        serde_yaml::to_writer(&mut out, &record).unwrap();
+    }
+    out
+}
+
+/// YAML documents, taking nulls=true|false for --nulls.
+pub struct YamlEncoderKind;
+
+impl EncoderKind for YamlEncoderKind {
+    fn format(&self) -> OutputFormat {
+        OutputFormat::Yaml
+    }
+
+    fn content_type(&self) -> &'static str {
+        "application/yaml"
+    }
+
+    fn open(&self, args: &CatArgs, options: &mut FormatOpts) -> Box<dyn Encoder> {
+        let explicit_nulls = options.value("nulls").unwrap_or(args.nulls);
+        Box::new(YamlEncoder(JsonLinesWriter::new(explicit_nulls)))
+    }
+}
+
+struct YamlEncoder(JsonLinesWriter);
+
+impl Encoder for YamlEncoder {
+    fn encode(&mut self, batch: RecordBatch) -> Serialize {
+        let encoder = self.0.encoder(batch);
+        Box::new(move || {
+            let mut lines = vec![];
+            encoder.encode(&mut lines);
+            yaml_documents(&lines)
+        })
+    }
+}
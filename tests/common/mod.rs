@@ -0,0 +1,505 @@
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::Arc;
+
+use arrow_array::builder::{
+    Int32Builder, ListBuilder, MapBuilder, StringBuilder, StringDictionaryBuilder,
+};
+use arrow_array::types::Int32Type;
+use arrow_array::{
+    ArrayRef, BinaryArray, BooleanArray, Date32Array, Decimal128Array, Float32Array, Float64Array,
+    Int16Array, Int32Array, Int64Array, Int8Array, RecordBatch, StringArray, StructArray,
+    TimestampMicrosecondArray, TimestampMillisecondArray, UInt32Array, UInt64Array,
+};
+use arrow_schema::{DataType, Field};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, Encoding};
+use parquet::data_type::{ByteArray, ByteArrayType};
+use parquet::file::properties::{WriterProperties, WriterVersion};
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use parquet::schema::types::ColumnPath;
+use wiremock::{Request, Respond, ResponseTemplate};
+
+/// Rows of the fixtures, spanning several pages of each column chunk.
+pub const ROWS: usize = 50;
+
+fn valid(row: i64) -> bool {
+    row % 7 != 3
+}
+
+fn nullable<T>(row: i64, value: T) -> Option<T> {
+    valid(row).then_some(value)
+}
+
+/// Values of every type read back as JSON, with nulls in every column.
+pub fn all_types_batch() -> RecordBatch {
+    let rows = 0..ROWS as i64;
+
+    let mut list = ListBuilder::new(Int32Builder::new());
+    let mut map = MapBuilder::new(None, StringBuilder::new(), Int32Builder::new());
+    let mut dictionary = StringDictionaryBuilder::<Int32Type>::new();
+    for row in rows.clone() {
+        if valid(row) {
+            list.append_value((0..row % 4).map(|item| Some(item as i32 * 10)));
+            for entry in 0..row % 3 {
+                map.keys().append_value(format!("key-{}", entry));
+                map.values().append_value((row * entry) as i32);
+            }
+            map.append(true).unwrap();
+            dictionary.append_value(["red", "green", "blue"][row as usize % 3]);
+        } else {
+            list.append_null();
+            map.append(false).unwrap();
+            dictionary.append_null();
+        }
+    }
+    let structs = StructArray::from(vec![
+        (
+            Arc::new(Field::new("a", DataType::Int32, true)),
+            Arc::new(Int32Array::from_iter(
+                rows.clone().map(|row| row as i32 * 3),
+            )) as ArrayRef,
+        ),
+        (
+            Arc::new(Field::new("b", DataType::Utf8, true)),
+            Arc::new(StringArray::from_iter(
+                rows.clone().map(|row| Some(format!("b{}", row))),
+            )) as ArrayRef,
+        ),
+    ]);
+
+    RecordBatch::try_from_iter(vec![
+        (
+            "bool",
+            Arc::new(BooleanArray::from_iter(
+                rows.clone().map(|row| nullable(row, row % 2 == 0)),
+            )) as ArrayRef,
+        ),
+        (
+            "int8",
+            Arc::new(Int8Array::from_iter(
+                rows.clone().map(|row| nullable(row, (row - 25) as i8)),
+            )),
+        ),
+        (
+            "int16",
+            Arc::new(Int16Array::from_iter(
+                rows.clone().map(|row| nullable(row, (row * 1000) as i16)),
+            )),
+        ),
+        (
+            "int32",
+            Arc::new(Int32Array::from_iter(
+                rows.clone()
+                    .map(|row| nullable(row, (row * 100_000) as i32)),
+            )),
+        ),
+        (
+            "int64",
+            Arc::new(Int64Array::from_iter(
+                rows.clone().map(|row| nullable(row, row * 10_000_000_000)),
+            )),
+        ),
+        (
+            "uint32",
+            Arc::new(UInt32Array::from_iter(
+                rows.clone().map(|row| nullable(row, u32::MAX - row as u32)),
+            )),
+        ),
+        (
+            "uint64",
+            Arc::new(UInt64Array::from_iter(
+                rows.clone().map(|row| nullable(row, u64::MAX - row as u64)),
+            )),
+        ),
+        (
+            "float32",
+            Arc::new(Float32Array::from_iter(
+                rows.clone().map(|row| nullable(row, row as f32 / 4.0)),
+            )),
+        ),
+        (
+            "float64",
+            Arc::new(Float64Array::from_iter(
+                rows.clone().map(|row| nullable(row, row as f64 * -1.5)),
+            )),
+        ),
+        (
+            "utf8",
+            Arc::new(StringArray::from_iter(
+                rows.clone()
+                    .map(|row| nullable(row, format!("row \"{}\"\té", row))),
+            )),
+        ),
+        (
+            "binary",
+            Arc::new(BinaryArray::from_iter(
+                rows.clone()
+                    .map(|row| nullable(row, vec![row as u8, 0, 255])),
+            )),
+        ),
+        (
+            "date32",
+            Arc::new(Date32Array::from_iter(
+                rows.clone().map(|row| nullable(row, 19_000 + row as i32)),
+            )),
+        ),
+        (
+            "timestamp_ms_utc",
+            Arc::new(
+                TimestampMillisecondArray::from_iter(
+                    rows.clone()
+                        .map(|row| nullable(row, 1_700_000_000_000 + row * 3_600_000)),
+                )
+                .with_timezone("UTC"),
+            ),
+        ),
+        (
+            "timestamp_us",
+            Arc::new(TimestampMicrosecondArray::from_iter(
+                rows.clone()
+                    .map(|row| nullable(row, 1_700_000_000_000_000 + row * 1_001)),
+            )),
+        ),
+        (
+            "decimal",
+            Arc::new(
+                Decimal128Array::from_iter(
+                    rows.clone().map(|row| nullable(row, row as i128 * 12_345)),
+                )
+                .with_precision_and_scale(12, 2)
+                .unwrap(),
+            ),
+        ),
+        ("list", Arc::new(list.finish())),
+        ("struct", Arc::new(structs)),
+        ("map", Arc::new(map.finish())),
+        ("dictionary", Arc::new(dictionary.finish())),
+    ])
+    .unwrap()
+}
+
+/// Writer properties of a fixture, with small pages so that column chunks span several.
+pub fn properties() -> parquet::file::properties::WriterPropertiesBuilder {
+    WriterProperties::builder()
+        .set_data_page_row_count_limit(8)
+        .set_write_batch_size(8)
+        .set_max_row_group_size(ROWS / 2)
+}
+
+/// Compression codecs fixtures are written with.
+pub fn codecs() -> Vec<(&'static str, Compression)> {
+    vec![
+        ("uncompressed", Compression::UNCOMPRESSED),
+        ("snappy", Compression::SNAPPY),
+        ("gzip", Compression::GZIP(Default::default())),
+        ("lz4_raw", Compression::LZ4_RAW),
+        ("zstd", Compression::ZSTD(Default::default())),
+        ("brotli", Compression::BROTLI(Default::default())),
+    ]
+}
+
+/// Writer properties of fixtures with the encodings of each physical type, each written with v1
+/// data pages and again with v2 data pages (as `<name>_v2`).
+pub fn encodings() -> Vec<(String, WriterProperties)> {
+    let encodings = |version| {
+        let properties = || properties().set_writer_version(version);
+        let columns = |encoding, columns: &[&str]| {
+            columns.iter().fold(
+                properties().set_dictionary_enabled(false),
+                |properties, column| {
+                    properties.set_column_encoding(ColumnPath::from(*column), encoding)
+                },
+            )
+        };
+        vec![
+            ("dictionary", properties().build()),
+            (
+                "plain",
+                properties()
+                    .set_dictionary_enabled(false)
+                    .set_encoding(Encoding::PLAIN)
+                    .build(),
+            ),
+            (
+                "delta_binary_packed",
+                columns(
+                    Encoding::DELTA_BINARY_PACKED,
+                    &[
+                        "int8", "int16", "int32", "int64", "uint32", "uint64", "date32",
+                    ],
+                )
+                .build(),
+            ),
+            (
+                "delta_byte_array",
+                columns(Encoding::DELTA_BYTE_ARRAY, &["utf8", "binary"]).build(),
+            ),
+            (
+                "delta_length_byte_array",
+                columns(Encoding::DELTA_LENGTH_BYTE_ARRAY, &["utf8", "binary"]).build(),
+            ),
+            (
+                "byte_stream_split",
+                columns(Encoding::BYTE_STREAM_SPLIT, &["float32", "float64"]).build(),
+            ),
+        ]
+    };
+    let v1 = encodings(WriterVersion::PARQUET_1_0)
+        .into_iter()
+        .map(|(name, properties)| (name.to_string(), properties));
+    let v2 = encodings(WriterVersion::PARQUET_2_0)
+        .into_iter()
+        .map(|(name, properties)| (format!("{}_v2", name), properties));
+    v1.chain(v2).collect()
+}
+
+/// Writes a Parquet fixture of the batch.
+pub fn write_fixture(path: &Path, batch: &RecordBatch, properties: WriterProperties) {
+    let file = File::create(path).unwrap();
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(properties)).unwrap();
+    writer.write(batch).unwrap();
+    writer.close().unwrap();
+}
+
+/// Writes the all types fixture to a directory, returning its path.
+pub fn all_types_fixture(dir: &Path) -> PathBuf {
+    let path = dir.join("all_types.parquet");
+    write_fixture(&path, &all_types_batch(), properties().build());
+    path
+}
+
+/// Writes a fixture of a single column to a path.
+pub fn write_column(path: &Path, name: &str, column: ArrayRef) {
+    let batch = RecordBatch::try_from_iter([(name, column)]).unwrap();
+    write_fixture(path, &batch, WriterProperties::default());
+}
+
+/// Writes a fixture of an id column of the even numbers from -128, of the given number of rows,
+/// to a directory, returning its path. It is written with default properties, as it is large.
+pub fn even_ids_fixture(dir: &Path, rows: i64) -> PathBuf {
+    let path = dir.join("even_ids.parquet");
+    let ids: ArrayRef = Arc::new(Int64Array::from_iter_values(
+        (0..rows).map(|row| row * 2 - 128),
+    ));
+    write_column(&path, "id", ids);
+    path
+}
+
+/// Writes a fixture of a string column with a row group per value, the second of which is not
+/// valid UTF-8, as Arrow writers cannot write, returning its path.
+pub fn invalid_utf8_fixture(dir: &Path) -> PathBuf {
+    let path = dir.join("invalid_utf8.parquet");
+    let schema = parse_message_type("message schema { REQUIRED BYTE_ARRAY s (STRING); }").unwrap();
+    let mut writer = SerializedFileWriter::new(
+        File::create(&path).unwrap(),
+        Arc::new(schema),
+        Arc::new(WriterProperties::default()),
+    )
+    .unwrap();
+    for value in [&b"a"[..], b"\xff", b"b"] {
+        let mut row_group = writer.next_row_group().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        (column.typed::<ByteArrayType>())
+            .write_batch(&[ByteArray::from(value.to_vec())], None, None)
+            .unwrap();
+        column.close().unwrap();
+        row_group.close().unwrap();
+    }
+    writer.close().unwrap();
+    path
+}
+
+/// Writes a file with a column of a logical type newer than the parquet crate, made from a date
+/// column by rewriting the footer.
+pub fn unknown_logical_types_fixture(dir: &Path) -> PathBuf {
+    let path = dir.join("unknown_logical_types.parquet");
+    let schema = parse_message_type("message schema { REQUIRED INT32 d (DATE); }").unwrap();
+    let mut writer = SerializedFileWriter::new(
+        File::create(&path).unwrap(),
+        Arc::new(schema),
+        Arc::new(WriterProperties::default()),
+    )
+    .unwrap();
+    let mut row_group = writer.next_row_group().unwrap();
+    let mut column = row_group.next_column().unwrap().unwrap();
+    (column.typed::<parquet::data_type::Int32Type>())
+        .write_batch(&[19000], None, None)
+        .unwrap();
+    column.close().unwrap();
+    row_group.close().unwrap();
+    writer.close().unwrap();
+
+    // Fields of the schema element of d in the thrift compact protocol: converted type DATE,
+    // then the DATE member (6) of the LogicalType union, replaced with a member 17 and no
+    // converted type
+    let mut bytes = std::fs::read(&path).unwrap();
+    let footer_start = bytes.len() - 8;
+    let metadata_len =
+        u32::from_le_bytes(bytes[footer_start..footer_start + 4].try_into().unwrap());
+    let mut metadata = bytes.split_off(footer_start - metadata_len as usize);
+    metadata.truncate(metadata_len as usize);
+    let date = [0x25, 0x0c, 0x4c, 0x6c, 0x00, 0x00];
+    let positions: Vec<usize> = (0..metadata.len() - date.len())
+        .filter(|position| metadata[*position..].starts_with(&date))
+        .collect();
+    assert_eq!(positions.len(), 1);
+    metadata.splice(
+        positions[0]..positions[0] + date.len(),
+        [0x6c, 0x0c, 0x22, 0x00, 0x00],
+    );
+    bytes.extend_from_slice(&metadata);
+    bytes.extend_from_slice(&(metadata.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(b"PAR1");
+    std::fs::write(&path, bytes).unwrap();
+    path
+}
+
+/// Runs parquet2json without AWS configuration or credentials from the environment.
+pub fn parquet2json(args: &[&str]) -> Output {
+    parquet2json_with_env(args, &[])
+}
+
+/// Runs parquet2json with only the given variables added to a bare environment.
+pub fn parquet2json_with_env(args: &[&str], env: &[(&str, &str)]) -> Output {
+    let home = tempfile::tempdir().unwrap();
+    Command::new(env!("CARGO_BIN_EXE_parquet2json"))
+        .args(args)
+        .env_clear()
+        .env("HOME", home.path())
+        .env("AWS_REGION", "us-east-1")
+        .env("RUST_BACKTRACE", "0")
+        .envs(env.iter().copied())
+        .output()
+        .unwrap()
+}
+
+/// Runs parquet2json, returning its stdout once it succeeds.
+pub fn stdout(args: &[&str]) -> String {
+    let output = parquet2json(args);
+    assert!(
+        output.status.success(),
+        "parquet2json {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Runs parquet2json, returning its stderr once it fails.
+pub fn stderr(args: &[&str]) -> String {
+    let output = parquet2json(args);
+    assert!(
+        !output.status.success(),
+        "parquet2json {} succeeded",
+        args.join(" ")
+    );
+    String::from_utf8(output.stderr).unwrap()
+}
+
+/// Asserts output is that of the golden file of the name under tests/golden, rewriting the file
+/// instead with UPDATE_GOLDEN=1.
+pub fn assert_golden(name: &str, output: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, output).unwrap();
+        return;
+    }
+    let golden = std::fs::read_to_string(&path)
+        .unwrap_or_else(|error| panic!("Missing golden file ({}: {})", path.display(), error));
+    assert!(
+        output == golden,
+        "Output differs from {} (rerun with UPDATE_GOLDEN=1 to update)\n{}",
+        path.display(),
+        output
+    );
+}
+
+/// Objects served by a mock HTTP or path-style S3 server, as the bucket/key paths of their
+/// contents.
+pub struct Objects {
+    pub objects: BTreeMap<String, Vec<u8>>,
+    /// Whether range requests are answered with the range rather than the whole object
+    pub ranges: bool,
+}
+
+impl Objects {
+    pub fn new(objects: impl IntoIterator<Item = (String, Vec<u8>)>) -> Self {
+        Objects {
+            objects: objects.into_iter().collect(),
+            ranges: true,
+        }
+    }
+
+    /// ListObjectsV2 of the keys of a bucket under a prefix.
+    fn list(&self, bucket: &str, prefix: &str) -> ResponseTemplate {
+        let contents: String = self
+            .objects
+            .iter()
+            .filter_map(|(path, data)| {
+                let key = path.strip_prefix(bucket)?.strip_prefix('/')?;
+                key.starts_with(prefix).then(|| {
+                    format!(
+                        "<Contents><Key>{}</Key><LastModified>2024-01-01T00:00:00.000Z</LastModified><ETag>\"{}\"</ETag><Size>{}</Size></Contents>",
+                        key,
+                        data.len(),
+                        data.len()
+                    )
+                })
+            })
+            .collect();
+        let body = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><ListBucketResult><Name>{}</Name><Prefix>{}</Prefix><IsTruncated>false</IsTruncated>{}</ListBucketResult>",
+            bucket, prefix, contents
+        );
+        ResponseTemplate::new(200).set_body_raw(body, "application/xml")
+    }
+}
+
+impl Respond for Objects {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let path = request.url.path().trim_start_matches('/');
+        let query: BTreeMap<_, _> = request.url.query_pairs().collect();
+        if query
+            .get("list-type")
+            .is_some_and(|list_type| list_type == "2")
+        {
+            let prefix = query.get("prefix").map(|prefix| prefix.as_ref());
+            return self.list(path.trim_end_matches('/'), prefix.unwrap_or_default());
+        }
+        let Some(data) = self.objects.get(path) else {
+            return ResponseTemplate::new(404);
+        };
+        let response = ResponseTemplate::new(200)
+            .insert_header("ETag", format!("\"{}\"", data.len()))
+            .insert_header("Last-Modified", "Mon, 01 Jan 2024 00:00:00 GMT");
+        let range = request
+            .headers
+            .get("Range")
+            .and_then(|range| range.to_str().ok()?.strip_prefix("bytes=")?.split_once('-'))
+            .filter(|_| self.ranges && request.method.as_str() == "GET");
+        match range {
+            Some((start, end)) => {
+                let start: usize = start.parse().unwrap();
+                let end = end.parse().map_or(data.len(), |end: usize| end + 1);
+                let end = end.min(data.len());
+                ResponseTemplate::new(206)
+                    .insert_header("ETag", format!("\"{}\"", data.len()))
+                    .insert_header("Last-Modified", "Mon, 01 Jan 2024 00:00:00 GMT")
+                    .insert_header(
+                        "Content-Range",
+                        format!("bytes {}-{}/{}", start, end - 1, data.len()),
+                    )
+                    .set_body_bytes(data[start..end].to_vec())
+            }
+            None => response.set_body_bytes(data.clone()),
+        }
+    }
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use std::time::Duration;
+
+use common::*;
+use wiremock::matchers::{any, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn serve(objects: Objects) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(any())
+        .respond_with(objects)
+        .mount(&server)
+        .await;
+    server
+}
+
+fn all_types_object() -> (String, Vec<u8>) {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    (
+        "data/all_types.parquet".to_string(),
+        std::fs::read(fixture).unwrap(),
+    )
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cat_with_range_requests() {
+    let server = serve(Objects::new([all_types_object()])).await;
+    let url = format!("{}/data/all_types.parquet", server.uri());
+    let output = tokio::task::spawn_blocking(move || stdout(&[&url, "cat"]))
+        .await
+        .unwrap();
+    assert_golden("all_types.jsonl", &output);
+
+    let requests = server.received_requests().await.unwrap();
+    assert!(requests
+        .iter()
+        .any(|request| request.headers.contains_key("Range")));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cat_without_range_requests() {
+    let mut objects = Objects::new([all_types_object()]);
+    objects.ranges = false;
+    let server = serve(objects).await;
+    let url = format!("{}/data/all_types.parquet", server.uri());
+    let output = tokio::task::spawn_blocking(move || stdout(&[&url, "cat"]))
+        .await
+        .unwrap();
+    assert_golden("all_types.jsonl", &output);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn missing_object() {
+    let server = serve(Objects::new([])).await;
+    let url = format!("{}/data/missing.parquet", server.uri());
+    let error = tokio::task::spawn_blocking(move || stderr(&[&url, "cat"]))
+        .await
+        .unwrap();
+    assert!(error.starts_with("error: "), "{}", error);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn metadata_timeout() {
+    let server = MockServer::start().await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(30)))
+        .mount(&server)
+        .await;
+    let url = format!("{}/data/all_types.parquet", server.uri());
+    let error =
+        tokio::task::spawn_blocking(move || stderr(&[&url, "cat", "--metadata-timeout", "1"]))
+            .await
+            .unwrap();
+    assert!(
+        error.contains("Timed out after 1s reading metadata"),
+        "{}",
+        error
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cat_where_reads_index_next_to_url() {
+    let server = serve(Objects::new([all_types_object()])).await;
+    let url = format!("{}/data/all_types.parquet", server.uri());
+    let output = tokio::task::spawn_blocking(move || {
+        stdout(&[&url, "cat", "--where", "int8 >= 0"])
+            .lines()
+            .count()
+    })
+    .await
+    .unwrap();
+    assert_eq!(output, 22);
+
+    let requests = server.received_requests().await.unwrap();
+    assert!(requests
+        .iter()
+        .any(|request| request.url.path() == "/data/all_types.parquet.p2jidx"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_auth_is_not_sent_to_outputs() {
+    let server = serve(Objects::new([all_types_object()])).await;
+    let sink = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&sink)
+        .await;
+    let url = format!("{}/data/all_types.parquet", server.uri());
+    let output = format!("post:{}/_bulk", sink.uri());
+    tokio::task::spawn_blocking(move || {
+        stdout(&[
+            &url,
+            "--http-auth",
+            "bearer:source",
+            "--output-http-auth",
+            "bearer:sink",
+            "cat",
+            "--output",
+            &output,
+        ])
+    })
+    .await
+    .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    assert!(requests
+        .iter()
+        .all(|request| request.headers["Authorization"] == "Bearer source"));
+    let posts = sink.received_requests().await.unwrap();
+    assert!(!posts.is_empty());
+    assert!(posts
+        .iter()
+        .all(|request| request.headers["Authorization"] == "Bearer sink"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn presigned_url_without_http_auth() {
+    let server = serve(Objects::new([all_types_object()])).await;
+    let url = format!(
+        "{}/data/all_types.parquet?X-Amz-Expires=60&X-Amz-Signature=abc",
+        server.uri()
+    );
+    let output = tokio::task::spawn_blocking(move || {
+        stdout(&[&url, "--http-auth", "bearer:mytoken", "cat"])
+    })
+    .await
+    .unwrap();
+    assert_golden("all_types.jsonl", &output);
+
+    let requests = server.received_requests().await.unwrap();
+    assert!(requests
+        .iter()
+        .all(|request| !request.headers.contains_key("Authorization")));
+}
+
+/// Runs self-update against a mock GitHub API whose latest release has the tag.
+async fn self_update(tag: &str, args: &[&str]) -> std::process::Output {
+    let server = MockServer::start().await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "tag_name": tag,
+            "assets": [],
+        })))
+        .mount(&server)
+        .await;
+    let mut args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    args.insert(0, "self-update".to_string());
+    tokio::task::spawn_blocking(move || {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        parquet2json_with_env(&args, &[("GITHUB_API_URL", &server.uri())])
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn self_update_check_orders_pre_releases() {
+    let current = env!("CARGO_PKG_VERSION");
+    for (tag, available) in [
+        (format!("v{}-rc.2", current), false),
+        (format!("v{}+build.5", current), false),
+        (format!("v{}", current), false),
+        ("v999.0.0-rc.1".to_string(), true),
+        ("v999.0.0".to_string(), true),
+    ] {
+        let output = self_update(&tag, &["--check"]).await;
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(
+            stdout.starts_with("Update available"),
+            available,
+            "{}: {}",
+            tag,
+            stdout
+        );
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn self_update_without_signing_key() {
+    let output = self_update("v999.0.0", &[]).await;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("no release signing key"), "{}", stderr);
+}
@@ -0,0 +1,781 @@
+mod common;
+
+use std::sync::Arc;
+
+use arrow_array::{Int64Array, UInt64Array};
+use common::*;
+
+#[test]
+fn cat_all_types() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    assert_golden(
+        "all_types.jsonl",
+        &stdout(&[fixture.to_str().unwrap(), "cat"]),
+    );
+}
+
+#[test]
+fn cat_target_batch_bytes() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    assert_golden(
+        "all_types.jsonl",
+        &stdout(&[
+            fixture.to_str().unwrap(),
+            "cat",
+            "--target-batch-bytes",
+            "1",
+        ]),
+    );
+}
+
+#[test]
+fn cat_all_codecs() {
+    let dir = tempfile::tempdir().unwrap();
+    let batch = all_types_batch();
+    for (name, codec) in codecs() {
+        let fixture = dir.path().join(format!("{}.parquet", name));
+        write_fixture(
+            &fixture,
+            &batch,
+            properties().set_compression(codec).build(),
+        );
+        assert_golden(
+            "all_types.jsonl",
+            &stdout(&[fixture.to_str().unwrap(), "cat"]),
+        );
+    }
+}
+
+#[test]
+fn cat_all_encodings() {
+    let dir = tempfile::tempdir().unwrap();
+    let batch = all_types_batch();
+    for (name, properties) in encodings() {
+        let fixture = dir.path().join(format!("{}.parquet", name));
+        write_fixture(&fixture, &batch, properties);
+        assert_golden(
+            "all_types.jsonl",
+            &stdout(&[fixture.to_str().unwrap(), "cat"]),
+        );
+
+        // The fixture has the pages and encodings it is named after
+        let advise = stdout(&[fixture.to_str().unwrap(), "advise"]);
+        let (encoding, version) = match name.strip_suffix("_v2") {
+            Some(encoding) => (encoding, "(v2)"),
+            None => (name.as_str(), "(v1)"),
+        };
+        assert!(advise.contains(version), "{}: {}", name, advise);
+        if encoding != "dictionary" {
+            assert!(
+                advise.contains(&format!(", {},", encoding.to_uppercase())),
+                "{}: {}",
+                name,
+                advise
+            );
+        }
+    }
+}
+
+#[test]
+fn cat_formats() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    for format in ["csv", "tsv", "es-bulk"] {
+        let extension = match format {
+            "es-bulk" => "bulk.jsonl",
+            format => format,
+        };
+        assert_golden(
+            &format!("all_types.{}", extension),
+            &stdout(&[
+                fixture.to_str().unwrap(),
+                "cat",
+                "--format",
+                format,
+                "--limit",
+                "10",
+            ]),
+        );
+    }
+}
+
+#[test]
+fn cat_es_bulk_create() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let output = stdout(&[
+        fixture.to_str().unwrap(),
+        "cat",
+        "--columns",
+        "int32,utf8",
+        "--limit",
+        "2",
+        "--format",
+        "es-bulk",
+        "--index",
+        "logs",
+        "--bulk-action",
+        "create",
+        "--id-column",
+        "int32",
+    ]);
+    assert_eq!(
+        output,
+        "{\"create\":{\"_index\":\"logs\",\"_id\":\"0\"}}\n\
+         {\"int32\":0,\"utf8\":\"row \\\"0\\\"\\t\u{e9}\"}\n\
+         {\"create\":{\"_index\":\"logs\",\"_id\":\"100000\"}}\n\
+         {\"int32\":100000,\"utf8\":\"row \\\"1\\\"\\t\u{e9}\"}\n"
+    );
+}
+
+#[test]
+fn cat_columns_and_limits() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let output = stdout(&[
+        fixture.to_str().unwrap(),
+        "cat",
+        "--columns",
+        "int32,utf8",
+        "--offset",
+        "2",
+        "--limit",
+        "2",
+    ]);
+    assert_eq!(
+        output,
+        "{\"int32\":200000,\"utf8\":\"row \\\"2\\\"\\té\"}\n{}\n"
+    );
+}
+
+#[test]
+fn schema_and_rowcount() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    assert_golden(
+        "all_types.schema",
+        &stdout(&[fixture.to_str().unwrap(), "schema"]),
+    );
+    assert_eq!(
+        stdout(&[fixture.to_str().unwrap(), "rowcount"]),
+        format!("{}\n", ROWS)
+    );
+}
+
+#[test]
+fn expect_fingerprint() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let fixture = fixture.to_str().unwrap();
+    let fingerprint = stdout(&[fixture, "schema", "--fingerprint"]);
+    let fingerprint = fingerprint.trim_end();
+    assert_eq!(fingerprint.len(), 64);
+    assert_golden(
+        "all_types.jsonl",
+        &stdout(&[fixture, "cat", "--expect-fingerprint", fingerprint]),
+    );
+    let changed = "0".repeat(64);
+    let output = parquet2json(&[fixture, "cat", "--expect-fingerprint", &changed]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("differs from --expect-fingerprint"));
+}
+
+#[test]
+fn output_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let fixture = fixture.to_str().unwrap();
+    let rowcount = dir.path().join("rowcount.txt");
+    let lines = dir.path().join("all_types.jsonl");
+    assert_eq!(
+        stdout(&[fixture, "rowcount", "--output", rowcount.to_str().unwrap()]),
+        ""
+    );
+    assert_eq!(
+        std::fs::read_to_string(rowcount).unwrap(),
+        format!("{}\n", ROWS)
+    );
+    let output = format!("file:{}", lines.to_str().unwrap());
+    stdout(&[fixture, "cat", "--output", &output]);
+    assert_golden("all_types.jsonl", &std::fs::read_to_string(lines).unwrap());
+    assert!(!dir.path().join("all_types.jsonl.partial").exists());
+}
+
+#[test]
+fn missing_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("missing.parquet");
+    assert!(stderr(&[missing.to_str().unwrap(), "cat"]).starts_with("error: "));
+}
+
+#[test]
+fn cat_pretty() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let output = stdout(&[fixture.to_str().unwrap(), "cat", "--pretty"]);
+    assert!(output.starts_with("{\n  \"bool\": true,\n  \"int8\": -25,\n"));
+    let records: Vec<serde_json::Value> = serde_json::Deserializer::from_str(&output)
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let golden: Vec<serde_json::Value> = std::fs::read_to_string("tests/golden/all_types.jsonl")
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records, golden);
+}
+
+#[test]
+fn cat_compress_gzip() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let output = parquet2json(&[fixture.to_str().unwrap(), "cat", "--compress", "gzip"]);
+    assert!(output.status.success());
+    let mut lines = String::new();
+    std::io::Read::read_to_string(
+        &mut flate2::read::GzDecoder::new(&output.stdout[..]),
+        &mut lines,
+    )
+    .unwrap();
+    assert_golden("all_types.jsonl", &lines);
+}
+
+#[test]
+fn cat_compress_zstd() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let output = parquet2json(&[fixture.to_str().unwrap(), "cat", "--compress", "zstd:19"]);
+    assert!(output.status.success());
+    let lines = zstd::decode_all(&output.stdout[..]).unwrap();
+    assert_golden("all_types.jsonl", &String::from_utf8(lines).unwrap());
+}
+
+#[test]
+fn cat_yaml() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let output = stdout(&[fixture.to_str().unwrap(), "cat", "--format", "yaml"]);
+    assert!(output.starts_with("---\nbool: true\nint8: -25\n"));
+    let records: Vec<serde_json::Value> = serde_yaml::Deserializer::from_str(&output)
+        .map(serde::Deserialize::deserialize)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let golden: Vec<serde_json::Value> = std::fs::read_to_string("tests/golden/all_types.jsonl")
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records, golden);
+}
+
+#[test]
+fn cat_sql() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let output = stdout(&[
+        fixture.to_str().unwrap(),
+        "cat",
+        "--limit",
+        "1",
+        "--format",
+        "sql",
+        "--table",
+        "sample",
+    ]);
+    assert!(output.starts_with("INSERT INTO sample (\"bool\", \"int8\", "));
+    assert!(output.ends_with(");\n"));
+    assert!(output.contains(" VALUES (TRUE, -25, "));
+    assert!(output.contains(", 'row \"0\"\t\u{e9}', X'0000ff', '2022-01-08', "));
+    assert!(output.contains(", 0.00, '[]', '{\"a\":0,\"b\":\"b0\"}', "));
+}
+
+#[test]
+fn cat_pg_copy() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let output = stdout(&[
+        fixture.to_str().unwrap(),
+        "cat",
+        "--limit",
+        "4",
+        "--format",
+        "pg-copy",
+    ]);
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("t\t-25\t"));
+    assert!(lines[0].contains("\trow \"0\"\\t\u{e9}\t\\\\x0000ff\t2022-01-08\t"));
+    assert!(lines[0].contains("\t0.00\t[]\t{\"a\":0,\"b\":\"b0\"}"));
+    assert!(lines[3].starts_with("\\N\t\\N\t"));
+}
+
+#[test]
+fn cat_derive() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let output = stdout(&[
+        fixture.to_str().unwrap(),
+        "cat",
+        "--offset",
+        "1",
+        "--limit",
+        "1",
+        "--columns",
+        "int8",
+        "--derive",
+        "x=int32*2+int8",
+        "--derive",
+        "day=date_trunc(day, timestamp_ms_utc)",
+        "--derive",
+        "s=upper(substr(utf8, 1, 5))",
+    ]);
+    assert_eq!(
+        output,
+        "{\"int8\":-24,\"x\":199976,\"day\":\"2023-11-14T00:00:00Z\",\"s\":\"ROW \\\"\"}\n"
+    );
+}
+
+#[test]
+fn cat_coerce_schemas() {
+    let dir = tempfile::tempdir().unwrap();
+    write_column(
+        &dir.path().join("a.parquet"),
+        "id",
+        Arc::new(Int64Array::from(vec![-1])),
+    );
+    write_column(
+        &dir.path().join("b.parquet"),
+        "id",
+        Arc::new(UInt64Array::from(vec![u64::MAX])),
+    );
+    let output = stdout(&[dir.path().to_str().unwrap(), "cat", "--coerce-schemas"]);
+    assert_eq!(
+        output,
+        "{\"id\":\"-1\"}\n{\"id\":\"18446744073709551615\"}\n"
+    );
+}
+
+#[test]
+fn cat_partition_by() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let out = dir.path().join("out");
+    stdout(&[
+        fixture.to_str().unwrap(),
+        "cat",
+        "--limit",
+        "4",
+        "--columns",
+        "int8",
+        "--partition-by",
+        "bool",
+        "--output",
+        out.to_str().unwrap(),
+    ]);
+    let partition = |name: &str| {
+        let shard = std::fs::File::open(out.join(name).join("part-00000.json.gz")).unwrap();
+        std::io::read_to_string(flate2::read::GzDecoder::new(shard)).unwrap()
+    };
+    assert_eq!(partition("bool=true"), "{\"int8\":-25}\n{\"int8\":-23}\n");
+    assert_eq!(partition("bool=false"), "{\"int8\":-24}\n");
+    assert_eq!(partition("bool=__HIVE_DEFAULT_PARTITION__"), "{}\n");
+}
+
+#[test]
+fn cat_unpivot_and_pivot() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let args = [
+        fixture.to_str().unwrap(),
+        "cat",
+        "--limit",
+        "2",
+        "--columns",
+        "bool,int8,int16",
+        "--unpivot",
+        "int*:name,value",
+    ];
+    assert_eq!(
+        stdout(&args),
+        "{\"bool\":true,\"name\":\"int8\",\"value\":-25}\n\
+         {\"bool\":true,\"name\":\"int16\",\"value\":0}\n\
+         {\"bool\":false,\"name\":\"int8\",\"value\":-24}\n\
+         {\"bool\":false,\"name\":\"int16\",\"value\":1000}\n"
+    );
+    let output = stdout(&[&args[..], &["--pivot", "name,value"]].concat());
+    assert_eq!(
+        output,
+        "{\"bool\":true,\"int8\":-25,\"int16\":0}\n{\"bool\":false,\"int8\":-24,\"int16\":1000}\n"
+    );
+}
+
+#[test]
+fn cat_markdown() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let output = stdout(&[
+        fixture.to_str().unwrap(),
+        "cat",
+        "--limit",
+        "2",
+        "--columns",
+        "bool,int8,utf8",
+        "--format",
+        "markdown",
+        "--max-width",
+        "5",
+    ]);
+    assert_eq!(
+        output,
+        "| bool | int8 | utf8 |\n\
+         | --- | ---: | --- |\n\
+         | true | -25 | row \u{2026} |\n\
+         | false | -24 | row \u{2026} |\n"
+    );
+}
+
+#[test]
+fn cat_table() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let output = stdout(&[
+        fixture.to_str().unwrap(),
+        "cat",
+        "--limit",
+        "2",
+        "--columns",
+        "int8,utf8",
+        "--format",
+        "table",
+        "--max-width",
+        "5",
+    ]);
+    assert_eq!(
+        output,
+        "┌──────┬───────┐\n\
+         │ int8 │ utf8  │\n\
+         ├──────┼───────┤\n\
+         │  -25 │ row … │\n\
+         │  -24 │ row … │\n\
+         └──────┴───────┘\n"
+    );
+}
+
+#[test]
+fn cat_null_checks() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let lines = |args: &[&str]| {
+        let output = stdout(&[&[fixture.to_str().unwrap(), "cat"], args].concat());
+        output.lines().count()
+    };
+    assert_eq!(lines(&["--drop-if-null", "int8,utf8"]), 43);
+    assert_eq!(lines(&["--keep-if-any-nonnull", "int8,struct"]), ROWS);
+    assert_eq!(
+        lines(&["--drop-if-null", "int8", "--where", "int8 >= -24"]),
+        42
+    );
+}
+
+#[test]
+fn cat_where_summary() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let output = parquet2json(&[
+        fixture.to_str().unwrap(),
+        "--summary",
+        "--deterministic",
+        "cat",
+        "--where",
+        "int8 >= -24",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().lines().count(),
+        42
+    );
+    let summary = String::from_utf8(output.stderr).unwrap();
+    assert!(summary.contains("rows filtered: 42 of 50 matched"));
+    assert!(!summary.contains("filter throughput"));
+}
+
+#[test]
+fn index_where() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let fixture = fixture.to_str().unwrap();
+    stdout(&[fixture, "index", "build", "--columns", "int8,utf8,binary"]);
+    let index_file = format!("{}.p2jidx", fixture);
+    let index: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&index_file).unwrap()).unwrap();
+    let columns = &index["row_groups"][0]["columns"];
+    assert_eq!(columns["int8"]["min"], -25);
+    assert_eq!(columns["utf8"]["min"], "row \"0\"\té");
+    assert!(columns.get("binary").is_none());
+
+    // Literals are quoted with any " and " within them
+    let args = [fixture, "cat", "--where", "int8 >= 0 and utf8 != 'a and b'"];
+    assert_eq!(stdout(&args).lines().count(), 22);
+
+    std::fs::write(&index_file, "corrupt").unwrap();
+    let output = parquet2json(&args);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().lines().count(),
+        22
+    );
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Ignoring index that cannot be read"));
+}
+
+#[test]
+fn cat_invalid_utf8() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = invalid_utf8_fixture(dir.path());
+    let fixture = fixture.to_str().unwrap();
+    let output = parquet2json(&[fixture, "cat"]);
+    assert!(output.status.success());
+    // Only the row group of the invalid value is skipped
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"s\":\"a\"}\n{\"s\":\"b\"}\n"
+    );
+    let warnings = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        warnings.contains("1 rows of row group 1 skipped"),
+        "{}",
+        warnings
+    );
+    assert!(warnings.contains("invalid UTF-8: 1"), "{}", warnings);
+
+    let error = stderr(&[fixture, "cat", "--on-invalid-utf8", "error"]);
+    assert!(error.contains("invalid UTF-8"), "{}", error);
+}
+
+#[test]
+fn cat_unknown_logical_types() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = unknown_logical_types_fixture(dir.path());
+    let fixture = fixture.to_str().unwrap();
+    let output = parquet2json(&[fixture, "cat"]);
+    assert!(output.status.success());
+    // The date column is read as its physical type, INT32
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "{\"d\":19000}\n");
+    let warnings = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        warnings.contains("column d of logical type 17"),
+        "{}",
+        warnings
+    );
+    assert!(warnings.contains("unknown logical type: 1"), "{}", warnings);
+
+    let error = stderr(&[fixture, "cat", "--on-unknown-logical-type", "error"]);
+    assert!(error.contains("unknown logical type"), "{}", error);
+}
+
+#[test]
+fn extract() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let sample = dir.path().join("sample.parquet");
+    stdout(&[
+        fixture.to_str().unwrap(),
+        "extract",
+        "--offset",
+        "2",
+        "--limit",
+        "3",
+        "--output",
+        sample.to_str().unwrap(),
+    ]);
+    let output = stdout(&[sample.to_str().unwrap(), "cat"]);
+    let golden = std::fs::read_to_string("tests/golden/all_types.jsonl").unwrap();
+    let expected: Vec<&str> = golden.lines().skip(2).take(3).collect();
+    assert_eq!(output.lines().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn cat_output_shards_by_hash() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let out = dir.path().join("out");
+    std::fs::create_dir(&out).unwrap();
+    let output = out.join("part-{hash}.json.gz");
+    let shards = || {
+        stdout(&[
+            fixture.to_str().unwrap(),
+            "cat",
+            "--output",
+            output.to_str().unwrap(),
+        ]);
+        let mut names: Vec<String> = std::fs::read_dir(&out)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        names
+    };
+    let names = shards();
+    assert_eq!(names.len(), 1);
+    let hash = names[0]
+        .strip_prefix("part-")
+        .and_then(|name| name.strip_suffix(".json.gz"))
+        .unwrap();
+    assert!(hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(shards(), names);
+}
+
+#[test]
+fn check_fk() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let users = dir.path().join("users.parquet");
+    stdout(&[
+        fixture.to_str().unwrap(),
+        "extract",
+        "--limit",
+        "10",
+        "--output",
+        users.to_str().unwrap(),
+    ]);
+    let against = format!("{}:int8", users.to_str().unwrap());
+
+    let output = parquet2json(&[
+        users.to_str().unwrap(),
+        "check-fk",
+        "--column",
+        "int8",
+        "--against",
+        &against,
+    ]);
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["checked"], 9);
+    assert_eq!(report["violations"], 0);
+
+    let output = parquet2json(&[
+        fixture.to_str().unwrap(),
+        "check-fk",
+        "--column",
+        "int8",
+        "--against",
+        &against,
+        "--samples",
+        "2",
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["checked"], 43);
+    assert_eq!(report["violations"], 34);
+    let samples = report["samples"].as_array().unwrap();
+    assert_eq!(samples.len(), 2);
+    assert_eq!(samples[0]["key"]["int8"], "-14");
+    assert_eq!(samples[0]["row"], 11);
+
+    // Reference keys beyond the memory budget are partitioned, with the same report
+    let even_ids = even_ids_fixture(dir.path(), 100_000);
+    let against = format!("{}:id", even_ids.to_str().unwrap());
+    let check = |memory_budget: &str| {
+        let output = parquet2json(&[
+            fixture.to_str().unwrap(),
+            "check-fk",
+            "--column",
+            "int8",
+            "--against",
+            &against,
+            "--memory-budget",
+            memory_budget,
+        ]);
+        assert_eq!(output.status.code(), Some(1));
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+    let report = check("1024");
+    assert_eq!(report["checked"], 43);
+    assert_eq!(report["violations"], 22);
+    assert_eq!(check("1"), report);
+}
+
+#[test]
+fn check_gaps() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let fixture = fixture.to_str().unwrap();
+    let check_gaps = |args: &[&str]| {
+        let mut all_args = vec![fixture, "check-gaps", "--by", "timestamp_ms_utc"];
+        all_args.extend_from_slice(args);
+        let output = parquet2json(&all_args);
+        let gaps: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        (output.status.code(), gaps)
+    };
+
+    // Hourly values, with nulls every 7 rows from row 3
+    let (code, gaps) = check_gaps(&["--interval", "1h"]);
+    assert_eq!(code, Some(1));
+    assert_eq!(gaps.len(), 7);
+    assert_eq!(gaps[0]["from"], "2023-11-15T01:00:00Z");
+    assert_eq!(gaps[0]["to"], "2023-11-15T02:00:00Z");
+    assert_eq!(gaps[0]["missing"], 1);
+
+    let (code, gaps) = check_gaps(&["--interval", "3h", "--group-by", "dictionary"]);
+    assert_eq!(code, Some(1));
+    assert_eq!(gaps.len(), 7);
+    assert_eq!(gaps[0]["group"]["dictionary"], "blue");
+
+    let (code, gaps) = check_gaps(&["--interval", "1d"]);
+    assert_eq!(code, Some(0));
+    assert!(gaps.is_empty());
+}
+
+#[test]
+fn cat_envelope() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = all_types_fixture(dir.path());
+    let output = stdout(&[
+        fixture.to_str().unwrap(),
+        "cat",
+        "--limit",
+        "2",
+        "--envelope",
+        "cloudevents",
+        "--event-type",
+        "com.example.row",
+    ]);
+    let events: Vec<serde_json::Value> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0]["specversion"], "1.0");
+    assert_eq!(events[0]["type"], "com.example.row");
+    assert!(events[0]["source"]
+        .as_str()
+        .unwrap()
+        .ends_with("all_types.parquet"));
+    assert_eq!(events[1]["id"], "1");
+    assert_eq!(events[1]["rowindex"], 1);
+    assert_eq!(events[1]["data"]["int8"], -24);
+    assert_eq!(events[0]["time"], events[1]["time"]);
+
+    // Row indices are those in the file, before rows are filtered
+    let output = stdout(&[
+        fixture.to_str().unwrap(),
+        "cat",
+        "--where",
+        "int8 = -23",
+        "--wrap",
+        "row_data",
+    ]);
+    let record: serde_json::Value = serde_json::from_str(output.trim_end()).unwrap();
+    assert_eq!(record["row"], 2);
+    assert_eq!(record["row_data"]["int8"], -23);
+    assert!(record["ingested_at"].is_string());
+}
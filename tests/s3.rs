@@ -0,0 +1,122 @@
+mod common;
+
+use common::*;
+use wiremock::matchers::{any, header_exists};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Serves the objects as path-style S3, running parquet2json against it anonymously.
+async fn cat_s3(objects: Objects, location: &str, args: &[&str]) -> String {
+    let server = MockServer::start().await;
+    Mock::given(any())
+        .respond_with(objects)
+        .mount(&server)
+        .await;
+    let endpoint_url = server.uri();
+    let mut args: Vec<String> = [location, "cat"]
+        .iter()
+        .chain(args)
+        .map(|arg| arg.to_string())
+        .collect();
+    args.extend(
+        [
+            "--endpoint-url",
+            &endpoint_url,
+            "--force-path-style",
+            "--no-sign-request",
+        ]
+        .map(String::from),
+    );
+    tokio::task::spawn_blocking(move || {
+        stdout(&args.iter().map(String::as_str).collect::<Vec<_>>())
+    })
+    .await
+    .unwrap()
+}
+
+fn fixture_bytes() -> Vec<u8> {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::read(all_types_fixture(dir.path())).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cat_object() {
+    let objects = Objects::new([("bucket/data/all_types.parquet".to_string(), fixture_bytes())]);
+    let output = cat_s3(objects, "s3://bucket/data/all_types.parquet", &[]).await;
+    assert_golden("all_types.jsonl", &output);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cat_prefix() {
+    let fixture = fixture_bytes();
+    let objects = Objects::new([
+        ("bucket/data/part-0.parquet".to_string(), fixture.clone()),
+        ("bucket/data/part-1.parquet".to_string(), fixture),
+        ("bucket/other/part-0.parquet".to_string(), vec![]),
+    ]);
+    let output = cat_s3(objects, "s3://bucket/data/", &["--columns", "int32"]).await;
+    assert_eq!(output.lines().count(), ROWS * 2);
+}
+
+/// Runs parquet2json with credentials from the environment against a server answering signed
+/// requests with the status, and serving the fixture to unsigned ones.
+async fn cat_signed(status: u16) -> (std::process::Output, Vec<wiremock::Request>) {
+    let server = MockServer::start().await;
+    Mock::given(header_exists("Authorization"))
+        .respond_with(ResponseTemplate::new(status))
+        .mount(&server)
+        .await;
+    Mock::given(any())
+        .respond_with(Objects::new([(
+            "bucket/data/all_types.parquet".to_string(),
+            fixture_bytes(),
+        )]))
+        .mount(&server)
+        .await;
+    let endpoint_url = server.uri();
+    let output = tokio::task::spawn_blocking(move || {
+        parquet2json_with_env(
+            &[
+                "s3://bucket/data/all_types.parquet",
+                "cat",
+                "--endpoint-url",
+                &endpoint_url,
+                "--force-path-style",
+                "--anonymous-fallback",
+            ],
+            &[
+                ("AWS_ACCESS_KEY_ID", "AKIDEXAMPLE"),
+                ("AWS_SECRET_ACCESS_KEY", "secret"),
+                ("AWS_EC2_METADATA_DISABLED", "true"),
+            ],
+        )
+    })
+    .await
+    .unwrap();
+    (output, server.received_requests().await.unwrap())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn anonymous_fallback_when_access_denied() {
+    let (output, requests) = cat_signed(403).await;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_golden(
+        "all_types.jsonl",
+        &String::from_utf8(output.stdout).unwrap(),
+    );
+    assert!(requests
+        .iter()
+        .any(|request| !request.headers.contains_key("Authorization")));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn no_fallback_when_request_fails_otherwise() {
+    let (output, requests) = cat_signed(400).await;
+    assert!(!output.status.success());
+    assert!(requests
+        .iter()
+        .all(|request| request.headers.contains_key("Authorization")));
+}